@@ -0,0 +1,165 @@
+use anyhow::{bail, Context, Result};
+use gomoku_core::{Analysis, Board, Coord, Difficulty, RuleSet, Rules, Stone, ENGINE_VERSION};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+/// 默认缓存文件路径
+pub const CACHE_PATH: &str = "gomoku_analysis.cache";
+
+// 缓存文件第一行，记录写入时的引擎版本
+const HEADER: &str = "engine";
+
+/// 局面分析缓存，超出容量时按插入顺序淘汰最旧的结果
+///
+/// 缓存文件第一行是 `engine 版本号`，之后每行一条记录：`key col row score`。
+pub struct AnalysisCache {
+    entries: HashMap<u64, Analysis>,
+    order: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl AnalysisCache {
+    /// 创建指定容量的空缓存
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// 计算局面的键，包含棋盘、行棋方、AI 难度和规则
+    ///
+    /// 使用 FNV-1a 而不是标准库的哈希，保证不同版本之间写入的键保持一致。
+    /// 标准五子的自由规则不参与计算，所以加入规则之前写入的键仍然有效。
+    pub fn key(board: &Board, stone: Stone, difficulty: Difficulty, rules: Rules) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let cells = Coord::all().map(|c| board.get(c) as u8);
+        let extra = [stone as u8, difficulty as u8 + 3];
+        let set = (rules.set() != RuleSet::Freestyle).then_some(rules.set() as u8 + 6);
+        let length = (rules.win_length() != Rules::DEFAULT_WIN_LENGTH).then_some(rules.win_length() as u8 + 8);
+        for cell in cells.chain(extra).chain(set).chain(length) {
+            hash ^= cell as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    /// 查询缓存
+    pub fn get(&self, key: u64) -> Option<Analysis> {
+        self.entries.get(&key).copied()
+    }
+
+    /// 写入缓存
+    pub fn insert(&mut self, key: u64, analysis: Analysis) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.insert(key, analysis).is_none() {
+            self.order.push_back(key);
+        }
+        self.evict();
+    }
+
+    /// 修改容量，多出的旧结果会被淘汰
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// 已缓存的局面数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 清空缓存
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// 从文件读取缓存，文件不存在或者由其他版本的引擎写入时返回空缓存
+    pub fn load(path: &Path, capacity: usize) -> Result<Self> {
+        let mut cache = Self::new(capacity);
+        if !path.exists() {
+            return Ok(cache);
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let mut lines = text.lines();
+        // 引擎改动之后旧的结果可能不再是最佳的一步，整个丢弃，下次保存时覆盖
+        if lines.next() != Some(Self::header().as_str()) {
+            return Ok(cache);
+        }
+        for (i, line) in lines.enumerate() {
+            let (key, analysis) = Self::parse_entry(line)
+                .with_context(|| format!("{} line {}: invalid cache entry: {}", path.display(), i + 2, line))?;
+            cache.insert(key, analysis);
+        }
+        Ok(cache)
+    }
+
+    /// 解析缓存文件中的一行
+    fn parse_entry(line: &str) -> Result<(u64, Analysis)> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [key, x, y, score] = fields[..] else {
+            bail!("expected 4 fields, found {}", fields.len());
+        };
+        let analysis = Analysis {
+            best_move: Coord::new(x.parse()?, y.parse()?),
+            score: score.parse()?,
+        };
+        if !analysis.best_move.is_valid() {
+            bail!("move out of board");
+        }
+        Ok((key.parse()?, analysis))
+    }
+
+    /// 写入缓存文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut text = format!("{}\n", Self::header());
+        for key in &self.order {
+            let analysis = self.entries[key];
+            let mv = analysis.best_move;
+            text.push_str(&format!("{} {} {} {}\n", key, mv.col, mv.row, analysis.score));
+        }
+        fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// 当前引擎版本的文件头
+    fn header() -> String {
+        format!("{} {}", HEADER, ENGINE_VERSION)
+    }
+
+    /// 淘汰超出容量的旧结果
+    fn evict(&mut self) {
+        while self.order.len() > self.capacity {
+            if let Some(key) = self.order.pop_front() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discards_results_from_another_engine_version() {
+        let path = std::env::temp_dir().join(format!("gomoku_analysis_{}.cache", std::process::id()));
+        let analysis = Analysis { best_move: Coord::new(7, 8), score: 120 };
+        let mut cache = AnalysisCache::new(10);
+        cache.insert(42, analysis);
+        cache.save(&path).unwrap();
+        assert_eq!(AnalysisCache::load(&path, 10).unwrap().get(42), Some(analysis));
+
+        fs::write(&path, format!("{} {}\n42 7 8 120\n", HEADER, ENGINE_VERSION + 1)).unwrap();
+        assert_eq!(AnalysisCache::load(&path, 10).unwrap().len(), 0);
+        // 加入版本号之前写入的文件没有文件头
+        fs::write(&path, "42 7 8 120\n").unwrap();
+        assert_eq!(AnalysisCache::load(&path, 10).unwrap().len(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+}
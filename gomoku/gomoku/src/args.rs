@@ -0,0 +1,60 @@
+use std::str::FromStr;
+
+/// 命令行参数，只支持 `--flag` 开关和 `--option value` 两种形式
+pub struct Args(Vec<String>);
+
+impl Args {
+    /// 读取程序的命令行参数
+    pub fn from_env() -> Self {
+        Self(std::env::args().skip(1).collect())
+    }
+
+    /// 是否给出了 flag
+    pub fn has(&self, flag: &str) -> bool {
+        self.0.iter().any(|a| a == flag)
+    }
+
+    /// flag 后面的值，flag 后面紧跟另一个选项时视为没有值
+    pub fn value(&self, flag: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .skip_while(|a| *a != flag)
+            .nth(1)
+            .map(String::as_str)
+            .filter(|value| !value.starts_with("--"))
+    }
+
+    /// 解析 flag 后面的值，没有值或无法解析时返回 None
+    pub fn parse<T: FromStr>(&self, flag: &str) -> Option<T> {
+        self.value(flag).and_then(|value| value.parse().ok())
+    }
+
+    /// 按名称（不区分大小写）从 all 中选出 flag 后面的选项
+    pub fn choice<T: Copy>(&self, flag: &str, all: &[T], name: fn(&T) -> &'static str) -> Option<T> {
+        let value = self.value(flag)?;
+        all.iter().find(|item| name(item).eq_ignore_ascii_case(value)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Args {
+        Args(line.split_whitespace().map(String::from).collect())
+    }
+
+    #[test]
+    fn reads_flags_and_values() {
+        let args = args("--selfplay 20 --difficulty hard --cli");
+        assert!(args.has("--cli"));
+        assert_eq!(args.parse::<usize>("--selfplay"), Some(20));
+        assert_eq!(args.value("--difficulty"), Some("hard"));
+        assert_eq!(args.value("--cli"), None);
+        assert_eq!(args.value("--seed"), None);
+
+        let args = self::args("--soak --seed 7");
+        assert_eq!(args.value("--soak"), None);
+        assert_eq!(args.parse::<u64>("--seed"), Some(7));
+    }
+}
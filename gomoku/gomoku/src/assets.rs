@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 资源目录，每种资源放在其中的一个子目录里，例如 `assets/sounds`
+pub const ASSETS_DIR: &str = "assets";
+
+// 调试版检查资源文件有没有改动的间隔（毫秒）
+const RELOAD_INTERVAL: u64 = 1000;
+
+/// 界面用到的资源种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Sound,
+    Theme,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 2] = [AssetKind::Sound, AssetKind::Theme];
+
+    /// 资源目录中存放这种资源的子目录
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            AssetKind::Sound => "sounds",
+            AssetKind::Theme => "themes",
+        }
+    }
+
+    /// 默认的覆盖目录
+    pub fn default_dir(&self) -> PathBuf {
+        Path::new(ASSETS_DIR).join(self.dir_name())
+    }
+}
+
+/// 资源管理器：按种类和名字查找覆盖内置资源的文件
+///
+/// 每种资源的内置版本都编译在程序里（合成的音效、代码里的主题颜色等），找不到文件时
+/// 由调用方使用内置版本，所以资源目录可以不存在，网页版也总是使用内置资源。查找顺序是
+/// 玩家在设置里指定的目录、资源目录下对应的子目录。
+///
+/// 调试版定时检查这些目录，文件有改动时 `poll` 返回 true，界面重新读取资源，
+/// 调整音效和主题时不必重启程序。
+pub struct Assets {
+    root: PathBuf,
+    // 玩家为某种资源指定的目录，例如设置里选择的音效包
+    overrides: Vec<(AssetKind, PathBuf)>,
+    // 上次检查时各目录中文件的修改时间，按路径排序
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+    // 上次检查的时刻，以界面启动后的毫秒数计
+    checked: u64,
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Self::new(Path::new(ASSETS_DIR))
+    }
+}
+
+impl Assets {
+    /// 以 root 为资源目录
+    pub fn new(root: &Path) -> Self {
+        let mut assets = Self { root: root.to_path_buf(), overrides: Vec::new(), stamps: Vec::new(), checked: 0 };
+        assets.stamps = assets.scan();
+        assets
+    }
+
+    /// 为 kind 指定优先查找的目录，None 表示只用资源目录
+    pub fn set_override(&mut self, kind: AssetKind, dir: Option<PathBuf>) {
+        self.overrides.retain(|(k, _)| *k != kind);
+        if let Some(dir) = dir {
+            self.overrides.push((kind, dir));
+        }
+        self.stamps = self.scan();
+    }
+
+    /// 按查找顺序排列的 kind 的目录
+    pub fn dirs(&self, kind: AssetKind) -> Vec<PathBuf> {
+        let overrides = self.overrides.iter().filter(|(k, _)| *k == kind).map(|(_, dir)| dir.clone());
+        overrides.chain([self.root.join(kind.dir_name())]).collect()
+    }
+
+    /// 查找名为 name 的 kind 资源文件，扩展名按 extensions 的顺序尝试，都没有时返回 None
+    pub fn find(&self, kind: AssetKind, name: &str, extensions: &[&str]) -> Option<PathBuf> {
+        self.dirs(kind)
+            .into_iter()
+            .flat_map(|dir| extensions.iter().map(move |ext| dir.join(format!("{}.{}", name, ext))))
+            .find(|path| path.is_file())
+    }
+
+    /// 调试版中到了检查的时间并且资源文件有增删或改动时返回 true，now 为界面启动后的毫秒数
+    pub fn poll(&mut self, now: u64) -> bool {
+        if !cfg!(debug_assertions) || now < self.checked + RELOAD_INTERVAL {
+            return false;
+        }
+        self.checked = now;
+        let stamps = self.scan();
+        if stamps == self.stamps {
+            return false;
+        }
+        self.stamps = stamps;
+        true
+    }
+
+    /// 所有目录中的文件和修改时间，目录不存在时跳过
+    fn scan(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mut stamps: Vec<_> = AssetKind::ALL
+            .iter()
+            .flat_map(|&kind| self.dirs(kind))
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| (entry.path(), entry.metadata().and_then(|m| m.modified()).ok()))
+            .collect();
+        stamps.sort();
+        stamps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_take_precedence_and_changes_are_noticed() {
+        let root = std::env::temp_dir().join(format!("gomoku_assets_{}", std::process::id()));
+        let pack = root.join("pack");
+        fs::create_dir_all(root.join("sounds")).unwrap();
+        fs::create_dir_all(&pack).unwrap();
+        fs::write(root.join("sounds").join("win.wav"), b"").unwrap();
+        fs::write(root.join("sounds").join("menu.ogg"), b"").unwrap();
+        fs::write(pack.join("win.ogg"), b"").unwrap();
+
+        let mut assets = Assets::new(&root);
+        assert_eq!(assets.find(AssetKind::Sound, "win", &["wav", "ogg"]), Some(root.join("sounds").join("win.wav")));
+        assert_eq!(assets.find(AssetKind::Theme, "win", &["wav", "ogg"]), None);
+        assets.set_override(AssetKind::Sound, Some(pack.clone()));
+        assert_eq!(assets.find(AssetKind::Sound, "win", &["wav", "ogg"]), Some(pack.join("win.ogg")));
+        // 指定的目录里没有时仍然从资源目录找
+        assert_eq!(assets.find(AssetKind::Sound, "menu", &["wav", "ogg"]), Some(root.join("sounds").join("menu.ogg")));
+
+        assert!(!assets.poll(RELOAD_INTERVAL));
+        fs::write(pack.join("lose.wav"), b"").unwrap();
+        // 检查间隔之内不会去读目录
+        assert!(!assets.poll(RELOAD_INTERVAL + 1));
+        assert_eq!(assets.poll(RELOAD_INTERVAL * 2), cfg!(debug_assertions));
+        assert!(!assets.poll(RELOAD_INTERVAL * 3));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,103 +1,291 @@
-use rodio::{Decoder, OutputStream, Sink};
-use std::io::Cursor;
+use crate::assets::Assets;
+#[cfg(feature = "native")]
+use crate::assets::AssetKind;
+#[cfg(feature = "native")]
+use rodio::{buffer::SamplesBuffer, source::Buffered, Decoder, OutputStream, Sink, Source};
+#[cfg(feature = "native")]
+use std::{fs, io::Cursor};
+
+// 自定义音效支持的格式，按顺序查找
+#[cfg(feature = "native")]
+const ASSET_EXTENSIONS: [&str; 2] = ["wav", "ogg"];
+
+// 播放队列里已经有这么多段音效时不再加入落子音效，快速落子时不会积压一长串
+#[cfg(feature = "native")]
+const MAX_QUEUED_MOVES: usize = 2;
+
+/// 落子音效方案
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundProfile {
+    LowTone,
+    HighTone,
+    WoodClick,
+    GlassTap,
+}
+
+impl SoundProfile {
+    /// 所有可选的音效方案
+    pub const ALL: [SoundProfile; 4] = [
+        SoundProfile::LowTone,
+        SoundProfile::HighTone,
+        SoundProfile::WoodClick,
+        SoundProfile::GlassTap,
+    ];
+
+    /// 音效方案名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoundProfile::LowTone => "Low Tone",
+            SoundProfile::HighTone => "High Tone",
+            SoundProfile::WoodClick => "Wood Click",
+            SoundProfile::GlassTap => "Glass Tap",
+        }
+    }
+}
+
+/// 界面中播放的音效
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sound {
+    // 落子，按方案区分黑白双方
+    Move(SoundProfile),
+    // 玩家获胜，或者双人对战分出胜负
+    Win,
+    // 玩家输掉对局
+    Lose,
+    // 切换界面
+    Menu,
+    // 落子被拒绝：点在已有棋子上或者禁手
+    Reject,
+}
+
+/// 内置音效的采样率
+pub const SAMPLE_RATE: u32 = 44100;
+
+/// 解码后缓存的一段音效，复制时共用同一份采样
+#[cfg(feature = "native")]
+type Clip = Buffered<Box<dyn Source<Item = f32> + Send>>;
 
 /// 音频管理器
+///
+/// 没有音频设备时退化为不发声的实现，程序照常运行。所有音效在创建时生成或读取一次，
+/// 之后每次播放只复制缓存。资源管理器找到的 `move`、`win`、`lose`、`menu`、`reject`
+/// （WAV 或 OGG）会替换对应的内置音效。
+#[cfg(feature = "native")]
 pub struct AudioManager {
-    _stream: OutputStream,
-    sink: Sink,
+    // 输出流必须保持存活，否则 sink 不会发声；初始化失败时为 None
+    output: Option<(OutputStream, Sink)>,
+    moves: [Clip; SoundProfile::ALL.len()],
+    custom_move: Option<Clip>,
+    win: Clip,
+    lose: Clip,
+    menu: Clip,
+    reject: Clip,
 }
 
+#[cfg(feature = "native")]
 impl AudioManager {
-    /// 创建新的音频管理器
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let (_stream, stream_handle) = OutputStream::try_default()?;
-        let sink = Sink::try_new(&stream_handle)?;
-        
-        Ok(AudioManager {
-            _stream,
-            sink,
-        })
+    /// 创建音频管理器并读取自定义音效，打开音频设备失败时返回不发声的管理器
+    pub fn new(assets: &Assets) -> Self {
+        let output = match OutputStream::try_default() {
+            Ok((stream, handle)) => match Sink::try_new(&handle) {
+                Ok(sink) => Some((stream, sink)),
+                Err(e) => {
+                    eprintln!("Audio disabled: {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                eprintln!("Audio disabled: {}", e);
+                None
+            }
+        };
+        Self {
+            output,
+            moves: SoundProfile::ALL.map(|profile| clip(synthesize_move(profile))),
+            custom_move: load_asset(assets, "move"),
+            win: load_asset(assets, "win").unwrap_or_else(|| clip(win_jingle())),
+            lose: load_asset(assets, "lose").unwrap_or_else(|| clip(lose_jingle())),
+            menu: load_asset(assets, "menu").unwrap_or_else(|| clip(menu_click())),
+            reject: load_asset(assets, "reject").unwrap_or_else(|| clip(reject_buzz())),
+        }
     }
 
-    /// 播放黑棋落子音效
-    pub fn play_black_move(&self) {
-        // 生成一个较低频率的音效（黑棋）
-        let frequency = 220.0; // A3音符
-        let duration = 0.2; // 200ms
-        self.play_tone(frequency, duration, 0.3);
+    /// 重新查找自定义音效，找不到的音效恢复为内置音效
+    pub fn load_sounds(&mut self, assets: &Assets) {
+        self.custom_move = load_asset(assets, "move");
+        self.win = load_asset(assets, "win").unwrap_or_else(|| clip(win_jingle()));
+        self.lose = load_asset(assets, "lose").unwrap_or_else(|| clip(lose_jingle()));
+        self.menu = load_asset(assets, "menu").unwrap_or_else(|| clip(menu_click()));
+        self.reject = load_asset(assets, "reject").unwrap_or_else(|| clip(reject_buzz()));
     }
 
-    /// 播放白棋落子音效
-    pub fn play_white_move(&self) {
-        // 生成一个较高频率的音效（白棋）
-        let frequency = 440.0; // A4音符
-        let duration = 0.2; // 200ms
-        self.play_tone(frequency, duration, 0.3);
+    /// 是否有可用的音频设备
+    pub fn is_available(&self) -> bool {
+        self.output.is_some()
     }
 
-    /// 播放指定频率的音调
-    fn play_tone(&self, frequency: f32, duration: f32, volume: f32) {
-        // 生成正弦波音频数据
-        let sample_rate = 44100;
-        let samples = (sample_rate as f32 * duration) as usize;
-        let mut audio_data = Vec::new();
-        
-        for i in 0..samples {
-            let t = i as f32 / sample_rate as f32;
-            let sample = (2.0 * std::f32::consts::PI * frequency * t).sin() * volume;
-            // 转换为16位PCM
-            let pcm_sample = (sample * 32767.0) as i16;
-            audio_data.extend_from_slice(&pcm_sample.to_le_bytes());
+    /// 以 volume（0.0 到 1.0）的音量播放音效
+    pub fn play(&self, sound: Sound, volume: f32) {
+        let Some((_, sink)) = &self.output else {
+            return;
+        };
+        if volume <= 0.0 {
+            return;
         }
-        
-        // 创建WAV格式的音频数据
-        let wav_data = self.create_wav_data(&audio_data, sample_rate);
-        
-        // 播放音频
-        let cursor = Cursor::new(wav_data);
-        if let Ok(source) = Decoder::new(cursor) {
-            self.sink.append(source);
+        if matches!(sound, Sound::Move(_)) && sink.len() >= MAX_QUEUED_MOVES {
+            return;
         }
-    }
-
-    /// 创建WAV格式的音频数据
-    fn create_wav_data(&self, pcm_data: &[u8], sample_rate: u32) -> Vec<u8> {
-        let mut wav_data = Vec::new();
-        
-        // WAV文件头
-        // RIFF header
-        wav_data.extend_from_slice(b"RIFF");
-        let file_size = 36 + pcm_data.len() as u32;
-        wav_data.extend_from_slice(&file_size.to_le_bytes());
-        wav_data.extend_from_slice(b"WAVE");
-        
-        // fmt chunk
-        wav_data.extend_from_slice(b"fmt ");
-        wav_data.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
-        wav_data.extend_from_slice(&1u16.to_le_bytes());  // audio format (PCM)
-        wav_data.extend_from_slice(&1u16.to_le_bytes());  // number of channels
-        wav_data.extend_from_slice(&sample_rate.to_le_bytes()); // sample rate
-        let byte_rate = sample_rate * 2; // 16 bits = 2 bytes
-        wav_data.extend_from_slice(&byte_rate.to_le_bytes());
-        wav_data.extend_from_slice(&2u16.to_le_bytes());  // block align
-        wav_data.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
-        
-        // data chunk
-        wav_data.extend_from_slice(b"data");
-        wav_data.extend_from_slice(&(pcm_data.len() as u32).to_le_bytes());
-        wav_data.extend_from_slice(pcm_data);
-        
-        wav_data
+        let clip = match sound {
+            Sound::Move(profile) => self.custom_move.as_ref().unwrap_or(&self.moves[profile as usize]),
+            Sound::Win => &self.win,
+            Sound::Lose => &self.lose,
+            Sound::Menu => &self.menu,
+            Sound::Reject => &self.reject,
+        };
+        sink.append(clip.clone().amplify(volume));
     }
 }
 
 impl Default for AudioManager {
     fn default() -> Self {
-        Self::new().unwrap_or_else(|_| {
-            // 如果音频初始化失败，创建一个空的实现
-            // 这确保了即使在没有音频设备的情况下程序也能正常运行
-            panic!("Failed to initialize audio system");
-        })
+        Self::new(&Assets::default())
+    }
+}
+
+/// 没有 `native` 功能时（网页版）的音频管理器：接口相同，始终不发声
+#[cfg(not(feature = "native"))]
+pub struct AudioManager {}
+
+#[cfg(not(feature = "native"))]
+impl AudioManager {
+    pub fn new(_assets: &Assets) -> Self {
+        Self {}
+    }
+
+    pub fn load_sounds(&mut self, _assets: &Assets) {}
+
+    pub fn is_available(&self) -> bool {
+        false
     }
+
+    pub fn play(&self, _sound: Sound, _volume: f32) {}
 }
 
+/// 生成落子音效方案对应的音效，返回 `SAMPLE_RATE` 采样率、[-1.0, 1.0] 范围内的单声道采样
+pub fn synthesize_move(profile: SoundProfile) -> Vec<f32> {
+    match profile {
+        // A3音符，200ms
+        SoundProfile::LowTone => tone(220.0, 0.2, 0.3),
+        // A4音符，200ms
+        SoundProfile::HighTone => tone(440.0, 0.2, 0.3),
+        // 低频泛音快速衰减，听起来像木头敲击
+        SoundProfile::WoodClick => struck(&[(180.0, 1.0), (420.0, 0.5), (960.0, 0.25)], 40.0, 0.08, 0.5),
+        // 高频泛音缓慢衰减，听起来像玻璃轻敲
+        SoundProfile::GlassTap => struck(&[(1800.0, 1.0), (2700.0, 0.6), (4200.0, 0.3)], 18.0, 0.25, 0.2),
+    }
+}
+
+/// 内置的获胜音效：C 大调琶音上行
+pub fn win_jingle() -> Vec<f32> {
+    jingle(&[523.25, 659.25, 783.99, 1046.5])
+}
+
+/// 用两个音报出坐标：先是列，再是行号，A 列和第 1 行都是 C4，每往后一格升高一级 C 大调音阶
+///
+/// 十五个位置正好是从 C4 到 C6 的两个八度，天元的两个音都是中间的 C5，听的人以此为参照。
+pub fn coordinate_tones(col: usize, row_number: usize) -> Vec<f32> {
+    jingle(&[scale_note(col), scale_note(row_number.saturating_sub(1))])
+}
+
+/// C 大调音阶从 C4 开始的第 degree 个音的频率
+fn scale_note(degree: usize) -> f32 {
+    const STEPS: [usize; 7] = [0, 2, 4, 5, 7, 9, 11];
+    let semitones = degree / 7 * 12 + STEPS[degree % 7];
+    261.63 * 2f32.powf(semitones as f32 / 12.0)
+}
+
+/// 内置的失败音效：小三和弦下行
+#[cfg(feature = "native")]
+fn lose_jingle() -> Vec<f32> {
+    jingle(&[392.0, 311.13, 261.63])
+}
+
+/// 内置的界面切换音效：短促的高音
+#[cfg(feature = "native")]
+fn menu_click() -> Vec<f32> {
+    struck(&[(1200.0, 1.0), (2400.0, 0.3)], 60.0, 0.05, 0.2)
+}
+
+/// 内置的落子被拒绝音效：低沉短促的闷响
+#[cfg(feature = "native")]
+fn reject_buzz() -> Vec<f32> {
+    struck(&[(140.0, 1.0), (210.0, 0.6)], 30.0, 0.12, 0.4)
+}
+
+/// 指定频率的音调
+fn tone(frequency: f32, duration: f32, volume: f32) -> Vec<f32> {
+    let samples = (SAMPLE_RATE as f32 * duration) as usize;
+    (0..samples)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            (2.0 * std::f32::consts::PI * frequency * t).sin() * volume
+        })
+        .collect()
+}
+
+/// 敲击音：若干泛音叠加并按指数衰减
+fn struck(partials: &[(f32, f32)], decay: f32, duration: f32, volume: f32) -> Vec<f32> {
+    let total: f32 = partials.iter().map(|(_, amp)| amp).sum();
+    let samples = (SAMPLE_RATE as f32 * duration) as usize;
+    (0..samples)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let sum: f32 = partials
+                .iter()
+                .map(|(frequency, amp)| (2.0 * std::f32::consts::PI * frequency * t).sin() * amp)
+                .sum();
+            sum / total * (-decay * t).exp() * volume
+        })
+        .collect()
+}
+
+/// 依次奏出几个音符的短曲，每个音符轻微衰减
+fn jingle(notes: &[f32]) -> Vec<f32> {
+    const NOTE: f32 = 0.14;
+    let samples = (SAMPLE_RATE as f32 * NOTE) as usize;
+    notes
+        .iter()
+        .flat_map(|&frequency| {
+            (0..samples).map(move |i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                (2.0 * std::f32::consts::PI * frequency * t).sin() * (-6.0 * t).exp() * 0.3
+            })
+        })
+        .collect()
+}
+
+/// 把 [-1.0, 1.0] 范围内的单声道采样包装成可以反复播放的音效
+#[cfg(feature = "native")]
+fn clip(samples: Vec<f32>) -> Clip {
+    let source: Box<dyn Source<Item = f32> + Send> = Box::new(SamplesBuffer::new(1, SAMPLE_RATE, samples));
+    source.buffered()
+}
+
+/// 读取名为 name 的自定义音效，没有文件时返回 None，文件无法解码时提示并返回 None
+#[cfg(feature = "native")]
+fn load_asset(assets: &Assets, name: &str) -> Option<Clip> {
+    let path = assets.find(AssetKind::Sound, name, &ASSET_EXTENSIONS)?;
+    let decoded = fs::read(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|bytes| Decoder::new(Cursor::new(bytes)).map_err(|e| e.to_string()));
+    match decoded {
+        Ok(decoder) => {
+            let source: Box<dyn Source<Item = f32> + Send> = Box::new(decoder.convert_samples::<f32>());
+            Some(source.buffered())
+        }
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", path.display(), e);
+            None
+        }
+    }
+}
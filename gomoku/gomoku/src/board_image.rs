@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use eframe::egui::pos2;
+use gomoku_core::{Board, Coord, CoordOrigin, Stone, BOARD_SIZE};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::geometry::BoardGeometry;
+use crate::theme::{hex, StoneStyle, Theme};
+
+// 导出图片的格距，四周各留一格放坐标
+const SPACING: f32 = 30.0;
+
+/// 把局面画成 SVG 图片，和界面上的棋盘一样按主题的颜色画出坐标，last 为最后一步，用小圆点标出
+pub fn svg(board: &Board, last: Option<Coord>, origin: CoordOrigin, theme: &Theme) -> String {
+    let geometry = BoardGeometry::new(pos2(SPACING, SPACING), SPACING);
+    let size = (BOARD_SIZE + 1) as f32 * SPACING;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n\
+         <rect width=\"{size}\" height=\"{size}\" fill=\"{}\"/>\n",
+        hex(theme.background)
+    );
+    // 写入 String 不会失败
+    if theme.stone_style == StoneStyle::Shaded {
+        // 立体棋子用从左上角高光到填充色的径向渐变
+        svg.push_str("<defs>\n");
+        for (id, fill, highlight) in [("black", theme.black_fill, theme.black_highlight), ("white", theme.white_fill, theme.white_highlight)] {
+            let _ = writeln!(
+                svg,
+                "<radialGradient id=\"{}\" cx=\"0.35\" cy=\"0.35\" r=\"0.65\">\
+                 <stop offset=\"0\" stop-color=\"{}\"/><stop offset=\"1\" stop-color=\"{}\"/></radialGradient>",
+                id,
+                hex(highlight),
+                hex(fill)
+            );
+        }
+        svg.push_str("</defs>\n");
+    }
+    for [a, b] in geometry.grid_lines() {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            a.x,
+            a.y,
+            b.x,
+            b.y,
+            hex(theme.grid),
+            theme.grid_width
+        );
+    }
+    for i in 0..BOARD_SIZE {
+        let letter = (b'A' + i as u8) as char;
+        let number = origin.row_number(i);
+        for (pos, label) in [(geometry.col_label_at(i), letter.to_string()), (geometry.row_label_at(i), number.to_string())] {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"12\" fill=\"{}\" \
+                 text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                pos.x,
+                pos.y,
+                hex(theme.labels),
+                label
+            );
+        }
+    }
+    for coord in Coord::all() {
+        let (id, fill, stroke) = match board.get(coord) {
+            Stone::Black => ("black", theme.black_fill, theme.black_edge),
+            Stone::White => ("white", theme.white_fill, theme.white_edge),
+            Stone::Empty => continue,
+        };
+        let fill = match theme.stone_style {
+            StoneStyle::Flat => hex(fill),
+            StoneStyle::Shaded => format!("url(#{})", id),
+        };
+        let center = geometry.pixel_at(coord);
+        let _ = writeln!(
+            svg,
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"{}\"/>",
+            center.x,
+            center.y,
+            geometry.stone_radius(),
+            fill,
+            hex(stroke)
+        );
+    }
+    if let Some(coord) = last {
+        let center = geometry.pixel_at(coord);
+        let radius = geometry.stone_radius() * theme.last_move_ratio;
+        let _ = writeln!(svg, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>", center.x, center.y, radius, hex(theme.last_move));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// 把局面保存为 SVG 图片
+pub fn save_svg(board: &Board, last: Option<Coord>, origin: CoordOrigin, theme: &Theme, path: &Path) -> Result<()> {
+    fs::write(path, svg(board, last, origin, theme)).with_context(|| format!("failed to write {}", path.display()))
+}
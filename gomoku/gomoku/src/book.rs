@@ -0,0 +1,20 @@
+use anyhow::{Context, Result};
+use gomoku_core::OpeningBook;
+use std::fs;
+use std::path::Path;
+
+/// 玩家自己的开局库文件，和内置开局库合在一起使用
+pub const BOOK_PATH: &str = "gomoku_openings.txt";
+
+/// 内置开局库加上 path 中的开局线，文件不存在时只有内置开局库
+///
+/// 文件格式与内置开局库相同，见 `gomoku_core::opening_book`。
+pub fn load(path: &Path) -> Result<OpeningBook> {
+    let mut book = OpeningBook::builtin();
+    if !path.exists() {
+        return Ok(book);
+    }
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    book.extend(&text).with_context(|| format!("invalid opening book {}", path.display()))?;
+    Ok(book)
+}
@@ -0,0 +1,270 @@
+use gomoku_core::win_rate;
+use gomoku_core::{
+    Ai, Coord, CoordOrigin, Difficulty, Evaluation, FirstMove, GameState, Outcome, RuleSet, Rules, SearchStats, Stone,
+    BOARD_SIZE,
+};
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+
+use crate::args::Args;
+use crate::rng::{self, XorShift};
+
+/// 默认的自对弈局数
+const DEFAULT_GAMES: usize = 100;
+
+// 自对弈时开局随机落下的步数，两个相同的 AI 否则每局都一模一样
+const RANDOM_OPENING_PLIES: usize = 2;
+
+// 拟合胜率模型时尝试的尺度范围和步长
+const SCALE_RANGE: std::ops::RangeInclusive<i32> = 100..=20_000;
+const SCALE_STEP: usize = 100;
+
+/// 命令行对局和自对弈共用的选项：`--rules freestyle|renju`、`--length N` 和 `--difficulty easy|medium|hard`
+fn game_options(args: &Args) -> (Rules, Difficulty) {
+    let set = args.choice("--rules", &RuleSet::ALL, RuleSet::name).unwrap_or_default();
+    let length = args
+        .parse("--length")
+        .filter(|length| Rules::WIN_LENGTHS.contains(length))
+        .unwrap_or(Rules::DEFAULT_WIN_LENGTH);
+    let difficulty = args.choice("--difficulty", &Difficulty::ALL, Difficulty::name).unwrap_or_default();
+    (Rules::new(set, length), difficulty)
+}
+
+/// 在终端里和 AI 下一局，输入 `H8` 这样的坐标落子
+///
+/// 用法：`gomoku --cli [--white] [--rules renju] [--length N] [--difficulty hard]`，默认玩家执黑。
+pub fn play(args: &Args) {
+    let (rules, difficulty) = game_options(args);
+    let player = if args.has("--white") { Stone::White } else { Stone::Black };
+    let ai = Ai::new(player.opponent(), difficulty).with_rules(rules);
+    let mut game = GameState::with_rules(rules);
+    println!("{} vs AI ({}), you play {}", rules, difficulty.name(), player.name());
+    println!("Enter moves like H8, or 'undo' / 'quit'.");
+
+    let mut input = io::stdin().lock();
+    while !game.is_over() {
+        if game.to_move() == ai.stone() {
+            let mv = ai.best_move(game.board()).best_move;
+            // AI 不会选择禁手或已有棋子的点，失败说明局面出了问题
+            if let Err(e) = game.play(mv) {
+                println!("AI could not play {}: {}", mv, e);
+                return;
+            }
+            println!("AI plays {}", mv);
+            continue;
+        }
+
+        print_board(&game);
+        print!("{} to move> ", player.name());
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        if input.read_line(&mut line).unwrap_or(0) == 0 {
+            return;
+        }
+        match line.trim().to_ascii_lowercase().as_str() {
+            "" => {}
+            "quit" | "exit" => return,
+            // 连同 AI 的回应一起撤销，回到玩家的回合
+            "undo" => {
+                game.undo();
+                if game.to_move() != player {
+                    game.undo();
+                }
+            }
+            text => match Coord::parse(text, CoordOrigin::default()) {
+                Some(mv) => {
+                    if let Err(e) = game.play(mv) {
+                        println!("Cannot play {}: {}", mv, e);
+                    }
+                }
+                None => println!("Cannot read '{}', enter a coordinate like H8", text),
+            },
+        }
+    }
+
+    print_board(&game);
+    match game.outcome() {
+        Some(Outcome::Draw) => println!("Draw."),
+        Some(outcome) if outcome.winner() == Some(player) => println!("You win!"),
+        _ => println!("AI wins."),
+    }
+}
+
+/// 打印棋盘，`X` 为黑子、`O` 为白子，最后一手用括号标出
+fn print_board(game: &GameState) {
+    let origin = CoordOrigin::default();
+    let letters: String = (0..BOARD_SIZE).map(|col| format!(" {}", (b'A' + col as u8) as char)).collect();
+    println!("   {}", letters);
+    for row in 0..BOARD_SIZE {
+        let mut line = format!("{:>2} ", origin.row_number(row));
+        let mut after_last = false;
+        for col in 0..BOARD_SIZE {
+            let coord = Coord::new(col, row);
+            let is_last = game.last_move() == Some(coord);
+            line.push(if is_last { '(' } else if after_last { ')' } else { ' ' });
+            line.push(match game.board().get(coord) {
+                Stone::Black => 'X',
+                Stone::White => 'O',
+                Stone::Empty => '.',
+            });
+            after_last = is_last;
+        }
+        if after_last {
+            line.push(')');
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+/// 不打开窗口让两个 AI 对下 N 局，打印胜率、平均步数、搜索的局面数和置换表命中率
+///
+/// 用法：`gomoku --selfplay [N] [--rules renju] [--length N] [--difficulty hard] [--seed N]`。
+/// 前两步在天元附近随机落子，同一个种子得到同样的结果。
+pub fn selfplay(args: &Args) {
+    let games = args.parse("--selfplay").unwrap_or(DEFAULT_GAMES);
+    let seed = args.parse("--seed").unwrap_or_else(rng::time_seed);
+    let (rules, difficulty) = game_options(args);
+    println!("selfplay: {} games, {}, {}, seed {}", games, rules, difficulty.name(), seed);
+
+    let started = Instant::now();
+    let mut rng = XorShift::new(seed);
+    let (mut black, mut white, mut draws) = (0, 0, 0);
+    let (mut moves, mut searched) = (0, 0);
+    let mut stats = SearchStats::default();
+    for _ in 0..games {
+        let mut game = GameState::with_rules(rules);
+        while !game.is_over() {
+            let mv = if game.moves().len() < RANDOM_OPENING_PLIES {
+                random_near_center(&game, &mut rng)
+            } else {
+                let ai = Ai::new(game.to_move(), difficulty).with_rules(rules);
+                let (analysis, search) = ai.best_move_with_stats(game.board());
+                searched += 1;
+                stats += search;
+                analysis.best_move
+            };
+            if game.play(mv).is_err() {
+                break;
+            }
+        }
+        moves += game.moves().len();
+        match game.winner() {
+            Some(Stone::Black) => black += 1,
+            Some(_) => white += 1,
+            None => draws += 1,
+        }
+    }
+
+    let percent = |count: usize| 100.0 * count as f64 / games.max(1) as f64;
+    println!(
+        "black {} ({:.1}%) / white {} ({:.1}%) / draw {} ({:.1}%)",
+        black,
+        percent(black),
+        white,
+        percent(white),
+        draws,
+        percent(draws)
+    );
+    println!(
+        "average length {:.1} moves, {} nodes searched ({:.0} per move, {:.1}% table hits) in {:.1?}",
+        moves as f64 / games.max(1) as f64,
+        stats.nodes,
+        stats.nodes as f64 / searched.max(1) as f64,
+        stats.hit_permille() as f64 / 10.0,
+        started.elapsed()
+    );
+}
+
+/// 在自对弈数据上拟合胜率模型的尺度 `win_rate::SCALE`，打印拟合结果以及分段的预测胜率和实际得分率
+///
+/// 用法：`gomoku --calibrate [N] [--rules renju] [--length N] [--difficulty hard] [--seed N]`。
+/// 开局和 `--selfplay` 一样随机落子，之后按难度的开局随机化变化。AI 每次落子时按 `Evaluation`
+/// 记下走棋一方的得分，终局后配上这一方的结果；搜索到强制胜负的局面是确定的，不参与拟合。
+pub fn calibrate(args: &Args) {
+    let games = args.parse("--calibrate").unwrap_or(DEFAULT_GAMES);
+    let seed = args.parse("--seed").unwrap_or_else(rng::time_seed);
+    let (rules, difficulty) = game_options(args);
+    println!("calibrate: {} games, {}, {}, seed {}", games, rules, difficulty.name(), seed);
+
+    let mut rng = XorShift::new(seed);
+    // (得分, 结果)，结果以半局为单位：胜 2、和 1、负 0
+    let mut samples: Vec<(i32, u32)> = Vec::new();
+    for _ in 0..games {
+        let mut game = GameState::with_rules(rules);
+        let mut positions = Vec::new();
+        while !game.is_over() {
+            let ply = game.moves().len();
+            let mv = if ply < RANDOM_OPENING_PLIES {
+                random_near_center(&game, &mut rng)
+            } else {
+                let stone = game.to_move();
+                let ai = Ai::new(stone, difficulty).with_rules(rules);
+                let analysis = ai.best_move(game.board());
+                if !analysis.is_win() && !analysis.is_loss() {
+                    positions.push((stone, Evaluation::new(ply + 1, game.board(), &ai, analysis).score));
+                }
+                ai.vary_opening(game.board(), ply, analysis, rng.next()).best_move
+            };
+            if game.play(mv).is_err() {
+                break;
+            }
+        }
+        let winner = game.winner();
+        samples.extend(positions.into_iter().map(|(stone, score)| match winner {
+            Some(w) if w == stone => (score, 2),
+            Some(_) => (score, 0),
+            None => (score, 1),
+        }));
+    }
+
+    // 平均对数损失，越小说明预测的胜率越接近实际结果
+    let log_loss = |scale: i32| {
+        let total: f64 = samples
+            .iter()
+            .map(|&(score, result)| {
+                let p = (win_rate::win_permille_with(score, scale) as f64 / 1000.0).clamp(0.001, 0.999);
+                let y = result as f64 / 2.0;
+                -(y * p.ln() + (1.0 - y) * (1.0 - p).ln())
+            })
+            .sum();
+        total / samples.len().max(1) as f64
+    };
+    let best = SCALE_RANGE.step_by(SCALE_STEP).min_by(|&a, &b| log_loss(a).total_cmp(&log_loss(b))).unwrap_or(win_rate::SCALE);
+    println!(
+        "{} positions: best scale {} (log loss {:.4}), current scale {} (log loss {:.4})",
+        samples.len(),
+        best,
+        log_loss(best),
+        win_rate::SCALE,
+        log_loss(win_rate::SCALE)
+    );
+    if best == *SCALE_RANGE.end() {
+        println!("the flattest curve fits best: scores barely predict the results of these games");
+    }
+
+    // 按拟合的尺度预测的胜率分成十段，对比每段的实际得分率
+    println!("predicted  positions  actual");
+    for bucket in 0..10 {
+        let in_bucket: Vec<u32> = samples
+            .iter()
+            .filter(|&&(score, _)| (win_rate::win_permille_with(score, best) / 100).min(9) == bucket)
+            .map(|&(_, result)| result)
+            .collect();
+        if in_bucket.is_empty() {
+            continue;
+        }
+        let actual = in_bucket.iter().sum::<u32>() as f64 * 50.0 / in_bucket.len() as f64;
+        println!("{:>3}-{:>3}%  {:>9}  {:>5.1}%", bucket * 10, bucket * 10 + 10, in_bucket.len(), actual);
+    }
+}
+
+/// 按 AI 第一步的 `FirstMove::NearCenter` 策略在天元附近随机选一个空点，不会是禁手
+fn random_near_center(game: &GameState, rng: &mut XorShift) -> Coord {
+    loop {
+        let coord = FirstMove::NearCenter.choose(None, rng.next());
+        let forbidden = game.rules().forbidden(game.board(), coord, game.to_move()).is_some();
+        if game.board().is_empty_at(coord) && !forbidden {
+            return coord;
+        }
+    }
+}
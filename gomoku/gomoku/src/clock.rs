@@ -0,0 +1,131 @@
+use gomoku_core::Stone;
+
+use crate::time_control::TimeControl;
+
+// 剩余时间不超过基础用时的几分之一、同时不超过上限（毫秒）时，分别提示时间不多和即将超时
+const LOW_DIVISOR: u64 = 4;
+const LOW_CAP: u64 = 60_000;
+const CRITICAL_DIVISOR: u64 = 12;
+const CRITICAL_CAP: u64 = 10_000;
+
+/// 剩余时间的紧张程度，决定棋钟显示的颜色
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeState {
+    Normal,
+    Low,
+    Critical,
+}
+
+/// 对局双方的棋钟
+///
+/// 只有轮到的一方在走时。AI 的思考时间发生在它的回合里，同样计入它的用时。
+/// 时间按整毫秒计算，同样的走时记录在任何平台上都得到相同的结果。
+pub struct GameClock {
+    control: TimeControl,
+    // 黑白双方剩余的时间（毫秒）
+    black: u64,
+    white: u64,
+}
+
+impl GameClock {
+    /// 按时间控制预设创建棋钟，不限时时返回 None
+    pub fn new(control: TimeControl) -> Option<Self> {
+        let base = control.base_secs()? * 1000;
+        Some(Self { control, black: base, white: base })
+    }
+
+    /// stone 方剩余的时间（毫秒）
+    pub fn remaining(&self, stone: Stone) -> u64 {
+        if stone == Stone::Black { self.black } else { self.white }
+    }
+
+    /// 轮到 stone 方时经过了 elapsed 毫秒，时间用完时返回 true
+    pub fn tick(&mut self, stone: Stone, elapsed: u64) -> bool {
+        let remaining = self.remaining_mut(stone);
+        *remaining = remaining.saturating_sub(elapsed);
+        *remaining == 0
+    }
+
+    /// stone 方落子之后加秒，按步计时的预设重新给满时间
+    pub fn finish_move(&mut self, stone: Stone) {
+        let control = self.control;
+        let remaining = self.remaining_mut(stone);
+        if control.is_per_move() {
+            *remaining = control.base_secs().unwrap_or(0) * 1000;
+        } else {
+            *remaining += control.increment_secs() * 1000;
+        }
+    }
+
+    /// 赛事裁判给 stone 方加上 delta 毫秒，负数为扣时，最少扣到 0
+    pub fn adjust(&mut self, stone: Stone, delta: i64) {
+        let remaining = self.remaining_mut(stone);
+        *remaining = remaining.saturating_add_signed(delta);
+    }
+
+    /// stone 方剩余时间的紧张程度，门槛按时间控制的基础用时缩放，超快棋不会一开局就告急
+    pub fn state(&self, stone: Stone) -> TimeState {
+        let base = self.control.base_secs().unwrap_or(0) * 1000;
+        let remaining = self.remaining(stone);
+        if remaining <= (base / CRITICAL_DIVISOR).min(CRITICAL_CAP) {
+            TimeState::Critical
+        } else if remaining <= (base / LOW_DIVISOR).min(LOW_CAP) {
+            TimeState::Low
+        } else {
+            TimeState::Normal
+        }
+    }
+
+    fn remaining_mut(&mut self, stone: Stone) -> &mut u64 {
+        if stone == Stone::Black { &mut self.black } else { &mut self.white }
+    }
+}
+
+/// 剩余 millis 毫秒时，`format_time` 显示的秒数还有多久变化
+pub fn until_next_second(millis: u64) -> u64 {
+    match millis % 1000 {
+        0 => 1000,
+        rest => rest,
+    }
+}
+
+/// 把毫秒数显示为 `m:ss`，超过一小时时显示为 `h:mm:ss`
+pub fn format_time(millis: u64) -> String {
+    // 向上取整到秒，显示 0:00 时时间正好用完
+    let secs = millis.div_ceil(1000);
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warns_relative_to_the_time_control() {
+        let mut clock = GameClock::new(TimeControl::Bullet).unwrap();
+        assert_eq!(clock.state(Stone::Black), TimeState::Normal);
+        // 一分钟的超快棋在剩 15 秒和 5 秒时告急
+        clock.tick(Stone::Black, 45_000);
+        assert_eq!(clock.state(Stone::Black), TimeState::Low);
+        clock.tick(Stone::Black, 10_000);
+        assert_eq!(clock.state(Stone::Black), TimeState::Critical);
+        assert_eq!(clock.state(Stone::White), TimeState::Normal);
+        clock.adjust(Stone::White, -50_000);
+        assert_eq!(clock.remaining(Stone::White), 10_000);
+        clock.adjust(Stone::White, -50_000);
+        assert_eq!(clock.remaining(Stone::White), 0);
+        clock.adjust(Stone::White, 30_000);
+        assert_eq!(clock.remaining(Stone::White), 30_000);
+
+        // 显示的秒数在剩余时间跨过整秒时变化
+        assert_eq!(format_time(4_001), "0:05");
+        assert_eq!(until_next_second(4_001), 1);
+        assert_eq!(until_next_second(4_000), 1000);
+        assert_eq!(format_time(3_000), "0:03");
+    }
+}
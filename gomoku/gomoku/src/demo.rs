@@ -0,0 +1,99 @@
+use crate::rng;
+use crate::spectator::{AiConfig, Spectator};
+use gomoku_core::{Difficulty, GameState, Weights};
+
+// 主菜单闲置多久之后开始演示（毫秒）
+const IDLE_DELAY: u64 = 4000;
+
+// 演示对局每步之间的间隔（秒），比观战模式的默认值慢
+const MOVE_DELAY: f32 = 1.5;
+
+// 一局结束后停留几步的时间再开始下一局
+const FINISHED_PAUSE_MOVES: u32 = 3;
+
+/// 主菜单背景里的演示对局：闲置一会儿之后两个 AI 慢慢对下，用户一有操作就暂停
+///
+/// 落子节奏和 AI 都沿用观战模式的 `Spectator`，只是对局不影响正在进行的棋局。
+pub struct Demo {
+    game: GameState,
+    spectator: Spectator,
+    // 距上次用户操作经过的时间（毫秒）
+    idle: u64,
+    // 对局结束后已经停留的步数
+    finished_for: u32,
+}
+
+impl Default for Demo {
+    fn default() -> Self {
+        // 演示用较弱的 AI，走得快，开局也更有变化
+        let config = AiConfig { difficulty: Difficulty::Easy, weights: Weights::default() };
+        let mut spectator = Spectator::default();
+        spectator.black = config;
+        spectator.white = config;
+        spectator.delay = MOVE_DELAY;
+        spectator.started = true;
+        Self { game: GameState::new(), spectator, idle: 0, finished_for: 0 }
+    }
+}
+
+impl Demo {
+    /// 演示对局当前的局面
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+
+    /// 是否正在演示；暂停时背景里仍然显示停下时的局面
+    pub fn is_running(&self) -> bool {
+        self.idle >= IDLE_DELAY
+    }
+
+    /// 用户有操作时立即暂停，重新计算闲置时间
+    pub fn interrupt(&mut self) {
+        self.idle = 0;
+        self.spectator.reset_timer();
+    }
+
+    /// 经过 delta_millis 毫秒，闲置足够久时按观战模式的节奏走下一步
+    pub fn tick(&mut self, delta_millis: u64) {
+        self.idle = self.idle.saturating_add(delta_millis);
+        if !self.is_running() || !self.spectator.tick(delta_millis as f32 / 1000.0) {
+            return;
+        }
+        if self.game.is_over() {
+            self.finished_for += 1;
+            if self.finished_for >= FINISHED_PAUSE_MOVES {
+                self.game = GameState::new();
+                self.finished_for = 0;
+            }
+            return;
+        }
+        // 低难度的开局随机化让每一局演示都不一样
+        let stone = self.game.to_move();
+        let board = self.game.board();
+        let ai = self.spectator.ai(stone, self.game.rules());
+        let pick = rng::XorShift::new(rng::time_seed()).next();
+        let analysis = ai.vary_opening(board, self.game.moves().len(), ai.best_move(board), pick);
+        let _ = self.game.play(analysis.best_move);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_only_after_the_menu_is_idle() {
+        let mut demo = Demo::default();
+        demo.tick(IDLE_DELAY - 1);
+        assert!(!demo.is_running());
+        demo.tick(2000);
+        assert!(demo.is_running());
+        assert_eq!(demo.game().moves().len(), 1);
+
+        // 用户一有操作就停下，局面保留
+        demo.interrupt();
+        demo.tick(2000);
+        assert!(!demo.is_running());
+        assert_eq!(demo.game().moves().len(), 1);
+    }
+}
@@ -0,0 +1,179 @@
+use gomoku_core::{Ai, Board, Coord, Difficulty, Rules, Stone};
+use std::env;
+use std::fs;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::engine::RemoteSearch;
+
+// 测试连接对手地址的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 一项检查的结果
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    pub fn new(name: impl Into<String>, passed: bool, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), passed, detail: detail.into() }
+    }
+}
+
+/// 后台检查需要的信息，从界面的设置中复制
+pub struct Targets {
+    // 程序读写的数据文件
+    pub files: Vec<PathBuf>,
+    // 联机时主机监听的端口
+    pub host_port: u16,
+    // 联机时加入的对手地址（host:port），为空时不检查
+    pub remote: String,
+    // 外部引擎，为空时不检查
+    pub engine: String,
+}
+
+/// 诊断界面上的所有检查
+///
+/// 音频设备和渲染器只能在界面线程中查询，由调用方先检查好传入；文件、网络和引擎的检查
+/// 可能要等几秒，在后台线程中逐项进行，界面每帧调用 `poll` 取回已经完成的结果。
+pub struct Diagnostics {
+    pub checks: Vec<Check>,
+    receiver: Option<Receiver<Check>>,
+}
+
+impl Diagnostics {
+    /// 从已经完成的 checks 开始，在后台检查 targets
+    pub fn start(checks: Vec<Check>, targets: Targets) -> Self {
+        let (tx, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(check_working_dir());
+            for path in &targets.files {
+                let _ = tx.send(check_file(path));
+            }
+            let _ = tx.send(check_host_port(targets.host_port));
+            if !targets.remote.trim().is_empty() {
+                let _ = tx.send(check_remote(targets.remote.trim()));
+            }
+            let _ = tx.send(check_builtin_engine());
+            if !targets.engine.trim().is_empty() {
+                let _ = tx.send(check_external_engine(targets.engine.trim()));
+            }
+        });
+        Self { checks, receiver: Some(receiver) }
+    }
+
+    /// 取回已经完成的检查，仍有检查在进行时返回 true
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        loop {
+            match receiver.try_recv() {
+                Ok(check) => self.checks.push(check),
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        self.receiver = None;
+        false
+    }
+
+    /// 所有检查结果的文字报告，可以复制给支持人员
+    pub fn report(&self) -> String {
+        let mut text = format!(
+            "Gomoku {} on {} {}\n",
+            env!("CARGO_PKG_VERSION"),
+            env::consts::OS,
+            env::consts::ARCH
+        );
+        for check in &self.checks {
+            let status = if check.passed { "OK" } else { "FAIL" };
+            text.push_str(&format!("[{}] {}: {}\n", status, check.name, check.detail));
+        }
+        text
+    }
+}
+
+/// 工作目录能否写入，所有数据文件都保存在这里
+pub fn check_working_dir() -> Check {
+    let dir = match env::current_dir() {
+        Ok(dir) => dir,
+        Err(e) => return Check::new("Data folder", false, e.to_string()),
+    };
+    let probe = dir.join(".gomoku_write_test");
+    let result = fs::write(&probe, b"").and_then(|_| fs::remove_file(&probe));
+    match result {
+        Ok(()) => Check::new("Data folder", true, format!("{} (writable)", dir.display())),
+        Err(e) => Check::new("Data folder", false, format!("{} (not writable: {})", dir.display(), e)),
+    }
+}
+
+/// 数据文件是否存在、能否写入；不存在的文件会在第一次保存时创建
+fn check_file(path: &Path) -> Check {
+    let name = path.display().to_string();
+    match fs::metadata(path) {
+        Ok(meta) if meta.permissions().readonly() => Check::new(name, false, "read-only"),
+        Ok(meta) => Check::new(name, true, format!("{} bytes", meta.len())),
+        Err(_) => Check::new(name, true, "not created yet"),
+    }
+}
+
+/// 能否在联机端口上等待对手
+fn check_host_port(port: u16) -> Check {
+    let name = format!("Host on port {}", port);
+    match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(_) => Check::new(name, true, "port is free"),
+        Err(e) => Check::new(name, false, e.to_string()),
+    }
+}
+
+/// 能否连上联机对手的地址，对方没有在等待时连接会被拒绝
+fn check_remote(address: &str) -> Check {
+    let name = format!("Reach {}", address);
+    let Some(addr) = address.to_socket_addrs().ok().and_then(|mut addrs| addrs.next()) else {
+        return Check::new(name, false, "cannot resolve address");
+    };
+    let started = Instant::now();
+    match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+        Ok(_) => Check::new(name, true, format!("connected in {} ms", started.elapsed().as_millis())),
+        Err(e) => Check::new(name, false, e.to_string()),
+    }
+}
+
+/// 内置 AI 自检：双方都有四连，轮到黑方时必须连成五子而不是去堵
+fn check_builtin_engine() -> Check {
+    let mut board = Board::new();
+    for col in 5..9 {
+        board.set(Coord::new(col, 7), Stone::Black);
+        board.set(Coord::new(col, 9), Stone::White);
+    }
+    let started = Instant::now();
+    let ai = Ai::new(Stone::Black, Difficulty::Hard).with_rules(Rules::default());
+    let mv = ai.best_move(&board).best_move;
+    let elapsed = started.elapsed().as_millis();
+    if mv == Coord::new(4, 7) || mv == Coord::new(9, 7) {
+        Check::new("Built-in AI", true, format!("found the winning move in {} ms", elapsed))
+    } else {
+        Check::new("Built-in AI", false, format!("played {} instead of completing five", mv))
+    }
+}
+
+/// 外部引擎自检：在空棋盘上要一步棋
+fn check_external_engine(engine: &str) -> Check {
+    let started = Instant::now();
+    match RemoteSearch::start(engine, Rules::default(), &[], Stone::Black).wait() {
+        Ok(mv) if mv.is_valid() => Check::new(
+            "External engine",
+            true,
+            format!("played {} in {} ms", mv, started.elapsed().as_millis()),
+        ),
+        Ok(mv) => Check::new("External engine", false, format!("played {} off the board", mv)),
+        Err(e) => Check::new("External engine", false, format!("{:#}", e)),
+    }
+}
@@ -0,0 +1,52 @@
+#[cfg(feature = "native")]
+use crate::save::SAVE_EXTENSION;
+use std::path::{Path, PathBuf};
+
+/// 是否可以弹出文件对话框
+///
+/// 网页版没有本地文件系统，界面据此隐藏保存、打开文件和选择音效目录的按钮，
+/// 下面的函数在网页版中总是返回 None。
+pub const AVAILABLE: bool = cfg!(feature = "native");
+
+/// 选择保存对局的文件，可以是 SGF 棋谱、存档、棋盘图片或解说复盘
+#[cfg(feature = "native")]
+pub fn save_game() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("SGF", &["sgf"])
+        .add_filter("Gomoku Save", &[SAVE_EXTENSION])
+        .add_filter("Board Image", &["svg"])
+        .add_filter("Narrated Replay", &["wav"])
+        .set_file_name("gomoku.sgf")
+        .save_file()
+}
+
+/// 选择要打开的 SGF 棋谱或存档
+#[cfg(feature = "native")]
+pub fn open_game() -> Option<PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("Game Records", &["sgf", SAVE_EXTENSION])
+        .add_filter("SGF", &["sgf"])
+        .add_filter("Gomoku Save", &[SAVE_EXTENSION])
+        .pick_file()
+}
+
+/// 从 start 开始选择自定义音效所在的目录
+#[cfg(feature = "native")]
+pub fn sound_folder(start: &Path) -> Option<PathBuf> {
+    rfd::FileDialog::new().set_title("Choose Sound Pack Folder").set_directory(start).pick_folder()
+}
+
+#[cfg(not(feature = "native"))]
+pub fn save_game() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(feature = "native"))]
+pub fn open_game() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(not(feature = "native"))]
+pub fn sound_folder(_start: &Path) -> Option<PathBuf> {
+    None
+}
@@ -0,0 +1,161 @@
+use gomoku_core::protocol::Message;
+use gomoku_core::Coord;
+use std::cmp::Ordering;
+
+use crate::puzzle::{PuzzleSession, FULL_POINTS, PUZZLES};
+use crate::rng::XorShift;
+
+/// 一场对战的题目数
+pub const ROUNDS: usize = 10;
+
+/// 一场对战的限时（毫秒）
+pub const TIME_LIMIT: u64 = 180_000;
+
+/// 联机解题对战：双方按同样的顺序做同一组题，限时内得分高的一方获胜
+///
+/// 主机选定种子并发出 `DUEL`，加入的一方收到之后才开始。每做完一题（解出或跳过）
+/// 就把总分发给对方，任何一方做完所有题目或者时间用完时对战结束。
+pub struct Duel {
+    pub is_host: bool,
+    // 题目在题库中的编号，加入的一方收到种子之前为空
+    pub order: Vec<usize>,
+    // 本方正在做的是第几题，等于 order.len() 时已经做完
+    pub round: usize,
+    // 当前题目的棋盘、提示和本方总分
+    pub puzzles: PuzzleSession,
+    // 对方做完的题数和总分
+    pub opponent_rounds: usize,
+    pub opponent_score: u32,
+    // 剩余时间（毫秒）
+    pub remaining: u64,
+}
+
+impl Duel {
+    pub fn new(is_host: bool) -> Self {
+        Self {
+            is_host,
+            order: Vec::new(),
+            round: 0,
+            puzzles: PuzzleSession::default(),
+            opponent_rounds: 0,
+            opponent_score: 0,
+            remaining: TIME_LIMIT,
+        }
+    }
+
+    /// 按种子排好题目，开始计时
+    pub fn start(&mut self, seed: u64) {
+        let mut rng = XorShift::new(seed);
+        self.order = (0..ROUNDS).map(|_| rng.next() as usize % PUZZLES.len()).collect();
+        self.puzzles.go_to(self.order[0]);
+    }
+
+    /// 是否已经收到种子开始对战
+    pub fn is_started(&self) -> bool {
+        !self.order.is_empty()
+    }
+
+    /// 任何一方做完所有题目或者时间用完
+    pub fn is_over(&self) -> bool {
+        self.is_started() && (self.remaining == 0 || self.round >= self.order.len() || self.opponent_rounds >= self.order.len())
+    }
+
+    /// 本方得分与对方相比，对战还没结束时为 None
+    pub fn outcome(&self) -> Option<Ordering> {
+        self.is_over().then(|| self.puzzles.score.cmp(&self.opponent_score))
+    }
+
+    /// 经过了 elapsed 毫秒
+    pub fn tick(&mut self, elapsed: u64) {
+        if self.is_started() && !self.is_over() {
+            self.remaining = self.remaining.saturating_sub(elapsed);
+        }
+    }
+
+    /// 本方在当前题目上落子，解出时进入下一题并返回要发给对方的总分
+    pub fn try_move(&mut self, mv: Coord) -> Option<Message> {
+        if self.is_over() || !self.is_started() || !self.puzzles.try_move(mv) {
+            return None;
+        }
+        Some(self.advance())
+    }
+
+    /// 跳过当前题目，不得分
+    pub fn skip(&mut self) -> Option<Message> {
+        if self.is_over() || !self.is_started() {
+            return None;
+        }
+        self.puzzles.message = "Skipped".to_string();
+        Some(self.advance())
+    }
+
+    /// 做完当前题目，进入下一题，保留上一题的反馈
+    fn advance(&mut self) -> Message {
+        let message = Message::Score { round: self.round, score: self.puzzles.score };
+        self.round += 1;
+        if let Some(&index) = self.order.get(self.round) {
+            let feedback = std::mem::take(&mut self.puzzles.message);
+            self.puzzles.go_to(index);
+            self.puzzles.message = feedback;
+        }
+        message
+    }
+
+    /// 处理对方发来的消息，不合法时返回原因
+    pub fn receive(&mut self, message: Message) -> Result<(), &'static str> {
+        match message {
+            // 只有加入的一方接受种子，且只接受一次
+            Message::Duel { seed } if !self.is_host && !self.is_started() => {
+                self.start(seed);
+                Ok(())
+            }
+            // 对方必须按顺序做题，每题最多得满分
+            Message::Score { round, score }
+                if self.is_started()
+                    && round == self.opponent_rounds
+                    && round < self.order.len()
+                    && score >= self.opponent_score
+                    && score - self.opponent_score <= FULL_POINTS =>
+            {
+                self.opponent_rounds += 1;
+                self.opponent_score = score;
+                Ok(())
+            }
+            _ => Err("Opponent sent an invalid duel message"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_sides_race_through_the_same_puzzles() {
+        let mut host = Duel::new(true);
+        let mut guest = Duel::new(false);
+        host.start(42);
+        assert!(!guest.is_started());
+        guest.receive(Message::Duel { seed: 42 }).unwrap();
+        assert_eq!(host.order, guest.order);
+        assert!(host.receive(Message::Duel { seed: 7 }).is_err());
+
+        // 主机解出第一题，加入的一方跳过
+        let solution = host.puzzles.solution().unwrap().mv;
+        let report = host.try_move(solution).unwrap();
+        assert_eq!(report, Message::Score { round: 0, score: FULL_POINTS });
+        guest.receive(report).unwrap();
+        host.receive(guest.skip().unwrap()).unwrap();
+        assert_eq!((guest.opponent_rounds, guest.opponent_score), (1, FULL_POINTS));
+        assert_eq!((host.round, host.opponent_score), (1, 0));
+        assert_eq!(host.puzzles.index, host.order[1]);
+        // 跳题、重复通报或超过满分都不接受
+        assert!(guest.receive(Message::Score { round: 0, score: FULL_POINTS }).is_err());
+        assert!(guest.receive(Message::Score { round: 1, score: 3 * FULL_POINTS }).is_err());
+
+        assert_eq!(host.outcome(), None);
+        host.tick(TIME_LIMIT);
+        assert_eq!(host.outcome(), Some(Ordering::Greater));
+        assert!(host.skip().is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::{bail, Context, Result};
+use gomoku_core::gomocup::{self, Reply};
+use gomoku_core::{Coord, Rules, Stone};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 连接引擎的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 等待引擎回复的最长时间，超过之后改用内置 AI
+const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 向外部引擎请求一步棋，在后台线程中进行
+pub struct RemoteSearch {
+    receiver: Receiver<Result<Coord>>,
+    started: Instant,
+}
+
+impl RemoteSearch {
+    /// 请求引擎为 engine 方落子，engine_path 是本机的 pbrain 可执行文件或远程引擎的 `host:port`
+    pub fn start(engine_path: &str, rules: Rules, moves: &[Coord], engine: Stone) -> Self {
+        let (tx, receiver) = mpsc::channel();
+        let engine_path = engine_path.to_string();
+        let moves = moves.to_vec();
+        thread::spawn(move || {
+            let result = if Path::new(&engine_path).is_file() {
+                ask_process(&engine_path, rules, &moves, engine)
+            } else {
+                ask_remote(&engine_path, rules, &moves, engine)
+            };
+            let _ = tx.send(result);
+        });
+        Self { receiver, started: Instant::now() }
+    }
+
+    /// 取回结果，仍在等待引擎时返回 None
+    pub fn poll(&self) -> Option<Result<Coord>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            // 本机引擎的输出没有读取超时，由这里放弃等待
+            Err(TryRecvError::Empty) if self.started.elapsed() > CONNECT_TIMEOUT + REPLY_TIMEOUT => {
+                Some(Err(anyhow::anyhow!("engine did not reply in time")))
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow::anyhow!("engine thread stopped"))),
+        }
+    }
+
+    /// 等待引擎给出结果，只在后台线程中调用
+    pub fn wait(&self) -> Result<Coord> {
+        match self.receiver.recv_timeout(CONNECT_TIMEOUT + REPLY_TIMEOUT) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => bail!("engine did not reply in time"),
+            Err(RecvTimeoutError::Disconnected) => bail!("engine thread stopped"),
+        }
+    }
+}
+
+/// 连接远程引擎，发送整个局面并等待落点
+fn ask_remote(address: &str, rules: Rules, moves: &[Coord], engine: Stone) -> Result<Coord> {
+    let addr = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .with_context(|| format!("cannot resolve {}", address))?;
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .with_context(|| format!("cannot connect to {}", address))?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    ask(&mut reader, &mut stream, rules, moves, engine)
+}
+
+/// 启动本机的 pbrain 引擎，通过标准输入输出对话，得到落点后结束进程
+fn ask_process(path: &str, rules: Rules, moves: &[Coord], engine: Stone) -> Result<Coord> {
+    let mut child = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("cannot start {}", path))?;
+    let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        bail!("cannot talk to {}", path);
+    };
+    let result = ask(&mut BufReader::new(stdout), &mut stdin, rules, moves, engine);
+    // 有的引擎收到 END 之后不会自己退出
+    drop(stdin);
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+/// 开始一局，发送整个局面并等待落点
+fn ask(
+    reader: &mut impl BufRead,
+    writer: &mut impl Write,
+    rules: Rules,
+    moves: &[Coord],
+    engine: Stone,
+) -> Result<Coord> {
+    let [start, info] = gomocup::start_commands(rules);
+    send(writer, &[start])?;
+    match read_reply(reader)? {
+        Reply::Ok => {}
+        reply => bail!("engine refused to start: {:?}", reply),
+    }
+    send(writer, &[info])?;
+    send(writer, &gomocup::board_commands(moves, engine))?;
+    let reply = read_reply(reader)?;
+    // 引擎已经给出落点，结束失败也不影响结果
+    let _ = send(writer, &["END".to_string()]);
+    match reply {
+        Reply::Move(coord) => Ok(coord),
+        reply => bail!("unexpected reply from engine: {:?}", reply),
+    }
+}
+
+fn send(writer: &mut impl Write, commands: &[String]) -> Result<()> {
+    for command in commands {
+        writeln!(writer, "{}", command).context("engine closed the connection")?;
+    }
+    writer.flush().context("engine closed the connection")
+}
+
+/// 读取下一条有意义的回复，跳过 MESSAGE 等输出，引擎报错时返回错误
+fn read_reply(reader: &mut impl BufRead) -> Result<Reply> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).context("no reply from engine")? == 0 {
+            bail!("engine closed the connection");
+        }
+        match gomocup::parse_reply(&line) {
+            Reply::Other => continue,
+            Reply::Error(message) => bail!("engine error: {}", message),
+            reply => return Ok(reply),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use anyhow::{bail, Context, Result};
+use gomoku_core::learning::Tally;
+use gomoku_core::{Coord, Experience};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// AI 对 player_name 这位玩家积累的经验保存在哪个文件，名字中只保留字母和数字
+pub fn profile_path(player_name: &str) -> PathBuf {
+    let slug: String = player_name
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let slug = if slug.is_empty() { "player".to_string() } else { slug };
+    PathBuf::from(format!("gomoku_learned_{}.txt", slug))
+}
+
+/// 读取经验文件，最多保留 capacity 条记录，文件不存在时返回空经验
+///
+/// 经验文件每行一条记录：`key col row wins losses draws`。
+pub fn load(path: &Path, capacity: usize) -> Result<Experience> {
+    let mut experience = Experience::new();
+    experience.set_capacity(capacity);
+    if !path.exists() {
+        return Ok(experience);
+    }
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    for (i, line) in text.lines().enumerate() {
+        let (key, mv, tally) = parse_entry(line)
+            .with_context(|| format!("{} line {}: invalid entry: {}", path.display(), i + 1, line))?;
+        experience.insert(key, mv, tally);
+    }
+    experience.set_capacity(capacity);
+    Ok(experience)
+}
+
+/// 解析经验文件中的一行
+fn parse_entry(line: &str) -> Result<(u64, Coord, Tally)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [key, x, y, wins, losses, draws] = fields[..] else {
+        bail!("expected 6 fields, found {}", fields.len());
+    };
+    let mv = Coord::new(x.parse()?, y.parse()?);
+    if !mv.is_valid() {
+        bail!("move out of board");
+    }
+    let tally = Tally { wins: wins.parse()?, losses: losses.parse()?, draws: draws.parse()? };
+    Ok((key.parse()?, mv, tally))
+}
+
+/// 写入经验文件
+pub fn save(experience: &Experience, path: &Path) -> Result<()> {
+    let mut text = String::new();
+    for (key, mv, tally) in experience.entries() {
+        text.push_str(&format!("{} {} {} {} {} {}\n", key, mv.col, mv.row, tally.wins, tally.losses, tally.draws));
+    }
+    fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+}
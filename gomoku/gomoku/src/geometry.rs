@@ -0,0 +1,190 @@
+use eframe::egui::{pos2, vec2, Pos2, Rect};
+use gomoku_core::{Coord, BOARD_SIZE};
+
+// 棋子半径与格距之比
+const STONE_RADIUS_RATIO: f32 = 14.0 / 30.0;
+
+// 坐标标签到最外侧网格线的距离与格距之比，留出棋子半径之外的空间
+const LABEL_OFFSET_RATIO: f32 = 0.8;
+
+// 自适应大小时网格四周留出的空白与格距之比，放得下坐标标签
+const MARGIN_RATIO: f32 = 1.2;
+
+// 点击离最近的交叉点不超过这个距离（与格距之比）才算点中，点在格子中间不落子
+const SNAP_TOLERANCE_RATIO: f32 = 0.4;
+
+// 窗口再小格距也不小于这个值，避免棋盘缩成一点
+const MIN_SPACING: f32 = 8.0;
+
+/// 棋盘交叉点与画布像素之间的换算
+///
+/// 列对应水平方向，从左往右增大；行对应竖直方向，从上往下增大。
+/// 所有绘制和点击判断都通过这里换算，不要在别处直接乘格距。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardGeometry {
+    // 左上角交叉点 (0, 0) 在画布上的位置
+    pub origin: Pos2,
+    // 相邻两条线之间的像素距离
+    pub spacing: f32,
+}
+
+impl Default for BoardGeometry {
+    fn default() -> Self {
+        // 上方留出工具栏和列号，左侧留出行号
+        Self::new(pos2(40.0, 70.0), 30.0)
+    }
+}
+
+impl BoardGeometry {
+    pub fn new(origin: Pos2, spacing: f32) -> Self {
+        Self { origin, spacing }
+    }
+
+    /// 在 rect 中放下整个棋盘和坐标的最大尺寸，棋盘保持正方形并居中
+    pub fn fit(rect: Rect) -> Self {
+        let cells = (BOARD_SIZE - 1) as f32 + 2.0 * MARGIN_RATIO;
+        let spacing = (rect.width().min(rect.height()) / cells).max(MIN_SPACING);
+        let half_span = (BOARD_SIZE - 1) as f32 * spacing / 2.0;
+        Self::new(rect.center() - vec2(half_span, half_span), spacing)
+    }
+
+    /// 交叉点在画布上的位置
+    pub fn pixel_at(&self, coord: Coord) -> Pos2 {
+        self.origin + vec2(coord.col as f32, coord.row as f32) * self.spacing
+    }
+
+    /// 画布上的位置最近的交叉点，离棋盘边线超过半格时返回 None
+    pub fn coord_at(&self, pos: Pos2) -> Option<Coord> {
+        let offset = (pos - self.origin) / self.spacing;
+        let col = offset.x.round();
+        let row = offset.y.round();
+        if col < 0.0 || row < 0.0 {
+            return None;
+        }
+        let coord = Coord::new(col as usize, row as usize);
+        coord.is_valid().then_some(coord)
+    }
+
+    /// 点击位置对应的交叉点：最近的交叉点在吸附距离之内时返回它，否则返回 None
+    pub fn snap(&self, pos: Pos2) -> Option<Coord> {
+        let coord = self.coord_at(pos)?;
+        (pos.distance(self.pixel_at(coord)) <= self.spacing * SNAP_TOLERANCE_RATIO).then_some(coord)
+    }
+
+    /// 棋子半径
+    pub fn stone_radius(&self) -> f32 {
+        self.spacing * STONE_RADIUS_RATIO
+    }
+
+    /// 网格线覆盖的区域，从第一条线到最后一条线
+    pub fn grid_rect(&self) -> Rect {
+        let span = (BOARD_SIZE - 1) as f32 * self.spacing;
+        Rect::from_min_size(self.origin, vec2(span, span))
+    }
+
+    /// 能点中某个交叉点的区域，比网格向外多出半格
+    pub fn hit_rect(&self) -> Rect {
+        self.grid_rect().expand(self.spacing / 2.0)
+    }
+
+    /// 第 col 列的列号标签位置，在最上方横线的上面
+    pub fn col_label_at(&self, col: usize) -> Pos2 {
+        self.pixel_at(Coord::new(col, 0)) - vec2(0.0, self.spacing * LABEL_OFFSET_RATIO)
+    }
+
+    /// 第 row 行的行号标签位置，在最左侧竖线的左边
+    pub fn row_label_at(&self, row: usize) -> Pos2 {
+        self.pixel_at(Coord::new(0, row)) - vec2(self.spacing * LABEL_OFFSET_RATIO, 0.0)
+    }
+
+    /// 所有网格线的两个端点，先横线后竖线
+    pub fn grid_lines(&self) -> impl Iterator<Item = [Pos2; 2]> + '_ {
+        let last = BOARD_SIZE - 1;
+        let rows = (0..BOARD_SIZE).map(move |i| [Coord::new(0, i), Coord::new(last, i)]);
+        let cols = (0..BOARD_SIZE).map(move |i| [Coord::new(i, 0), Coord::new(i, last)]);
+        rows.chain(cols).map(|[a, b]| [self.pixel_at(a), self.pixel_at(b)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_are_horizontal_and_rows_vertical() {
+        let geometry = BoardGeometry::default();
+        assert_eq!(geometry.pixel_at(Coord::new(0, 0)), pos2(40.0, 70.0));
+        assert_eq!(geometry.pixel_at(Coord::new(3, 0)), pos2(130.0, 70.0));
+        assert_eq!(geometry.pixel_at(Coord::new(0, 3)), pos2(40.0, 160.0));
+        assert_eq!(geometry.pixel_at(Coord::new(14, 14)), pos2(460.0, 490.0));
+    }
+
+    #[test]
+    fn pixel_round_trips_to_the_same_coord() {
+        let geometry = BoardGeometry::new(pos2(20.0, 40.0), 25.0);
+        for coord in Coord::all() {
+            assert_eq!(geometry.coord_at(geometry.pixel_at(coord)), Some(coord));
+        }
+    }
+
+    #[test]
+    fn snaps_to_the_nearest_intersection() {
+        let geometry = BoardGeometry::default();
+        assert_eq!(geometry.coord_at(pos2(125.0, 95.0)), Some(Coord::new(3, 1)));
+        assert_eq!(geometry.coord_at(pos2(26.0, 56.0)), Some(Coord::new(0, 0)));
+        assert_eq!(geometry.coord_at(pos2(474.0, 504.0)), Some(Coord::new(14, 14)));
+    }
+
+    #[test]
+    fn rejects_points_off_the_board() {
+        let geometry = BoardGeometry::default();
+        assert_eq!(geometry.coord_at(pos2(15.0, 100.0)), None);
+        assert_eq!(geometry.coord_at(pos2(100.0, 45.0)), None);
+        assert_eq!(geometry.coord_at(pos2(480.0, 100.0)), None);
+        assert_eq!(geometry.coord_at(pos2(100.0, 510.0)), None);
+    }
+
+    #[test]
+    fn snaps_only_near_an_intersection() {
+        let geometry = BoardGeometry::default();
+        assert_eq!(geometry.snap(pos2(125.0, 95.0)), Some(Coord::new(3, 1)));
+        assert_eq!(geometry.snap(pos2(33.0, 63.0)), Some(Coord::new(0, 0)));
+        // 两个交叉点正中间，或者离最近的交叉点太远
+        assert_eq!(geometry.snap(pos2(115.0, 100.0)), None);
+        assert_eq!(geometry.snap(pos2(126.0, 86.0)), None);
+        assert_eq!(geometry.snap(pos2(15.0, 100.0)), None);
+    }
+
+    #[test]
+    fn hit_rect_extends_half_a_cell_past_the_grid() {
+        let geometry = BoardGeometry::default();
+        assert_eq!(geometry.hit_rect(), Rect::from_min_max(pos2(25.0, 55.0), pos2(475.0, 505.0)));
+        assert_eq!(geometry.grid_lines().count(), 2 * BOARD_SIZE);
+    }
+
+    #[test]
+    fn fits_a_centered_square_board_with_labels_inside_the_rect() {
+        let rect = Rect::from_min_size(pos2(10.0, 50.0), vec2(800.0, 500.0));
+        let geometry = BoardGeometry::fit(rect);
+        let grid = geometry.grid_rect();
+        assert_eq!(grid.width(), grid.height());
+        assert_eq!(grid.center(), rect.center());
+        assert!(rect.contains(geometry.row_label_at(0)) && rect.contains(geometry.col_label_at(0)));
+        assert!(rect.contains_rect(geometry.hit_rect()));
+        assert!(grid.height() > 0.8 * rect.height());
+        // 点击按同一套换算落到交叉点上
+        let coord = Coord::new(4, 11);
+        assert_eq!(geometry.coord_at(geometry.pixel_at(coord) + vec2(3.0, -3.0)), Some(coord));
+    }
+
+    #[test]
+    fn labels_sit_outside_the_stones() {
+        let geometry = BoardGeometry::default();
+        let grid = geometry.grid_rect();
+        assert_eq!(geometry.col_label_at(3).x, geometry.pixel_at(Coord::new(3, 0)).x);
+        assert!(geometry.col_label_at(3).y < grid.min.y - geometry.stone_radius());
+        assert_eq!(geometry.row_label_at(5).y, geometry.pixel_at(Coord::new(0, 5)).y);
+        assert!(geometry.row_label_at(5).x < grid.min.x - geometry.stone_radius());
+        assert!(geometry.row_label_at(5).x > 0.0);
+    }
+}
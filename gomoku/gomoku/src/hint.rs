@@ -0,0 +1,56 @@
+use gomoku_core::{Ai, Analysis, Board, Difficulty, Rules, Stone};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+/// 提示列出的候选点个数
+pub const HINT_MOVES: usize = 3;
+
+/// 为轮到的一方计算的落子提示
+///
+/// 搜索在后台线程中进行，界面每帧调用 `poll` 取回结果，不会因为搜索卡住。
+/// 网页版没有线程，在 `start` 里直接搜索完，下一帧 `poll` 就能取回结果。
+pub struct Hint {
+    // 请求提示时的步数，局面变化之后提示就作废
+    pub ply: usize,
+    // 提示给哪一方
+    pub stone: Stone,
+    // 搜索结果，按得分从高到低排列，搜索完成之前为空
+    pub moves: Vec<Analysis>,
+    receiver: Option<Receiver<Vec<Analysis>>>,
+}
+
+impl Hint {
+    /// 开始为 stone 方在 board 上搜索，ply 为当前步数
+    pub fn start(board: &Board, stone: Stone, rules: Rules, ply: usize) -> Self {
+        let (tx, receiver) = mpsc::channel();
+        let board = *board;
+        // 提示按最高难度搜索，比对手 AI 看得更远
+        let ai = Ai::new(stone, Difficulty::Hard).with_rules(rules);
+        let search = move || {
+            let _ = tx.send(ai.top_moves(&board, HINT_MOVES));
+        };
+        #[cfg(feature = "native")]
+        std::thread::spawn(search);
+        #[cfg(not(feature = "native"))]
+        search();
+        Self { ply, stone, moves: Vec::new(), receiver: Some(receiver) }
+    }
+
+    /// 是否仍在搜索
+    pub fn is_searching(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// 取回搜索结果，仍在搜索时返回 true
+    pub fn poll(&mut self) -> bool {
+        let Some(receiver) = &self.receiver else {
+            return false;
+        };
+        match receiver.try_recv() {
+            Ok(moves) => self.moves = moves,
+            Err(TryRecvError::Empty) => return true,
+            Err(TryRecvError::Disconnected) => {}
+        }
+        self.receiver = None;
+        false
+    }
+}
@@ -0,0 +1,101 @@
+use gomoku_core::threat::{self, ThreatLevel};
+use gomoku_core::win_rate;
+use gomoku_core::{Ai, Board, Checksum, Coord, CoordOrigin, Difficulty, Evaluation, Rules, Stone};
+
+// 给棋谱加引擎分析时使用的难度；保存时逐步搜索，太深的搜索会让界面卡住很久
+const ANALYSIS_DIFFICULTY: Difficulty = Difficulty::Medium;
+
+/// 导出带注释的文字棋谱，适合直接粘贴到论坛或者附在问题报告里
+///
+/// `Checksum` 是最后局面的校验码，与界面上显示的相同。
+///
+/// ```text
+/// Black: Player
+/// White: AI
+/// Result: Black wins
+/// Rules: Freestyle
+/// Moves: 9
+/// Coordinates: Top-left
+/// Checksum: 3F2A09C1
+///
+///   1. B H8
+///   2. W I9
+///   ...
+///   9. B L8  ; five in a row
+/// ```
+pub fn export(
+    moves: &[Coord],
+    black: &str,
+    white: &str,
+    result: &str,
+    rules: Rules,
+    origin: CoordOrigin,
+) -> String {
+    let mut record = String::new();
+    let mut board = Board::new();
+    for (i, &mv) in moves.iter().enumerate() {
+        let stone = Stone::for_ply(i);
+        let side = if stone == Stone::Black { "B" } else { "W" };
+        let line = format!("{:>3}. {} {}", i + 1, side, mv.notation(origin));
+        let annotation = annotate(&board, mv, stone, rules.win_length());
+        board.set(mv, stone);
+        if annotation.is_empty() {
+            record.push_str(&line);
+        } else {
+            record.push_str(&format!("{:<12}; {}", line, annotation));
+        }
+        record.push('\n');
+    }
+    format!(
+        "Black: {}\nWhite: {}\nResult: {}\nRules: {}\nMoves: {}\nCoordinates: {}\nChecksum: {}\n\n{}",
+        black,
+        white,
+        result,
+        rules,
+        moves.len(),
+        origin.name(),
+        Checksum::of(&board, Stone::for_ply(moves.len()), rules),
+        record
+    )
+}
+
+/// 根据威胁分析为一步棋生成注释：自己形成的威胁和挡住的对方威胁
+pub fn annotate(board: &Board, mv: Coord, stone: Stone, length: usize) -> String {
+    let mut notes = Vec::new();
+    let own = threat::threat_at(board, mv, stone, length).level;
+    if own >= ThreatLevel::OpenThree {
+        notes.push(own.name().to_string());
+    }
+    let opponent = threat::threat_at(board, mv, stone.opponent(), length).level;
+    if opponent >= ThreatLevel::Four {
+        notes.push(format!("blocks {}", opponent.name()));
+    }
+    notes.join(", ")
+}
+
+/// 逐步分析对局，为每一步生成引擎的评语：落子前这一方的胜率和引擎认为最好的一步
+///
+/// 写进 SGF 的 `C` 属性，其他看谱软件也能显示。
+pub fn engine_comments(moves: &[Coord], rules: Rules, origin: CoordOrigin) -> Vec<String> {
+    let mut board = Board::new();
+    moves
+        .iter()
+        .enumerate()
+        .map(|(i, &mv)| {
+            let stone = Stone::for_ply(i);
+            let ai = Ai::new(stone, ANALYSIS_DIFFICULTY).with_rules(rules);
+            let analysis = ai.best_move(&board);
+            let evaluation = Evaluation::new(i + 1, &board, &ai, analysis);
+            board.set(mv, stone);
+            let permille = win_rate::win_permille(evaluation.score);
+            let played = if analysis.best_move == mv { " (played)" } else { "" };
+            format!(
+                "{} to move: {}% winning chances\nEngine's choice: {}{}",
+                stone.name(),
+                (permille + 5) / 10,
+                analysis.best_move.notation(origin),
+                played
+            )
+        })
+        .collect()
+}
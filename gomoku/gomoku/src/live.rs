@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// 默认端口
+pub const DEFAULT_PORT: u16 = 8151;
+
+// 网页自动刷新的间隔（秒）
+const REFRESH_SECS: u32 = 2;
+
+// 读取请求的超时时间，对方迟迟不发请求时不会卡住服务线程
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 在局域网里直播本机的对局：内置一个只读的 HTTP 服务，浏览器打开后每隔几秒刷新一次棋盘
+///
+/// 不管请求的路径是什么都返回同一个网页，观看的一方不能做任何操作。
+/// 服务在后台线程中运行，界面在局面变化时调用 `show` 更新网页。
+pub struct LiveShare {
+    // 观看地址，开始服务时确定
+    url: String,
+    page: Arc<Mutex<String>>,
+    // 当前网页对应的局面，没有变化时不必重新生成
+    key: Option<u64>,
+    // 停止分享时通知后台线程
+    closed: Arc<AtomicBool>,
+}
+
+impl LiveShare {
+    /// 在 port 上开始服务，port 为 0 时由系统分配
+    pub fn start(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .with_context(|| format!("failed to listen on port {}", port))?;
+        let port = listener.local_addr()?.port();
+        // 非阻塞地接受连接，这样停止分享后线程可以退出
+        listener.set_nonblocking(true)?;
+        let page = Arc::new(Mutex::new(String::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+        let (shared, stop) = (page.clone(), closed.clone());
+        thread::spawn(move || loop {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            match listener.accept() {
+                Ok((peer, _)) => {
+                    let body = shared.lock().map(|page| page.clone()).unwrap_or_default();
+                    // 浏览器断开等错误只影响这一次请求
+                    let _ = respond(peer, &body);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(100)),
+                Err(_) => return,
+            }
+        });
+        Ok(Self { url: format!("http://{}:{}/", lan_address(), port), page, key: None, closed })
+    }
+
+    /// 局域网里的其他设备打开的地址
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// 网页是否已经是 key 对应的局面
+    pub fn is_showing(&self, key: u64) -> bool {
+        self.key == Some(key)
+    }
+
+    /// 把网页换成 key 对应的局面，page 是完整的 HTML
+    pub fn show(&mut self, key: u64, page: String) {
+        if let Ok(mut shared) = self.page.lock() {
+            *shared = page;
+            self.key = Some(key);
+        }
+    }
+}
+
+impl Drop for LiveShare {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 观看网页的 HTML：标题、一行状态和 SVG 棋盘，按 `REFRESH_SECS` 自动刷新
+pub fn page(status: &str, board_svg: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+         <meta http-equiv=\"refresh\" content=\"{}\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <title>Gomoku - Live</title>\
+         <style>body{{font-family:sans-serif;text-align:center}}svg{{max-width:100%;height:auto}}</style>\
+         </head><body>\n<h2>{}</h2>\n{}</body></html>\n",
+        REFRESH_SECS,
+        escape(status),
+        board_svg
+    )
+}
+
+/// 本机在局域网里的地址，找不到时用 localhost
+fn lan_address() -> String {
+    // 连接 UDP 套接字只是选一条路由，不会真的发出数据
+    UdpSocket::bind(("0.0.0.0", 0))
+        .and_then(|socket| socket.connect(("192.0.2.1", 80)).and(socket.local_addr()))
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+/// 读完请求头之后返回网页并关闭连接
+fn respond(peer: TcpStream, body: &str) -> std::io::Result<()> {
+    peer.set_nonblocking(false)?;
+    peer.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut writer = peer.try_clone()?;
+    for line in BufReader::new(peer).lines() {
+        if line?.trim().is_empty() {
+            break;
+        }
+    }
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn serves_the_latest_page() {
+        let mut live = LiveShare::start(0).unwrap();
+        live.show(1, page("Move 3 - White to move", "<svg></svg>"));
+        assert!(live.is_showing(1));
+        let port: u16 = live.url().trim_end_matches('/').rsplit(':').next().unwrap().parse().unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("<h2>Move 3 - White to move</h2>"));
+        assert!(response.contains("http-equiv=\"refresh\""));
+        assert_eq!(escape("<b>"), "&lt;b&gt;");
+    }
+}
@@ -1,96 +1,528 @@
 use eframe::{
     egui::{self, Frame, Margin, Ui, RichText},
-    epaint::{pos2, Color32, Pos2},
+    epaint::Pos2,
+    glow::{self, HasContext},
 };
 
+use anyhow::Context;
+use gomoku_core::commentary;
+use gomoku_core::gomocup;
+use gomoku_core::learning;
+use gomoku_core::line_room::{self, LineRoom};
+use gomoku_core::opening_book::BOOK_PLIES;
+use gomoku_core::practice::{self, Mistake};
+use gomoku_core::protocol::Message;
+use gomoku_core::threat::{self, PointThreat, ThreatLevel};
+use gomoku_core::win_rate;
+use gomoku_core::{
+    Adaptation, Ai, Analysis, Board, Checksum, Coord, CoordOrigin, Difficulty, EventBus, Evaluation, Experience, Forbidden,
+    FirstMove, GameCode, GameEvent, GameState, MoveError, OpeningBook, Outcome, RuleSet, Rules, Seat, SgfRecord, SkillReport, Stone, Swap2, Swap2Choice, Swap2Phase, BOARD_SIZE,
+};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+// wasm32 上 std::time::Instant::now 会 panic，改用浏览器的计时器
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+mod analysis_cache;
+mod assets;
+#[cfg(feature = "native")]
+mod args;
 mod audio;
-use audio::AudioManager;
+mod board_image;
+mod book;
+#[cfg(feature = "native")]
+mod cli;
+mod clock;
+mod demo;
+mod diagnostics;
+mod dialog;
+mod duel;
+mod engine;
+mod experience;
+mod geometry;
+mod hint;
+mod kifu;
+mod live;
+mod move_log;
+mod narration;
+mod net;
+#[cfg(feature = "native")]
+mod pbrain;
+mod puzzle;
+mod recovery;
+mod rng;
+mod save;
+mod settings;
+mod share;
+mod signature;
+#[cfg(feature = "native")]
+mod soak;
+mod spectator;
+mod stats;
+mod teaching;
+mod telemetry;
+mod theme;
+mod time_control;
+mod tournament;
+mod tutorial;
+mod weakness;
+use analysis_cache::{AnalysisCache, CACHE_PATH};
+use assets::{AssetKind, Assets};
+#[cfg(feature = "native")]
+use args::Args;
+use audio::{AudioManager, Sound, SoundProfile};
+use book::BOOK_PATH;
+use clock::{GameClock, TimeState};
+use demo::Demo;
+use diagnostics::{Check, Diagnostics, Targets};
+use duel::Duel;
+use engine::RemoteSearch;
+use geometry::BoardGeometry;
+use hint::Hint;
+use live::LiveShare;
+use move_log::MoveLog;
+use narration::Narration;
+use net::{NetEvent, NetSession, NetStatus, DEFAULT_PORT};
+use puzzle::{HintStage, PuzzleSession};
+use recovery::CorruptFile;
+use save::{SavedGame, SAVE_EXTENSION, SAVE_PATH};
+use settings::{Settings, SETTINGS_PATH};
+use share::ShareCode;
+use signature::{Verdict, SIGNING_KEY_PATH};
+use spectator::{AiConfig, Spectator};
+use stats::{Statistics, STATS_PATH};
+use teaching::{Lesson, Role, Tool};
+use telemetry::{Telemetry, TELEMETRY_PATH};
+use theme::{Theme, ThemeKind};
+use time_control::TimeControl;
+use tournament::{Director, Directive, Entry, Pairing, DIRECTOR_CLOCK_STEP};
+use tutorial::Tutorial;
+use weakness::{Drill, WEAKNESS_PATH};
+
+// AI 按经验改下时比较的候选点个数
+const LEARNING_CANDIDATES: usize = 4;
+
+// 坐标标签字号与格距之比
+const LABEL_FONT_RATIO: f32 = 0.4;
+
+// 鼠标指针处小棋子的半径与棋子半径之比
+const POINTER_STONE_RATIO: f32 = 0.6;
+
+// 落子被拒绝时提示圈显示的时间（秒）
+const REJECT_FLASH_SECS: f32 = 0.6;
+
+// 即将超时的棋钟闪烁时刷新界面的间隔
+const PULSE_FRAME: Duration = Duration::from_millis(33);
+
+// 主菜单背景演示棋盘上盖的底色的不透明度
+const DEMO_VEIL: f32 = 0.8;
+
+// 主菜单等待闲置和演示对局时刷新界面的间隔
+const DEMO_REFRESH: Duration = Duration::from_millis(100);
+
+// 复盘自动播放和快进时每步的间隔（秒）
+const AUTOPLAY_INTERVAL: f32 = 1.0;
+const FAST_FORWARD_INTERVAL: f32 = 0.1;
+
+// 是否为桌面版；网页版没有线程和套接字，隐藏联机、外部引擎、统计上报和自检
+const NATIVE: bool = cfg!(feature = "native");
+
+/// 输入坐标落子的文本框的 id，按 / 时用来把焦点移过去
+fn move_entry_id() -> egui::Id {
+    egui::Id::new("move_entry")
+}
 
 // 游戏模式枚举
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum GameMode {
     MainMenu,
     PlayerVsPlayer,
     PlayerVsAI,
+    Puzzle,
+    Tutorial,
+    Settings,
+    Statistics,
+    Diagnostics,
+    Replay,
+    Network,
+    Lesson,
+    Duel,
+    AiVsAi,
 }
 
+
 struct AppUI {
     // 游戏模式状态
     game_mode: GameMode,
     
-    // 一个 15 * 15 的棋盘，黑子用 1 表示，白子用 2 表示，空位用 0 表示
-    board_data: [[u8; 15]; 15],
+    // 当前对局：棋盘、落子记录和胜负
+    game: GameState,
+
+    // 对局事件的分发，各部件通过订阅得到自己关心的事件
+    events: EventBus,
+    // 音效订阅的落子事件
+    sound_events: Receiver<GameEvent>,
+    // 棋钟订阅的落子事件，落子之后加秒
+    clock_events: Receiver<GameEvent>,
+    // 落子时间记录订阅的落子、悔棋和重做事件
+    log_events: Receiver<GameEvent>,
+    // 每一步的落子时间和用时，鼠标停在棋子上时显示
+    move_log: MoveLog,
+
+    // 本地对局的棋钟，不限时时为 None
+    clock: Option<GameClock>,
+    // 棋钟按整毫秒走时：每帧取程序启动以来的毫秒数，与上一帧之差就是这一帧的用时，不会累积取整误差
+    launched: Instant,
+    frame_millis: u64,
 
-    // 棋盘起始点，棋盘左上角距离画布左上角的距离
-    start_point: Pos2,
+    // 棋盘交叉点与画布像素的换算，每帧按窗口大小重新计算
+    geometry: BoardGeometry,
 
-    // 是否该黑子落子了
-    is_black: bool,
+    // 是否暂停
+    is_paused: bool,
 
-    // 是否已经产生了赢家
-    is_winner: bool,
+    // 回看的步数，None 表示显示当前局面
+    view_ply: Option<usize>,
+
+    // 键盘下棋的光标，第一次按方向键之前为 None，不显示
+    cursor: Option<Coord>,
+
+    // 输入坐标落子的文本框内容，以及上次输入是否无法识别
+    move_entry: String,
+    move_entry_invalid: bool,
+
+    // Swap2 开局的进度，不使用 Swap2 时为 None；颜色确定之后仍然保留，用来限制悔棋
+    opening: Option<Swap2>,
+    // 人机对战的 Swap2 开局中玩家是哪一方
+    player_seat: Seat,
+
+    // 双人对战中提出和棋的一方，等待另一方答复
+    draw_offer: Option<Stone>,
+
+    // 练习模式中玩家刚刚下出的失误，等待玩家决定是否悔棋
+    mistake: Option<Mistake>,
+
+    // 正在显示的分享对话框，以及无法生成分享代码时的原因
+    share: Option<ShareCode>,
+    share_error: String,
+    // 主菜单中粘贴的对局代码，以及无法载入时的原因
+    game_code_entry: String,
+    game_code_error: String,
+    // 刚导入的带签名棋谱的检查结果，显示在对话框中
+    signature: Option<Verdict>,
+    // 保存对局失败的原因，显示在对话框中
+    save_error: Option<String>,
+    // 读取签名密钥失败的原因，棋谱照常保存但没有签名，显示在对话框中
+    key_error: Option<String>,
+    // 在局域网里直播当前对局的网页服务，以及上次开始直播失败的原因
+    live: Option<LiveShare>,
+    live_error: String,
+
+    // 玩家请求的落子提示，局面变化之后作废
+    hint: Option<Hint>,
+
+    // 玩家刚刚尝试的禁手，在棋盘上标出
+    forbidden_warning: Option<(Coord, Forbidden)>,
+    // 刚刚被拒绝的落子位置和时间，短暂闪一下提示
+    rejected_move: Option<(Coord, Instant)>,
+    // 威胁标注，按局面的哈希和规则缓存，局面变化时重新计算
+    threats: Option<(u64, Rules, Vec<PointThreat>)>,
+
+    // 复盘时是否自动播放、是否快进，以及距上一步经过的时间
+    autoplay: bool,
+    fast_forward: bool,
+    autoplay_timer: f32,
 
     // AI模式相关
     player_is_black: bool,  // 玩家是否为黑子
     ai_thinking: bool,      // AI是否正在思考
     color_selected: bool,   // 是否已选择颜色
     ai_delay_timer: f32,    // AI延迟计时器
-    ai_pending_move: Option<(usize, usize)>, // AI待执行的移动
+    ai_pending_move: Option<Coord>,          // AI待执行的移动
+    ai_history: Vec<Evaluation>,             // AI 每一步对局面的判断，用于赛后感想，每步至多一条，新开一局时清空
+    remote_search: Option<RemoteSearch>,     // 等待远程引擎回复的请求
+    experience: Experience,                  // AI 从当前玩家的对局中学到的经验
+    experience_path: Option<PathBuf>,        // experience 属于哪个玩家的经验文件，还没读取时为 None
+    engine_error: Option<String>,            // 外部引擎上一次失败的原因，这一步改用了内置 AI
+    analysis_cache: AnalysisCache,           // 跨会话保存的局面分析结果
+    opening_book: OpeningBook,               // 内置开局库加上玩家自己的开局线
 
-    // 音频系统
+    // 音效、主题等资源的查找，以及从中读取的音效和当前主题
+    assets: Assets,
     audio_manager: AudioManager,
+    theme: Theme,
+    // theme 是按哪个主题读取的，设置里换了主题时重新读取
+    theme_kind: ThemeKind,
+
+    // 游戏设置
+    settings: Settings,
+
+    // AI 对战观战的配置和进度
+    spectator: Spectator,
+
+    // 主菜单背景里的演示对局
+    demo: Demo,
+
+    // 解题模式进度
+    puzzle: PuzzleSession,
+    // 教程进度
+    tutorial: Tutorial,
+
+    // 是否正在等待确认离开对局
+    confirm_leave: bool,
+    // 对局中按 R 之后是否正在等待确认重新开始
+    confirm_restart: bool,
+
+    // 离开确认是否由关闭窗口触发
+    closing: bool,
+
+    // 联机对局的连接，以及大厅中填写的地址、端口和提示信息
+    network: Option<NetSession>,
+    net_address: String,
+    net_port: String,
+    net_message: String,
+    // 恢复断线的联机对局时本方的颜色，连上之后核对双方的记录而不是重新开局
+    net_resume: Option<Stone>,
+
+    // 通过联机连接进行的教学，在大厅中选择老师或学生时创建
+    lesson: Option<Lesson>,
+
+    // 通过联机连接进行的解题对战，在大厅中选择时创建
+    duel: Option<Duel>,
+
+    // 赛事：作为裁判时接受选手、编排对阵，作为选手时连着裁判，都在联机大厅中创建
+    director: Option<Director>,
+    tournament: Option<Entry>,
+
+    // 读取失败、等待玩家处理的数据文件，每个文件至多一条，处理后移除
+    corrupt_files: Vec<CorruptFile>,
+    // 玩家选择保留的损坏文件，本次运行中不再写入
+    kept_files: Vec<PathBuf>,
+
+    // 匿名使用统计
+    telemetry: Telemetry,
+
+    // 玩家在各模式下的战绩
+    stats: Statistics,
+    // 各局累计的弱点统计
+    weakness: SkillReport,
+
+    // 当前对局的结果是否已经计入统计
+    game_recorded: bool,
+
+    // 显卡和 OpenGL 版本，启动时查询一次
+    renderer: String,
+
+    // 启动时没有通过的检查，在主菜单上提示
+    startup_problems: Vec<Check>,
+
+    // 诊断界面的检查结果
+    diagnostics: Option<Diagnostics>,
 
     frame: egui::Frame,
 }
 
 impl Default for AppUI {
     fn default() -> Self {
+        let mut events = EventBus::new();
+        let sound_events = events.subscribe(|e| matches!(e, GameEvent::Played { .. } | GameEvent::Won(_)));
+        let clock_events = events.subscribe(|e| matches!(e, GameEvent::Played { .. }));
+        let log_events =
+            events.subscribe(|e| matches!(e, GameEvent::Played { .. } | GameEvent::Undone { .. } | GameEvent::Redone { .. }));
         Self {
             game_mode: GameMode::MainMenu,
             frame: Frame {
                 inner_margin: Margin::same(0.0),
                 outer_margin: Margin::same(0.0),
-                fill: egui::Color32::LIGHT_YELLOW,
+                fill: Theme::CLASSIC.background,
                 ..Default::default()
             },
-            board_data: [[0; 15]; 15],
-            // 棋盘左上角距离画布左上角的距离
-            start_point: pos2(15.0, 15.0),
-            is_black: true,
-            is_winner: false,
+            game: GameState::new(),
+            events,
+            sound_events,
+            clock_events,
+            log_events,
+            move_log: MoveLog::default(),
+            clock: None,
+            launched: Instant::now(),
+            frame_millis: 0,
+            geometry: BoardGeometry::default(),
+            is_paused: false,
+            view_ply: None,
+            cursor: None,
+            move_entry: String::new(),
+            move_entry_invalid: false,
+            opening: None,
+            player_seat: Seat::First,
+            draw_offer: None,
+            mistake: None,
+            share: None,
+            game_code_entry: String::new(),
+            game_code_error: String::new(),
+            share_error: String::new(),
+            signature: None,
+            save_error: None,
+            key_error: None,
+            live: None,
+            live_error: String::new(),
+            hint: None,
+            forbidden_warning: None,
+            rejected_move: None,
+            threats: None,
+            autoplay: false,
+            fast_forward: false,
+            autoplay_timer: 0.0,
             player_is_black: true,  // 默认玩家为黑子
             ai_thinking: false,
             color_selected: false,
             ai_delay_timer: 0.0,
             ai_pending_move: None,
-            audio_manager: AudioManager::new().unwrap_or_else(|_| {
-                // 如果音频初始化失败，程序仍然可以运行，只是没有音效
-                panic!("Failed to initialize audio system");
-            }),
+            ai_history: Vec::new(),
+            remote_search: None,
+            experience: Experience::new(),
+            experience_path: None,
+            engine_error: None,
+            analysis_cache: AnalysisCache::new(0),
+            opening_book: OpeningBook::builtin(),
+            assets: Assets::default(),
+            audio_manager: AudioManager::default(),
+            theme: Theme::CLASSIC,
+            theme_kind: ThemeKind::Classic,
+            settings: Settings::default(),
+            spectator: Spectator::default(),
+            demo: Demo::default(),
+            puzzle: PuzzleSession::default(),
+            tutorial: Tutorial::default(),
+            confirm_leave: false,
+            confirm_restart: false,
+            closing: false,
+            network: None,
+            net_address: "127.0.0.1".to_string(),
+            net_port: DEFAULT_PORT.to_string(),
+            net_message: String::new(),
+            net_resume: None,
+            lesson: None,
+            duel: None,
+            director: None,
+            tournament: None,
+            corrupt_files: Vec::new(),
+            kept_files: Vec::new(),
+            telemetry: Telemetry::default(),
+            stats: Statistics::default(),
+            weakness: SkillReport::default(),
+            game_recorded: false,
+            renderer: "unknown".to_string(),
+            startup_problems: Vec::new(),
+            diagnostics: None,
         }
     }
 }
 
 impl AppUI {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self::default()
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        if let Some(gl) = &cc.gl {
+            // 只读取字符串参数，OpenGL 上下文此时已经可用
+            let (renderer, version) =
+                unsafe { (gl.get_parameter_string(glow::RENDERER), gl.get_parameter_string(glow::VERSION)) };
+            app.renderer = format!("{} (OpenGL {})", renderer, version);
+        }
+        let settings_path = Path::new(SETTINGS_PATH);
+        app.settings = Settings::load(settings_path).unwrap_or_else(|e| {
+            app.corrupt_files.push(CorruptFile::new(settings_path, &e));
+            Settings::default()
+        });
+        if app.settings.sound_pack().is_some() {
+            app.assets.set_override(AssetKind::Sound, app.settings.sound_pack());
+            app.audio_manager.load_sounds(&app.assets);
+        }
+        app.load_theme();
+        let stats_path = Path::new(STATS_PATH);
+        app.stats = Statistics::load(stats_path).unwrap_or_else(|e| {
+            app.corrupt_files.push(CorruptFile::new(stats_path, &e));
+            Statistics::default()
+        });
+        let weakness_path = Path::new(WEAKNESS_PATH);
+        app.weakness = weakness::load(weakness_path).unwrap_or_else(|e| {
+            app.corrupt_files.push(CorruptFile::new(weakness_path, &e));
+            SkillReport::default()
+        });
+        let capacity = app.settings.analysis_cache_size;
+        let cache_path = Path::new(CACHE_PATH);
+        app.analysis_cache = AnalysisCache::load(cache_path, capacity).unwrap_or_else(|e| {
+            app.corrupt_files.push(CorruptFile::new(cache_path, &e));
+            AnalysisCache::new(capacity)
+        });
+        let book_path = Path::new(BOOK_PATH);
+        app.opening_book = book::load(book_path).unwrap_or_else(|e| {
+            app.corrupt_files.push(CorruptFile::new(book_path, &e));
+            OpeningBook::builtin()
+        });
+        let telemetry_path = Path::new(TELEMETRY_PATH);
+        app.telemetry = Telemetry::load(telemetry_path).unwrap_or_else(|e| {
+            app.corrupt_files.push(CorruptFile::new(telemetry_path, &e));
+            Telemetry::default()
+        });
+        app.telemetry.begin_session();
+        app.save_telemetry();
+        // 网页版没有工作目录，也总是不发声，不必提示
+        if NATIVE {
+            app.startup_problems = [diagnostics::check_working_dir(), app.audio_check()]
+                .into_iter()
+                .filter(|check| !check.passed)
+                .collect();
+        }
+        app
     }
 
     /// 渲染颜色选择界面
     fn render_color_selection(&mut self, ui: &mut Ui) {
         ui.vertical_centered(|ui| {
             ui.add_space(100.0);
-            ui.heading(RichText::new("Choose Your Color").size(32.0).color(egui::Color32::DARK_BLUE));
+            ui.heading(RichText::new("Choose Your Color").size(32.0).color(self.theme().accent));
             ui.add_space(40.0);
             
             ui.vertical_centered(|ui| {
+                // AI 难度选择
+                egui::ComboBox::from_label("AI Level")
+                    .selected_text(self.settings.ai_difficulty.name())
+                    .show_ui(ui, |ui| {
+                        for difficulty in Difficulty::ALL {
+                            ui.selectable_value(&mut self.settings.ai_difficulty, difficulty, difficulty.name());
+                        }
+                    });
+                ui.checkbox(&mut self.settings.practice, "Practice Mode (point out mistakes)");
+                ui.checkbox(&mut self.settings.eval_bar, "Show Win Estimate");
+
                 ui.add_space(20.0);
+
+                // Swap2 开局先选谁来摆子，颜色在开局之后决定
+                if self.opening.is_some() {
+                    if ui.add_sized([180.0, 60.0], egui::Button::new(RichText::new("Place Opening").size(18.0))).clicked() {
+                        self.player_seat = Seat::First;
+                        self.color_selected = true;
+                    }
+
+                    ui.add_space(20.0);
+
+                    if ui.add_sized([180.0, 60.0], egui::Button::new(RichText::new("Let AI Open").size(18.0))).clicked() {
+                        self.player_seat = Seat::Second;
+                        self.color_selected = true;
+                    }
+
+                    ui.add_space(30.0);
+                    ui.label(RichText::new("Swap2: the opener places three stones, the other side picks a color").size(14.0).color(self.theme().muted));
+                    return;
+                }
                 
                 // 黑子按钮
                 if ui.add_sized([180.0, 60.0], egui::Button::new(RichText::new("Black (First Move)").size(18.0))).clicked() {
                     self.player_is_black = true;
-                    self.color_selected = true;
-                    self.is_black = true; // 玩家先手
+                    self.color_selected = true; // 玩家先手
                 }
                 
                 ui.add_space(20.0);
@@ -99,405 +531,3647 @@ impl AppUI {
                 if ui.add_sized([180.0, 60.0], egui::Button::new(RichText::new("White (Second Move)").size(18.0))).clicked() {
                     self.player_is_black = false;
                     self.color_selected = true;
-                    self.is_black = true; // AI先手
-                    // AI第一步下在中央
-                    self.board_data[7][7] = 1; // 黑子下在中央
-                    self.audio_manager.play_black_move(); // 播放AI落子音效
-                    self.is_black = false; // 轮到白子
                 }
                 
                 ui.add_space(30.0);
                 
                 // 说明文字
-                ui.label(RichText::new("Black always goes first").size(14.0).color(egui::Color32::GRAY));
+                ui.label(RichText::new("Black always goes first").size(14.0).color(self.theme().muted));
             });
         });
     }
 
+    /// 推进主菜单的演示对局，有任何输入就暂停，减少动态效果时不演示
+    fn step_demo(&mut self, ctx: &egui::Context, delta_millis: u64) {
+        if self.settings.reduce_motion {
+            return;
+        }
+        if ctx.input(|i| !i.events.is_empty()) {
+            self.demo.interrupt();
+        }
+        self.demo.tick(delta_millis);
+        // 等待闲置和演示的节奏都很慢，定时刷新即可，不必每帧重绘
+        ctx.request_repaint_after(DEMO_REFRESH);
+    }
+
+    /// 在主菜单背后画出淡化的演示棋盘
+    fn render_demo(&self, ui: &Ui) {
+        if self.settings.reduce_motion {
+            return;
+        }
+        let rect = ui.available_rect_before_wrap();
+        let geometry = BoardGeometry::fit(rect);
+        let theme = self.theme();
+        let painter = ui.painter();
+        let stroke = egui::Stroke::new(theme.grid_width, theme.grid);
+        for line in geometry.grid_lines() {
+            painter.line_segment(line, stroke);
+        }
+        let board = self.demo.game().board();
+        for coord in Coord::all() {
+            theme.paint_stone(painter, geometry.pixel_at(coord), geometry.stone_radius(), board.get(coord));
+        }
+        // 盖一层半透明的底色，菜单文字保持清晰
+        painter.rect_filled(rect, 0.0, theme.background.gamma_multiply(DEMO_VEIL));
+    }
+
     /// 渲染主菜单界面
     fn render_main_menu(&mut self, ui: &mut Ui) {
         ui.vertical_centered(|ui| {
             // 标题
-            ui.add_space(50.0);
-            ui.heading(RichText::new("Gomoku Game").size(36.0).color(egui::Color32::DARK_BLUE));
-            ui.add_space(30.0);
+            ui.add_space(15.0);
+            ui.heading(RichText::new("Gomoku Game").size(36.0).color(self.theme().accent));
+            ui.add_space(10.0);
             
+            // 启动检查发现的问题，详情见诊断界面
+            for check in &self.startup_problems {
+                ui.label(RichText::new(format!("{}: {}", check.name, check.detail)).color(self.theme().error));
+            }
+
             // 模式选择按钮
             ui.vertical_centered(|ui| {
-                ui.add_space(20.0);
+                ui.add_space(10.0);
                 
                 // 双人对战按钮
-                if ui.add_sized([200.0, 50.0], egui::Button::new(RichText::new("Player vs Player").size(20.0))).clicked() {
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Player vs Player").size(20.0))).clicked() {
                     self.game_mode = GameMode::PlayerVsPlayer;
                     self.restart();
                 }
                 
-                ui.add_space(15.0);
+                ui.add_space(10.0);
                 
                 // 人机对战按钮
-                if ui.add_sized([200.0, 50.0], egui::Button::new(RichText::new("Player vs AI").size(20.0))).clicked() {
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Player vs AI").size(20.0))).clicked() {
                     self.game_mode = GameMode::PlayerVsAI;
                     self.restart();
                     self.color_selected = false; // 重置颜色选择状态
                 }
+
+                ui.add_space(10.0);
+
+                // AI 对战观战按钮
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("AI vs AI").size(20.0))).clicked() {
+                    self.game_mode = GameMode::AiVsAi;
+                    self.restart();
+                    self.spectator.started = false;
+                }
+
+                ui.add_space(10.0);
+
+                // 联机对战按钮，网页版不能打开套接字
+                if NATIVE {
+                    if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Network Game").size(20.0))).clicked() {
+                        self.game_mode = GameMode::Network;
+                        self.network = None;
+                        self.net_message.clear();
+                        self.net_resume = None;
+                    }
+
+                    ui.add_space(10.0);
+                }
+
+                // 解题模式按钮
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Puzzles").size(20.0))).clicked() {
+                    self.game_mode = GameMode::Puzzle;
+                    self.puzzle = PuzzleSession::default();
+                }
+
+                ui.add_space(10.0);
+
+                // 教程按钮
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Tutorial").size(20.0))).clicked() {
+                    self.game_mode = GameMode::Tutorial;
+                    self.tutorial = Tutorial::default();
+                }
                 
+                // 继续上次保存的对局
+                if Path::new(SAVE_PATH).exists() {
+                    ui.add_space(10.0);
+                    if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Resume Saved Game").size(20.0))).clicked() {
+                        self.load_game(Path::new(SAVE_PATH));
+                    }
+                }
+
+                // 打开 SGF 棋谱或存档
+                if dialog::AVAILABLE {
+                    ui.add_space(10.0);
+                    if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Load Game").size(20.0))).clicked() {
+                        self.open_game_file();
+                    }
+                }
+
+                // 载入朋友分享的对局代码，网页版没有文件对话框，也可以这样打开对局
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    let entry = egui::TextEdit::singleline(&mut self.game_code_entry)
+                        .desired_width(140.0)
+                        .hint_text("GMK1-F5-...");
+                    let response = ui.add(entry);
+                    let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button("Load Game Code").clicked() || entered {
+                        self.load_game_code();
+                    }
+                });
+                if !self.game_code_error.is_empty() {
+                    ui.label(RichText::new(&self.game_code_error).color(self.theme().error));
+                }
+
+                ui.add_space(10.0);
+
+                // 设置按钮
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Settings").size(20.0))).clicked() {
+                    self.game_mode = GameMode::Settings;
+                }
+
+                ui.add_space(10.0);
+
+                // 战绩按钮
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Statistics").size(20.0))).clicked() {
+                    self.game_mode = GameMode::Statistics;
+                }
+
+                ui.add_space(20.0);
+
+                // 时间控制选择
+                egui::ComboBox::from_label("Time Control")
+                    .selected_text(self.settings.time_control.label())
+                    .show_ui(ui, |ui| {
+                        for preset in TimeControl::ALL {
+                            ui.selectable_value(&mut self.settings.time_control, preset, preset.label());
+                        }
+                    });
+
+                // 规则选择，联机对局总是使用自由规则
+                egui::ComboBox::from_label("Rules")
+                    .selected_text(self.settings.rules.name())
+                    .show_ui(ui, |ui| {
+                        for rules in RuleSet::ALL {
+                            ui.selectable_value(&mut self.settings.rules, rules, rules.name());
+                        }
+                    });
+
+                // 获胜需要的连子数，连珠规则固定为五子
+                if self.settings.rules == RuleSet::Freestyle {
+                    egui::ComboBox::from_label("Win Length")
+                        .selected_text(format!("{} in a row", self.settings.win_length))
+                        .show_ui(ui, |ui| {
+                            for length in Rules::WIN_LENGTHS {
+                                ui.selectable_value(&mut self.settings.win_length, length, format!("{} in a row", length));
+                            }
+                        });
+                }
+
+                // 联机对局不使用 Swap2
+                ui.checkbox(&mut self.settings.swap2, "Swap2 Opening");
+
                 ui.add_space(20.0);
                 
                 // 说明文字
-                ui.label(RichText::new("Choose your game mode").size(14.0).color(egui::Color32::GRAY));
+                ui.label(RichText::new("Choose your game mode").size(14.0).color(self.theme().muted));
             });
         });
     }
 
-    /// 绘制棋盘
-    fn render_board(&self, ui: &Ui) {
-        let stroke = egui::Stroke::new(1.0, egui::Color32::DARK_GRAY);
+    /// 渲染 AI 对战的配置界面
+    fn render_spectator_setup(&mut self, ui: &mut Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(30.0);
+            ui.heading(RichText::new("AI vs AI").size(32.0).color(self.theme().accent));
+            ui.add_space(20.0);
 
-        // 先画横线
-        for i in 0..15 {
-            let start = self.start_point + egui::Vec2::new(0.0, i as f32 * 30.0);
-            let end = start + egui::Vec2::new(420.0, 0.0);
-            ui.painter().line_segment([start, end], stroke);
-        }
-        // 再画竖线
-        for i in 0..15 {
-            let start = self.start_point + egui::Vec2::new(i as f32 * 30.0, 0.0);
-            let end = start + egui::Vec2::new(0.0, 420.0);
-            ui.painter().line_segment([start, end], stroke);
-        }
-    }
+            Self::render_ai_config(ui, "Black", &mut self.spectator.black);
+            ui.add_space(10.0);
+            Self::render_ai_config(ui, "White", &mut self.spectator.white);
+            ui.add_space(20.0);
 
-    /// 画圆
-    fn render_circle(&self, ui: &Ui, center: egui::Pos2, color: Color32, stroke_color: Color32) {
-        let stroke = egui::Stroke::new(1.0, stroke_color);
-        ui.painter().circle(center, 14.0, color, stroke)
-    }
+            ui.add(egui::Slider::new(&mut self.spectator.delay, Spectator::DELAYS).text("Seconds per Move"));
+            ui.add_space(20.0);
 
-    /// 画白子
-    fn render_white(&self, ui: &Ui, center: egui::Pos2) {
-        self.render_circle(ui, center, Color32::WHITE, Color32::GRAY)
+            if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Start").size(20.0))).clicked() {
+                self.restart();
+                self.spectator.started = true;
+            }
+            ui.add_space(10.0);
+            if ui.button("Back to Menu").clicked() {
+                self.game_mode = GameMode::MainMenu;
+            }
+        });
     }
 
-    /// 画黑子
-    fn render_black(&self, ui: &Ui, center: egui::Pos2) {
-        self.render_circle(ui, center, Color32::BLACK, Color32::BLACK)
+    /// 渲染一方 AI 的难度和评估权重
+    fn render_ai_config(ui: &mut Ui, name: &str, config: &mut AiConfig) {
+        egui::ComboBox::from_label(format!("{} AI Level", name))
+            .selected_text(config.difficulty.name())
+            .show_ui(ui, |ui| {
+                for difficulty in Difficulty::ALL {
+                    ui.selectable_value(&mut config.difficulty, difficulty, difficulty.name());
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Attack");
+            ui.add(egui::DragValue::new(&mut config.weights.attack).clamp_range(0..=100));
+            ui.label("Defense");
+            ui.add(egui::DragValue::new(&mut config.weights.defense).clamp_range(0..=100));
+        });
     }
 
-    /// 绘制棋子
-    fn render_piece(&self, ui: &Ui) {
-        // 遍历棋子数组数据
-        for (i, x) in self.board_data.iter().enumerate() {
-            for (j, y) in x.iter().enumerate() {
-                match y {
-                    1 => self.render_black(ui, self.get_position(i, j)),
-                    2 => self.render_white(ui, self.get_position(i, j)),
-                    _ => {}
+    /// 渲染 AI 对战的棋盘和工具栏
+    fn render_spectator_game(&mut self, ctx: &egui::Context, ui: &mut Ui) {
+        let finished = self.game.is_over();
+        ui.horizontal(|ui| {
+            if ui.button("Back to Menu").clicked() {
+                self.game_mode = GameMode::MainMenu;
+                return;
+            }
+            if !finished {
+                let pause_text = if self.is_paused { "Resume" } else { "Pause" };
+                if ui.button(pause_text).clicked() {
+                    self.is_paused = !self.is_paused;
                 }
             }
-        }
-    }
+            if ui.button("Slower").clicked() {
+                self.spectator.slower();
+            }
+            if ui.button("Faster").clicked() {
+                self.spectator.faster();
+            }
+            ui.label(format!("{:.1}s/move", self.spectator.delay));
 
-    fn get_position(&self, x: usize, y: usize) -> Pos2 {
-        // start + ( 30 * x, 30 * y )
-        let x = x as f32;
-        let y = y as f32;
-        self.start_point + egui::Vec2::new(30.0 * x, 30.0 * y)
-    }
+            let side = self.game.to_move();
+            ui.label(format!("Current Turn: {} ({})", side.name(), self.spectator.config(side).label()));
 
-    /// 处理鼠标点击事件
-    fn handle_click(&mut self, pos: Pos2) {
-        // 在AI模式下，只有玩家的回合才能点击
-        if self.game_mode == GameMode::PlayerVsAI {
-            let ai_piece = if self.player_is_black { 2 } else { 1 };
-            let current_piece = if self.is_black { 1 } else { 2 };
-            if current_piece == ai_piece {
-                return; // AI的回合，不允许玩家点击
+            // 回看时显示当前所在的步数
+            if let Some(ply) = self.view_ply {
+                ui.label(format!("Move {}/{}", ply, self.game.moves().len()));
             }
-        }
+        });
+
+        self.handle_history_keys(ctx);
+
+        self.render_board(ui);
+        self.render_position(ui);
 
-        // 首先 xy 都减去 15，然后除以 30，然后四舍五入
-        let x = ((pos.x - 15.0) / 30.0).round() as usize;
-        let y = ((pos.y - 15.0) / 30.0).round() as usize;
-        // 如果点击了棋盘以外的空间，或者该点位已有棋子，什么事都不做
-        if x > 14 || y > 14 || self.board_data[x][y] != 0 {
+        if !finished {
             return;
         }
-        let piece_type = if self.is_black { 1 } else { 2 };
-        self.board_data[x][y] = piece_type;
-        
-        // 播放相应的音效
-        if piece_type == 1 {
-            self.audio_manager.play_black_move();
-        } else {
-            self.audio_manager.play_white_move();
-        }
-        
-        if self.check_winner(x, y) {
-            self.is_winner = true;
-            return;
-        };
-        self.is_black = !self.is_black;
+        egui::Window::new(self.winner_text())
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    if ui.button("Restart").clicked() {
+                        self.restart();
+                    }
+                    if ui.button("Back to Menu").clicked() {
+                        self.game_mode = GameMode::MainMenu;
+                    }
+                    if ui.button("Copy Record").clicked() {
+                        let record = self.export_kifu();
+                        ui.output_mut(|o| o.copied_text = record);
+                    }
+                    if ui.button("Review").clicked() {
+                        self.open_replay();
+                    }
+                });
+            });
     }
 
-    /// 检查是否有获胜者
-    fn check_winner(&self, x: usize, y: usize) -> bool {
-        // 从最后一次的落点开始检查
-        let current = self.board_data[x][y];
-        let mut count = 1;
+    /// 渲染设置界面
+    fn render_settings(&mut self, ui: &mut Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(30.0);
+            ui.heading(RichText::new("Settings").size(32.0).color(self.theme().accent));
+            ui.add_space(30.0);
 
-        // 先往左数，再往右数，累加，检查是否大于等于 5
-        for i in 1..5 {
-            if x < i || self.board_data[x - i][y] != current {
-                break;
-            }
-            count += 1;
-        }
-        for i in 1..5 {
-            if x + i > 14 || self.board_data[x + i][y] != current {
-                break;
-            }
-            count += 1;
-        }
-        if count >= 5 {
-            return true;
-        } else {
-            count = 1;
-        }
+            // 棋谱和战绩中玩家的名字
+            ui.horizontal(|ui| {
+                ui.label("Player Name");
+                ui.text_edit_singleline(&mut self.settings.player_name);
+            });
 
-        // 先往上数，再往下数，累加，检查是否大于等于 5
-        for i in 1..5 {
-            if y < i || self.board_data[x][y - i] != current {
-                break;
-            }
-            count += 1;
-        }
-        for i in 1..5 {
-            if y + i > 14 || self.board_data[x][y + i] != current {
-                break;
-            }
-            count += 1;
-        }
-        if count >= 5 {
-            return true;
-        } else {
-            count = 1;
-        }
+            // 界面主题，选中后立即生效
+            egui::ComboBox::from_label("Theme")
+                .selected_text(self.settings.theme.name())
+                .show_ui(ui, |ui| {
+                    for kind in ThemeKind::ALL {
+                        ui.selectable_value(&mut self.settings.theme, kind, kind.name());
+                    }
+                });
 
-        // 先往左上数，再往右下数，累加，检查是否大于等于 5
-        for i in 1..5 {
-            if x < i || y < i || self.board_data[x - i][y - i] != current {
-                break;
+            // 对动态效果敏感的玩家可以关闭过渡动画
+            ui.checkbox(&mut self.settings.reduce_motion, "Reduce motion");
+
+            // 音量和静音，没有音频设备时只显示提示
+            if self.audio_manager.is_available() {
+                ui.horizontal(|ui| {
+                    ui.add_enabled(!self.settings.muted, egui::Slider::new(&mut self.settings.volume, 0.0..=1.0).text("Volume"));
+                    ui.checkbox(&mut self.settings.muted, "Mute");
+                });
+            } else {
+                ui.label(RichText::new("No audio device found, sound is off").color(self.theme().muted));
             }
-            count += 1;
-        }
-        for i in 1..5 {
-            if x + i > 14 || y + i > 14 || self.board_data[x + i][y + i] != current {
-                break;
+
+            // 自定义音效所在的目录
+            if dialog::AVAILABLE {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Sound Pack: {}", self.settings.sound_dir().display()));
+                    if ui.button("Choose...").clicked() {
+                        if let Some(dir) = dialog::sound_folder(&self.settings.sound_dir()) {
+                            self.settings.sound_dir = dir.display().to_string();
+                            self.assets.set_override(AssetKind::Sound, Some(dir));
+                            self.audio_manager.load_sounds(&self.assets);
+                        }
+                    }
+                    if !self.settings.sound_dir.is_empty() && ui.button("Default").clicked() {
+                        self.settings.sound_dir.clear();
+                        self.assets.set_override(AssetKind::Sound, None);
+                        self.audio_manager.load_sounds(&self.assets);
+                    }
+                });
             }
-            count += 1;
-        }
-        if count >= 5 {
-            return true;
-        } else {
-            count = 1;
-        }
 
-        // 先往左下数，再往右上数，累加，检查是否大于等于 5
-        // 往左下是 x- y+
-        for i in 1..5 {
-            if x < i || y + i > 14 || self.board_data[x - i][y + i] != current {
-                break;
+            // 黑白双方的落子音效，音效目录中的自定义音效优先
+            egui::ComboBox::from_label("Black Sound")
+                .selected_text(self.settings.black_sound.name())
+                .show_ui(ui, |ui| {
+                    for profile in SoundProfile::ALL {
+                        ui.selectable_value(&mut self.settings.black_sound, profile, profile.name());
+                    }
+                });
+            egui::ComboBox::from_label("White Sound")
+                .selected_text(self.settings.white_sound.name())
+                .show_ui(ui, |ui| {
+                    for profile in SoundProfile::ALL {
+                        ui.selectable_value(&mut self.settings.white_sound, profile, profile.name());
+                    }
+                });
+
+            // 棋谱坐标原点
+            egui::ComboBox::from_label("Coordinate Origin")
+                .selected_text(self.settings.coord_origin.name())
+                .show_ui(ui, |ui| {
+                    for origin in CoordOrigin::ALL {
+                        ui.selectable_value(&mut self.settings.coord_origin, origin, origin.name());
+                    }
+                });
+
+            // 局面分析缓存大小
+            let slider = egui::Slider::new(&mut self.settings.analysis_cache_size, 0..=100_000)
+                .text("Analysis Cache Size");
+            if ui.add(slider).changed() {
+                self.analysis_cache.set_capacity(self.settings.analysis_cache_size);
             }
-            count += 1;
-        }
-        // 往右上是 x+ y-
-        for i in 1..5 {
-            if x + i > 14 || y < i || self.board_data[x + i][y - i] != current {
-                break;
+            let clear_text = format!("Clear Analysis Cache ({} positions)", self.analysis_cache.len());
+            if ui.button(clear_text).clicked() {
+                self.analysis_cache.clear();
+                self.save_analysis_cache();
             }
-            count += 1;
-        }
-        if count >= 5 {
-            return true;
-        }
 
-        false
-    }
+            // 联机教学和赛事中保留的箭头、公告和选手数，新开的教学或赛事生效
+            ui.add(egui::Slider::new(&mut self.settings.arrow_limit, 1..=500).text("Lesson Arrow Limit"));
+            let slider = egui::Slider::new(&mut self.settings.announcement_limit, 1..=200).text("Tournament News Limit");
+            ui.add(slider);
+            ui.add(egui::Slider::new(&mut self.settings.entrant_limit, 2..=512).text("Tournament Player Limit"));
 
-    fn restart(&mut self) {
-        self.board_data = [[0; 15]; 15];
-        self.is_black = true;
-        self.is_winner = false;
-        self.player_is_black = true;  // 重置为玩家黑子先手
-        self.ai_thinking = false;
-        self.ai_delay_timer = 0.0;
-        self.ai_pending_move = None;
-    }
+            // AI 怎样利用从这位玩家的对局中学到的经验
+            egui::ComboBox::from_label("AI Learning")
+                .selected_text(self.settings.ai_learning.name())
+                .show_ui(ui, |ui| {
+                    for adaptation in Adaptation::ALL {
+                        ui.selectable_value(&mut self.settings.ai_learning, adaptation, adaptation.name());
+                    }
+                });
+            egui::ComboBox::from_label("AI First Move")
+                .selected_text(self.settings.ai_first_move.name())
+                .show_ui(ui, |ui| {
+                    for policy in FirstMove::ALL {
+                        ui.selectable_value(&mut self.settings.ai_first_move, policy, policy.name());
+                    }
+                });
+            let slider = egui::Slider::new(&mut self.settings.experience_size, 0..=100_000)
+                .text("Learned Moves Limit");
+            if ui.add(slider).on_hover_text("The least played moves are forgotten first.").changed() {
+                let size = self.settings.experience_size;
+                self.experience().set_capacity(size);
+            }
+            let forget_text = format!("Forget Learned Games ({} moves)", self.experience().len());
+            if ui.button(forget_text).clicked() {
+                self.experience().clear();
+                self.save_experience();
+            }
+            let book_text = format!("Opening Book ({} positions)", self.opening_book.len());
+            ui.checkbox(&mut self.settings.opening_book, book_text);
+            ui.checkbox(&mut self.settings.sgf_analysis, "Add Engine Analysis to Saved SGF").on_hover_text(
+                "Comment every move with the side's winning chances and the engine's choice. Saving takes longer.",
+            );
 
-    /// AI落子逻辑
-    fn ai_move(&mut self, delta_time: f32) {
-        if self.game_mode != GameMode::PlayerVsAI || self.is_winner {
-            return;
-        }
+            // 外部引擎、统计上报和自检都要用到线程或套接字，网页版不显示
+            if NATIVE {
+                // 外部引擎，为空时使用内置 AI
+                ui.horizontal(|ui| {
+                    ui.label("External Engine");
+                    let hint = "pbrain executable or host:port";
+                    ui.add(egui::TextEdit::singleline(&mut self.settings.engine_address).hint_text(hint));
+                });
+                ui.label(RichText::new("Gomocup protocol, five in a row only").size(12.0).color(self.theme().muted));
 
-        // 检查是否轮到AI
-        let ai_piece = if self.player_is_black { 2 } else { 1 }; // AI为白子或黑子
-        let current_piece = if self.is_black { 1 } else { 2 };
-        
-        if current_piece != ai_piece {
-            return; // 不是AI的回合
-        }
+                // 导出的 SGF 用本机密钥签名，选手把公钥交给赛事组织者核对
+                let hint = "Saved SGF records are signed with this computer's key";
+                if ui.button("Copy Record Signing Key").on_hover_text(hint).clicked() {
+                    match signature::local_key(Path::new(SIGNING_KEY_PATH)) {
+                        Ok(key) => ui.output_mut(|o| o.copied_text = signature::public_key(&key)),
+                        Err(e) => self.key_error = Some(format!("The signing key could not be loaded: {:#}", e)),
+                    }
+                }
 
-        // 如果有待执行的移动，检查延迟时间
-        if let Some((x, y)) = self.ai_pending_move {
-            self.ai_delay_timer += delta_time;
-            if self.ai_delay_timer >= 0.5 {
-                // 执行AI移动
-                self.board_data[x][y] = ai_piece;
-                
-                // 播放AI落子音效
-                if ai_piece == 1 {
-                    self.audio_manager.play_black_move();
-                } else {
-                    self.audio_manager.play_white_move();
+                // 匿名使用统计，默认关闭
+                let mut telemetry_changed = ui
+                    .checkbox(&mut self.telemetry.enabled, "Share anonymous usage statistics")
+                    .changed();
+                if self.telemetry.enabled {
+                    ui.horizontal(|ui| {
+                        ui.label("Endpoint");
+                        telemetry_changed |= ui.text_edit_singleline(&mut self.telemetry.endpoint).lost_focus();
+                    });
+                    ui.collapsing("Preview data sent", |ui| {
+                        ui.monospace(self.telemetry.payload());
+                    });
                 }
-                
-                if self.check_winner(x, y) {
-                    self.is_winner = true;
-                    self.ai_pending_move = None;
-                    self.ai_thinking = false;
-                    return;
+                if telemetry_changed {
+                    self.save_telemetry();
                 }
-                self.is_black = !self.is_black;
-                
-                // 重置状态
-                self.ai_pending_move = None;
-                self.ai_thinking = false;
-                self.ai_delay_timer = 0.0;
-            }
-        } else {
-            // 计算AI移动并设置延迟
-            self.ai_thinking = true;
-            let (best_x, best_y) = self.find_best_move();
-            self.ai_pending_move = Some((best_x, best_y));
-            self.ai_delay_timer = 0.0;
-        }
-    }
 
-    /// 寻找最佳落子位置
-    fn find_best_move(&self) -> (usize, usize) {
-        let ai_piece = if self.player_is_black { 2 } else { 1 };
-        let player_piece = if self.player_is_black { 1 } else { 2 };
-        
-        let mut best_score = -1000;
-        let mut best_move = (7, 7); // 默认中心位置
-        
-        // 遍历所有空位
-        for x in 0..15 {
-            for y in 0..15 {
-                if self.board_data[x][y] == 0 {
-                    let score = self.evaluate_position(x, y, ai_piece, player_piece);
-                    if score > best_score {
-                        best_score = score;
-                        best_move = (x, y);
-                    }
+                ui.add_space(30.0);
+
+                if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Diagnostics").size(20.0))).clicked() {
+                    self.start_diagnostics(ui.ctx());
+                    self.game_mode = GameMode::Diagnostics;
                 }
             }
-        }
-        
-        best_move
-    }
 
-    /// 评估位置的价值
-    fn evaluate_position(&self, x: usize, y: usize, ai_piece: u8, player_piece: u8) -> i32 {
-        let mut score = 0;
-        
-        // 检查四个方向
-        let directions = [(1, 0), (0, 1), (1, 1), (1, -1)]; // 水平、垂直、对角线
-        
-        for (dx, dy) in directions {
-            // 评估AI在该方向的得分
-            score += self.evaluate_direction(x, y, dx, dy, ai_piece) * 10;
-            // 评估玩家在该方向的得分（防守）
-            score += self.evaluate_direction(x, y, dx, dy, player_piece) * 8;
-        }
-        
-        // 中心位置加分
-        let center_distance = ((x as i32 - 7).abs() + (y as i32 - 7).abs()) as i32;
-        score += (14 - center_distance) * 2;
-        
-        score
+            ui.add_space(10.0);
+
+            if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Back").size(20.0))).clicked() {
+                self.save_settings();
+                self.game_mode = GameMode::MainMenu;
+            }
+        });
     }
 
-    /// 评估某个方向的得分
-    fn evaluate_direction(&self, x: usize, y: usize, dx: i32, dy: i32, piece: u8) -> i32 {
-        let mut count = 0;
-        let mut blocked = 0;
-        
-        // 向一个方向计数
-        for i in 1..5 {
-            let nx = (x as i32 + dx * i) as usize;
-            let ny = (y as i32 + dy * i) as usize;
-            
-            if nx >= 15 || ny >= 15 {
-                blocked += 1;
+    /// 渲染战绩界面
+    fn render_statistics(&mut self, ui: &mut Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(30.0);
+            ui.heading(RichText::new("Statistics").size(32.0).color(self.theme().accent));
+            ui.add_space(10.0);
+            ui.label(RichText::new(&self.settings.player_name).size(18.0));
+            ui.add_space(20.0);
+
+            egui::Grid::new("statistics").striped(true).min_col_width(70.0).show(ui, |ui| {
+                ui.strong("Mode");
+                ui.strong("Won");
+                ui.strong("Lost");
+                ui.strong("Drawn");
+                ui.strong("Games");
+                ui.end_row();
+
+                let mut rows: Vec<_> = Difficulty::ALL
+                    .iter()
+                    .map(|&difficulty| (format!("vs AI ({})", difficulty.name()), self.stats.ai(difficulty)))
+                    .collect();
+                rows.push(("Network".to_string(), self.stats.network));
+                for (mode, record) in rows {
+                    ui.label(mode);
+                    ui.label(record.wins.to_string());
+                    ui.label(record.losses.to_string());
+                    ui.label(record.draws.to_string());
+                    ui.label(record.games().to_string());
+                    ui.end_row();
+                }
+            });
+
+            // 双人对战没有“自己”一方，单独按黑白胜负显示
+            let pvp = self.stats.pvp;
+            ui.add_space(10.0);
+            ui.label(format!(
+                "Player vs Player: {} games, Black won {}, White won {}, {} drawn",
+                pvp.games(),
+                pvp.wins,
+                pvp.losses,
+                pvp.draws
+            ));
+
+            self.render_weakness_report(ui);
+
+            ui.add_space(30.0);
+
+            if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Reset").size(20.0))).clicked() {
+                self.stats = Statistics::default();
+                self.save_stats();
+                self.weakness = SkillReport::default();
+                self.save_weakness();
+            }
+
+            ui.add_space(10.0);
+
+            if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Back").size(20.0))).clicked() {
+                self.game_mode = GameMode::MainMenu;
+            }
+        });
+    }
+
+    /// 弱点报告：人机和联机对局里做得最差的几项，每项都可以直接去做对应的练习
+    fn render_weakness_report(&mut self, ui: &mut Ui) {
+        ui.add_space(20.0);
+        ui.heading(RichText::new("Weakness Report").color(self.theme().accent));
+        let weakest = self.weakness.weakest(weakness::MIN_CHANCES);
+        if weakest.is_empty() {
+            let hint = "Play a few games against the AI or online to see where you slip.";
+            ui.label(RichText::new(hint).color(self.theme().muted));
+            return;
+        }
+        for (skill, rate) in weakest {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} ({} chances)", skill.describe(rate), rate.chances));
+                let drill = weakness::drill(skill);
+                if ui.button(format!("Practice: {}", drill.title())).clicked() {
+                    self.open_drill(drill);
+                }
+            });
+        }
+    }
+
+    /// 打开针对某项弱点的练习
+    fn open_drill(&mut self, drill: Drill) {
+        match drill {
+            Drill::Puzzle(index) => {
+                self.game_mode = GameMode::Puzzle;
+                self.puzzle = PuzzleSession::default();
+                self.puzzle.go_to(index);
+            }
+            Drill::Tutorial(index) => {
+                self.game_mode = GameMode::Tutorial;
+                self.tutorial.go_to(index);
+            }
+        }
+    }
+
+    /// 渲染诊断界面：逐项显示检查结果，可以复制报告给支持人员
+    fn render_diagnostics(&mut self, ui: &mut Ui) {
+        let running = self.diagnostics.as_mut().is_some_and(Diagnostics::poll);
+        if running {
+            ui.ctx().request_repaint();
+        }
+        ui.vertical_centered(|ui| {
+            ui.add_space(30.0);
+            ui.heading(RichText::new("Diagnostics").size(32.0).color(self.theme().accent));
+            ui.add_space(20.0);
+
+            if let Some(diagnostics) = &self.diagnostics {
+                egui::ScrollArea::vertical().max_height(ui.available_height() - 150.0).show(ui, |ui| {
+                    egui::Grid::new("diagnostics").striped(true).show(ui, |ui| {
+                        for check in &diagnostics.checks {
+                            ui.label(&check.name);
+                            if check.passed {
+                                ui.label(RichText::new("OK").color(self.theme().success));
+                            } else {
+                                ui.label(RichText::new("FAIL").color(self.theme().warning));
+                            }
+                            ui.label(&check.detail);
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+            if running {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Running checks...");
+                });
+            }
+
+            ui.add_space(20.0);
+
+            if ui.add_enabled(!running, egui::Button::new("Run Again")).clicked() {
+                self.start_diagnostics(ui.ctx());
+            }
+            if ui.button("Copy Report").clicked() {
+                let report = self.diagnostics.as_ref().map(Diagnostics::report).unwrap_or_default();
+                ui.output_mut(|o| o.copied_text = report);
+            }
+
+            ui.add_space(10.0);
+
+            if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Back").size(20.0))).clicked() {
+                self.game_mode = GameMode::Settings;
+            }
+        });
+    }
+
+    /// 开始诊断：音频、渲染器和显示在这里检查，其余的交给后台线程
+    fn start_diagnostics(&mut self, ctx: &egui::Context) {
+        let screen = ctx.screen_rect().size();
+        let checks = vec![
+            self.audio_check(),
+            Check::new("Renderer", self.renderer != "unknown", self.renderer.clone()),
+            Check::new(
+                "Display",
+                true,
+                format!("{:.0} x {:.0} points at {:.2} pixels per point", screen.x, screen.y, ctx.pixels_per_point()),
+            ),
+        ];
+        let files = [
+            SETTINGS_PATH,
+            SAVE_PATH,
+            STATS_PATH,
+            WEAKNESS_PATH,
+            CACHE_PATH,
+            TELEMETRY_PATH,
+            BOOK_PATH,
+            SIGNING_KEY_PATH,
+        ]
+        .into_iter()
+            .map(PathBuf::from)
+            .chain([experience::profile_path(&self.settings.player_name)])
+            .collect();
+        let host_port = self.net_port.trim().parse().unwrap_or(DEFAULT_PORT);
+        // 本机地址只有自己在等待时才连得上，不需要检查
+        let address = self.net_address.trim();
+        let remote = if address.is_empty() || address == "127.0.0.1" || address == "localhost" {
+            String::new()
+        } else {
+            format!("{}:{}", address, host_port)
+        };
+        let targets = Targets { files, host_port, remote, engine: self.settings.engine_address.clone() };
+        self.diagnostics = Some(Diagnostics::start(checks, targets));
+    }
+
+    /// 音频设备的检查结果
+    fn audio_check(&self) -> Check {
+        if self.audio_manager.is_available() {
+            Check::new("Audio", true, "output device ready")
+        } else {
+            Check::new("Audio", false, "no output device, sound is off")
+        }
+    }
+
+    /// 渲染联机大厅：创建或加入对局，等待连接时可以取消
+    fn render_lobby(&mut self, ui: &mut Ui) {
+        ui.vertical_centered(|ui| {
+            ui.add_space(15.0);
+            ui.heading(RichText::new("Network Game").size(30.0).color(self.theme().accent));
+            ui.add_space(15.0);
+
+            let status = self.network.as_ref().map(|n| n.status().clone());
+            match status {
+                Some(NetStatus::Waiting) if self.net_resume.is_some() => {
+                    ui.label(format!("Waiting for your opponent to rejoin on port {}...", self.net_port.trim()));
+                    if ui.button("Cancel").clicked() {
+                        self.network = None;
+                    }
+                }
+                Some(NetStatus::Waiting) => {
+                    ui.label(format!("Waiting for an opponent on port {}...", self.net_port.trim()));
+                    if ui.button("Cancel").clicked() {
+                        self.network = None;
+                    }
+                }
+                Some(NetStatus::Connecting) => {
+                    ui.label(format!("Connecting to {}:{}...", self.net_address.trim(), self.net_port.trim()));
+                    if ui.button("Cancel").clicked() {
+                        self.network = None;
+                    }
+                }
+                // 重新连接失败之后可以再试，或者放弃这局
+                _ if self.net_resume.is_some() => {
+                    ui.label("Resume the interrupted game");
+                    self.render_resume(ui);
+                    if ui.button("Abandon Game").clicked() {
+                        self.net_resume = None;
+                    }
+                }
+                _ => {
+                    ui.horizontal(|ui| {
+                        ui.label("Address");
+                        ui.text_edit_singleline(&mut self.net_address);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Port");
+                        ui.text_edit_singleline(&mut self.net_port);
+                    });
+                    ui.add_space(10.0);
+
+                    // 主机执黑先行，加入的一方执白
+                    let port = self.net_port.trim().parse::<u16>();
+                    let host = ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Host (Black)").size(20.0)));
+                    let time_hint = format!("Both players use your time control: {}", self.settings.time_control.label());
+                    if host.on_hover_text(time_hint).clicked() {
+                        match port {
+                            Ok(port) => self.host_network(port, None),
+                            Err(_) => self.net_message = "Invalid port".to_string(),
+                        }
+                    }
+                    ui.add_space(10.0);
+                    if ui.add_sized([200.0, 40.0], egui::Button::new(RichText::new("Join (White)").size(20.0))).clicked() {
+                        match port {
+                            Ok(port) => self.join_network(port, None),
+                            Err(_) => self.net_message = "Invalid port".to_string(),
+                        }
+                    }
+
+                    // 教学：老师创建，学生加入，棋盘和标注同步到学生一方
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Teach (Host)").clicked() {
+                            match port {
+                                Ok(port) => self.host_network(port, Some(Lesson::new(Role::Teacher, self.settings.arrow_limit))),
+                                Err(_) => self.net_message = "Invalid port".to_string(),
+                            }
+                        }
+                        if ui.button("Join as Student").clicked() {
+                            match port {
+                                Ok(port) => self.join_network(port, Some(Lesson::new(Role::Student, self.settings.arrow_limit))),
+                                Err(_) => self.net_message = "Invalid port".to_string(),
+                            }
+                        }
+                    });
+
+                    // 解题对战：双方做同一组题，比谁在限时内得分更高
+                    ui.horizontal(|ui| {
+                        if ui.button("Puzzle Duel (Host)").clicked() {
+                            match port {
+                                Ok(port) => {
+                                    self.host_network(port, None);
+                                    self.duel = self.network.is_some().then(|| Duel::new(true));
+                                }
+                                Err(_) => self.net_message = "Invalid port".to_string(),
+                            }
+                        }
+                        if ui.button("Join Puzzle Duel").clicked() {
+                            match port {
+                                Ok(port) => {
+                                    self.join_network(port, None);
+                                    self.duel = Some(Duel::new(false));
+                                }
+                                Err(_) => self.net_message = "Invalid port".to_string(),
+                            }
+                        }
+                    });
+
+                    // 赛事：裁判在这个端口上接受所有选手，选手连上裁判之后按编排对局
+                    ui.horizontal(|ui| {
+                        if ui.button("Direct Tournament").clicked() {
+                            match port {
+                                Ok(port) => match Director::start(port, port.wrapping_add(1), self.settings.entrant_limit) {
+                                    Ok(director) => {
+                                        self.director = Some(director);
+                                        self.net_message.clear();
+                                    }
+                                    Err(e) => self.net_message = format!("{:#}", e),
+                                },
+                                Err(_) => self.net_message = "Invalid port".to_string(),
+                            }
+                        }
+                        if self.tournament.is_none() && ui.button("Join Tournament").clicked() {
+                            match port {
+                                Ok(port) => {
+                                    let address = format!("{}:{}", self.net_address.trim(), port);
+                                    self.tournament = Some(Entry::join(&address, self.settings.announcement_limit));
+                                    self.net_message.clear();
+                                }
+                                Err(_) => self.net_message = "Invalid port".to_string(),
+                            }
+                        }
+                    });
+                }
+            }
+
+            self.render_tournament_entry(ui);
+
+            // 上一次连接失败的原因
+            if !self.net_message.is_empty() {
+                ui.add_space(10.0);
+                ui.label(RichText::new(&self.net_message).color(self.theme().error));
+            }
+
+            ui.add_space(20.0);
+            if ui.button("Back to Menu").clicked() {
+                self.network = None;
+                self.game_mode = GameMode::MainMenu;
+            }
+        });
+    }
+
+    /// 选手一方的赛事面板：与裁判的连接、最近的公告和裁判编排的对局
+    fn render_tournament_entry(&mut self, ui: &mut Ui) {
+        let Some(entry) = self.tournament.as_ref() else {
+            return;
+        };
+        ui.add_space(10.0);
+        ui.separator();
+        match entry.session.status() {
+            NetStatus::Connected => ui.label("Tournament: connected to the director"),
+            NetStatus::Closed(reason) => {
+                ui.label(RichText::new(format!("Tournament: {}", reason)).color(self.theme().error))
+            }
+            _ => ui.label("Tournament: connecting to the director..."),
+        };
+        for text in entry.announcements.iter().rev().take(5) {
+            ui.label(RichText::new(text).color(self.theme().accent));
+        }
+        if let Some(pairing) = entry.pairing.clone() {
+            ui.label(format!("Round {}, table {}: you play {}", pairing.round, pairing.table, pairing.stone.name()));
+            if self.network.is_none() && ui.button("Start Game").clicked() {
+                self.start_pairing(&pairing);
+            }
+        }
+        if ui.button("Leave Tournament").clicked() {
+            self.tournament = None;
+        }
+    }
+
+    /// 按裁判的编排开始对局：执黑的在编排的端口上创建，执白的加入执黑一方
+    fn start_pairing(&mut self, pairing: &Pairing) {
+        let Some((host, port)) = pairing.host_and_port() else {
+            self.net_message = "The director sent an invalid address".to_string();
+            return;
+        };
+        self.net_port = port.to_string();
+        if pairing.stone == Stone::Black {
+            self.host_network(port, None);
+        } else {
+            self.net_address = host.to_string();
+            self.join_network(port, None);
+        }
+        if let Some(entry) = self.tournament.as_mut() {
+            entry.playing = self.network.is_some();
+        }
+    }
+
+    /// 裁判一方的面板：选手名单、编排本轮的对阵、判定结果、调整棋钟和发布公告
+    fn render_director(&mut self, ui: &mut Ui) {
+        let accent = self.theme().accent;
+        let Some(director) = self.director.as_mut() else {
+            return;
+        };
+        ui.vertical_centered(|ui| {
+            ui.add_space(15.0);
+            ui.heading(RichText::new(format!("Tournament Round {}", director.round)).size(30.0).color(accent));
+            ui.label(format!("Players join on port {}", director.port));
+        });
+        ui.add_space(10.0);
+
+        ui.label(RichText::new("Players").strong());
+        for (i, entrant) in director.entrants.iter().enumerate() {
+            let state = match entrant.session.status() {
+                NetStatus::Connected => "connected".to_string(),
+                NetStatus::Closed(reason) => reason.clone(),
+                _ => "connecting".to_string(),
+            };
+            ui.label(format!("{}. {} ({})", i + 1, entrant.label(), state));
+        }
+
+        // 编排对阵：执黑的一方在对局端口上创建对局，执白的一方加入
+        ui.separator();
+        let names: Vec<String> = director.entrants.iter().map(|entrant| entrant.label()).collect();
+        let mut pair = false;
+        ui.horizontal(|ui| {
+            for (label, pick) in [("Black", &mut director.black), ("White", &mut director.white)] {
+                let selected = names.get(*pick).cloned().unwrap_or_default();
+                egui::ComboBox::from_label(label).selected_text(selected).show_ui(ui, |ui| {
+                    for (i, name) in names.iter().enumerate() {
+                        ui.selectable_value(pick, i, name);
+                    }
+                });
+            }
+            ui.label("Game Port");
+            ui.add(egui::TextEdit::singleline(&mut director.game_port).desired_width(60.0));
+            pair = ui.button("Pair").clicked();
+        });
+        if pair {
+            let (black, white) = (director.black, director.white);
+            match director.pair(black, white) {
+                Ok(()) => self.net_message.clear(),
+                Err(reason) => self.net_message = reason.to_string(),
+            }
+        }
+
+        // 本轮的台次：选手上报的结果，裁判可以改判或者调整棋钟
+        ui.separator();
+        let mut verdict = None;
+        let mut adjustment = None;
+        for (t, table) in director.tables.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Table {}: {} vs {}", t + 1, names[table.black], names[table.white]));
+                let result = match table.result {
+                    Some(Some(stone)) => format!("{} wins", stone.name()),
+                    Some(None) => "Draw".to_string(),
+                    None => "Playing".to_string(),
+                };
+                ui.label(RichText::new(result).strong());
+                let verdicts = [("Black Wins", Some(Stone::Black)), ("White Wins", Some(Stone::White)), ("Draw", None)];
+                for (label, winner) in verdicts {
+                    if ui.button(label).clicked() {
+                        verdict = Some((t, winner));
+                    }
+                }
+                for stone in [Stone::Black, Stone::White] {
+                    if ui.button(format!("{} +30s", stone.name())).clicked() {
+                        adjustment = Some((t, stone, DIRECTOR_CLOCK_STEP));
+                    }
+                    if ui.button(format!("{} -30s", stone.name())).clicked() {
+                        adjustment = Some((t, stone, -DIRECTOR_CLOCK_STEP));
+                    }
+                }
+            });
+        }
+        if let Some((t, winner)) = verdict {
+            director.force_result(t, winner);
+        }
+        if let Some((t, stone, delta)) = adjustment {
+            director.adjust_clock(t, stone, delta);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Announcement");
+            ui.text_edit_singleline(&mut director.draft);
+            if ui.button("Send").clicked() {
+                director.announce();
+            }
+        });
+
+        ui.add_space(10.0);
+        if !self.net_message.is_empty() {
+            ui.label(RichText::new(&self.net_message).color(self.theme().error));
+        }
+        ui.horizontal(|ui| {
+            if let Some(director) = self.director.as_mut() {
+                if ui.button("Next Round").clicked() {
+                    director.next_round();
+                }
+            }
+            if ui.button("End Tournament").clicked() {
+                self.director = None;
+            }
+        });
+    }
+
+    /// 在 port 上等待对方加入，lesson 不为 None 时连上之后进入教学
+    fn host_network(&mut self, port: u16, lesson: Option<Lesson>) {
+        match NetSession::host(port) {
+            Ok(session) => {
+                self.network = Some(session);
+                self.lesson = lesson;
+                self.duel = None;
+                self.net_resume = None;
+                self.net_message.clear();
+            }
+            Err(e) => self.net_message = format!("{:#}", e),
+        }
+    }
+
+    /// 加入大厅中填写的地址，lesson 不为 None 时连上之后进入教学
+    fn join_network(&mut self, port: u16, lesson: Option<Lesson>) {
+        let address = format!("{}:{}", self.net_address.trim(), port);
+        self.network = Some(NetSession::join(&address));
+        self.lesson = lesson;
+        self.duel = None;
+        self.net_resume = None;
+        self.net_message.clear();
+    }
+
+    /// 断线之后重新创建（host 为 true）或重新加入连接，沿用原来的颜色，对局记录留在本地等双方核对
+    fn resume_network(&mut self, host: bool) {
+        let Some(local) = self.net_resume.or(self.network.as_ref().map(NetSession::local_stone)) else {
+            return;
+        };
+        let Ok(port) = self.net_port.trim().parse::<u16>() else {
+            self.net_message = "Invalid port".to_string();
+            return;
+        };
+        let previous = self.network.take();
+        if host {
+            self.host_network(port, None);
+        } else {
+            self.join_network(port, None);
+        }
+        match self.network.as_mut() {
+            Some(session) => session.keep_stone(local),
+            // 创建失败时留在原来的提示里显示原因
+            None => self.network = previous,
+        }
+        self.net_resume = Some(local);
+    }
+
+    /// 断线提示里恢复对局的选项：任何一方重新创建连接，另一方填上它的地址重新加入
+    fn render_resume(&mut self, ui: &mut Ui) {
+        ui.label("Either player can host the game again; the other rejoins and both records are checked.");
+        ui.horizontal(|ui| {
+            ui.label("Address");
+            ui.text_edit_singleline(&mut self.net_address);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Port");
+            ui.text_edit_singleline(&mut self.net_port);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Host Again").clicked() {
+                self.resume_network(true);
+            }
+            if ui.button("Rejoin").clicked() {
+                self.resume_network(false);
+            }
+        });
+    }
+
+    /// 核对对方恢复对局时发来的记录，步数和局面校验码都一致才接着下
+    fn verify_resume(&mut self, ply: usize, checksum: Checksum) {
+        let local = self.game.checksum();
+        let moves = self.game.moves().len();
+        if self.net_resume.is_none() {
+            self.close_network("Opponent chose a different mode");
+        } else if ply != moves || checksum != local {
+            self.close_network(&format!(
+                "Game records differ (yours {} moves, {}; opponent's {} moves, {})",
+                moves, local, ply, checksum
+            ));
+        } else {
+            self.net_resume = None;
+        }
+    }
+
+    /// 渲染教学界面：老师的工具栏或学生的提示、同步的棋盘和箭头
+    fn render_lesson(&mut self, ui: &mut Ui) {
+        let closed = match self.network.as_ref().map(NetSession::status) {
+            Some(NetStatus::Closed(reason)) => Some(reason.clone()),
+            _ => None,
+        };
+        let theme = *self.theme();
+        let mut outgoing = Vec::new();
+        let mut leave = false;
+        let Some(lesson) = self.lesson.as_mut() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            if ui.button("Back to Menu").clicked() {
+                leave = true;
+            }
+            match lesson.role {
+                Role::Teacher => {
+                    for tool in Tool::ALL {
+                        ui.selectable_value(&mut lesson.tool, tool, tool.name());
+                    }
+                    if ui.button("Clear Board").clicked() {
+                        outgoing.push(lesson.command(Message::ClearBoard));
+                    }
+                    if ui.button("Clear Arrows").clicked() {
+                        outgoing.push(lesson.command(Message::ClearArrows));
+                    }
+                    // 交给学生做练习，或者收回落子权
+                    let student = lesson.student_in_control;
+                    let text = if student { "Take Control" } else { "Hand to Student" };
+                    if ui.button(text).clicked() {
+                        outgoing.push(lesson.command(Message::Control { student: !student }));
+                    }
+                }
+                Role::Student if lesson.student_in_control => {
+                    ui.label(format!("Your exercise: play {}", lesson.to_move.name()));
+                }
+                Role::Student => {
+                    ui.label("Watching the teacher");
+                }
+            }
+            if let Some(line) = lesson.last_move.and_then(|mv| lesson.board.winning_line(mv, Rules::DEFAULT_WIN_LENGTH)) {
+                ui.label(RichText::new(format!("{} made five", lesson.board.get(line.start).name())).strong());
+            }
+        });
+        if let Some(reason) = &closed {
+            ui.label(RichText::new(reason).color(theme.error));
+        }
+
+        let board = lesson.board;
+        self.render_board(ui);
+        self.render_piece(ui, &board);
+        let Some(lesson) = self.lesson.as_mut() else {
+            return;
+        };
+        let geometry = self.geometry;
+        if let Some(coord) = lesson.last_move {
+            ui.painter().circle_filled(geometry.pixel_at(coord), geometry.stone_radius() * theme.last_move_ratio, theme.last_move);
+        }
+        let stroke = egui::Stroke::new(theme.annotation_width, theme.annotation);
+        for &(from, to) in &lesson.arrows {
+            let from = geometry.pixel_at(from);
+            ui.painter().arrow(from, geometry.pixel_at(to) - from, stroke);
+        }
+        if let Some(start) = lesson.arrow_start {
+            ui.painter().circle_stroke(geometry.pixel_at(start), geometry.stone_radius(), stroke);
+        }
+
+        // 工具栏按钮先占用点击，棋盘只响应落在按钮以外的点击
+        let response = ui.interact(geometry.hit_rect(), ui.id().with("lesson_board"), egui::Sense::click());
+        let clicked = response.interact_pointer_pos().filter(|_| response.clicked() && closed.is_none());
+        if let Some(coord) = clicked.and_then(|pos| geometry.snap(pos)) {
+            outgoing.extend(lesson.click(coord));
+        }
+
+        if let Some(session) = self.network.as_mut() {
+            for message in outgoing {
+                session.send(message);
+            }
+        }
+        if leave {
+            self.network = None;
+            self.lesson = None;
+            self.game_mode = GameMode::MainMenu;
+        }
+    }
+
+    /// 处理教学中对方发来的消息，不合法的消息结束教学
+    fn poll_lesson(&mut self) {
+        while let Some(event) = self.network.as_mut().and_then(NetSession::poll) {
+            let NetEvent::Received(message) = event else {
+                continue;
+            };
+            let result = match (message, self.lesson.as_mut()) {
+                (Message::Bye, _) => Err("The other side left the lesson"),
+                (message, Some(lesson)) => lesson.receive(message),
+                (_, None) => Ok(()),
+            };
+            if let Err(reason) = result {
+                self.close_network(reason);
+            }
+        }
+    }
+
+    /// 渲染解题对战：双方得分、剩余时间和当前题目
+    fn render_duel(&mut self, ui: &mut Ui) {
+        let closed = match self.network.as_ref().map(NetSession::status) {
+            Some(NetStatus::Closed(reason)) => Some(reason.clone()),
+            _ => None,
+        };
+        let theme = *self.theme();
+        let mut outgoing = None;
+        let mut leave = false;
+        let Some(duel) = self.duel.as_mut() else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            if ui.button("Back to Menu").clicked() {
+                leave = true;
+            }
+            let playing = duel.is_started() && !duel.is_over() && closed.is_none();
+            let can_hint = playing && duel.puzzles.hint < HintStage::Move;
+            if ui.add_enabled(can_hint, egui::Button::new("Hint")).clicked() {
+                duel.puzzles.take_hint();
+            }
+            if ui.add_enabled(playing, egui::Button::new("Skip")).clicked() {
+                outgoing = duel.skip();
+            }
+            ui.label(format!("Time: {}", clock::format_time(duel.remaining)));
+            ui.label(format!(
+                "You: {} ({}/{})   Opponent: {} ({}/{})",
+                duel.puzzles.score,
+                duel.round,
+                duel.order.len(),
+                duel.opponent_score,
+                duel.opponent_rounds,
+                duel.order.len()
+            ));
+        });
+        let status = match duel.outcome() {
+            _ if !duel.is_started() => "Waiting for the host to start the duel".to_string(),
+            Some(std::cmp::Ordering::Greater) => format!("You win {} : {}", duel.puzzles.score, duel.opponent_score),
+            Some(std::cmp::Ordering::Less) => format!("You lose {} : {}", duel.puzzles.score, duel.opponent_score),
+            Some(std::cmp::Ordering::Equal) => format!("Draw {} : {}", duel.puzzles.score, duel.opponent_score),
+            None => {
+                let puzzle = duel.puzzles.puzzle();
+                let title = format!("{}/{}: {} ({} to move)", duel.round + 1, duel.order.len(), puzzle.title, puzzle.to_move.name());
+                match duel.puzzles.message.as_str() {
+                    "" => title,
+                    feedback => format!("{}   {}", title, feedback),
+                }
+            }
+        };
+        ui.label(RichText::new(status).strong());
+        if let Some(reason) = &closed {
+            ui.label(RichText::new(reason).color(theme.error));
+        }
+
+        let board = duel.puzzles.board;
+        let playing = duel.is_started() && !duel.is_over() && closed.is_none();
+        let started = duel.is_started();
+        self.render_board(ui);
+        if started {
+            self.render_piece(ui, &board);
+        }
+        if playing {
+            if let Some(duel) = self.duel.as_ref() {
+                self.render_puzzle_hint(ui, &duel.puzzles);
+            }
+
+            // 工具栏按钮先占用点击，棋盘只响应落在按钮以外的点击
+            let response = ui.interact(self.geometry.hit_rect(), ui.id().with("duel_board"), egui::Sense::click());
+            let clicked = response.interact_pointer_pos().filter(|_| response.clicked());
+            if let (Some(coord), Some(duel)) = (clicked.and_then(|pos| self.geometry.snap(pos)), self.duel.as_mut()) {
+                let stone = duel.puzzles.puzzle().to_move;
+                outgoing = outgoing.or(duel.try_move(coord));
+                if outgoing.is_some() {
+                    self.play_stone_sound(stone);
+                }
+            }
+        }
+
+        if let (Some(session), Some(message)) = (self.network.as_mut(), outgoing) {
+            session.send(message);
+        }
+        if leave {
+            self.network = None;
+            self.duel = None;
+            self.game_mode = GameMode::MainMenu;
+        }
+    }
+
+    /// 处理解题对战中对方发来的消息，不合法的消息结束对战
+    fn poll_duel(&mut self) {
+        while let Some(event) = self.network.as_mut().and_then(NetSession::poll) {
+            let NetEvent::Received(message) = event else {
+                continue;
+            };
+            let result = match (message, self.duel.as_mut()) {
+                (Message::Bye, Some(duel)) if duel.is_over() => Ok(()),
+                (Message::Bye, _) => Err("Opponent left the duel"),
+                (message, Some(duel)) => duel.receive(message),
+                (_, None) => Ok(()),
+            };
+            if let Err(reason) = result {
+                self.close_network(reason);
+            }
+        }
+    }
+
+    /// 渲染联机对局：工具栏、棋盘，以及结束或断线提示
+    fn render_network_game(&mut self, ctx: &egui::Context, ui: &mut Ui) {
+        let Some(session) = self.network.as_ref() else {
+            return;
+        };
+        let local = session.local_stone();
+        let dropped = session.was_dropped();
+        let closed_reason = match session.status() {
+            NetStatus::Closed(reason) => Some(reason.clone()),
+            _ => None,
+        };
+
+        ui.horizontal(|ui| {
+            if ui.button("Back to Menu").clicked() {
+                self.request_leave();
+                return;
+            }
+            if dialog::AVAILABLE && ui.button("Save Game").clicked() {
+                self.save_game_as();
+            }
+
+            let side = self.game.to_move();
+            let who = if side == local { "You" } else { "Opponent" };
+            if self.game.is_over() {
+                ui.label(RichText::new(self.winner_text()).strong());
+            } else {
+                ui.label(format!("Current Turn: {} ({})", who, side.name()));
+            }
+            if let Some(clock) = &self.clock {
+                for stone in [Stone::Black, Stone::White] {
+                    ui.label(self.clock_text(clock, stone));
+                }
+            }
+            if let Some(text) = self.cursor_text() {
+                ui.label(text);
+            }
+            if !self.game.is_over() && closed_reason.is_none() {
+                self.render_move_entry(ui);
+            }
+            self.render_checksum(ui);
+
+            if let Some(ply) = self.view_ply {
+                ui.label(format!("Move {}/{}", ply, self.game.moves().len()));
+            }
+            if let Some(text) = self.tournament.as_ref().and_then(|entry| entry.announcements.last()) {
+                ui.label(RichText::new(text).color(self.theme().accent));
+            }
+        });
+
+        self.handle_history_keys(ctx);
+        if closed_reason.is_none() {
+            self.handle_play_keys(ctx);
+        }
+
+        self.render_board(ui);
+        self.render_position(ui);
+        self.render_cursor(ui);
+
+        if self.game.is_over() || closed_reason.is_some() {
+            let title = if self.game.is_over() { self.winner_text() } else { "Game ended" };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.vertical_centered(|ui| {
+                        if let Some(reason) = closed_reason.as_ref().filter(|_| !self.game.is_over()) {
+                            ui.label(reason);
+                        }
+                        if dropped && !self.game.is_over() {
+                            ui.separator();
+                            self.render_resume(ui);
+                            if !self.net_message.is_empty() {
+                                ui.label(RichText::new(&self.net_message).color(self.theme().error));
+                            }
+                            ui.separator();
+                        }
+                        if ui.button("Back to Menu").clicked() {
+                            self.network = None;
+                            self.game_mode = GameMode::MainMenu;
+                        }
+                        if ui.button("Copy Record").clicked() {
+                            let record = self.export_kifu();
+                            ui.output_mut(|o| o.copied_text = record);
+                        }
+                        if dialog::AVAILABLE && ui.button("Save Game").clicked() {
+                            self.save_game_as();
+                        }
+                        if ui.button("Review").clicked() {
+                            self.open_replay();
+                        }
+                    });
+                });
+            return;
+        }
+
+        // 工具栏按钮先占用点击，棋盘只响应落在按钮以外的点击
+        let board_rect = self.geometry.hit_rect();
+        let response = ui.interact(board_rect, ui.id().with("network_board"), egui::Sense::click());
+        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+            self.handle_click(pos);
+        }
+        self.render_pointer_stone(ui, &response);
+        self.render_stone_tooltip(ui, &response);
+    }
+
+    /// 按 ui 剩余的空间重新计算棋盘大小，绘制棋盘和四周的坐标，行号按设置的原点编号，与棋谱记法一致
+    ///
+    /// 之后的棋子绘制和点击判断都使用这里算出的 `geometry`，每帧先调用这个函数。
+    fn render_board(&mut self, ui: &Ui) {
+        self.geometry = BoardGeometry::fit(ui.available_rect_before_wrap());
+        let theme = self.theme();
+        let stroke = egui::Stroke::new(theme.grid_width, theme.grid);
+        for line in self.geometry.grid_lines() {
+            ui.painter().line_segment(line, stroke);
+        }
+        let font = egui::FontId::proportional(self.geometry.spacing * LABEL_FONT_RATIO);
+        for i in 0..BOARD_SIZE {
+            let letter = ((b'A' + i as u8) as char).to_string();
+            let number = self.settings.coord_origin.row_number(i).to_string();
+            let painter = ui.painter();
+            painter.text(self.geometry.col_label_at(i), egui::Align2::CENTER_CENTER, letter, font.clone(), theme.labels);
+            painter.text(self.geometry.row_label_at(i), egui::Align2::CENTER_CENTER, number, font.clone(), theme.labels);
+        }
+    }
+
+    /// 右侧的落子列表，点击一步跳到那一步的局面，只在可以回看时可用
+    fn render_move_list(&mut self, ctx: &egui::Context) {
+        let browsable = self.can_browse();
+        let current = self.view_ply.unwrap_or(self.game.moves().len());
+        let mut target = None;
+        egui::SidePanel::right("move_list").resizable(false).exact_width(130.0).show(ctx, |ui| {
+            ui.heading("Moves");
+            egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                for (i, &mv) in self.game.moves().iter().enumerate() {
+                    let text = format!("{}. {}", i + 1, mv.notation(self.settings.coord_origin));
+                    let response = ui
+                        .add_enabled(browsable, egui::SelectableLabel::new(current == i + 1, text))
+                        .on_disabled_hover_text("Pause or finish the game to review moves");
+                    if response.clicked() {
+                        target = Some(i + 1);
+                    }
+                }
+            });
+        });
+        if let Some(ply) = target {
+            self.show_ply(ply);
+        }
+    }
+
+    /// 能否回看之前的局面：复盘中、对局结束或暂停时
+    fn can_browse(&self) -> bool {
+        self.game_mode == GameMode::Replay || self.game.is_over() || self.is_paused
+    }
+
+    /// 当前设置的界面主题
+    fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// 按设置读取主题，主题文件有错时提示并使用内置的颜色
+    fn load_theme(&mut self) {
+        let kind = self.settings.theme;
+        self.theme = kind.load(&self.assets).unwrap_or_else(|e| {
+            eprintln!("Failed to load theme: {:#}", e);
+            *kind.theme()
+        });
+        self.theme_kind = kind;
+    }
+
+    /// 换了主题时重新读取；调试版中资源文件有改动时重新读取音效和主题，不必重启
+    fn refresh_assets(&mut self) {
+        let changed = self.assets.poll(self.frame_millis);
+        if changed {
+            self.audio_manager.load_sounds(&self.assets);
+        }
+        if changed || self.theme_kind != self.settings.theme {
+            self.load_theme();
+        }
+    }
+
+    /// 绘制棋子，按主题画成平面或立体的
+    fn render_piece(&self, ui: &Ui, board: &Board) {
+        let theme = self.theme();
+        // 遍历棋盘上的每个交叉点
+        for coord in Coord::all() {
+            let stone = board.get(coord);
+            theme.paint_stone(ui.painter(), self.geometry.pixel_at(coord), self.geometry.stone_radius(), stone);
+        }
+    }
+
+    /// 绘制正在查看的局面，标出最后一步，回到终局时画出获胜的连线
+    fn render_position(&self, ui: &Ui) {
+        let (board, last) = match self.view_ply {
+            Some(ply) => (self.game.board_at(ply), ply.checked_sub(1).map(|i| self.game.moves()[i])),
+            None => (*self.game.board(), self.game.last_move()),
+        };
+        self.render_piece(ui, &board);
+
+        // 最后一步上画一个小圆点
+        let theme = self.theme();
+        if let Some(coord) = last {
+            let radius = self.geometry.stone_radius() * theme.last_move_ratio;
+            ui.painter().circle_filled(self.geometry.pixel_at(coord), radius, theme.last_move);
+        }
+
+        if let Some(line) = self.game.winning_line().filter(|_| self.view_ply.is_none()) {
+            let stroke = egui::Stroke::new(theme.win_line_width, theme.win_line);
+            ui.painter().line_segment([self.geometry.pixel_at(line.start), self.geometry.pixel_at(line.end)], stroke);
+        }
+    }
+
+    /// 处理回看棋谱的键盘事件，只在对局结束或暂停时生效
+    fn handle_history_keys(&mut self, ctx: &egui::Context) {
+        if !self.can_browse() {
+            self.view_ply = None;
+            return;
+        }
+        let total = self.game.moves().len();
+        let current = self.view_ply.unwrap_or(total);
+        let target = ctx.input(|i| {
+            if i.key_pressed(egui::Key::Home) {
+                Some(0)
+            } else if i.key_pressed(egui::Key::End) {
+                Some(total)
+            } else if i.key_pressed(egui::Key::ArrowLeft) {
+                Some(current.saturating_sub(1))
+            } else if i.key_pressed(egui::Key::ArrowRight) {
+                Some((current + 1).min(total))
+            } else {
+                None
+            }
+        });
+        if let Some(ply) = target {
+            self.show_ply(ply);
+        }
+    }
+
+    /// 键盘下棋：方向键移动光标，Enter 或空格在光标处落子，/ 跳到输入坐标的文本框，Esc 返回主菜单；
+    /// 本地对局中 U 悔棋，R 重新开始
+    ///
+    /// 回看棋谱时方向键用来翻看，不移动光标。输入框有焦点时不处理。
+    fn handle_play_keys(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // egui 没有 / 键的 Key，按输入的字符判断
+        if ctx.input(|i| i.events.iter().any(|e| matches!(e, egui::Event::Text(text) if text == "/"))) {
+            ctx.memory_mut(|m| m.request_focus(move_entry_id()));
+            return;
+        }
+        let (step, place, undo, restart, leave) = ctx.input(|i| {
+            let step = [
+                (egui::Key::ArrowLeft, (-1, 0)),
+                (egui::Key::ArrowRight, (1, 0)),
+                (egui::Key::ArrowUp, (0, -1)),
+                (egui::Key::ArrowDown, (0, 1)),
+            ]
+            .into_iter()
+            .find(|&(key, _)| i.key_pressed(key))
+            .map(|(_, step)| step);
+            let place = i.key_pressed(egui::Key::Enter) || i.key_pressed(egui::Key::Space);
+            (step, place, i.key_pressed(egui::Key::U), i.key_pressed(egui::Key::R), i.key_pressed(egui::Key::Escape))
+        });
+        if let Some((dcol, drow)) = step.filter(|_| !self.can_browse()) {
+            // 第一次按方向键只显示光标，从最后一步或者天元开始
+            self.cursor = Some(match self.cursor {
+                Some(cursor) => cursor.offset(dcol, drow).unwrap_or(cursor),
+                None => self.game.moves().last().copied().unwrap_or(Coord::CENTER),
+            });
+        }
+        if let Some(coord) = self.cursor.filter(|_| place && !self.game.is_over()) {
+            self.place_stone(coord);
+        }
+        let local = matches!(self.game_mode, GameMode::PlayerVsPlayer | GameMode::PlayerVsAI);
+        if local && undo && self.can_undo() {
+            self.undo();
+        }
+        // 一个键就会丢掉整局棋，对局进行中先确认
+        if local && restart {
+            if self.is_game_in_progress() {
+                self.confirm_restart = true;
+            } else {
+                self.restart();
+            }
+        }
+        if leave {
+            if self.game.is_over() {
+                self.network = None;
+                self.game_mode = GameMode::MainMenu;
+            } else {
+                self.request_leave();
+            }
+        }
+    }
+
+    /// 在键盘光标所在的交叉点画一个方框
+    fn render_cursor(&self, ui: &Ui) {
+        let Some(coord) = self.cursor.filter(|_| self.view_ply.is_none()) else {
+            return;
+        };
+        let rect = egui::Rect::from_center_size(self.geometry.pixel_at(coord), egui::Vec2::splat(self.geometry.spacing));
+        ui.painter().rect_stroke(rect, 2.0, egui::Stroke::new(self.theme().annotation_width, self.theme().annotation));
+    }
+
+    /// 输入坐标落子的文本框：按 / 获得焦点，输入 H8 之类的坐标后按 Enter 落子，Esc 离开
+    ///
+    /// 落子后焦点留在文本框里，可以接着输入下一步。
+    fn render_move_entry(&mut self, ui: &mut Ui) {
+        let error = self.theme().error;
+        let mut entry = egui::TextEdit::singleline(&mut self.move_entry)
+            .id(move_entry_id())
+            .desired_width(48.0)
+            .hint_text("/ move");
+        if self.move_entry_invalid {
+            entry = entry.text_color(error);
+        }
+        let response = ui.add(entry).on_hover_text("Type a coordinate such as H8 and press Enter to play it");
+        if response.changed() {
+            self.move_entry_invalid = false;
+        }
+        if !response.lost_focus() || !ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            return;
+        }
+        response.request_focus();
+        let text = self.move_entry.trim().trim_start_matches('/');
+        if text.is_empty() {
+            return;
+        }
+        match Coord::parse(text, self.settings.coord_origin) {
+            Some(coord) => {
+                self.move_entry.clear();
+                self.place_stone(coord);
+            }
+            None => self.move_entry_invalid = true,
+        }
+    }
+
+    /// 光标位置的文字说明，不用看棋盘也能知道光标在哪、上面有没有棋子
+    fn cursor_text(&self) -> Option<String> {
+        let coord = self.cursor?;
+        let stone = match self.game.board().get(coord) {
+            Stone::Empty => "empty",
+            stone => stone.name(),
+        };
+        Some(format!("Cursor: {} ({})", coord.notation(self.settings.coord_origin), stone))
+    }
+
+    /// 显示前 ply 步的局面，回到最后一步时恢复显示当前局面
+    fn show_ply(&mut self, ply: usize) {
+        let total = self.game.moves().len();
+        self.view_ply = if ply >= total { None } else { Some(ply) };
+    }
+
+    /// 进入复盘界面，从第一步开始
+    fn open_replay(&mut self) {
+        // 联机对局进入复盘后就不再需要连接
+        self.network = None;
+        self.game_mode = GameMode::Replay;
+        self.view_ply = Some(0);
+        self.autoplay = false;
+        self.fast_forward = false;
+        self.autoplay_timer = 0.0;
+    }
+
+    /// 开始或停止复盘的自动播放，fast 为 true 时快进
+    fn toggle_autoplay(&mut self, fast: bool) {
+        self.autoplay = !(self.autoplay && self.fast_forward == fast);
+        self.fast_forward = fast;
+        self.autoplay_timer = 0.0;
+    }
+
+    /// 复盘自动播放，到最后一步时停止
+    ///
+    /// 正常速度每秒前进一步并播放落子音效；快进时不播放音效，否则音效会一段接一段排在后面。
+    /// 只在下一步到时刷新界面，不必每帧重绘。
+    fn step_autoplay(&mut self, ctx: &egui::Context, delta_time: f32) {
+        if !self.autoplay {
+            return;
+        }
+        let interval = if self.fast_forward { FAST_FORWARD_INTERVAL } else { AUTOPLAY_INTERVAL };
+        self.autoplay_timer += delta_time;
+        if self.autoplay_timer >= interval {
+            self.autoplay_timer = 0.0;
+            let total = self.game.moves().len();
+            let ply = self.view_ply.unwrap_or(total) + 1;
+            self.show_ply(ply);
+            if !self.fast_forward {
+                self.play_stone_sound(Stone::for_ply(ply - 1));
+            }
+            if ply >= total {
+                self.autoplay = false;
+                return;
+            }
+        }
+        ctx.request_repaint_after(Duration::from_secs_f32(interval - self.autoplay_timer));
+    }
+
+    /// 渲染复盘工具栏
+    fn render_replay_controls(&mut self, ui: &mut Ui) {
+        let total = self.game.moves().len();
+        let ply = self.view_ply.unwrap_or(total);
+        ui.horizontal(|ui| {
+            if ui.button("Back to Menu").clicked() {
+                self.autoplay = false;
+                self.game_mode = GameMode::MainMenu;
+                return;
+            }
+            if ui.add_enabled(ply > 0, egui::Button::new("|<")).clicked() {
+                self.show_ply(0);
+            }
+            if ui.add_enabled(ply > 0, egui::Button::new("<")).clicked() {
+                self.show_ply(ply - 1);
+            }
+            let play_text = if self.autoplay && !self.fast_forward { "Stop" } else { "Play" };
+            if ui.add_enabled(ply < total, egui::Button::new(play_text)).clicked() {
+                self.toggle_autoplay(false);
+            }
+            let fast_text = if self.autoplay && self.fast_forward { "Stop" } else { "Fast Forward" };
+            if ui.add_enabled(ply < total, egui::Button::new(fast_text)).clicked() {
+                self.toggle_autoplay(true);
+            }
+            if ui.add_enabled(ply < total, egui::Button::new(">")).clicked() {
+                self.show_ply(ply + 1);
+            }
+            if ui.add_enabled(ply < total, egui::Button::new(">|")).clicked() {
+                self.show_ply(total);
+            }
+            ui.label(format!("Move {}/{}", ply, total));
+            self.threat_overlay_toggle(ui);
+        });
+    }
+
+    /// 开关威胁标注的复选框，说明各种标记的含义
+    fn threat_overlay_toggle(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.settings.threat_overlay, "Threats").on_hover_text(
+            "Mark points where either side could make an open three (small) or a four (large). \
+             A ring marks a double threat that usually cannot be stopped.",
+        );
+    }
+
+    /// 初学者提示：轮到玩家时，在和己方棋子相邻的空位上画一个小点，
+    /// 绿色表示连线两端都有余地，琥珀色表示贴着棋盘边，红色表示再也连不成
+    fn render_edge_hints(&self, ui: &Ui) {
+        if !self.settings.edge_hints || self.view_ply.is_some() || !self.can_place_stone() {
+            return;
+        }
+        let board = self.game.board();
+        let stone = self.game.to_move();
+        let rules = self.game.rules();
+        let theme = self.theme();
+        let radius = self.geometry.stone_radius() * 0.18;
+        for coord in Coord::all().filter(|&c| board.is_empty_at(c)) {
+            // 禁手检查比较慢，只对要画的点做
+            let Some(room) = line_room::line_room(board, coord, stone, rules.win_length()) else {
+                continue;
+            };
+            if rules.forbidden(board, coord, stone).is_some() {
+                continue;
+            }
+            let color = match room {
+                LineRoom::Open => theme.success,
+                LineRoom::EdgeBound => theme.warning,
+                LineRoom::Dead => theme.error,
+            };
+            ui.painter().circle_filled(self.geometry.pixel_at(coord), radius, color.gamma_multiply(0.7));
+        }
+    }
+
+    /// 在显示的局面上标出双方落子后能形成活三、冲四或双威胁的空位
+    ///
+    /// 每个点画一个半透明的对应颜色的棋子，四比活三大；双威胁外面再加一个警告色的圈。
+    /// 双方在同一点都有威胁时左右错开。
+    fn render_threats(&mut self, ui: &Ui) {
+        if !self.settings.threat_overlay {
+            return;
+        }
+        let board = match self.view_ply {
+            Some(ply) => self.game.board_at(ply),
+            None => *self.game.board(),
+        };
+        let rules = self.game.rules();
+        if !self.threats.as_ref().is_some_and(|(hash, cached, _)| *hash == board.hash() && *cached == rules) {
+            self.threats = Some((board.hash(), rules, threat::threat_map(&board, rules)));
+        }
+        let Some((_, _, threats)) = &self.threats else {
+            return;
+        };
+        let theme = self.theme();
+        let radius = self.geometry.stone_radius();
+        for t in threats {
+            let shared = threats.iter().any(|other| other.mv == t.mv && other.stone != t.stone);
+            let shift = match (shared, t.stone) {
+                (false, _) => 0.0,
+                (true, Stone::Black) => -0.35,
+                (true, _) => 0.35,
+            };
+            let center = self.geometry.pixel_at(t.mv) + egui::vec2(radius * shift, 0.0);
+            let size = if t.level >= ThreatLevel::Four { 0.55 } else { 0.35 };
+            let edge = if t.stone == Stone::Black { theme.black_edge } else { theme.white_edge };
+            let fill = theme.stone_fill(t.stone).gamma_multiply(0.6);
+            ui.painter().circle(center, radius * size, fill, egui::Stroke::new(1.0, edge));
+            if t.double {
+                ui.painter().circle_stroke(center, radius * (size + 0.25), egui::Stroke::new(2.0, theme.warning));
+            }
+        }
+    }
+
+    /// 绘制解题提示，每个阶段都基于威胁分析得到的解答
+    fn render_puzzle_hint(&self, ui: &Ui, puzzle: &PuzzleSession) {
+        let Some(solution) = puzzle.solution() else {
+            return;
+        };
+        let stroke = egui::Stroke::new(2.0, self.theme().puzzle_hint);
+        let (start, end) = solution.line;
+        if puzzle.hint >= HintStage::Area {
+            // 圈出威胁线所在的区域，向外多扩一格
+            let top_left = Coord::new(start.col.min(end.col), start.row.min(end.row));
+            let bottom_right = Coord::new(start.col.max(end.col), start.row.max(end.row));
+            let min = self.geometry.pixel_at(top_left) - egui::Vec2::splat(self.geometry.spacing);
+            let max = self.geometry.pixel_at(bottom_right) + egui::Vec2::splat(self.geometry.spacing);
+            ui.painter().rect_stroke(egui::Rect::from_min_max(min, max), 4.0, stroke);
+        }
+        if puzzle.hint >= HintStage::Line {
+            let from = self.geometry.pixel_at(start);
+            let to = self.geometry.pixel_at(end);
+            ui.painter().line_segment([from, to], stroke);
+        }
+        if puzzle.hint >= HintStage::Move {
+            let center = self.geometry.pixel_at(solution.mv);
+            ui.painter().circle_stroke(center, self.geometry.stone_radius(), stroke);
+        }
+    }
+
+    /// 处理解题模式下的点击
+    fn handle_puzzle_click(&mut self, pos: Pos2) {
+        let Some(coord) = self.geometry.snap(pos) else {
+            return;
+        };
+        if self.puzzle.try_move(coord) {
+            self.play_stone_sound(self.puzzle.puzzle().to_move);
+        }
+    }
+
+    /// 处理教程模式下的点击，对手的应手和学生的落子一起出现
+    fn handle_tutorial_click(&mut self, pos: Pos2) {
+        let Some(coord) = self.geometry.snap(pos) else {
+            return;
+        };
+        if self.tutorial.try_move(coord) {
+            self.play_stone_sound(self.tutorial.exercise().student);
+        }
+    }
+
+    /// 处理棋盘上的点击，离交叉点太远时什么事都不做，AI 思考或者展示落子期间忽略点击
+    fn handle_click(&mut self, pos: Pos2) {
+        if self.ai_thinking || self.ai_pending_move.is_some() {
+            return;
+        }
+        if let Some(coord) = self.geometry.snap(pos) {
+            self.place_stone(coord);
+        }
+    }
+
+    /// 现在是否允许玩家落子
+    fn can_place_stone(&self) -> bool {
+        // 暂停、回看、等待确认离开、等待答复提和或正在分享时不允许落子
+        if self.is_paused || self.view_ply.is_some() || self.confirm_leave || self.confirm_restart {
+            return false;
+        }
+        if self.draw_offer.is_some() || self.share.is_some() {
+            return false;
+        }
+        // Swap2 开局等待选择时不能落子
+        if self.opening.is_some_and(|o| o.chooser().is_some()) {
+            return false;
+        }
+        // 在AI模式下，只有玩家的回合才能落子
+        self.is_player_turn()
+    }
+
+    /// 鼠标停在棋盘上且可以落子时，把指针换成一颗当前一方颜色的小棋子，
+    /// 双人轮流下棋时一眼就能看出轮到谁
+    fn render_pointer_stone(&self, ui: &Ui, response: &egui::Response) {
+        let Some(pos) = response.hover_pos() else {
+            return;
+        };
+        let waiting = self.game.is_over() || self.ai_thinking || self.ai_pending_move.is_some();
+        if waiting || !self.can_place_stone() {
+            return;
+        }
+        ui.ctx().set_cursor_icon(egui::CursorIcon::None);
+        let radius = self.geometry.stone_radius() * POINTER_STONE_RATIO;
+        self.theme().paint_stone(ui.painter(), pos, radius, self.game.to_move());
+    }
+
+    /// 鼠标停在（触屏上按住）显示的局面里的一颗棋子上时，显示它是第几手、哪一方、什么时候下的和想了多久
+    fn render_stone_tooltip(&self, ui: &Ui, response: &egui::Response) {
+        let Some(coord) = response.hover_pos().and_then(|pos| self.geometry.snap(pos)) else {
+            return;
+        };
+        let shown = self.view_ply.unwrap_or(self.game.moves().len());
+        let Some(ply) = self.game.moves()[..shown].iter().position(|&mv| mv == coord) else {
+            return;
+        };
+        let text = move_log::describe(ply, Stone::for_ply(ply), self.move_log.get(ply));
+        egui::show_tooltip_at_pointer(ui.ctx(), ui.id().with("stone_tooltip"), |ui| ui.label(text));
+    }
+
+    /// 玩家在 coord 落子，鼠标点击和键盘共用
+    fn place_stone(&mut self, coord: Coord) {
+        if !self.can_place_stone() {
+            return;
+        }
+
+        // 练习模式在落子之后检查失误，开局阶段不检查
+        let practice = self.game_mode == GameMode::PlayerVsAI && self.settings.practice && self.opening_seat().is_none();
+        let before = *self.game.board();
+        let stone = self.game.to_move();
+        // 落子音效由事件订阅播放，联机对局还要把落子发给对方
+        match self.game.play(coord) {
+            Ok(()) => {
+                self.forbidden_warning = None;
+                if practice {
+                    self.mistake = practice::check_move(&before, coord, stone, self.game.rules());
+                }
+                if let Some(opening) = self.opening.as_mut() {
+                    opening.advance(self.game.moves().len());
+                }
+                if self.game_mode == GameMode::Network {
+                    let ply = self.game.moves().len() - 1;
+                    if let Some(session) = self.network.as_mut() {
+                        session.send(Message::Move { ply, coord, checksum: self.game.checksum() });
+                    }
+                }
+            }
+            Err(MoveError::Forbidden(kind)) => {
+                self.forbidden_warning = Some((coord, kind));
+                self.reject_move(coord);
+            }
+            Err(MoveError::Occupied) => self.reject_move(coord),
+            Err(_) => {}
+        }
+    }
+
+    /// 落子被拒绝时在 coord 上闪一下红圈，并播放提示音
+    fn reject_move(&mut self, coord: Coord) {
+        self.rejected_move = Some((coord, Instant::now()));
+        self.play_sound(Sound::Reject);
+    }
+
+    /// 按“减少动态效果”设置开关 egui 的过渡动画（折叠面板、窗口淡入等）
+    ///
+    /// 棋盘上的提示和警告（最后一手的圆点、禁手的叉、连五的连线）本来就是静态的，不需要替换。
+    fn apply_motion_setting(&self, ctx: &egui::Context) {
+        let animation_time = if self.settings.reduce_motion { 0.0 } else { egui::Style::default().animation_time };
+        if ctx.style().animation_time != animation_time {
+            let mut style = (*ctx.style()).clone();
+            style.animation_time = animation_time;
+            ctx.set_style(style);
+        }
+    }
+
+    /// 按主题切换 egui 的深浅色控件和窗口底色
+    fn apply_theme(&mut self, ctx: &egui::Context) {
+        let theme = *self.theme();
+        self.frame.fill = theme.background;
+        if ctx.style().visuals.dark_mode != theme.dark_mode {
+            ctx.set_visuals(if theme.dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() });
+        }
+    }
+
+    /// 在刚刚被拒绝的落子位置画一个逐渐淡去的圈，减少动态效果时不淡出，到时直接消失
+    fn render_rejected_move(&mut self, ui: &Ui) {
+        let Some((coord, at)) = self.rejected_move else {
+            return;
+        };
+        let elapsed = at.elapsed().as_secs_f32();
+        if elapsed >= REJECT_FLASH_SECS {
+            self.rejected_move = None;
+            return;
+        }
+        let fade = if self.settings.reduce_motion { 1.0 } else { 1.0 - elapsed / REJECT_FLASH_SECS };
+        let color = self.theme().warning.gamma_multiply(fade);
+        let radius = self.geometry.stone_radius() * 1.15;
+        ui.painter().circle_stroke(self.geometry.pixel_at(coord), radius, egui::Stroke::new(3.0, color));
+        ui.ctx().request_repaint();
+    }
+
+    /// 人机对战中 AI 最近一次判断换算成的玩家胜率
+    fn player_win_permille(evaluation: &Evaluation) -> u32 {
+        1000 - win_rate::win_permille(evaluation.score)
+    }
+
+    /// 工具栏里的胜率条：AI 上一次落子时估计的玩家胜率
+    fn render_eval_bar(&self, ui: &mut Ui) {
+        let Some(evaluation) = self.ai_history.last().filter(|_| self.settings.eval_bar) else {
+            return;
+        };
+        let permille = Self::player_win_permille(evaluation);
+        let bar = egui::ProgressBar::new(permille as f32 / 1000.0)
+            .desired_width(90.0)
+            .text(format!("You {}%", (permille + 5) / 10));
+        ui.add(bar).on_hover_text("The AI's estimate of your winning chances, calibrated on self-play games");
+    }
+
+    /// 终局后画出 AI 每次落子时估计的玩家胜率曲线，中间的横线是 50%
+    fn render_win_graph(&self, ui: &mut Ui) {
+        if !self.settings.eval_bar || self.ai_history.len() < 2 {
+            return;
+        }
+        ui.label("Your winning chances");
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(220.0, 70.0), egui::Sense::hover());
+        let theme = self.theme();
+        let painter = ui.painter();
+        painter.rect_stroke(rect, 2.0, egui::Stroke::new(1.0, theme.muted));
+        painter.line_segment([rect.left_center(), rect.right_center()], egui::Stroke::new(1.0, theme.muted));
+        let last = (self.ai_history.len() - 1) as f32;
+        let points = self
+            .ai_history
+            .iter()
+            .enumerate()
+            .map(|(i, evaluation)| {
+                let share = Self::player_win_permille(evaluation) as f32 / 1000.0;
+                egui::pos2(rect.left() + rect.width() * i as f32 / last, rect.bottom() - rect.height() * share)
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(2.0, theme.accent)));
+    }
+
+    /// 显示当前局面的校验码，联机纠纷或报告问题时双方可以核对
+    fn render_checksum(&self, ui: &mut Ui) {
+        let text = RichText::new(self.game.checksum().to_string()).monospace().color(self.theme().muted);
+        ui.label(text).on_hover_text("Position checksum: compare it with your opponent or include it in bug reports");
+    }
+
+    /// 在玩家刚刚尝试的禁手位置画一个叉
+    fn render_forbidden_warning(&self, ui: &Ui) {
+        let Some((coord, _)) = self.forbidden_warning else {
+            return;
+        };
+        let center = self.geometry.pixel_at(coord);
+        let r = self.geometry.stone_radius() * 0.7;
+        let stroke = egui::Stroke::new(3.0, self.theme().warning);
+        ui.painter().line_segment([center + egui::vec2(-r, -r), center + egui::vec2(r, r)], stroke);
+        ui.painter().line_segment([center + egui::vec2(-r, r), center + egui::vec2(r, -r)], stroke);
+    }
+
+    /// 现在能否请求提示：轮到玩家、不在开局或回看中，并且没有正在进行的提示
+    fn can_hint(&self) -> bool {
+        !self.game.is_over()
+            && self.is_player_turn()
+            && self.opening_seat().is_none()
+            && self.view_ply.is_none()
+            && !self.is_paused
+            && self.hint.is_none()
+    }
+
+    /// 取回后台搜索的提示，落子之后提示作废
+    fn update_hint(&mut self, ctx: &egui::Context) {
+        let Some(hint) = self.hint.as_mut() else {
+            return;
+        };
+        if hint.ply != self.game.moves().len() || self.game.is_over() {
+            self.hint = None;
+        } else if hint.poll() {
+            // 搜索还没完成，继续刷新界面等待结果
+            ctx.request_repaint();
+        }
+    }
+
+    /// 在提示的候选点上画半透明的棋子，标出名次和得分
+    fn render_hint(&self, ui: &Ui) {
+        let Some(hint) = self.hint.as_ref().filter(|_| self.view_ply.is_none()) else {
+            return;
+        };
+        let theme = self.theme();
+        let ghost = match hint.stone {
+            Stone::Black => theme.black_fill.gamma_multiply(0.43),
+            _ => theme.white_fill.gamma_multiply(0.63),
+        };
+        let text_color = theme.stone_fill(hint.stone.opponent());
+        let radius = self.geometry.stone_radius();
+        for (rank, analysis) in hint.moves.iter().enumerate() {
+            let center = self.geometry.pixel_at(analysis.best_move);
+            if rank == 0 {
+                ui.painter().circle_filled(center, radius, ghost);
+            } else {
+                ui.painter().circle_stroke(center, radius, egui::Stroke::new(1.5, ghost));
+            }
+            let color = if rank == 0 { text_color } else { theme.labels };
+            ui.painter().text(center, egui::Align2::CENTER_CENTER, rank + 1, egui::FontId::proportional(12.0), color);
+
+            let score = if analysis.is_win() {
+                "win".to_string()
+            } else if analysis.is_loss() {
+                "loss".to_string()
+            } else {
+                format!("{:+}", analysis.score)
+            };
+            let below = center + egui::vec2(0.0, radius + 1.0);
+            ui.painter().text(below, egui::Align2::CENTER_TOP, score, egui::FontId::proportional(9.0), theme.accent);
+        }
+    }
+
+    /// 失误的说明文字
+    fn mistake_text(&self, mistake: Mistake) -> String {
+        let origin = self.settings.coord_origin;
+        match mistake {
+            Mistake::MissedWin(coord) => format!(
+                "You had a forced win starting at {}, but this move lets it slip.",
+                coord.notation(origin)
+            ),
+            Mistake::AllowedLoss(coord) => format!(
+                "This move lets the AI force a win starting at {}.",
+                coord.notation(origin)
+            ),
+        }
+    }
+
+    /// 双人对战中一方提和之后，由另一方接受或拒绝
+    fn render_draw_offer_dialog(&mut self, ctx: &egui::Context) {
+        let Some(side) = self.draw_offer else {
+            return;
+        };
+        egui::Window::new("Draw Offered")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("{} offers a draw. Does {} accept?", side.name(), side.opponent().name()));
+                ui.horizontal(|ui| {
+                    if ui.button("Accept").clicked() {
+                        self.game.agree_draw();
+                        self.draw_offer = None;
+                    }
+                    if ui.button("Decline").clicked() {
+                        self.draw_offer = None;
+                    }
+                });
+            });
+    }
+
+    /// 载入主菜单中输入的对局代码，按双人对战继续，已经结束的对局直接进入复盘
+    fn load_game_code(&mut self) {
+        match GameCode::parse(&self.game_code_entry) {
+            Ok(code) => {
+                self.game_code_entry.clear();
+                self.game_code_error.clear();
+                self.start_from_moves(GameMode::PlayerVsPlayer, true, code.rules, &code.moves, None);
+                if self.game.is_over() {
+                    self.open_replay();
+                }
+            }
+            Err(e) => self.game_code_error = format!("Invalid game code: {}", e),
+        }
+    }
+
+    /// 生成当前对局的分享代码并打开分享对话框
+    fn open_share(&mut self) {
+        let code = GameCode { rules: self.game.rules(), moves: self.game.moves().to_vec() };
+        match ShareCode::new(&code) {
+            Ok(share) => {
+                self.share = Some(share);
+                self.share_error.clear();
+            }
+            Err(e) => self.share_error = format!("Failed to share game: {:#}", e),
+        }
+    }
+
+    /// 开始或停止直播的按钮，直播时显示观看地址
+    fn render_live_toggle(&mut self, ui: &mut Ui) {
+        match &self.live {
+            Some(live) => {
+                let url = live.url().to_string();
+                if ui.button("Stop Live").clicked() {
+                    self.live = None;
+                }
+                let hint = "Open this address on another device on the same network to follow the game. Click to copy.";
+                let label = ui.label(RichText::new(&url).monospace()).on_hover_text(hint);
+                if label.interact(egui::Sense::click()).clicked() {
+                    ui.output_mut(|o| o.copied_text = url);
+                }
+            }
+            None => {
+                let hint = "Serve a read-only, auto-refreshing board page on the local network";
+                if ui.button("Share Live").on_hover_text(hint).clicked() {
+                    // 默认端口被占用时改用系统分配的端口，观看地址照样显示在按钮旁边
+                    match LiveShare::start(live::DEFAULT_PORT).or_else(|_| LiveShare::start(0)) {
+                        Ok(live) => {
+                            self.live = Some(live);
+                            self.live_error.clear();
+                        }
+                        Err(e) => self.live_error = format!("Failed to share live: {:#}", e),
+                    }
+                }
+                if !self.live_error.is_empty() {
+                    ui.label(RichText::new(&self.live_error).color(self.theme().error));
+                }
+            }
+        }
+    }
+
+    /// 局面变化时更新直播的网页，离开对局时停止直播
+    fn publish_live(&mut self) {
+        if !matches!(self.game_mode, GameMode::PlayerVsPlayer | GameMode::PlayerVsAI) {
+            self.live = None;
+            return;
+        }
+        let board = self.game.board();
+        let key = board.hash() ^ self.game.is_over() as u64;
+        let Some(live) = self.live.as_ref() else {
+            return;
+        };
+        if live.is_showing(key) {
+            return;
+        }
+        let status = if self.game.is_over() {
+            self.winner_text().to_string()
+        } else {
+            format!("Move {} - {} to move", self.game.moves().len() + 1, self.game.to_move().name())
+        };
+        let svg = board_image::svg(board, self.game.last_move(), self.settings.coord_origin, self.theme());
+        let page = live::page(&status, &svg);
+        if let Some(live) = self.live.as_mut() {
+            live.show(key, page);
+        }
+    }
+
+    /// 显示对局代码的二维码，朋友用手机版扫码即可载入同一局棋
+    fn render_share_dialog(&mut self, ctx: &egui::Context) {
+        let Some(share) = &self.share else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("Share Game")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    share.show(ui, 240.0);
+                    ui.label(RichText::new(&share.text).monospace());
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy Code").clicked() {
+                            ui.output_mut(|o| o.copied_text = share.text.clone());
+                        }
+                        if ui.button("Close").clicked() {
+                            close = true;
+                        }
+                    });
+                });
+            });
+        if close {
+            self.share = None;
+        }
+    }
+
+    /// 导入带签名的棋谱后显示签名是否有效，组织者可以核对签名的公钥是不是选手登记的那一把
+    fn render_signature_dialog(&mut self, ctx: &egui::Context) {
+        let Some(verdict) = &self.signature else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("Record Signature")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                match verdict {
+                    Verdict::Valid(public_key) => {
+                        ui.label("The signature is valid. The record has not been changed since it was signed by:");
+                        ui.label(RichText::new(public_key).monospace());
+                    }
+                    _ => {
+                        let warning = "The signature does not match. The record was modified after it was signed.";
+                        ui.label(RichText::new(warning).color(self.theme().error));
+                    }
+                }
+                if ui.button("OK").clicked() {
+                    close = true;
+                }
+            });
+        if close {
+            self.signature = None;
+        }
+    }
+
+    /// 保存对局失败时告诉玩家原因，例如目录没有写权限或者磁盘已满
+    fn render_save_error_dialog(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let Some(error) = &self.save_error else {
+            return;
+        };
+        // Some(true) 表示重试后退出，Some(false) 表示不保存直接退出，只在关闭窗口时出现
+        let mut quit = None;
+        let mut close = false;
+        egui::Window::new("Save Failed")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The game could not be saved:");
+                ui.label(RichText::new(error).color(self.theme().error));
+                if !self.closing {
+                    close = ui.button("OK").clicked();
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Retry").clicked() {
+                        quit = Some(true);
+                    }
+                    if ui.button("Quit Without Saving").clicked() {
+                        quit = Some(false);
+                    }
+                    close = ui.button("Cancel").clicked();
+                });
+            });
+        if close {
+            self.save_error = None;
+            self.closing = false;
+        }
+        if let Some(retry) = quit {
+            if retry {
+                self.save_error = None;
+            }
+            frame.close();
+        }
+    }
+
+    /// 读取签名密钥失败时的提示
+    fn render_key_error_dialog(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.key_error else {
+            return;
+        };
+        let mut close = false;
+        egui::Window::new("Signing Key Unavailable")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(RichText::new(error).color(self.theme().error));
+                if ui.button("OK").clicked() {
+                    close = true;
+                }
+            });
+        if close {
+            self.key_error = None;
+        }
+    }
+
+    /// 练习模式中指出失误，玩家可以立即悔棋或者继续
+    fn render_mistake_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mistake) = self.mistake else {
+            return;
+        };
+        let text = self.mistake_text(mistake);
+        egui::Window::new("Mistake")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -20.0))
+            .show(ctx, |ui| {
+                ui.label(text);
+                ui.horizontal(|ui| {
+                    if ui.button("Take Back").clicked() {
+                        self.undo();
+                    }
+                    if ui.button("Keep Playing").clicked() {
+                        self.mistake = None;
+                    }
+                });
+            });
+    }
+
+    /// AI 执哪一方
+    fn ai_stone(&self) -> Stone {
+        if self.player_is_black { Stone::White } else { Stone::Black }
+    }
+
+    /// Swap2 开局中现在该哪一方摆子或选择，开局已经结束时返回 None
+    fn opening_seat(&self) -> Option<Seat> {
+        let opening = self.opening.filter(|o| !o.is_done())?;
+        opening.placer().or(opening.chooser())
+    }
+
+    /// 当前是否轮到玩家（双人对战中总是轮到玩家，联机对局中要求连接正常且轮到本方）
+    fn is_player_turn(&self) -> bool {
+        match self.game_mode {
+            GameMode::PlayerVsAI => match self.opening_seat() {
+                Some(seat) => seat == self.player_seat,
+                None => self.game.to_move() != self.ai_stone(),
+            },
+            GameMode::Network => self
+                .network
+                .as_ref()
+                .is_some_and(|n| n.is_connected() && self.game.to_move() == n.local_stone()),
+            _ => true,
+        }
+    }
+
+    /// 是否可以悔棋，人机对战中至少要有一步玩家自己的棋
+    ///
+    /// Swap2 开局进行中不能悔棋，颜色确定之后也不能悔到开局的棋子。
+    fn can_undo(&self) -> bool {
+        if self.game.is_final() {
+            return false;
+        }
+        let first = match self.opening {
+            Some(opening) if !opening.is_done() => return false,
+            Some(opening) => opening.stones(),
+            None => 0,
+        };
+        let moves = self.game.moves().len();
+        if self.game_mode == GameMode::PlayerVsAI {
+            (first..moves).any(|i| Stone::for_ply(i) != self.ai_stone())
+        } else {
+            moves > first
+        }
+    }
+
+    /// Swap2 开局中选择方做出决定，颜色确定后更新玩家执哪一方
+    fn choose_opening(&mut self, choice: Swap2Choice) {
+        let Some(opening) = self.opening.as_mut() else {
+            return;
+        };
+        if opening.choose(choice).is_ok() {
+            if let Some(stone) = opening.stone_of(self.player_seat) {
+                self.player_is_black = stone == Stone::Black;
+            }
+        }
+    }
+
+    /// Swap2 开局的提示文字，开局已经结束时返回 None
+    fn opening_status(&self) -> Option<String> {
+        let seat = self.opening_seat()?;
+        let who = match self.game_mode {
+            GameMode::PlayerVsAI if seat == self.player_seat => "Player",
+            GameMode::PlayerVsAI => "AI",
+            _ if seat == Seat::First => "First player",
+            _ => "Second player",
+        };
+        let action = if self.opening.is_some_and(|o| o.placer().is_some()) {
+            format!("places {}", self.game.to_move().name())
+        } else {
+            "chooses a color".to_string()
+        };
+        Some(format!("Swap2: {} {}", who, action))
+    }
+
+    /// 轮到玩家在 Swap2 开局中做选择时弹出选择框，双人对战中双方共用
+    fn render_swap2_dialog(&mut self, ctx: &egui::Context) {
+        let Some(opening) = self.opening else {
+            return;
+        };
+        let Some(chooser) = opening.chooser() else {
+            return;
+        };
+        if self.game_mode == GameMode::PlayerVsAI && chooser != self.player_seat {
+            return;
+        }
+        let title = match (self.game_mode, chooser) {
+            (GameMode::PlayerVsAI, _) => "Choose Your Color",
+            (_, Seat::First) => "First Player: Choose a Color",
+            (_, Seat::Second) => "Second Player: Choose a Color",
+        };
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.vertical_centered(|ui| {
+                    if ui.button("Take Black").clicked() {
+                        self.choose_opening(Swap2Choice::TakeBlack);
+                    }
+                    if ui.button("Take White").clicked() {
+                        self.choose_opening(Swap2Choice::TakeWhite);
+                    }
+                    if opening.phase() == Swap2Phase::FirstChoice && ui.button("Place Two More").clicked() {
+                        self.choose_opening(Swap2Choice::PlaceTwo);
+                    }
+                });
+            });
+    }
+
+    /// 悔棋：双人对战退回一步，人机对战连同 AI 的应手一起退回到玩家的回合
+    fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+        while self.game.undo().is_some() {
+            if self.is_player_turn() {
+                break;
+            }
+        }
+        self.view_ply = None;
+        self.forbidden_warning = None;
+        self.rejected_move = None;
+        self.mistake = None;
+        self.draw_offer = None;
+        self.hint = None;
+        self.remote_search = None;
+        let played = self.game.moves().len();
+        self.ai_history.retain(|e| e.move_number <= played);
+        self.ai_thinking = false;
+        self.ai_delay_timer = 0.0;
+        self.ai_pending_move = None;
+    }
+
+    /// 重做：恢复最近一次悔棋撤销的落子
+    fn redo(&mut self) {
+        while self.game.redo().is_some() {
+            if self.game.is_over() || self.is_player_turn() {
+                break;
+            }
+        }
+        self.view_ply = None;
+        self.hint = None;
+    }
+
+    /// 把对局事件分发给订阅者，再由各部件处理自己收到的事件
+    fn dispatch_game_events(&mut self) {
+        for event in self.game.take_events() {
+            self.events.publish(event);
+        }
+        // 一帧里落下好几步时（例如观战快进）只播放最后一步的音效
+        let mut placed = None;
+        let mut winner = None;
+        for event in self.sound_events.try_iter() {
+            match event {
+                GameEvent::Played { stone, .. } => placed = Some(stone),
+                GameEvent::Won(stone) => winner = Some(stone),
+                _ => {}
+            }
+        }
+        if let Some(stone) = placed {
+            self.play_stone_sound(stone);
+        }
+        if let Some(winner) = winner {
+            self.play_result_sound(winner);
+        }
+        for event in self.clock_events.try_iter() {
+            if let (Some(clock), GameEvent::Played { stone, .. }) = (self.clock.as_mut(), event) {
+                clock.finish_move(stone);
+            }
+        }
+        for event in self.log_events.try_iter() {
+            self.move_log.record(event, self.frame_millis);
+        }
+        self.move_log.sync(self.game.moves().len());
+    }
+
+    /// 播放落子音效，黑白双方使用各自的音效方案
+    fn play_stone_sound(&self, stone: Stone) {
+        let profile = if stone == Stone::Black {
+            self.settings.black_sound
+        } else {
+            self.settings.white_sound
+        };
+        self.play_sound(Sound::Move(profile));
+    }
+
+    /// 对局分出胜负时播放短曲，人机和联机对局按玩家一方的胜负区分
+    fn play_result_sound(&self, winner: Stone) {
+        let has_player = matches!(self.game_mode, GameMode::PlayerVsAI | GameMode::Network);
+        let player_lost = has_player && (winner == Stone::Black) != self.player_is_black;
+        self.play_sound(if player_lost { Sound::Lose } else { Sound::Win });
+    }
+
+    /// 按设置的音量播放音效，静音时不播放
+    fn play_sound(&self, sound: Sound) {
+        if !self.settings.muted {
+            self.audio_manager.play(sound, self.settings.volume);
+        }
+    }
+
+    /// 胜利提示文字
+    fn winner_text(&self) -> &'static str {
+        let Some(winner) = self.game.winner() else {
+            return "Draw!";
+        };
+        let black_wins = winner == Stone::Black;
+        if self.game_mode == GameMode::PlayerVsAI {
+            if black_wins == self.player_is_black { "Player Wins!" } else { "AI Wins!" }
+        } else if self.game_mode == GameMode::Network {
+            if black_wins == self.player_is_black { "You Win!" } else { "Opponent Wins!" }
+        } else if black_wins {
+            "Black Wins!"
+        } else {
+            "White Wins!"
+        }
+    }
+
+    /// 导出带注释的文字棋谱
+    fn export_kifu(&self) -> String {
+        let (black, white) = self.player_names();
+        kifu::export(self.game.moves(), black, white, self.result_text(), self.game.rules(), self.settings.coord_origin)
+    }
+
+    /// 当前对局的解说复盘，双方用各自的落子音效
+    fn narration(&self) -> Narration {
+        Narration::new(
+            self.game.moves(),
+            [self.settings.black_sound, self.settings.white_sound],
+            self.game.outcome(),
+            self.result_text(),
+            self.game.rules(),
+            self.settings.coord_origin,
+        )
+    }
+
+    /// 棋谱和解说中的对局结果
+    fn result_text(&self) -> &'static str {
+        match self.game.outcome() {
+            Some(Outcome::Win(Stone::Black)) => "Black wins",
+            Some(Outcome::Win(_)) => "White wins",
+            Some(Outcome::Resignation(Stone::Black)) => "Black wins by resignation",
+            Some(Outcome::Resignation(_)) => "White wins by resignation",
+            Some(Outcome::Timeout(Stone::Black)) => "Black wins on time",
+            Some(Outcome::Timeout(_)) => "White wins on time",
+            Some(Outcome::Draw) => "Draw",
+            None => "Unfinished",
+        }
+    }
+
+    /// 棋谱中黑白双方的名字
+    fn player_names(&self) -> (&str, &str) {
+        let player = self.settings.player_name.as_str();
+        match (self.game_mode, self.player_is_black) {
+            (GameMode::PlayerVsAI, true) => (player, "AI"),
+            (GameMode::PlayerVsAI, false) => ("AI", player),
+            (GameMode::Network, true) => ("You", "Opponent"),
+            (GameMode::Network, false) => ("Opponent", "You"),
+            _ => ("Black", "White"),
+        }
+    }
+
+    /// 对局是否正在进行中（已经落子且尚未分出胜负）
+    fn is_game_in_progress(&self) -> bool {
+        matches!(self.game_mode, GameMode::PlayerVsPlayer | GameMode::PlayerVsAI)
+            && !self.game.is_over()
+            && !self.game.moves().is_empty()
+    }
+
+    /// 联机对局是否正在进行中，此时离开视为认输
+    fn is_network_game_in_progress(&self) -> bool {
+        self.game_mode == GameMode::Network
+            && self.network.as_ref().is_some_and(NetSession::is_connected)
+            && !self.game.is_over()
+    }
+
+    /// 能否写入 path：读取失败的文件在玩家处理之前、以及玩家选择保留之后都不写入，
+    /// 否则顶替它的默认值会覆盖掉原来的数据
+    fn can_write(&self, path: &Path) -> bool {
+        !self.corrupt_files.iter().any(|file| file.path == path) && !self.kept_files.iter().any(|kept| kept == path)
+    }
+
+    /// 保存局面分析缓存
+    fn save_analysis_cache(&self) {
+        if !self.can_write(Path::new(CACHE_PATH)) {
+            return;
+        }
+        if let Err(e) = self.analysis_cache.save(Path::new(CACHE_PATH)) {
+            eprintln!("Failed to save analysis cache: {:#}", e);
+        }
+    }
+
+    /// 保存使用统计
+    fn save_telemetry(&self) {
+        // 文件损坏时等玩家处理之后再写，否则备份到的是刚写入的默认统计
+        if !self.can_write(Path::new(TELEMETRY_PATH)) {
+            return;
+        }
+        if let Err(e) = self.telemetry.save(Path::new(TELEMETRY_PATH)) {
+            eprintln!("Failed to save telemetry: {:#}", e);
+        }
+    }
+
+    /// 保存设置
+    fn save_settings(&self) {
+        if !self.can_write(Path::new(SETTINGS_PATH)) {
+            return;
+        }
+        if let Err(e) = self.settings.save(Path::new(SETTINGS_PATH)) {
+            eprintln!("Failed to save settings: {:#}", e);
+        }
+    }
+
+    /// 保存 AI 对当前玩家的经验
+    fn save_experience(&self) {
+        let Some(path) = self.experience_path.as_ref().filter(|path| self.can_write(path)) else {
+            return;
+        };
+        if let Err(e) = experience::save(&self.experience, path) {
+            eprintln!("Failed to save learned games: {:#}", e);
+        }
+    }
+
+    /// 保存战绩
+    fn save_stats(&self) {
+        if !self.can_write(Path::new(STATS_PATH)) {
+            return;
+        }
+        if let Err(e) = self.stats.save(Path::new(STATS_PATH)) {
+            eprintln!("Failed to save statistics: {:#}", e);
+        }
+    }
+
+    /// 对局结束时计入统计和战绩，悔棋后再次结束不重复计算
+    fn record_finished_game(&mut self) {
+        let Some(outcome) = self.game.outcome().filter(|_| !self.game_recorded) else {
+            return;
+        };
+        self.game_recorded = true;
+        let winner = outcome.winner();
+        match self.game_mode {
+            GameMode::PlayerVsPlayer => {
+                self.telemetry.record_game(None);
+                stats::add_result(&mut self.stats.pvp, winner.map(|w| w == Stone::Black));
+            }
+            GameMode::PlayerVsAI => {
+                self.telemetry.record_game(Some(self.settings.ai_difficulty));
+                let player = if self.player_is_black { Stone::Black } else { Stone::White };
+                stats::add_result(self.stats.ai_mut(self.settings.ai_difficulty), winner.map(|w| w == player));
+                // 不管是否使用经验都记下这局，之后打开时马上就能用
+                let (moves, rules) = (self.game.moves().to_vec(), self.game.rules());
+                self.experience().record_game(&moves, player.opponent(), rules, winner.map(|w| w != player));
+                self.save_experience();
+                self.record_weakness(player);
+            }
+            GameMode::Network => {
+                let Some(local) = self.network.as_ref().map(NetSession::local_stone) else {
+                    return;
+                };
+                stats::add_result(&mut self.stats.network, winner.map(|w| w == local));
+                self.record_weakness(local);
+            }
+            _ => return,
+        }
+        self.save_stats();
+    }
+
+    /// 分析玩家在这局里的每一步，计入弱点报告
+    fn record_weakness(&mut self, player: Stone) {
+        let report = weakness::analyze_game(self.game.moves(), player, self.game.rules());
+        self.weakness.merge(&report);
+        self.save_weakness();
+    }
+
+    /// 保存弱点报告
+    fn save_weakness(&self) {
+        if !self.can_write(Path::new(WEAKNESS_PATH)) {
+            return;
+        }
+        if let Err(e) = weakness::save(&self.weakness, Path::new(WEAKNESS_PATH)) {
+            eprintln!("Failed to save weakness report: {:#}", e);
+        }
+    }
+
+    /// 当前对局的存档内容
+    fn saved_game(&self) -> SavedGame {
+        SavedGame {
+            mode: self.game_mode,
+            player_is_black: self.player_is_black,
+            rules: self.game.rules(),
+            moves: self.game.moves().to_vec(),
+        }
+    }
+
+    /// 保存当前对局到默认存档
+    fn save_game(&mut self) {
+        if !self.can_write(Path::new(SAVE_PATH)) {
+            let reason = format!("{} could not be read and was kept as it is, so the game was not saved.", SAVE_PATH);
+            self.save_error = Some(reason);
+        } else if let Err(e) = self.saved_game().save(Path::new(SAVE_PATH)) {
+            self.save_error = Some(format!("{:#}", e));
+        }
+        self.save_analysis_cache();
+    }
+
+    /// 从存档恢复对局
+    fn load_game(&mut self, path: &Path) {
+        let saved = match SavedGame::load(path) {
+            Ok(saved) => saved,
+            Err(e) => {
+                self.corrupt_files.push(CorruptFile::new(path, &e));
+                return;
+            }
+        };
+        self.start_from_moves(saved.mode, saved.player_is_black, saved.rules, &saved.moves, None);
+    }
+
+    /// 用读取到的落子记录开始对局，result 是记录中的结果
+    fn start_from_moves(
+        &mut self,
+        mode: GameMode,
+        player_is_black: bool,
+        rules: Rules,
+        moves: &[Coord],
+        result: Option<Outcome>,
+    ) {
+        self.game_mode = mode;
+        self.restart();
+        self.game = GameState::with_rules(rules);
+        self.player_is_black = player_is_black;
+        self.color_selected = true;
+        // 读取的对局直接按记录的颜色继续，不再进行开局
+        self.opening = None;
+        for &mv in moves {
+            // 存档和棋谱在读取时已经校验过，这里不会失败
+            let _ = self.game.play(mv);
+        }
+        // 认输和同意和棋不体现在落子中，按记录的结果补上
+        match result {
+            Some(Outcome::Resignation(winner)) => self.game.resign(winner.opponent()),
+            Some(Outcome::Draw) => self.game.agree_draw(),
+            _ => {}
+        }
+        // 重放的落子不需要通知订阅者，避免一次播放很多音效
+        self.game.take_events();
+        // 读取的对局在之前已经计入过统计
+        self.game_recorded = self.game.is_over();
+    }
+
+    /// 选择文件并按扩展名把当前对局保存为 SGF 棋谱、存档、棋盘图片或解说复盘，没有扩展名时保存为 SGF
+    fn save_game_as(&mut self) {
+        let Some(mut path) = dialog::save_game() else {
+            return;
+        };
+        let result = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(SAVE_EXTENSION) => self.saved_game().save(&path),
+            Some("svg") => {
+                let board = self.game.board();
+                board_image::save_svg(board, self.game.last_move(), self.settings.coord_origin, self.theme(), &path)
+            }
+            Some("wav") => self.narration().save(&path),
+            other => {
+                if other.is_none() {
+                    path.set_extension("sgf");
+                }
+                let (black, white) = self.player_names();
+                let (moves, rules) = (self.game.moves(), self.game.rules());
+                let comments = if self.settings.sgf_analysis {
+                    kifu::engine_comments(moves, rules, self.settings.coord_origin)
+                } else {
+                    Vec::new()
+                };
+                let record = SgfRecord {
+                    black: black.to_string(),
+                    white: white.to_string(),
+                    rules,
+                    result: self.game.outcome(),
+                    moves: moves.to_vec(),
+                    comments,
+                };
+                // 签名失败时照常保存，只是没有签名，并提示玩家
+                let key = signature::local_key(Path::new(SIGNING_KEY_PATH))
+                    .map_err(|e| self.key_error = Some(format!("The record was saved without a signature: {:#}", e)))
+                    .ok();
+                save::save_sgf(&record, key.as_ref(), &path)
+            }
+        };
+        if let Err(e) = result {
+            self.save_error = Some(format!("{:#}", e));
+        }
+    }
+
+    /// 选择 SGF 棋谱或存档并从最后一步继续对局
+    ///
+    /// 一方名为 `AI` 的棋谱按人机对战恢复，其余按双人对战恢复。
+    fn open_game_file(&mut self) {
+        let Some(path) = dialog::open_game() else {
+            return;
+        };
+        if path.extension().and_then(|ext| ext.to_str()) == Some(SAVE_EXTENSION) {
+            self.load_game(&path);
+            return;
+        }
+        let (record, verdict) = match save::load_sgf(&path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.corrupt_files.push(CorruptFile::new(&path, &e));
+                return;
+            }
+        };
+        let (mode, player_is_black) = match (record.black.as_str(), record.white.as_str()) {
+            (_, "AI") => (GameMode::PlayerVsAI, true),
+            ("AI", _) => (GameMode::PlayerVsAI, false),
+            _ => (GameMode::PlayerVsPlayer, true),
+        };
+        self.start_from_moves(mode, player_is_black, record.rules, &record.moves, record.result);
+        // 没有签名的棋谱大多来自其他软件，不必提示
+        self.signature = (verdict != Verdict::Unsigned).then_some(verdict);
+        // 已经结束的对局直接进入复盘
+        if self.game.is_over() {
+            self.open_replay();
+        }
+    }
+
+    /// 请求回到主菜单，对局未结束时先弹出确认框
+    fn request_leave(&mut self) {
+        if self.is_game_in_progress() || self.is_network_game_in_progress() {
+            self.confirm_leave = true;
+        } else {
+            self.network = None;
+            self.game_mode = GameMode::MainMenu;
+        }
+    }
+
+    /// 渲染离开确认对话框
+    fn render_leave_dialog(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if !self.confirm_leave {
+            return;
+        }
+        if self.game_mode == GameMode::Network {
+            self.render_resign_dialog(ctx, frame);
+            return;
+        }
+        // Some(true) 表示保存后离开，Some(false) 表示直接放弃
+        let mut save = None;
+        egui::Window::new("Leave unfinished game?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The current game has not finished yet.");
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        save = Some(true);
+                    }
+                    if ui.button("Abandon").clicked() {
+                        save = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_leave = false;
+                    }
+                });
+            });
+        if let Some(save) = save {
+            if save {
+                self.save_game();
+            }
+            self.confirm_leave = false;
+            self.game_mode = GameMode::MainMenu;
+        }
+    }
+
+    /// 渲染按 R 重新开始的确认对话框，可以先把当前对局存档
+    fn render_restart_dialog(&mut self, ctx: &egui::Context) {
+        if !self.confirm_restart {
+            return;
+        }
+        // 等待确认期间对局已经结束或离开了对局，不需要再确认
+        if !self.is_game_in_progress() {
+            self.confirm_restart = false;
+            return;
+        }
+        // Some(true) 表示保存后重新开始，Some(false) 表示直接放弃
+        let mut save = None;
+        egui::Window::new("Restart unfinished game?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The current game has not finished yet.");
+                ui.horizontal(|ui| {
+                    if ui.button("Save and Restart").clicked() {
+                        save = Some(true);
+                    }
+                    if ui.button("Restart").clicked() {
+                        save = Some(false);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_restart = false;
+                    }
+                });
+            });
+        if let Some(save) = save {
+            if save {
+                self.save_game();
+            }
+            self.confirm_restart = false;
+            self.restart();
+        }
+    }
+
+    /// 渲染联机对局的认输确认框，由关闭窗口触发时认输后直接退出
+    fn render_resign_dialog(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // 等待确认期间对局已经结束或断线，不需要再认输
+        if !self.is_network_game_in_progress() {
+            self.confirm_leave = false;
+            if self.closing {
+                frame.close();
+            }
+            return;
+        }
+        let mut resign = false;
+        egui::Window::new("Resign the game?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("Leaving a network game counts as a resignation.");
+                ui.horizontal(|ui| {
+                    let text = if self.closing { "Resign and Quit" } else { "Resign" };
+                    if ui.button(text).clicked() {
+                        resign = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_leave = false;
+                        self.closing = false;
+                    }
+                });
+            });
+        if !resign {
+            return;
+        }
+        // 先记下结果再断开，离开之后战绩里也有这一局
+        if let Some(local) = self.network.as_ref().map(NetSession::local_stone) {
+            self.game.resign(local);
+            self.record_finished_game();
+        }
+        if let Some(mut session) = self.network.take() {
+            session.send(Message::Resign);
+            session.close("resigned");
+        }
+        self.confirm_leave = false;
+        if self.closing {
+            frame.close();
+        } else {
+            self.game_mode = GameMode::MainMenu;
+        }
+    }
+
+    /// 渲染损坏文件对话框，一次处理一个文件
+    fn render_corrupt_file_dialog(&mut self, ctx: &egui::Context) {
+        let Some(file) = self.corrupt_files.first() else {
+            return;
+        };
+        // Some(true) 表示备份并重置，Some(false) 表示暂不处理
+        let mut reset = None;
+        egui::Window::new("Corrupted file")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(format!("Could not read {}", file.path.display()));
+                ui.label(RichText::new(&file.error).color(self.theme().error));
+                ui.label(format!(
+                    "Back it up to {} and start fresh?",
+                    file.backup_path().display()
+                ));
+                ui.horizontal(|ui| {
+                    if ui.button("Back Up and Reset").clicked() {
+                        reset = Some(true);
+                    }
+                    let hint = "Leave the file untouched. Changes are not saved to it until the next start.";
+                    if ui.button("Keep File").on_hover_text(hint).clicked() {
+                        reset = Some(false);
+                    }
+                });
+            });
+        let Some(reset) = reset else {
+            return;
+        };
+        if !reset {
+            let file = self.corrupt_files.remove(0);
+            self.kept_files.push(file.path);
+        } else if let Err(e) = self.corrupt_files[0].back_up_and_reset() {
+            // 没能备份时不能覆盖，把原因加到对话框里，玩家可以重试或者保留文件
+            let file = &mut self.corrupt_files[0];
+            file.error = format!("{}\nBackup failed: {:#}", file.error.lines().next().unwrap_or_default(), e);
+        } else {
+            self.corrupt_files.remove(0);
+        }
+    }
+
+    fn restart(&mut self) {
+        // 双方的规则必须一致，联机对局总是使用标准五子的自由规则
+        let rules = if self.game_mode == GameMode::Network { Rules::default() } else { self.settings.game_rules() };
+        self.game = GameState::with_rules(rules);
+        self.move_log.start(self.frame_millis, 0);
+        self.game_recorded = false;
+        self.is_paused = false;
+        self.view_ply = None;
+        self.forbidden_warning = None;
+        self.rejected_move = None;
+        self.mistake = None;
+        self.draw_offer = None;
+        self.share = None;
+        self.share_error.clear();
+        self.hint = None;
+        self.player_is_black = true;  // 重置为玩家黑子先手
+        let local = matches!(self.game_mode, GameMode::PlayerVsPlayer | GameMode::PlayerVsAI);
+        self.opening = (local && self.settings.swap2).then(Swap2::new);
+        // 联机对局连上之后按主机的时间控制开钟
+        self.clock = if local { GameClock::new(self.settings.time_control) } else { None };
+        self.ai_thinking = false;
+        self.ai_delay_timer = 0.0;
+        self.ai_pending_move = None;
+        self.ai_history.clear();
+        self.remote_search = None;
+        self.spectator.reset_timer();
+    }
+
+    /// 处理联机连接的事件：开局、对方落子、认输和断线
+    fn poll_network(&mut self) {
+        while let Some(event) = self.network.as_mut().and_then(NetSession::poll) {
+            match event {
+                // 教学不需要对局，老师先告诉学生开始教学
+                NetEvent::Connected if self.lesson.is_some() => {
+                    let teacher = self.lesson.as_ref().is_some_and(|lesson| lesson.role == Role::Teacher);
+                    if let Some(session) = self.network.as_mut().filter(|_| teacher) {
+                        session.send(Message::Lesson);
+                    }
+                    self.game_mode = GameMode::Lesson;
+                    return;
+                }
+                // 解题对战由主机定下题目顺序
+                NetEvent::Connected if self.duel.is_some() => {
+                    if let Some(duel) = self.duel.as_mut().filter(|duel| duel.is_host) {
+                        let seed = rng::time_seed();
+                        duel.start(seed);
+                        if let Some(session) = self.network.as_mut() {
+                            session.send(Message::Duel { seed });
+                        }
+                    }
+                    self.game_mode = GameMode::Duel;
+                    return;
+                }
+                // 恢复对局时不重新开局，先把本方的记录发给对方核对
+                NetEvent::Connected if self.net_resume.is_some() => {
+                    let message = Message::Resume { ply: self.game.moves().len(), checksum: self.game.checksum() };
+                    if let Some(session) = self.network.as_mut() {
+                        session.send(message);
+                    }
+                    self.net_message.clear();
+                }
+                NetEvent::Connected => {
+                    self.restart();
+                    self.player_is_black = self.network.as_ref().map(NetSession::local_stone) == Some(Stone::Black);
+                    self.net_message.clear();
+                    // 主机执黑，按自己选的时间控制开钟并告诉对方
+                    if self.player_is_black {
+                        let control = self.settings.time_control;
+                        self.clock = GameClock::new(control);
+                        if let Some(session) = self.network.as_mut() {
+                            session.send(Message::Time(control.code()));
+                        }
+                    }
+                }
+                NetEvent::Received(Message::Move { ply, coord, checksum }) => self.receive_network_move(ply, coord, checksum),
+                NetEvent::Received(Message::Resume { ply, checksum }) => self.verify_resume(ply, checksum),
+                // 加入的一方在第一步之前收到主机的时间控制
+                NetEvent::Received(Message::Time(code)) => {
+                    let control = TimeControl::from_code(&code);
+                    match control.filter(|_| !self.player_is_black && self.game.moves().is_empty()) {
+                        Some(control) => self.clock = GameClock::new(control),
+                        None => self.close_network("Opponent sent an invalid time control"),
+                    }
+                }
+                // 对方只能在自己的回合里超时
+                NetEvent::Received(Message::Timeout) => {
+                    let opponent = self.network.as_ref().map(|session| session.local_stone().opponent());
+                    match opponent.filter(|&stone| stone == self.game.to_move()) {
+                        Some(stone) => self.game.time_out(stone),
+                        None => self.close_network("Opponent sent an invalid timeout"),
+                    }
+                }
+                NetEvent::Received(Message::Resign) => {
+                    if let Some(local) = self.network.as_ref().map(NetSession::local_stone) {
+                        self.game.resign(local.opponent());
+                    }
+                    self.close_network("Opponent resigned");
+                }
+                NetEvent::Received(Message::Bye) => self.close_network("Opponent left the game"),
+                NetEvent::Received(Message::Hello(_)) => self.close_network("Opponent sent an unexpected handshake"),
+                NetEvent::Received(_) => self.close_network("Opponent chose a different mode"),
+                NetEvent::Disconnected(reason) => {
+                    // 还没开局就失败时回到大厅显示原因
+                    if !self.network.as_ref().is_some_and(NetSession::has_started) {
+                        self.network = None;
+                        self.net_message = reason;
+                    }
+                }
+            }
+        }
+    }
+
+    /// 校验并落下对方的棋：必须轮到对方，且步数与本地记录一致
+    ///
+    /// 落子之后的局面校验码与对方的不一致时说明双方局面已经不同，记录两边的校验码并结束对局。
+    fn receive_network_move(&mut self, ply: usize, coord: Coord, checksum: Checksum) {
+        let stone = self.game.to_move();
+        let opponent_turn = self.network.as_ref().is_some_and(|n| stone != n.local_stone());
+        if !opponent_turn || ply != self.game.moves().len() || self.game.play(coord).is_err() {
+            self.close_network("Opponent sent an invalid move");
+            return;
+        }
+        self.view_ply = None;
+        let local = self.game.checksum();
+        if local != checksum {
+            eprintln!("Network game out of sync after move {}: local {}, opponent {}", ply + 1, local, checksum);
+            self.close_network(&format!("Positions differ (yours {}, opponent's {})", local, checksum));
+        }
+    }
+
+    /// 结束联机对局并通知对方
+    /// 处理赛事的消息：裁判接受报名和上报，选手执行裁判的判定和调钟，编排的对局下完后上报结果
+    fn poll_tournament(&mut self) {
+        if let Some(director) = self.director.as_mut() {
+            director.poll();
+        }
+        while let Some(event) = self.tournament.as_mut().and_then(|entry| entry.session.poll()) {
+            let Some(entry) = self.tournament.as_mut() else {
                 break;
+            };
+            match event {
+                NetEvent::Connected => {
+                    let name = self.settings.player_name.trim();
+                    if !name.is_empty() {
+                        entry.session.send(Message::Player(name.to_string()));
+                    }
+                }
+                NetEvent::Received(message) => match entry.receive(message) {
+                    Ok(Some(directive)) => self.apply_directive(directive),
+                    Ok(None) => {}
+                    Err(reason) => entry.session.close(reason),
+                },
+                NetEvent::Disconnected(_) => {}
             }
-            
-            if self.board_data[nx][ny] == piece {
-                count += 1;
-            } else if self.board_data[nx][ny] == 0 {
-                break;
-            } else {
-                blocked += 1;
-                break;
+        }
+        let finished = self.game.is_over() && self.network.as_ref().is_some_and(NetSession::has_started);
+        if let Some(entry) = self.tournament.as_mut().filter(|entry| entry.playing && finished) {
+            entry.session.send(Message::Result(self.game.winner()));
+            entry.playing = false;
+        }
+    }
+
+    /// 把裁判的判定或调钟作用到正在下的联机对局上
+    fn apply_directive(&mut self, directive: Directive) {
+        let started = self.network.as_ref().is_some_and(NetSession::has_started);
+        if !started || self.game.is_over() {
+            return;
+        }
+        match directive {
+            Directive::Result(Some(winner)) => self.game.resign(winner.opponent()),
+            Directive::Result(None) => self.game.agree_draw(),
+            Directive::Clock { stone, delta } => {
+                if let Some(clock) = self.clock.as_mut() {
+                    clock.adjust(stone, delta);
+                }
+                return;
             }
         }
-        
-        // 向另一个方向计数
-        for i in 1..5 {
-            let nx = (x as i32 - dx * i) as usize;
-            let ny = (y as i32 - dy * i) as usize;
-            
-            if nx >= 15 || ny >= 15 {
-                blocked += 1;
-                break;
+        self.close_network("Result set by the tournament director");
+    }
+
+    fn close_network(&mut self, reason: &str) {
+        if let Some(session) = self.network.as_mut() {
+            session.close(reason);
+        }
+    }
+
+    /// AI落子逻辑
+    fn ai_move(&mut self, delta_time: f32) {
+        if self.game_mode != GameMode::PlayerVsAI || self.game.is_over() || self.is_paused {
+            return;
+        }
+
+        // 练习模式中等玩家决定是否悔棋
+        if self.mistake.is_some() {
+            return;
+        }
+
+        // Swap2 开局中按开局的步骤行动
+        if let Some(opening) = self.opening.filter(|o| !o.is_done()) {
+            self.ai_opening(opening, delta_time);
+            return;
+        }
+
+        // 检查是否轮到AI
+        let ai_stone = self.ai_stone(); // AI为白子或黑子
+        if self.game.to_move() != ai_stone {
+            return; // 不是AI的回合
+        }
+
+        // 如果有待执行的移动，检查延迟时间
+        if let Some(mv) = self.ai_pending_move {
+            self.ai_delay_timer += delta_time;
+            if self.ai_delay_timer >= 0.5 {
+                // 执行AI移动
+                let _ = self.game.play(mv);
+                
+                // 重置状态
+                self.ai_pending_move = None;
+                self.ai_thinking = false;
+                self.ai_delay_timer = 0.0;
             }
-            
-            if self.board_data[nx][ny] == piece {
-                count += 1;
-            } else if self.board_data[nx][ny] == 0 {
-                break;
-            } else {
-                blocked += 1;
-                break;
+        } else if let Some(search) = &self.remote_search {
+            // 等待远程引擎，失败或者落点不合法时改用内置 AI
+            let Some(result) = search.poll() else {
+                return;
+            };
+            self.remote_search = None;
+            let board = self.game.board();
+            let rules = self.game.rules();
+            let legal = |mv: &Coord| board.is_empty_at(*mv) && rules.forbidden(board, *mv, ai_stone).is_none();
+            match result.and_then(|mv| Some(mv).filter(legal).context("engine played an illegal move")) {
+                Ok(mv) => {
+                    self.engine_error = None;
+                    self.ai_pending_move = Some(mv);
+                    self.ai_delay_timer = 0.0;
+                }
+                Err(e) => {
+                    self.engine_error = Some(format!("{:#}", e));
+                    self.search_locally(ai_stone);
+                }
             }
+        } else if NATIVE && !self.settings.engine_address.is_empty() && gomocup::supports(self.game.rules()) {
+            // 远程引擎思考期间由棋钟照常走时
+            self.ai_thinking = true;
+            let address = self.settings.engine_address.trim();
+            self.remote_search = Some(RemoteSearch::start(address, self.game.rules(), self.game.moves(), ai_stone));
+        } else {
+            self.search_locally(ai_stone);
         }
-        
-        // 根据连子数和阻塞情况给分
-        match count {
-            4 => 10000,  // 五连
-            3 => if blocked == 0 { 1000 } else { 100 },
-            2 => if blocked == 0 { 100 } else { 10 },
-            1 => if blocked == 0 { 10 } else { 1 },
-            _ => 0,
+    }
+
+    /// 开局阶段按设置利用对这位玩家的经验，改下赢过玩家或者没走过的一步
+    fn adapt_to_player(&mut self, ai: &Ai, board: &Board, analysis: Analysis) -> Analysis {
+        let adaptation = self.settings.ai_learning;
+        let rules = self.game.rules();
+        let opening = self.game.moves().len() < learning::LEARNED_PLIES;
+        if adaptation == Adaptation::Off || !opening || !self.experience().knows(board, rules) {
+            return analysis;
+        }
+        let candidates = ai.top_moves(board, LEARNING_CANDIDATES);
+        self.experience().choose(board, rules, adaptation, &candidates).unwrap_or(analysis)
+    }
+
+    /// 开局阶段从开局库的应对中随机选一步，设置关闭、库里没有或者应对是禁手时返回 None
+    fn book_move(&self, board: &Board, stone: Stone) -> Option<Coord> {
+        let rules = self.game.rules();
+        let opening = self.game.moves().len() < BOOK_PLIES && rules.win_length() == Rules::DEFAULT_WIN_LENGTH;
+        if !self.settings.opening_book || !opening {
+            return None;
+        }
+        let moves: Vec<Coord> = self
+            .opening_book
+            .moves(board)
+            .into_iter()
+            .filter(|&mv| rules.forbidden(board, mv, stone).is_none())
+            .collect();
+        let pick = rng::XorShift::new(rng::time_seed()).next() as usize;
+        moves.get(pick % moves.len().max(1)).copied()
+    }
+
+    /// 当前玩家的经验，换了玩家名字之后重新读取
+    fn experience(&mut self) -> &mut Experience {
+        let path = experience::profile_path(&self.settings.player_name);
+        if self.experience_path.as_ref() != Some(&path) {
+            let size = self.settings.experience_size;
+            self.experience = experience::load(&path, size).unwrap_or_else(|e| {
+                self.corrupt_files.push(CorruptFile::new(&path, &e));
+                let mut experience = Experience::new();
+                experience.set_capacity(size);
+                experience
+            });
+            self.experience_path = Some(path);
+        }
+        &mut self.experience
+    }
+
+    /// 用内置 AI 计算落子并设置延迟，开局阶段先查开局库，之前分析过的局面直接使用缓存
+    fn search_locally(&mut self, ai_stone: Stone) {
+        self.ai_thinking = true;
+        let board = *self.game.board();
+        let difficulty = self.settings.ai_difficulty;
+        let rules = self.game.rules();
+        let key = AnalysisCache::key(&board, ai_stone, difficulty, rules);
+        let started = Instant::now();
+        let ai = Ai::new(ai_stone, difficulty).with_rules(rules).with_first_move(self.settings.ai_first_move);
+        let ply = self.game.moves().len();
+        let pick = rng::XorShift::new(rng::time_seed()).next();
+        // 空棋盘上按设置的策略下第一步，之后的开局阶段先查开局库
+        let unsearched = match ai.opening_move(&board, Some(&self.opening_book), pick) {
+            Some(first) => Some(first.best_move),
+            None => self.book_move(&board, ai_stone),
+        };
+        // 缓存里保存搜索的最佳点，开局随机化在取出之后再做；按经验改下时优先于随机化
+        let analysis = match (unsearched, self.analysis_cache.get(key)) {
+            // 第一步和开局库的落子没有搜索得分，按均势记录
+            (Some(mv), _) => Analysis { best_move: mv, score: 0 },
+            (None, Some(cached)) if board.is_empty_at(cached.best_move) => {
+                let varied = ai.vary_opening(&board, ply, cached, pick);
+                self.adapt_to_player(&ai, &board, varied)
+            }
+            (None, _) => {
+                let analysis = ai.best_move(&board);
+                self.analysis_cache.insert(key, analysis);
+                let varied = ai.vary_opening(&board, ply, analysis, pick);
+                self.adapt_to_player(&ai, &board, varied)
+            }
+        };
+        let move_number = ply + 1;
+        self.ai_history.push(Evaluation::new(move_number, &board, &ai, analysis));
+        self.ai_pending_move = Some(analysis.best_move);
+        self.ai_delay_timer = 0.0;
+
+        // 搜索用的时间计入 AI 的棋钟，之后展示落子的延迟不计
+        let flagged = self
+            .clock
+            .as_mut()
+            .is_some_and(|clock| clock.tick(ai_stone, started.elapsed().as_millis() as u64));
+        if flagged {
+            self.game.time_out(ai_stone);
+        }
+    }
+
+    /// 轮到的一方走时，时间用完时判负
+    ///
+    /// 暂停、Swap2 开局、等待玩家处理失误以及 AI 展示落子的延迟期间不走时。联机对局断线后也不走时，
+    /// 并且只判本方超时再通知对方，对方的钟停在 0:00 等对方的通知，网络延迟不会让双方各判各的。
+    fn tick_clock(&mut self, ctx: &egui::Context, elapsed: u64) {
+        if self.clock.is_none() || self.game.is_over() {
+            return;
+        }
+        let waiting = self.is_paused || self.mistake.is_some() || self.ai_pending_move.is_some();
+        let offline = self.network.as_ref().is_some_and(|session| !session.is_connected());
+        if waiting || offline || self.opening_seat().is_some() {
+            return;
+        }
+        let stone = self.game.to_move();
+        let remote = self.network.as_ref().is_some_and(|session| session.local_stone() != stone);
+        let Some(clock) = self.clock.as_mut() else {
+            return;
+        };
+        if clock.tick(stone, elapsed) {
+            if remote {
+                return;
+            }
+            if let Some(session) = self.network.as_mut() {
+                session.send(Message::Timeout);
+            }
+            self.game.time_out(stone);
+            return;
+        }
+        // 只在显示的秒数变化时刷新，不必每帧重绘；即将超时的一方闪烁时才持续刷新
+        if clock.state(stone) == TimeState::Critical && !self.settings.reduce_motion {
+            ctx.request_repaint_after(PULSE_FRAME);
+        } else {
+            ctx.request_repaint_after(Duration::from_millis(clock::until_next_second(clock.remaining(stone))));
+        }
+    }
+
+    /// 棋钟的文字：轮到的一方加粗，时间不多时变成警告色，即将超时时变成错误色并闪烁
+    fn clock_text(&self, clock: &GameClock, stone: Stone) -> RichText {
+        let remaining = clock.remaining(stone);
+        let text = RichText::new(format!("{} {}", stone.name(), clock::format_time(remaining)));
+        let running = stone == self.game.to_move() && !self.game.is_over();
+        let text = if running { text.strong() } else { text };
+        let theme = self.theme();
+        match clock.state(stone) {
+            TimeState::Normal => text,
+            TimeState::Low => text.color(theme.warning),
+            TimeState::Critical if running && !self.settings.reduce_motion => {
+                // 每秒闪一次，和秒数的跳变对齐
+                let phase = (remaining % 1000) as f32 / 1000.0;
+                text.color(theme.error.gamma_multiply(0.55 + 0.45 * (phase * std::f32::consts::TAU).cos()))
+            }
+            TimeState::Critical => text.color(theme.error),
+        }
+    }
+
+    /// 观战模式中轮到的一方 AI 按设定的间隔落子，棋盘下满时停止
+    fn step_spectator(&mut self, delta_time: f32) {
+        if !self.spectator.started || self.is_paused || self.game.is_over() {
+            return;
+        }
+        if !self.spectator.tick(delta_time) {
+            return;
+        }
+        let stone = self.game.to_move();
+        let board = self.game.board();
+        let ai = self.spectator.ai(stone, self.game.rules());
+        let pick = rng::XorShift::new(rng::time_seed()).next();
+        let analysis = ai.vary_opening(board, self.game.moves().len(), ai.best_move(board), pick);
+        let _ = self.game.play(analysis.best_move);
+    }
+
+    /// AI 在 Swap2 开局中摆子或做选择，摆子和正常落子一样有延迟
+    fn ai_opening(&mut self, opening: Swap2, delta_time: f32) {
+        let ai_seat = self.player_seat.other();
+        if opening.chooser() == Some(ai_seat) {
+            let choice = opening.ai_choice(self.game.board(), self.game.rules());
+            self.choose_opening(choice);
+            return;
+        }
+        if opening.placer() != Some(ai_seat) {
+            return;
+        }
+        self.ai_thinking = true;
+        self.ai_delay_timer += delta_time;
+        if self.ai_delay_timer >= 0.5 {
+            // 摆的第一子和正常对局一样按第一步的策略选，其余两子跟着平移
+            let pick = rng::XorShift::new(rng::time_seed()).next();
+            let first = self.settings.ai_first_move.choose(Some(&self.opening_book), pick);
+            let mv = opening.ai_move(self.game.board(), self.game.rules(), self.settings.ai_difficulty, first);
+            if self.game.play(mv).is_ok() {
+                if let Some(opening) = self.opening.as_mut() {
+                    opening.advance(self.game.moves().len());
+                }
+            }
+            self.ai_thinking = false;
+            self.ai_delay_timer = 0.0;
         }
     }
 }
 
 impl eframe::App for AppUI {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // 获取时间增量
+    fn on_close_event(&mut self) -> bool {
+        // 联机对局中关闭窗口视为认输，先确认，确认后通知对方再退出
+        if self.is_network_game_in_progress() {
+            self.confirm_leave = true;
+            self.closing = true;
+            return false;
+        }
+        self.network = None;
+
+        // 本地对局关闭窗口时自动保存，下次可以从主菜单继续；保存失败时先不退出，
+        // 对话框里可以重试或者不保存直接退出，后者保留失败原因再次关闭窗口
+        let discard = self.closing && self.save_error.is_some();
+        self.save_error = None;
+        if !self.is_game_in_progress() {
+            self.save_analysis_cache();
+        } else if !discard {
+            self.save_game();
+            if self.save_error.is_some() {
+                self.closing = true;
+                return false;
+            }
+        }
+        self.save_settings();
+        self.telemetry.end_session();
+        self.save_telemetry();
+        true
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // 获取时间增量，动画用秒，棋钟用毫秒
         let delta_time = ctx.input(|i| i.unstable_dt);
+        let now_millis = self.launched.elapsed().as_millis() as u64;
+        let delta_millis = now_millis - std::mem::replace(&mut self.frame_millis, now_millis);
+        self.apply_motion_setting(ctx);
+        self.refresh_assets();
+        self.apply_theme(ctx);
+        let mode_before = self.game_mode;
         
         match self.game_mode {
             GameMode::MainMenu => {
+                self.step_demo(ctx, delta_millis);
                 egui::CentralPanel::default()
                     .frame(self.frame)
                     .show(ctx, |ui| {
+                        self.render_demo(ui);
                         self.render_main_menu(ui);
                     });
             }
+            GameMode::Settings => {
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        self.render_settings(ui);
+                    });
+            }
+            GameMode::Diagnostics => {
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        self.render_diagnostics(ui);
+                    });
+            }
+            GameMode::Statistics => {
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        self.render_statistics(ui);
+                    });
+            }
+            GameMode::Puzzle => {
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Back to Menu").clicked() {
+                                self.game_mode = GameMode::MainMenu;
+                                return;
+                            }
+                            let can_hint = !self.puzzle.solved && self.puzzle.hint < HintStage::Move;
+                            if ui.add_enabled(can_hint, egui::Button::new("Hint")).clicked() {
+                                self.puzzle.take_hint();
+                            }
+                            if ui.button("Next Puzzle").clicked() {
+                                self.puzzle.next_puzzle();
+                            }
+                            ui.label(format!("Score: {}", self.puzzle.score));
+                        });
+                        // 有反馈时显示反馈，否则显示题目
+                        if self.puzzle.message.is_empty() {
+                            let puzzle = self.puzzle.puzzle();
+                            ui.label(format!("{} ({} to move)", puzzle.title, puzzle.to_move.name()));
+                        } else {
+                            ui.label(&self.puzzle.message);
+                        }
+
+                        self.render_board(ui);
+                        self.render_piece(ui, &self.puzzle.board);
+                        self.render_puzzle_hint(ui, &self.puzzle);
+
+                        // 工具栏按钮先占用点击，棋盘只响应落在按钮以外的点击
+                        let board_rect = self.geometry.hit_rect();
+                        let response = ui.interact(board_rect, ui.id().with("puzzle_board"), egui::Sense::click());
+                        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                            self.handle_puzzle_click(pos);
+                        }
+                    });
+            }
+            GameMode::Tutorial => {
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            if ui.button("Back to Menu").clicked() {
+                                self.game_mode = GameMode::MainMenu;
+                                return;
+                            }
+                            if ui.button("Restart Lesson").clicked() {
+                                self.tutorial.go_to(self.tutorial.index);
+                            }
+                            if ui.button("Next Lesson").clicked() {
+                                self.tutorial.next_exercise();
+                            }
+                            let exercise = self.tutorial.exercise();
+                            ui.label(format!("{} (you play {})", exercise.title, exercise.student.name()));
+                        });
+                        ui.label(self.tutorial.text());
+
+                        self.render_board(ui);
+                        self.render_piece(ui, &self.tutorial.board);
+                        // 最后一步上画一个小圆点，看清对手应在哪里
+                        if let Some(coord) = self.tutorial.last_move {
+                            let theme = self.theme();
+                            let radius = self.geometry.stone_radius() * theme.last_move_ratio;
+                            ui.painter().circle_filled(self.geometry.pixel_at(coord), radius, theme.last_move);
+                        }
+
+                        let board_rect = self.geometry.hit_rect();
+                        let response = ui.interact(board_rect, ui.id().with("tutorial_board"), egui::Sense::click());
+                        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
+                            self.handle_tutorial_click(pos);
+                        }
+                    });
+            }
             GameMode::PlayerVsAI if !self.color_selected => {
                 egui::CentralPanel::default()
                     .frame(self.frame)
@@ -506,84 +4180,343 @@ impl eframe::App for AppUI {
                     });
             }
             GameMode::PlayerVsPlayer | GameMode::PlayerVsAI => {
+                self.render_move_list(ctx);
                 egui::CentralPanel::default()
                     .frame(self.frame)
                     .show(ctx, |ui| {
                         // 添加返回主菜单按钮和游戏信息
                         ui.horizontal(|ui| {
                             if ui.button("Back to Menu").clicked() {
-                                self.game_mode = GameMode::MainMenu;
+                                self.request_leave();
                                 return;
                             }
+
+                            if ui.add_enabled(self.can_undo(), egui::Button::new("Undo")).clicked() {
+                                self.undo();
+                            }
+                            if ui.add_enabled(self.game.can_redo(), egui::Button::new("Redo")).clicked() {
+                                self.redo();
+                            }
+                            if dialog::AVAILABLE && ui.button("Save Game").clicked() {
+                                self.save_game_as();
+                            }
+                            if ui.button("Share").clicked() {
+                                self.open_share();
+                            }
+                            if !self.share_error.is_empty() {
+                                ui.label(RichText::new(&self.share_error).color(self.theme().error));
+                            }
+                            if NATIVE {
+                                self.render_live_toggle(ui);
+                            }
+                            if ui.add_enabled(self.can_hint(), egui::Button::new("Hint")).clicked() {
+                                let stone = self.game.to_move();
+                                let ply = self.game.moves().len();
+                                self.hint = Some(Hint::start(self.game.board(), stone, self.game.rules(), ply));
+                            }
+                            self.threat_overlay_toggle(ui);
+                            ui.checkbox(&mut self.settings.edge_hints, "Edge Hints").on_hover_text(
+                                "Mark where your next stone would extend a line: green when both ends have room, \
+                                 amber when the line is pressed against the edge, red when it can never reach five.",
+                            );
+
+                            if !self.game.is_over() {
+                                let pause_text = if self.is_paused { "Resume" } else { "Pause" };
+                                if ui.button(pause_text).clicked() {
+                                    self.is_paused = !self.is_paused;
+                                }
+                            }
+
+                            // 双人对战中由轮到的一方认输或提和
+                            if self.game_mode == GameMode::PlayerVsPlayer && !self.game.is_over() && self.draw_offer.is_none() {
+                                if ui.button("Resign").clicked() {
+                                    self.game.resign(self.game.to_move());
+                                }
+                                if ui.button("Offer Draw").clicked() {
+                                    self.draw_offer = Some(self.game.to_move());
+                                }
+                            }
                             
-                            // 显示当前回合信息
-                            if self.game_mode == GameMode::PlayerVsAI {
-                                let current_player = if self.is_black {
-                                    if self.player_is_black { "Player (Black)" } else { "AI (Black)" }
-                                } else {
-                                    if self.player_is_black { "AI (White)" } else { "Player (White)" }
-                                };
+                            // 显示当前回合信息，结束后显示胜负
+                            if self.game.is_over() {
+                                ui.label(RichText::new(self.winner_text()).strong());
+                            } else if let Some(status) = self.opening_status() {
+                                ui.label(status);
+                                if self.ai_thinking {
+                                    ui.label("AI is thinking...");
+                                }
+                            } else if self.game_mode == GameMode::PlayerVsAI {
+                                let side = self.game.to_move();
+                                let who = if side == self.ai_stone() { "AI" } else { "Player" };
                                 
-                                ui.label(format!("Current Turn: {}", current_player));
+                                ui.label(format!("Current Turn: {} ({})", who, side.name()));
                                 
                                 if self.ai_thinking || self.ai_pending_move.is_some() {
                                     ui.label("AI is thinking...");
                                 }
+                                self.render_eval_bar(ui);
                             } else {
-                                let current_player = if self.is_black { "Black" } else { "White" };
-                                ui.label(format!("Current Turn: {}", current_player));
+                                ui.label(format!("Current Turn: {}", self.game.to_move().name()));
+                            }
+                            if let Some(text) = self.cursor_text() {
+                                ui.label(text);
+                            }
+                            if !self.game.is_over() {
+                                self.render_move_entry(ui);
+                            }
+
+                            // 双方的剩余时间，轮到的一方加粗，时间不多时变色
+                            if let Some(clock) = &self.clock {
+                                for stone in [Stone::Black, Stone::White] {
+                                    ui.label(self.clock_text(clock, stone));
+                                }
+                            }
+                            if self.game.rules() != Rules::default() {
+                                ui.label(self.game.rules().to_string());
+                            }
+                            self.render_checksum(ui);
+                            if let Some((_, kind)) = self.forbidden_warning {
+                                ui.label(RichText::new(format!("Forbidden: {}", kind)).color(self.theme().warning));
+                            }
+
+                            // 回看时显示当前所在的步数
+                            if let Some(ply) = self.view_ply {
+                                ui.label(format!("Move {}/{}", ply, self.game.moves().len()));
+                            }
+                            if self.hint.as_ref().is_some_and(Hint::is_searching) {
+                                ui.label("Finding a hint...");
+                            }
+                            if let Some(error) = &self.engine_error {
+                                ui.label(RichText::new("External engine failed").color(self.theme().warning)).on_hover_text(error);
                             }
                         });
                         
+                        self.handle_history_keys(ctx);
+                        self.handle_play_keys(ctx);
+
                         self.render_board(ui);
-                        self.render_piece(ui);
-
-                        if self.is_winner {
-                            let text = if self.game_mode == GameMode::PlayerVsAI {
-                                if self.is_black {
-                                    if self.player_is_black { "Player Wins!" } else { "AI Wins!" }
-                                } else {
-                                    if self.player_is_black { "AI Wins!" } else { "Player Wins!" }
-                                }
-                            } else {
-                                if self.is_black { "Black Wins!" } else { "White Wins!" }
-                            };
-                            egui::Window::new(text)
+                        self.render_position(ui);
+                        if !self.game.is_over() {
+                            self.render_threats(ui);
+                            self.render_edge_hints(ui);
+                        }
+                        self.render_cursor(ui);
+                        self.render_forbidden_warning(ui);
+                        self.render_rejected_move(ui);
+                        self.render_hint(ui);
+                        self.render_share_dialog(ctx);
+
+                        if self.game.is_over() {
+                            egui::Window::new(self.winner_text())
                                 .collapsible(false)
                                 .resizable(false)
                                 .show(ctx, |ui| {
                                     ui.vertical_centered(|ui| {
+                                        match self.game.outcome() {
+                                            Some(Outcome::Resignation(winner)) => {
+                                                ui.label(format!("{} resigned", winner.opponent().name()));
+                                            }
+                                            Some(Outcome::Timeout(winner)) => {
+                                                ui.label(format!("{} ran out of time", winner.opponent().name()));
+                                            }
+                                            _ => {}
+                                        }
+                                        // 人机对战中 AI 说说自己对这局棋的感受
+                                        if let Some(outcome) = self.game.outcome().filter(|_| self.game_mode == GameMode::PlayerVsAI) {
+                                            for line in commentary::summarize(&self.ai_history, self.ai_stone(), outcome) {
+                                                ui.label(RichText::new(format!("AI: {}", line)).italics());
+                                            }
+                                            self.render_win_graph(ui);
+                                        }
                                         if ui.button("Restart").clicked() {
                                             self.restart();
                                         }
                                         if ui.button("Back to Menu").clicked() {
                                             self.game_mode = GameMode::MainMenu;
                                         }
+                                        // 复制文字棋谱到剪贴板
+                                        if ui.button("Copy Record").clicked() {
+                                            let record = self.export_kifu();
+                                            ui.output_mut(|o| o.copied_text = record);
+                                        }
+                                        if dialog::AVAILABLE && ui.button("Save Game").clicked() {
+                                            self.save_game_as();
+                                        }
+                                        if ui.button("Review").clicked() {
+                                            self.open_replay();
+                                        }
                                     });
                                 });
                             return;
                         }
 
-                        // 监听点击事件
-                        if let Some(pos) = ctx.input(|i| i.pointer.press_origin()) {
+                        self.render_swap2_dialog(ctx);
+                        self.render_draw_offer_dialog(ctx);
+                        self.render_mistake_dialog(ctx);
+
+                        // 只响应在棋盘上按下并松开的左键点击，工具栏、对话框和拖动都不算落子
+                        let response = ui.interact(self.geometry.hit_rect(), ui.id().with("game_board"), egui::Sense::click());
+                        if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked()) {
                             self.handle_click(pos);
                         }
+                        self.render_pointer_stone(ui, &response);
+                        self.render_stone_tooltip(ui, &response);
                     });
                 
+                self.tick_clock(ctx, delta_millis);
+                self.update_hint(ctx);
+
                 // 在AI模式下，玩家落子后调用AI逻辑
-                if self.game_mode == GameMode::PlayerVsAI && !self.is_winner {
+                if self.game_mode == GameMode::PlayerVsAI && !self.game.is_over() {
                     self.ai_move(delta_time);
                 }
+                // 远程引擎的回复由后台线程收取，需要持续刷新界面
+                if self.remote_search.is_some() {
+                    ctx.request_repaint();
+                }
+                self.record_finished_game();
+            }
+            GameMode::Network => {
+                self.poll_network();
+                self.poll_tournament();
+                // 对方的消息由后台线程收取，需要持续刷新界面才能及时处理
+                ctx.request_repaint();
+                let started = self.network.as_ref().is_some_and(NetSession::has_started);
+                if started {
+                    self.tick_clock(ctx, delta_millis);
+                    self.record_finished_game();
+                    self.render_move_list(ctx);
+                }
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        if started {
+                            self.render_network_game(ctx, ui);
+                        } else if self.director.is_some() {
+                            self.render_director(ui);
+                        } else {
+                            self.render_lobby(ui);
+                        }
+                    });
+            }
+            GameMode::Lesson => {
+                self.poll_lesson();
+                // 对方的消息由后台线程收取，需要持续刷新界面才能及时处理
+                ctx.request_repaint();
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        self.render_lesson(ui);
+                    });
+            }
+            GameMode::Duel => {
+                self.poll_duel();
+                if let Some(duel) = self.duel.as_mut() {
+                    duel.tick(delta_millis);
+                }
+                // 对方的消息由后台线程收取，计时也需要持续刷新界面
+                ctx.request_repaint();
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        self.render_duel(ui);
+                    });
+            }
+            GameMode::AiVsAi => {
+                self.step_spectator(delta_time);
+                // 两个 AI 自动落子，需要持续刷新界面
+                if self.spectator.started {
+                    ctx.request_repaint();
+                    self.render_move_list(ctx);
+                }
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        if self.spectator.started {
+                            self.render_spectator_game(ctx, ui);
+                        } else {
+                            self.render_spectator_setup(ui);
+                        }
+                    });
             }
+            GameMode::Replay => {
+                self.step_autoplay(ctx, delta_time);
+                self.render_move_list(ctx);
+                egui::CentralPanel::default()
+                    .frame(self.frame)
+                    .show(ctx, |ui| {
+                        self.render_replay_controls(ui);
+                        self.handle_history_keys(ctx);
+
+                        self.render_board(ui);
+                        self.render_position(ui);
+                        self.render_threats(ui);
+                    });
+            }
+        }
+
+        self.dispatch_game_events();
+        self.publish_live();
+        self.render_leave_dialog(ctx, frame);
+        self.render_restart_dialog(ctx);
+        self.render_corrupt_file_dialog(ctx);
+        self.render_signature_dialog(ctx);
+        self.render_save_error_dialog(ctx, frame);
+        self.render_key_error_dialog(ctx);
+
+        // 切换界面时的提示音
+        if self.game_mode != mode_before {
+            self.play_sound(Sound::Menu);
         }
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() {
+    #[cfg(feature = "native")]
+    {
+        // 隐藏的压力测试模式，不打开窗口
+        let args = Args::from_env();
+        if args.has("--soak") {
+            std::process::exit(if soak::run(&args) { 0 } else { 1 });
+        }
+        // 作为 Gomocup 引擎运行，供其他管理程序调用
+        if args.has("--pbrain") {
+            pbrain::run(&args);
+            return;
+        }
+        // 不打开窗口的命令行对局和自对弈
+        if args.has("--cli") {
+            cli::play(&args);
+            return;
+        }
+        if args.has("--selfplay") {
+            cli::selfplay(&args);
+            return;
+        }
+        if args.has("--calibrate") {
+            cli::calibrate(&args);
+            return;
+        }
+    }
+
     let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::Vec2::new(450.0, 450.0)),
-        resizable: false,
+        // 棋盘随窗口缩放，右侧留出落子列表
+        initial_window_size: Some(egui::Vec2::new(620.0, 520.0)),
+        min_window_size: Some(egui::Vec2::new(400.0, 320.0)),
+        resizable: true,
         ..Default::default()
     };
     eframe::run_native("Gomoku", options, Box::new(|cc| Box::new(AppUI::new(cc)))).unwrap();
 }
+
+/// 网页版入口：界面画在 index.html 中 id 为 `gomoku_canvas` 的 canvas 上
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    let options = eframe::WebOptions::default();
+    wasm_bindgen_futures::spawn_local(async {
+        eframe::start_web("gomoku_canvas", options, Box::new(|cc| Box::new(AppUI::new(cc))))
+            .await
+            .expect("failed to start eframe");
+    });
+}
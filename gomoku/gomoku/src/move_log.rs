@@ -0,0 +1,109 @@
+use gomoku_core::{GameEvent, Stone};
+
+/// 一步棋的时间信息（毫秒）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MoveTime {
+    // 落子时离开局过了多久
+    pub at: u64,
+    // 这一方想了多久，从上一步落下开始算
+    pub spent: u64,
+}
+
+/// 对局中每一步的落子时间和用时，按对局事件维护，下标与 `GameState::moves` 相同
+///
+/// 读取的存档和棋谱没有时间记录，这些步记为 None。
+#[derive(Default)]
+pub struct MoveLog {
+    times: Vec<Option<MoveTime>>,
+    // 悔棋撤销的步，重做时恢复原来的时间
+    undone: Vec<Option<MoveTime>>,
+    // 开局和这一步开始思考的时刻，以界面启动后的毫秒数计
+    started: u64,
+    turn_started: u64,
+}
+
+impl MoveLog {
+    /// 在 now 开始新的一局，之前已有 moves 步时间不明的棋
+    pub fn start(&mut self, now: u64, moves: usize) {
+        self.times = vec![None; moves];
+        self.undone.clear();
+        self.started = now;
+        self.turn_started = now;
+    }
+
+    /// 在 now 收到一个对局事件
+    pub fn record(&mut self, event: GameEvent, now: u64) {
+        match event {
+            GameEvent::Played { .. } => {
+                let time = MoveTime { at: now.saturating_sub(self.started), spent: now.saturating_sub(self.turn_started) };
+                self.times.push(Some(time));
+                self.undone.clear();
+            }
+            GameEvent::Undone { .. } => {
+                if let Some(time) = self.times.pop() {
+                    self.undone.push(time);
+                }
+            }
+            GameEvent::Redone { .. } => self.times.push(self.undone.pop().flatten()),
+            GameEvent::Won(_) | GameEvent::Drawn => return,
+        }
+        self.turn_started = now;
+    }
+
+    /// 和对局的步数对齐：绕过事件换掉对局时补上或截掉时间不明的步
+    pub fn sync(&mut self, moves: usize) {
+        self.times.resize(moves, None);
+    }
+
+    /// 第 ply 步（从 0 开始）的时间，没有记录时返回 None
+    pub fn get(&self, ply: usize) -> Option<MoveTime> {
+        self.times.get(ply).copied().flatten()
+    }
+}
+
+/// 落子的说明文字：第几手、哪一方，有记录时再加上落子时间和用时
+pub fn describe(ply: usize, stone: Stone, time: Option<MoveTime>) -> String {
+    let mut text = format!("Move {} - {}", ply + 1, stone.name());
+    if let Some(time) = time {
+        text.push_str(&format!(
+            "\nPlayed at {} into the game\nThought for {}",
+            crate::clock::format_time(time.at),
+            crate::clock::format_time(time.spent)
+        ));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gomoku_core::Coord;
+
+    #[test]
+    fn tracks_moves_through_undo_and_redo() {
+        let played = GameEvent::Played { coord: Coord::CENTER, stone: Stone::Black };
+        let undone = GameEvent::Undone { coord: Coord::CENTER, stone: Stone::Black };
+        let redone = GameEvent::Redone { coord: Coord::CENTER, stone: Stone::Black };
+        let mut log = MoveLog::default();
+        log.start(1_000, 0);
+        log.record(played, 4_000);
+        log.record(played, 9_000);
+        assert_eq!(log.get(1), Some(MoveTime { at: 8_000, spent: 5_000 }));
+
+        log.record(undone, 10_000);
+        assert_eq!(log.get(1), None);
+        log.record(redone, 11_000);
+        assert_eq!(log.get(1), Some(MoveTime { at: 8_000, spent: 5_000 }));
+        // 下一步从重做之后开始计时
+        log.record(played, 12_500);
+        assert_eq!(log.get(2), Some(MoveTime { at: 11_500, spent: 1_500 }));
+
+        // 读取的对局时间不明
+        log.start(20_000, 3);
+        assert_eq!(log.get(0), None);
+        log.sync(1);
+        log.record(played, 21_000);
+        assert_eq!(log.get(1), Some(MoveTime { at: 1_000, spent: 1_000 }));
+        assert_eq!(describe(0, Stone::White, None), "Move 1 - White");
+    }
+}
@@ -0,0 +1,172 @@
+use crate::audio::{self, SoundProfile, SAMPLE_RATE};
+use crate::kifu;
+use anyhow::{Context, Result};
+use gomoku_core::{Board, Coord, CoordOrigin, Outcome, Rules, Stone};
+use std::fs;
+use std::path::Path;
+
+// 第一步之前的留白、相邻两步之间的间隔，以及结果字幕显示的时长，毫秒
+const LEAD_IN: u64 = 500;
+const MOVE_INTERVAL: u64 = 1500;
+const RESULT_CAPTION: u64 = 3000;
+// 落子音效之后多久报出坐标，毫秒
+const COORDINATE_DELAY: u64 = 300;
+
+/// 一条字幕：开始、结束时间（毫秒）和文字
+struct Caption {
+    start: u64,
+    end: u64,
+    text: String,
+}
+
+/// 解说复盘：每步棋的落子音效按固定节奏排成一条音轨，配上同步的字幕
+///
+/// 每个落子音效之后用两个音报出坐标（见 `audio::coordinate_tones`），只听音频也能跟上棋局；
+/// 字幕写出坐标和注释。保存为 WAV 音频和同名的 SRT 字幕，视频编辑软件和播放器都能把两者对齐。
+pub struct Narration {
+    samples: Vec<f32>,
+    captions: Vec<Caption>,
+}
+
+impl Narration {
+    /// sounds 依次是黑方和白方的落子音效方案，result 是最后一条字幕，分出胜负时配上获胜音效
+    pub fn new(
+        moves: &[Coord],
+        sounds: [SoundProfile; 2],
+        outcome: Option<Outcome>,
+        result: &str,
+        rules: Rules,
+        origin: CoordOrigin,
+    ) -> Self {
+        let placements = sounds.map(audio::synthesize_move);
+        let mut narration = Self { samples: Vec::new(), captions: Vec::new() };
+        let mut board = Board::new();
+        let mut start = LEAD_IN;
+        for (i, &mv) in moves.iter().enumerate() {
+            let stone = Stone::for_ply(i);
+            let (side, sound) = if stone == Stone::Black { ("Black", &placements[0]) } else { ("White", &placements[1]) };
+            let annotation = kifu::annotate(&board, mv, stone, rules.win_length());
+            board.set(mv, stone);
+            let mut text = format!("{}. {} {}", i + 1, side, mv.notation(origin));
+            if !annotation.is_empty() {
+                text = format!("{}: {}", text, annotation);
+            }
+            narration.mix(start, sound);
+            narration.mix(start + COORDINATE_DELAY, &audio::coordinate_tones(mv.col, origin.row_number(mv.row)));
+            narration.captions.push(Caption { start, end: start + MOVE_INTERVAL, text });
+            start += MOVE_INTERVAL;
+        }
+        if matches!(outcome, Some(outcome) if outcome != Outcome::Draw) {
+            narration.mix(start, &audio::win_jingle());
+        }
+        let end = start + RESULT_CAPTION;
+        narration.captions.push(Caption { start, end, text: result.to_string() });
+        narration.samples.resize(narration.samples.len().max(sample_index(end)), 0.0);
+        narration
+    }
+
+    /// 把 sound 叠加到音轨上 at 毫秒的位置
+    fn mix(&mut self, at: u64, sound: &[f32]) {
+        let offset = sample_index(at);
+        if self.samples.len() < offset + sound.len() {
+            self.samples.resize(offset + sound.len(), 0.0);
+        }
+        for (sample, value) in self.samples[offset..].iter_mut().zip(sound) {
+            *sample += value;
+        }
+    }
+
+    /// 16 位单声道 PCM 的 WAV 文件内容
+    fn wav(&self) -> Vec<u8> {
+        let data_len = self.samples.len() as u32 * 2;
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        // PCM 格式、单声道、采样率、每秒字节数、每帧字节数、采样位数
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in &self.samples {
+            // 几个音效叠在一起时可能超出范围，削掉而不是回绕
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// SRT 格式的字幕
+    fn srt(&self) -> String {
+        self.captions
+            .iter()
+            .enumerate()
+            .map(|(i, caption)| {
+                format!("{}\n{} --> {}\n{}\n\n", i + 1, timestamp(caption.start), timestamp(caption.end), caption.text)
+            })
+            .collect()
+    }
+
+    /// 把音轨保存到 path，字幕保存到同名的 .srt 文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.wav()).with_context(|| format!("failed to write {}", path.display()))?;
+        let captions = path.with_extension("srt");
+        fs::write(&captions, self.srt()).with_context(|| format!("failed to write {}", captions.display()))
+    }
+}
+
+/// 音轨上 millis 毫秒处的采样下标
+fn sample_index(millis: u64) -> usize {
+    (millis * SAMPLE_RATE as u64 / 1000) as usize
+}
+
+/// SRT 的时间格式：`时:分:秒,毫秒`
+fn timestamp(millis: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_and_captions_line_up() {
+        let moves: Vec<Coord> = [(7, 7), (8, 8), (7, 8)].into_iter().map(Coord::from).collect();
+        let narration = Narration::new(
+            &moves,
+            [SoundProfile::LowTone, SoundProfile::HighTone],
+            None,
+            "Unfinished",
+            Rules::default(),
+            CoordOrigin::default(),
+        );
+        let end = LEAD_IN + 3 * MOVE_INTERVAL + RESULT_CAPTION;
+        assert_eq!(narration.samples.len(), sample_index(end));
+
+        // 落子音效之后报出坐标
+        let tones = audio::coordinate_tones(7, 8);
+        let at = sample_index(LEAD_IN + COORDINATE_DELAY);
+        assert_eq!(narration.samples[at + 100], tones[100]);
+
+        let wav = narration.wav();
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(wav.len(), 44 + narration.samples.len() * 2);
+
+        let srt = narration.srt();
+        assert!(srt.starts_with("1\n00:00:00,500 --> 00:00:02,000\n1. Black"));
+        assert!(srt.ends_with("4\n00:00:05,000 --> 00:00:08,000\nUnfinished\n\n"));
+        assert_eq!(timestamp(3_723_004), "01:02:03,004");
+    }
+}
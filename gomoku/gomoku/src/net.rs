@@ -0,0 +1,310 @@
+use anyhow::{Context, Result};
+use gomoku_core::protocol::{Message, PROTOCOL_VERSION};
+use gomoku_core::Stone;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{Shutdown, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// 默认端口
+pub const DEFAULT_PORT: u16 = 5151;
+
+// 连接对方的超时时间
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 握手完成后用于发送消息的连接，由后台线程写入
+type SharedStream = Arc<Mutex<Option<TcpStream>>>;
+
+/// 后台线程发给界面的事件
+pub enum NetEvent {
+    // 握手完成，可以开始对局
+    Connected,
+    // 收到对方的消息
+    Received(Message),
+    // 连接断开或握手失败，附带原因
+    Disconnected(String),
+}
+
+/// 连接状态
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NetStatus {
+    // 主机等待对方加入
+    Waiting,
+    // 正在连接主机
+    Connecting,
+    // 对局中
+    Connected,
+    // 连接已结束，附带原因
+    Closed(String),
+}
+
+/// 一次联机对局的连接，新开的对局主机执黑先行
+///
+/// 连接和读取都在后台线程中进行，界面每帧调用 `poll` 取出事件。
+pub struct NetSession {
+    // 本方执哪一方
+    local: Stone,
+    status: NetStatus,
+    // 是否完成过握手，用于区分连接失败和对局中断线
+    started: bool,
+    // 开局之后连接是否意外断开，而不是某一方主动离开
+    dropped: bool,
+    events: Receiver<NetEvent>,
+    stream: SharedStream,
+    // 取消等待或关闭连接时通知后台线程
+    closed: Arc<AtomicBool>,
+}
+
+impl NetSession {
+    /// 在 port 上等待对方加入
+    pub fn host(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .with_context(|| format!("failed to listen on port {}", port))?;
+        // 非阻塞地接受连接，这样取消等待后线程可以退出
+        listener.set_nonblocking(true)?;
+        let (session, tx) = Self::new(true, NetStatus::Waiting);
+        let stream = session.stream.clone();
+        let closed = session.closed.clone();
+        thread::spawn(move || loop {
+            if closed.load(Ordering::Relaxed) {
+                return;
+            }
+            match listener.accept() {
+                Ok((peer, _)) => {
+                    let _ = peer.set_nonblocking(false);
+                    return run_connection(peer, tx, stream);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(100)),
+                Err(e) => {
+                    let _ = tx.send(NetEvent::Disconnected(format!("failed to accept: {}", e)));
+                    return;
+                }
+            }
+        });
+        Ok(session)
+    }
+
+    /// 对方的 IP 地址，还没连上时为 None
+    pub fn peer_ip(&self) -> Option<String> {
+        let stream = self.stream.lock().ok()?;
+        Some(stream.as_ref()?.peer_addr().ok()?.ip().to_string())
+    }
+
+    /// 连接 address（`host:port`）上的主机
+    pub fn join(address: &str) -> Self {
+        let (session, tx) = Self::new(false, NetStatus::Connecting);
+        let stream = session.stream.clone();
+        let address = address.to_string();
+        thread::spawn(move || {
+            let peer = address
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| format!("cannot resolve {}", address))
+                .and_then(|addr| {
+                    TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+                        .map_err(|e| format!("cannot connect to {}: {}", address, e))
+                });
+            match peer {
+                Ok(peer) => run_connection(peer, tx, stream),
+                Err(reason) => {
+                    let _ = tx.send(NetEvent::Disconnected(reason));
+                }
+            }
+        });
+        session
+    }
+
+    fn new(is_host: bool, status: NetStatus) -> (Self, Sender<NetEvent>) {
+        let (tx, events) = mpsc::channel();
+        let session = Self {
+            local: if is_host { Stone::Black } else { Stone::White },
+            status,
+            started: false,
+            dropped: false,
+            events,
+            stream: Arc::new(Mutex::new(None)),
+            closed: Arc::new(AtomicBool::new(false)),
+        };
+        (session, tx)
+    }
+
+    /// 本方执哪一方
+    pub fn local_stone(&self) -> Stone {
+        self.local
+    }
+
+    /// 恢复断线的对局时沿用原来的颜色，不管哪一方重新创建连接
+    pub fn keep_stone(&mut self, stone: Stone) {
+        self.local = stone;
+    }
+
+    /// 连接状态
+    pub fn status(&self) -> &NetStatus {
+        &self.status
+    }
+
+    /// 是否已经开始对局，之后即使断线也不再回到大厅
+    pub fn has_started(&self) -> bool {
+        self.started
+    }
+
+    /// 开局之后连接是否意外断开，这时可以重新连接恢复对局
+    pub fn was_dropped(&self) -> bool {
+        self.dropped
+    }
+
+    /// 是否正在对局
+    pub fn is_connected(&self) -> bool {
+        self.status == NetStatus::Connected
+    }
+
+    /// 取出下一个事件并更新连接状态，没有事件或连接已关闭时返回 None
+    pub fn poll(&mut self) -> Option<NetEvent> {
+        // 关闭之后后台线程剩下的消息都不再处理
+        if matches!(self.status, NetStatus::Closed(_)) {
+            return None;
+        }
+        let event = self.events.try_recv().ok()?;
+        match &event {
+            NetEvent::Connected => {
+                self.status = NetStatus::Connected;
+                self.started = true;
+            }
+            NetEvent::Disconnected(reason) => {
+                self.dropped = self.started;
+                self.set_closed(reason);
+            }
+            NetEvent::Received(_) => {}
+        }
+        Some(event)
+    }
+
+    /// 发送一条消息，发送失败视为断开
+    pub fn send(&mut self, message: Message) {
+        let result = match self.stream.lock() {
+            Ok(mut stream) => match stream.as_mut() {
+                Some(stream) => writeln!(stream, "{}", message.encode()),
+                None => return,
+            },
+            Err(_) => return,
+        };
+        if let Err(e) = result {
+            self.set_closed(&format!("connection lost: {}", e));
+        }
+    }
+
+    /// 通知对方离开并关闭连接
+    pub fn close(&mut self, reason: &str) {
+        if matches!(self.status, NetStatus::Closed(_)) {
+            return;
+        }
+        if self.is_connected() {
+            self.send(Message::Bye);
+        }
+        self.set_closed(reason);
+    }
+
+    fn set_closed(&mut self, reason: &str) {
+        self.status = NetStatus::Closed(reason.to_string());
+        self.closed.store(true, Ordering::Relaxed);
+        if let Some(stream) = self.stream.lock().ok().and_then(|mut stream| stream.take()) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Drop for NetSession {
+    fn drop(&mut self) {
+        self.close("left the game");
+    }
+}
+
+/// 在一个端口上持续接受连接，每个连接是一个独立的 `NetSession`，赛事裁判用它接受所有选手
+pub struct Listener {
+    sessions: Receiver<NetSession>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Listener {
+    /// 在 port 上开始接受连接
+    pub fn bind(port: u16) -> Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .with_context(|| format!("failed to listen on port {}", port))?;
+        listener.set_nonblocking(true)?;
+        let (tx, sessions) = mpsc::channel();
+        let closed = Arc::new(AtomicBool::new(false));
+        let stop = closed.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((peer, _)) => {
+                        let _ = peer.set_nonblocking(false);
+                        let (session, events) = NetSession::new(true, NetStatus::Connecting);
+                        let stream = session.stream.clone();
+                        thread::spawn(move || run_connection(peer, events, stream));
+                        if tx.send(session).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(100)),
+                    Err(_) => return,
+                }
+            }
+        });
+        Ok(Self { sessions, closed })
+    }
+
+    /// 取出一个新的连接，还在握手的连接由 `NetSession::poll` 报告结果
+    pub fn accept(&self) -> Option<NetSession> {
+        self.sessions.try_recv().ok()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// 握手，然后持续读取对方的消息直到连接断开
+fn run_connection(peer: TcpStream, tx: Sender<NetEvent>, shared: SharedStream) {
+    if let Err(reason) = handshake_and_read(peer, &tx, &shared) {
+        let _ = tx.send(NetEvent::Disconnected(reason));
+    }
+}
+
+fn handshake_and_read(peer: TcpStream, tx: &Sender<NetEvent>, shared: &SharedStream) -> Result<(), String> {
+    let mut writer = peer.try_clone().map_err(|e| e.to_string())?;
+    writeln!(writer, "{}", Message::Hello(PROTOCOL_VERSION).encode()).map_err(|e| e.to_string())?;
+
+    let mut lines = BufReader::new(peer).lines();
+    let hello = lines.next().and_then(|line| line.ok());
+    match hello.as_deref().and_then(Message::decode) {
+        Some(Message::Hello(PROTOCOL_VERSION)) => {}
+        Some(Message::Hello(version)) => {
+            return Err(format!(
+                "opponent uses protocol version {}, this game uses {}",
+                version, PROTOCOL_VERSION
+            ))
+        }
+        _ => return Err("handshake failed".to_string()),
+    }
+    if let Ok(mut shared) = shared.lock() {
+        *shared = Some(writer);
+    }
+    let _ = tx.send(NetEvent::Connected);
+
+    for line in lines {
+        let line = line.map_err(|_| "opponent disconnected".to_string())?;
+        let message = Message::decode(&line).ok_or_else(|| format!("invalid message: {}", line))?;
+        if tx.send(NetEvent::Received(message)).is_err() {
+            // 界面已经丢弃了这次连接
+            return Ok(());
+        }
+    }
+    Err("opponent disconnected".to_string())
+}
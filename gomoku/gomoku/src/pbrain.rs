@@ -0,0 +1,28 @@
+use gomoku_core::gomocup::Brain;
+use gomoku_core::Difficulty;
+use std::io::{self, BufRead, Write};
+
+use crate::args::Args;
+
+/// 以 Gomocup（pbrain）引擎的身份运行内置 AI，从标准输入读命令，向标准输出写回复
+///
+/// 用法：`gomoku --pbrain [easy|medium|hard]`，默认最高难度。管理程序发送 END 或关闭输入时退出。
+pub fn run(args: &Args) {
+    let difficulty = args.choice("--pbrain", &Difficulty::ALL, Difficulty::name).unwrap_or(Difficulty::Hard);
+    let mut brain = Brain::new(difficulty);
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if let Some(reply) = brain.handle(&line) {
+            // 管理程序等待每一行回复，必须立即刷新
+            if writeln!(stdout, "{}", reply).and_then(|_| stdout.flush()).is_err() {
+                break;
+            }
+        }
+        if brain.is_finished() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use gomoku_core::threat::{self, Threat, ThreatLevel};
+use gomoku_core::{Board, Coord, Rules, Stone};
+
+/// 一道题目
+pub struct Puzzle {
+    pub title: &'static str,
+    pub black: &'static [Coord],
+    pub white: &'static [Coord],
+    // 轮到哪方落子
+    pub to_move: Stone,
+}
+
+/// 内置题目
+pub const PUZZLES: [Puzzle; 3] = [
+    Puzzle {
+        title: "Finish the five",
+        black: &[Coord::new(5, 7), Coord::new(6, 7), Coord::new(7, 7), Coord::new(8, 7)],
+        white: &[Coord::new(4, 7), Coord::new(6, 8), Coord::new(7, 8), Coord::new(8, 6)],
+        to_move: Stone::Black,
+    },
+    Puzzle {
+        title: "Make an open four",
+        black: &[Coord::new(6, 6), Coord::new(7, 7), Coord::new(8, 8), Coord::new(7, 9)],
+        white: &[Coord::new(6, 7), Coord::new(8, 7), Coord::new(9, 6), Coord::new(5, 9)],
+        to_move: Stone::Black,
+    },
+    Puzzle {
+        title: "Fill the gap",
+        black: &[Coord::new(5, 7), Coord::new(6, 8), Coord::new(8, 5), Coord::new(4, 7), Coord::new(7, 8)],
+        white: &[Coord::new(3, 10), Coord::new(4, 9), Coord::new(6, 7), Coord::new(7, 6)],
+        to_move: Stone::White,
+    },
+];
+
+/// 每道题的满分
+pub const FULL_POINTS: u32 = 100;
+
+/// 提示阶段：先圈出区域，再画出威胁线，最后给出落点
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintStage {
+    None,
+    Area,
+    Line,
+    Move,
+}
+
+impl HintStage {
+    /// 下一个提示阶段
+    pub fn next(self) -> Self {
+        match self {
+            HintStage::None => HintStage::Area,
+            HintStage::Area => HintStage::Line,
+            HintStage::Line | HintStage::Move => HintStage::Move,
+        }
+    }
+
+    /// 到达该阶段累计扣除的分数
+    pub fn cost(self) -> u32 {
+        match self {
+            HintStage::None => 0,
+            HintStage::Area => 10,
+            HintStage::Line => 30,
+            HintStage::Move => 70,
+        }
+    }
+}
+
+/// 解题进度
+pub struct PuzzleSession {
+    // 当前题目编号
+    pub index: usize,
+    // 已经使用的提示阶段
+    pub hint: HintStage,
+    // 当前题目是否已解出
+    pub solved: bool,
+    // 累计得分
+    pub score: u32,
+    // 给玩家的反馈
+    pub message: String,
+    // 当前棋盘，解出后包含玩家的落子
+    pub board: Board,
+}
+
+impl Default for PuzzleSession {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            hint: HintStage::None,
+            solved: false,
+            score: 0,
+            message: String::new(),
+            board: PUZZLES[0].board(),
+        }
+    }
+}
+
+impl Puzzle {
+    /// 题目的初始棋盘
+    pub fn board(&self) -> Board {
+        let mut board = Board::new();
+        for &coord in self.black {
+            board.set(coord, Stone::Black);
+        }
+        for &coord in self.white {
+            board.set(coord, Stone::White);
+        }
+        board
+    }
+}
+
+impl PuzzleSession {
+    /// 当前题目
+    pub fn puzzle(&self) -> &'static Puzzle {
+        &PUZZLES[self.index]
+    }
+
+    /// 由威胁分析得到的解答，提示的三个阶段都基于它
+    pub fn solution(&self) -> Option<Threat> {
+        threat::best_threats(&self.puzzle().board(), self.puzzle().to_move, Rules::DEFAULT_WIN_LENGTH)
+            .into_iter()
+            .find(|t| t.level >= ThreatLevel::OpenFour)
+    }
+
+    /// 使用下一阶段的提示
+    pub fn take_hint(&mut self) {
+        if !self.solved {
+            self.hint = self.hint.next();
+        }
+    }
+
+    /// 尝试在 mv 落子，返回是否解出
+    pub fn try_move(&mut self, mv: Coord) -> bool {
+        if self.solved || !mv.is_valid() || !self.board.is_empty_at(mv) {
+            return false;
+        }
+        let to_move = self.puzzle().to_move;
+        let best = self.solution().map(|t| t.level);
+        let level = threat::threat_at(&self.board, mv, to_move, Rules::DEFAULT_WIN_LENGTH).level;
+        if Some(level) == best {
+            self.board.set(mv, to_move);
+            let points = FULL_POINTS - self.hint.cost();
+            self.score += points;
+            self.solved = true;
+            self.message = format!("Correct! +{} points", points);
+            true
+        } else {
+            self.message = "Not quite, try again.".to_string();
+            false
+        }
+    }
+
+    /// 进入下一题，做完最后一题后从头开始
+    pub fn next_puzzle(&mut self) {
+        self.go_to((self.index + 1) % PUZZLES.len());
+    }
+
+    /// 进入第 index 题，保留累计得分
+    pub fn go_to(&mut self, index: usize) {
+        self.index = index;
+        self.hint = HintStage::None;
+        self.solved = false;
+        self.message.clear();
+        self.board = self.puzzle().board();
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 一个读取失败的数据文件
+pub struct CorruptFile {
+    // 文件路径
+    pub path: PathBuf,
+    // 读取失败的原因，包含完整的错误链
+    pub error: String,
+}
+
+impl CorruptFile {
+    pub fn new(path: &Path, error: &anyhow::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            error: format!("{:#}", error),
+        }
+    }
+
+    /// 备份路径：在原文件名后加上 `.bak`
+    pub fn backup_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".bak");
+        self.path.with_file_name(name)
+    }
+
+    /// 把损坏的文件改名为备份文件，原路径随后会按默认值重新生成
+    pub fn back_up_and_reset(&self) -> Result<PathBuf> {
+        let backup = self.backup_path();
+        fs::rename(&self.path, &backup).with_context(|| {
+            format!("failed to move {} to {}", self.path.display(), backup.display())
+        })?;
+        Ok(backup)
+    }
+}
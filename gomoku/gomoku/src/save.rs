@@ -0,0 +1,162 @@
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::SigningKey;
+use gomoku_core::{Coord, GameState, RuleSet, Rules, SgfRecord};
+use std::fs;
+use std::path::Path;
+
+use crate::signature::{self, Verdict};
+use crate::GameMode;
+
+/// 默认存档路径
+pub const SAVE_PATH: &str = "gomoku_save.txt";
+
+/// 另存的存档文件的扩展名，和默认存档一样是文本文件
+pub const SAVE_EXTENSION: &str = "txt";
+
+/// 存档内容
+///
+/// 存档是一个简单的文本文件，每行一个 `key=value`：
+///
+/// ```text
+/// mode=pvai
+/// player=black
+/// rules=freestyle
+/// win_length=5
+/// moves=7,7;8,8;6,6
+/// ```
+///
+/// 没有 `rules` 或 `win_length` 的旧存档按标准五子的自由规则读取。
+pub struct SavedGame {
+    pub mode: GameMode,
+    pub player_is_black: bool,
+    pub rules: Rules,
+    pub moves: Vec<Coord>,
+}
+
+impl SavedGame {
+    /// 写入存档文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mode = match self.mode {
+            GameMode::PlayerVsAI => "pvai",
+            _ => "pvp",
+        };
+        let player = if self.player_is_black { "black" } else { "white" };
+        let rules = match self.rules.set() {
+            RuleSet::Freestyle => "freestyle",
+            RuleSet::Renju => "renju",
+        };
+        let win_length = self.rules.win_length();
+        let moves = self
+            .moves
+            .iter()
+            .map(|mv| format!("{},{}", mv.col, mv.row))
+            .collect::<Vec<_>>()
+            .join(";");
+        let text = format!(
+            "mode={}\nplayer={}\nrules={}\nwin_length={}\nmoves={}\n",
+            mode, player, rules, win_length, moves
+        );
+        fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// 读取存档文件
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        let mut mode = GameMode::PlayerVsPlayer;
+        let mut player_is_black = true;
+        let mut rules = RuleSet::Freestyle;
+        let mut win_length = Rules::DEFAULT_WIN_LENGTH;
+        let mut moves = Vec::new();
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            match key.trim() {
+                "mode" => {
+                    mode = match value.trim() {
+                        "pvp" => GameMode::PlayerVsPlayer,
+                        "pvai" => GameMode::PlayerVsAI,
+                        other => bail!("unknown game mode: {}", other),
+                    }
+                }
+                "player" => {
+                    player_is_black = match value.trim() {
+                        "black" => true,
+                        "white" => false,
+                        other => bail!("unknown player color: {}", other),
+                    }
+                }
+                "rules" => {
+                    rules = match value.trim() {
+                        "freestyle" => RuleSet::Freestyle,
+                        "renju" => RuleSet::Renju,
+                        other => bail!("unknown rules: {}", other),
+                    }
+                }
+                "win_length" => {
+                    win_length = value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|length| Rules::WIN_LENGTHS.contains(length))
+                        .with_context(|| format!("invalid win length: {}", value.trim()))?;
+                }
+                "moves" => {
+                    for item in value.split(';').filter(|s| !s.trim().is_empty()) {
+                        let (col, row) = item
+                            .split_once(',')
+                            .with_context(|| format!("invalid move: {}", item))?;
+                        let col = col.trim().parse().with_context(|| format!("invalid move: {}", item))?;
+                        let row = row.trim().parse().with_context(|| format!("invalid move: {}", item))?;
+                        let mv = Coord::new(col, row);
+                        if !mv.is_valid() {
+                            bail!("move out of board: {}", item);
+                        }
+                        moves.push(mv);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let saved = Self {
+            mode,
+            player_is_black,
+            rules: Rules::new(rules, win_length),
+            moves,
+        };
+        saved
+            .validate()
+            .with_context(|| format!("invalid save file {}", path.display()))?;
+        Ok(saved)
+    }
+
+    /// 按规则重放所有落子，确认存档是一局合法的对局
+    pub fn validate(&self) -> Result<()> {
+        let mut game = GameState::with_rules(self.rules);
+        for (i, &mv) in self.moves.iter().enumerate() {
+            game.play(mv)
+                .with_context(|| format!("illegal move {} at {}", i + 1, mv))?;
+        }
+        Ok(())
+    }
+}
+
+/// 把棋谱写成 SGF 文件，有密钥时附上签名
+pub fn save_sgf(record: &SgfRecord, key: Option<&SigningKey>, path: &Path) -> Result<()> {
+    let text = match key {
+        Some(key) => signature::sign(&record.to_sgf(), key),
+        None => record.to_sgf(),
+    };
+    fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// 读取 SGF 文件，同时检查签名
+pub fn load_sgf(path: &Path) -> Result<(SgfRecord, Verdict)> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let record = SgfRecord::parse(&text).with_context(|| format!("invalid SGF file {}", path.display()))?;
+    Ok((record, signature::verify(&text)))
+}
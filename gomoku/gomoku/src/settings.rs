@@ -0,0 +1,272 @@
+use anyhow::{bail, Context, Result};
+use gomoku_core::{learning, Adaptation, CoordOrigin, Difficulty, FirstMove, RuleSet, Rules};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::assets::AssetKind;
+use crate::audio::SoundProfile;
+use crate::teaching;
+use crate::theme::ThemeKind;
+use crate::time_control::TimeControl;
+use crate::tournament;
+
+/// 默认设置文件路径
+pub const SETTINGS_PATH: &str = "gomoku_settings.txt";
+
+/// 游戏设置，退出时保存，下次启动时读取
+///
+/// 设置文件每行一个 `key=value`，选项按界面上显示的名称保存：
+///
+/// ```text
+/// player_name=Player
+/// rules=Renju
+/// ai_difficulty=Hard
+/// ```
+pub struct Settings {
+    // 棋谱和统计中玩家的名字
+    pub player_name: String,
+
+    // 新对局使用的时间控制
+    pub time_control: TimeControl,
+
+    // 新对局使用的规则，以及自由规则下获胜需要的连子数
+    pub rules: RuleSet,
+    pub win_length: usize,
+
+    // 本地对局是否使用 Swap2 开局
+    pub swap2: bool,
+
+    // 人机对战的 AI 难度
+    pub ai_difficulty: Difficulty,
+
+    // 练习模式：人机对战中玩家落子后立即指出失误
+    pub practice: bool,
+
+    // 人机对战中显示 AI 估计的玩家胜率
+    pub eval_bar: bool,
+
+    // 在棋盘上标出双方的活三、冲四和双威胁点
+    pub threat_overlay: bool,
+
+    // 初学者提示：标出落子后形成的连线是贴边的还是两端都有余地
+    pub edge_hints: bool,
+
+    // 人机对战中 AI 怎样利用从玩家对局中学到的经验
+    pub ai_learning: Adaptation,
+
+    // 人机对战中 AI 执黑时第一步的下法
+    pub ai_first_move: FirstMove,
+
+    // 人机对战中 AI 在开局阶段是否按开局库落子
+    pub opening_book: bool,
+
+    // 保存 SGF 棋谱时是否在每一步的注释里写上引擎的分析
+    pub sgf_analysis: bool,
+
+    // 界面主题
+    pub theme: ThemeKind,
+
+    // 减少动态效果：关闭界面的过渡动画，提示和警告只用静态的标记
+    pub reduce_motion: bool,
+
+    // 音效音量，0.0 到 1.0
+    pub volume: f32,
+
+    // 静音
+    pub muted: bool,
+
+    // 自定义音效所在的目录，为空时使用 `assets/sounds`
+    pub sound_dir: String,
+
+    // 黑子落子音效
+    pub black_sound: SoundProfile,
+
+    // 白子落子音效
+    pub white_sound: SoundProfile,
+
+    // 棋谱坐标的原点
+    pub coord_origin: CoordOrigin,
+
+    // 局面分析缓存最多保存的局面数
+    pub analysis_cache_size: usize,
+
+    // AI 对每位玩家最多记住的开局落子条数
+    pub experience_size: usize,
+
+    // 联机教学中最多保留的箭头数
+    pub arrow_limit: usize,
+
+    // 参加赛事时最多保留的裁判公告条数
+    pub announcement_limit: usize,
+
+    // 担任赛事裁判时最多接受的选手数
+    pub entrant_limit: usize,
+
+    // 人机对战中代替内置 AI 的外部引擎：本机 pbrain 程序的路径或远程引擎地址（host:port），为空时使用内置 AI
+    pub engine_address: String,
+}
+
+impl Settings {
+    /// 新对局使用的完整规则
+    pub fn game_rules(&self) -> Rules {
+        Rules::new(self.rules, self.win_length)
+    }
+
+    /// 读取自定义音效的目录
+    pub fn sound_dir(&self) -> PathBuf {
+        if self.sound_dir.is_empty() {
+            AssetKind::Sound.default_dir()
+        } else {
+            PathBuf::from(&self.sound_dir)
+        }
+    }
+
+    /// 玩家选择的音效包，没有选择时为 None
+    pub fn sound_pack(&self) -> Option<PathBuf> {
+        (!self.sound_dir.is_empty()).then(|| PathBuf::from(&self.sound_dir))
+    }
+
+    /// 从文件读取设置，文件不存在时返回默认值，缺少的项使用默认值
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut settings = Self::default();
+        if !path.exists() {
+            return Ok(settings);
+        }
+        let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let number = || value.parse::<usize>().with_context(|| format!("invalid value for {}: {}", key, value));
+            match key.trim() {
+                "player_name" => settings.player_name = value.to_string(),
+                "time_control" => settings.time_control = named(&TimeControl::ALL, TimeControl::name, value)?,
+                "rules" => settings.rules = named(&RuleSet::ALL, RuleSet::name, value)?,
+                "win_length" => {
+                    settings.win_length = Some(number()?)
+                        .filter(|length| Rules::WIN_LENGTHS.contains(length))
+                        .with_context(|| format!("invalid win length: {}", value))?;
+                }
+                "swap2" => settings.swap2 = parse_bool(value)?,
+                "ai_difficulty" => settings.ai_difficulty = named(&Difficulty::ALL, Difficulty::name, value)?,
+                "practice" => settings.practice = parse_bool(value)?,
+                "eval_bar" => settings.eval_bar = parse_bool(value)?,
+                "threat_overlay" => settings.threat_overlay = parse_bool(value)?,
+                "edge_hints" => settings.edge_hints = parse_bool(value)?,
+                "ai_learning" => settings.ai_learning = named(&Adaptation::ALL, Adaptation::name, value)?,
+                "ai_first_move" => settings.ai_first_move = named(&FirstMove::ALL, FirstMove::name, value)?,
+                "opening_book" => settings.opening_book = parse_bool(value)?,
+                "sgf_analysis" => settings.sgf_analysis = parse_bool(value)?,
+                "theme" => settings.theme = named(&ThemeKind::ALL, ThemeKind::name, value)?,
+                "reduce_motion" => settings.reduce_motion = parse_bool(value)?,
+                "volume" => {
+                    let volume: f32 = value.parse().with_context(|| format!("invalid value for {}: {}", key, value))?;
+                    settings.volume = volume.clamp(0.0, 1.0);
+                }
+                "muted" => settings.muted = parse_bool(value)?,
+                "sound_dir" => settings.sound_dir = value.to_string(),
+                "black_sound" => settings.black_sound = named(&SoundProfile::ALL, SoundProfile::name, value)?,
+                "white_sound" => settings.white_sound = named(&SoundProfile::ALL, SoundProfile::name, value)?,
+                "coord_origin" => settings.coord_origin = named(&CoordOrigin::ALL, CoordOrigin::name, value)?,
+                "analysis_cache_size" => settings.analysis_cache_size = number()?,
+                "experience_size" => settings.experience_size = number()?,
+                "arrow_limit" => settings.arrow_limit = number()?,
+                "announcement_limit" => settings.announcement_limit = number()?,
+                "entrant_limit" => settings.entrant_limit = number()?,
+                "engine_address" => settings.engine_address = value.to_string(),
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+
+    /// 写入设置文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = format!(
+            "player_name={}\ntime_control={}\nrules={}\nwin_length={}\nswap2={}\nai_difficulty={}\npractice={}\neval_bar={}\nthreat_overlay={}\nedge_hints={}\nai_learning={}\n\
+             ai_first_move={}\nopening_book={}\nsgf_analysis={}\ntheme={}\nreduce_motion={}\nvolume={}\nmuted={}\nsound_dir={}\nblack_sound={}\nwhite_sound={}\ncoord_origin={}\n\
+             analysis_cache_size={}\nexperience_size={}\narrow_limit={}\nannouncement_limit={}\nentrant_limit={}\nengine_address={}\n",
+            self.player_name,
+            self.time_control.name(),
+            self.rules.name(),
+            self.win_length,
+            self.swap2,
+            self.ai_difficulty.name(),
+            self.practice,
+            self.eval_bar,
+            self.threat_overlay,
+            self.edge_hints,
+            self.ai_learning.name(),
+            self.ai_first_move.name(),
+            self.opening_book,
+            self.sgf_analysis,
+            self.theme.name(),
+            self.reduce_motion,
+            self.volume,
+            self.muted,
+            self.sound_dir,
+            self.black_sound.name(),
+            self.white_sound.name(),
+            self.coord_origin.name(),
+            self.analysis_cache_size,
+            self.experience_size,
+            self.arrow_limit,
+            self.announcement_limit,
+            self.entrant_limit,
+            self.engine_address
+        );
+        fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// 按名称在 all 中查找选项
+fn named<T: Copy>(all: &[T], name: fn(&T) -> &'static str, value: &str) -> Result<T> {
+    match all.iter().find(|item| name(item) == value) {
+        Some(&item) => Ok(item),
+        None => bail!("unknown option {}", value),
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => bail!("expected true or false, found {}", other),
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            player_name: "Player".to_string(),
+            time_control: TimeControl::Unlimited,
+            rules: RuleSet::Freestyle,
+            win_length: Rules::DEFAULT_WIN_LENGTH,
+            swap2: false,
+            ai_difficulty: Difficulty::Medium,
+            practice: false,
+            eval_bar: true,
+            threat_overlay: false,
+            edge_hints: false,
+            theme: ThemeKind::Classic,
+            reduce_motion: false,
+            volume: 0.8,
+            muted: false,
+            sound_dir: String::new(),
+            ai_learning: Adaptation::Off,
+            ai_first_move: FirstMove::Center,
+            opening_book: true,
+            sgf_analysis: false,
+            black_sound: SoundProfile::LowTone,
+            white_sound: SoundProfile::HighTone,
+            coord_origin: CoordOrigin::TopLeft,
+            analysis_cache_size: 10_000,
+            experience_size: learning::DEFAULT_CAPACITY,
+            arrow_limit: teaching::DEFAULT_ARROW_LIMIT,
+            announcement_limit: tournament::DEFAULT_ANNOUNCEMENT_LIMIT,
+            entrant_limit: tournament::DEFAULT_ENTRANT_LIMIT,
+            engine_address: String::new(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::Context;
+use eframe::egui::{self, Color32, Rounding, Ui};
+use gomoku_core::GameCode;
+use qrcode::{Color, EcLevel, QrCode};
+
+// 二维码四周留白的模块数，扫码软件要求至少 4 个
+const QUIET_ZONE: usize = 4;
+
+/// 分享对局的代码和对应的二维码
+pub struct ShareCode {
+    pub text: String,
+    // 每边的模块数，以及按行排列的深色模块
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl ShareCode {
+    /// 为对局生成代码和二维码
+    pub fn new(code: &GameCode) -> anyhow::Result<Self> {
+        let text = code.encode();
+        // 代码只含大写字母、数字和 `-`，会自动使用字母数字模式
+        let qr = QrCode::with_error_correction_level(&text, EcLevel::L).context("game is too long to share")?;
+        let dark = qr.to_colors().into_iter().map(|color| color == Color::Dark).collect();
+        Ok(Self { text, width: qr.width(), dark })
+    }
+
+    /// 在 size 像素见方的区域里画出二维码
+    pub fn show(&self, ui: &mut Ui, size: f32) {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(size, size), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::none(), Color32::WHITE);
+
+        let module = size / (self.width + 2 * QUIET_ZONE) as f32;
+        let origin = rect.min + egui::vec2(module, module) * QUIET_ZONE as f32;
+        for (i, _) in self.dark.iter().enumerate().filter(|(_, &dark)| dark) {
+            let (x, y) = ((i % self.width) as f32, (i / self.width) as f32);
+            let min = origin + egui::vec2(x, y) * module;
+            painter.rect_filled(egui::Rect::from_min_size(min, egui::vec2(module, module)), Rounding::none(), Color32::BLACK);
+        }
+    }
+}
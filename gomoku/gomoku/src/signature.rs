@@ -0,0 +1,131 @@
+use anyhow::{anyhow, Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use std::fs;
+use std::path::Path;
+
+/// 默认签名密钥路径，文件里是 32 字节私钥的十六进制
+pub const SIGNING_KEY_PATH: &str = "gomoku_signing_key.txt";
+
+// 签名写在 SGF 根节点开头的两个私有属性里：公钥和签名，其他软件读取时会忽略
+const SIGNED_PREFIX: &str = "(;XK[";
+const SIGNATURE_PROPERTY: &str = "]XS[";
+
+/// 导入的棋谱的签名检查结果
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    // 没有签名，例如其他软件写的棋谱
+    Unsigned,
+    // 签名有效，附带签名者公钥的十六进制
+    Valid(String),
+    // 有签名但和内容对不上：棋谱被改过，或者签名本身被破坏
+    Invalid,
+}
+
+/// 读取本机的签名密钥，文件不存在时生成一把新的并保存
+pub fn local_key(path: &Path) -> Result<SigningKey> {
+    if path.exists() {
+        let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let seed = decode_hex::<32>(text.trim()).ok_or_else(|| anyhow!("invalid signing key in {}", path.display()))?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
+    let key = SigningKey::from_bytes(&random_seed()?);
+    fs::write(path, format!("{}\n", encode_hex(&key.to_bytes())))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(key)
+}
+
+/// 公钥的十六进制，交给赛事组织者用来核对签名者
+pub fn public_key(key: &SigningKey) -> String {
+    encode_hex(key.verifying_key().as_bytes())
+}
+
+/// 给 `SgfRecord::to_sgf` 生成的文本签名，在开头的 `(;` 之后插入公钥和签名
+///
+/// 签名覆盖插入之前的整段文本，名字、结果、落子和注释改动任何一个字符都会让签名失效。
+pub fn sign(sgf: &str, key: &SigningKey) -> String {
+    let Some(rest) = sgf.strip_prefix("(;") else {
+        return sgf.to_string();
+    };
+    let signature = key.sign(sgf.as_bytes());
+    format!("{}{}{}{}]{}", SIGNED_PREFIX, public_key(key), SIGNATURE_PROPERTY, encode_hex(&signature.to_bytes()), rest)
+}
+
+/// 检查 SGF 文本的签名
+pub fn verify(text: &str) -> Verdict {
+    let Some(signed) = text.trim_start().strip_prefix(SIGNED_PREFIX) else {
+        return Verdict::Unsigned;
+    };
+    let Some((public, rest)) = signed.split_once(SIGNATURE_PROPERTY) else {
+        return Verdict::Invalid;
+    };
+    let Some((signature, rest)) = rest.split_once(']') else {
+        return Verdict::Invalid;
+    };
+    let (Some(public_bytes), Some(signature)) = (decode_hex::<32>(public), decode_hex::<64>(signature)) else {
+        return Verdict::Invalid;
+    };
+    let Ok(verifying_key) = VerifyingKey::from_bytes(&public_bytes) else {
+        return Verdict::Invalid;
+    };
+    let original = format!("(;{}", rest);
+    match verifying_key.verify_strict(original.as_bytes(), &Signature::from_bytes(&signature)) {
+        Ok(()) => Verdict::Valid(public.to_string()),
+        Err(_) => Verdict::Invalid,
+    }
+}
+
+// 私钥必须不可预测，不能用 `rng::time_seed`
+#[cfg(feature = "native")]
+fn random_seed() -> Result<[u8; 32]> {
+    let mut seed = [0; 32];
+    getrandom::getrandom(&mut seed).map_err(|e| anyhow!("failed to generate a signing key: {}", e))?;
+    Ok(seed)
+}
+
+// 网页版没有本地文件，也不会导出棋谱
+#[cfg(not(feature = "native"))]
+fn random_seed() -> Result<[u8; 32]> {
+    Err(anyhow!("signing keys are not available in the web version"))
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn decode_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    if text.len() != N * 2 || !text.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (byte, pair) in bytes.iter_mut().zip(text.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gomoku_core::{Coord, SgfRecord};
+
+    #[test]
+    fn detects_tampered_records() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let record = SgfRecord {
+            black: "Alice".to_string(),
+            white: "Bob".to_string(),
+            moves: vec![Coord::new(7, 7), Coord::new(8, 8)],
+            ..SgfRecord::default()
+        };
+        let signed = sign(&record.to_sgf(), &key);
+        assert_eq!(verify(&signed), Verdict::Valid(public_key(&key)));
+        // 签名属性不影响读取
+        assert_eq!(SgfRecord::parse(&signed).unwrap(), record);
+
+        assert_eq!(verify(&signed.replace("PW[Bob]", "PW[Eve]")), Verdict::Invalid);
+        assert_eq!(verify(&signed.replace("W[ii]", "W[ij]")), Verdict::Invalid);
+        assert_eq!(verify(&signed.replacen("XS[", "XS[00", 1)), Verdict::Invalid);
+        assert_eq!(verify(&record.to_sgf()), Verdict::Unsigned);
+        assert_eq!(decode_hex::<2>("0aff"), Some([10, 255]));
+    }
+}
@@ -0,0 +1,132 @@
+use gomoku_core::{Ai, Board, Coord, Difficulty, GameState, RuleSet, Rules, Stone, BOARD_SIZE};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use crate::args::Args;
+use crate::rng::{self, XorShift};
+
+/// 默认对局数
+const DEFAULT_GAMES: usize = 1000;
+
+// 每隔多少局让两个 AI 对下一局，其余对局随机落子
+const AI_GAME_INTERVAL: usize = 20;
+
+/// 压力测试：不打开窗口，尽快下完大量随机或 AI 对局，每一步都检查对局状态的一致性
+///
+/// 用法：`gomoku --soak [games] [--seed N]`。发现问题时打印对局编号和种子并返回 false。
+pub fn run(args: &Args) -> bool {
+    let games = args.parse("--soak").unwrap_or(DEFAULT_GAMES);
+    let seed = args.parse("--seed").unwrap_or_else(rng::time_seed);
+    println!("soak: {} games, seed {}", games, seed);
+
+    let started = Instant::now();
+    let mut rng = XorShift::new(seed);
+    let (mut moves, mut black, mut white, mut draws, mut failures) = (0, 0, 0, 0, 0);
+    for n in 0..games {
+        let game_seed = rng.next();
+        let use_ai = n % AI_GAME_INTERVAL == 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| play_game(game_seed, use_ai)));
+        match result {
+            Ok(Ok(game)) => {
+                moves += game.moves().len();
+                match game.winner() {
+                    Some(Stone::Black) => black += 1,
+                    Some(_) => white += 1,
+                    None => draws += 1,
+                }
+            }
+            Ok(Err(desync)) => {
+                failures += 1;
+                eprintln!("soak: game {} (seed {}) desync: {}", n, game_seed, desync);
+            }
+            Err(_) => {
+                failures += 1;
+                eprintln!("soak: game {} (seed {}) panicked", n, game_seed);
+            }
+        }
+    }
+
+    println!(
+        "soak: {} moves, black {} / white {} / draw {}, {} failures in {:.1?}",
+        moves,
+        black,
+        white,
+        draws,
+        failures,
+        started.elapsed()
+    );
+    failures == 0
+}
+
+/// 下完一局，每步之后检查不变量
+fn play_game(seed: u64, use_ai: bool) -> Result<GameState, String> {
+    let mut rng = XorShift::new(seed);
+    // 随机选择一种连子数
+    let length = Rules::WIN_LENGTHS[rng.next() as usize % Rules::WIN_LENGTHS.len()];
+    let mut game = GameState::with_rules(Rules::new(RuleSet::Freestyle, length));
+    while !game.is_over() {
+        let mv = if use_ai {
+            Ai::new(game.to_move(), Difficulty::Easy)
+                .with_rules(game.rules())
+                .best_move(game.board()).best_move
+        } else {
+            random_empty(game.board(), &mut rng)
+        };
+        game.play(mv).map_err(|e| format!("move {} at {}: {}", game.moves().len() + 1, mv, e))?;
+        check_invariants(&game)?;
+
+        // 偶尔悔棋再重做，确认局面能完全恢复
+        if rng.next().is_multiple_of(8) {
+            let before = *game.board();
+            game.undo();
+            game.redo();
+            if *game.board() != before {
+                return Err(format!("undo/redo changed the board after move {}", game.moves().len()));
+            }
+        }
+    }
+    Ok(game)
+}
+
+/// 检查棋盘、落子记录和胜负三者保持一致
+fn check_invariants(game: &GameState) -> Result<(), String> {
+    let board = game.board();
+    let moves = game.moves();
+    let stones = Coord::all().filter(|&c| !board.is_empty_at(c)).count();
+    if stones != moves.len() {
+        return Err(format!("{} stones on the board but {} moves recorded", stones, moves.len()));
+    }
+    for (i, &mv) in moves.iter().enumerate() {
+        if board.get(mv) != Stone::for_ply(i) {
+            return Err(format!("move {} at {} has the wrong color", i + 1, mv));
+        }
+    }
+    if game.board_at(moves.len()) != *board {
+        return Err("replaying the moves gives a different board".to_string());
+    }
+    let last = moves[moves.len() - 1];
+    let won = game.rules().is_win(board, last);
+    if won != game.winner().is_some() {
+        return Err(format!("winner is {:?} after move {}", game.winner(), moves.len()));
+    }
+    if game.is_draw() != (!won && board.is_full()) {
+        return Err(format!(
+            "draw is {} after move {} with {} empty cells",
+            game.is_draw(),
+            moves.len(),
+            game.empty_cells()
+        ));
+    }
+    Ok(())
+}
+
+fn random_empty(board: &Board, rng: &mut XorShift) -> Coord {
+    loop {
+        let col = (rng.next() % BOARD_SIZE as u64) as usize;
+        let row = (rng.next() % BOARD_SIZE as u64) as usize;
+        let coord = Coord::new(col, row);
+        if board.is_empty_at(coord) {
+            return coord;
+        }
+    }
+}
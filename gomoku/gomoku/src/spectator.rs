@@ -0,0 +1,90 @@
+use gomoku_core::{Ai, Difficulty, Rules, Stone, Weights};
+
+// 每步之间延迟的范围（秒）
+const MIN_DELAY: f32 = 0.1;
+const MAX_DELAY: f32 = 5.0;
+
+/// 观战模式中一方 AI 的配置
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AiConfig {
+    pub difficulty: Difficulty,
+    pub weights: Weights,
+}
+
+impl AiConfig {
+    /// 显示在界面上的简短说明，默认权重时只显示难度
+    pub fn label(&self) -> String {
+        if self.weights == Weights::default() {
+            self.difficulty.name().to_string()
+        } else {
+            format!("{} {}/{}", self.difficulty.name(), self.weights.attack, self.weights.defense)
+        }
+    }
+}
+
+/// AI 对战观战：两个 AI 按固定间隔轮流落子
+///
+/// 可以用来观赏对局，也可以在调整评估函数时让新旧两套权重对下，比较强弱。
+pub struct Spectator {
+    pub black: AiConfig,
+    pub white: AiConfig,
+    // 每步之间的延迟（秒）
+    pub delay: f32,
+    // 是否已经开始对局，否则显示配置界面
+    pub started: bool,
+    // 距上一步经过的时间
+    timer: f32,
+}
+
+impl Default for Spectator {
+    fn default() -> Self {
+        Self {
+            black: AiConfig::default(),
+            white: AiConfig::default(),
+            delay: 1.0,
+            started: false,
+            timer: 0.0,
+        }
+    }
+}
+
+impl Spectator {
+    /// 可以设置的延迟范围
+    pub const DELAYS: std::ops::RangeInclusive<f32> = MIN_DELAY..=MAX_DELAY;
+
+    /// 执 stone 方的配置
+    pub fn config(&self, stone: Stone) -> AiConfig {
+        if stone == Stone::Black { self.black } else { self.white }
+    }
+
+    /// 执 stone 方的 AI
+    pub fn ai(&self, stone: Stone, rules: Rules) -> Ai {
+        let config = self.config(stone);
+        Ai::new(stone, config.difficulty).with_rules(rules).with_weights(config.weights)
+    }
+
+    /// 累计经过的时间，到了该走下一步时返回 true
+    pub fn tick(&mut self, delta_time: f32) -> bool {
+        self.timer += delta_time;
+        if self.timer < self.delay {
+            return false;
+        }
+        self.timer = 0.0;
+        true
+    }
+
+    /// 重新开始计时
+    pub fn reset_timer(&mut self) {
+        self.timer = 0.0;
+    }
+
+    /// 加快一倍
+    pub fn faster(&mut self) {
+        self.delay = (self.delay / 2.0).max(MIN_DELAY);
+    }
+
+    /// 放慢一倍
+    pub fn slower(&mut self) {
+        self.delay = (self.delay * 2.0).min(MAX_DELAY);
+    }
+}
@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use gomoku_core::Difficulty;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// 默认战绩文件路径
+pub const STATS_PATH: &str = "gomoku_stats.txt";
+
+/// 一类对局的胜、负、和局数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Record {
+    /// 一共下了几局
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// 解析 `Display` 输出的 `胜/负/和`
+    fn parse(value: &str) -> Option<Record> {
+        let mut parts = value.split('/').map(|part| part.trim().parse().ok());
+        let record = Record { wins: parts.next()??, losses: parts.next()??, draws: parts.next()?? };
+        parts.next().is_none().then_some(record)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.wins, self.losses, self.draws)
+    }
+}
+
+/// 玩家在各模式下的战绩，保存在本地，不会上报
+///
+/// 战绩文件每行一个 `key=胜/负/和`。双人对战没有“自己”一方，按黑方胜、白方胜、和棋记录：
+///
+/// ```text
+/// pvp=3/2/0
+/// ai_hard=1/4/0
+/// network=2/2/1
+/// ```
+#[derive(Default)]
+pub struct Statistics {
+    pub pvp: Record,
+    pub ai_easy: Record,
+    pub ai_medium: Record,
+    pub ai_hard: Record,
+    pub network: Record,
+}
+
+impl Statistics {
+    /// 从文件读取战绩，文件不存在时返回空战绩
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut stats = Self::default();
+        if !path.exists() {
+            return Ok(stats);
+        }
+        let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let record = Record::parse(value).with_context(|| format!("invalid value for {}: {}", key, value))?;
+            match key.trim() {
+                "pvp" => stats.pvp = record,
+                "ai_easy" => stats.ai_easy = record,
+                "ai_medium" => stats.ai_medium = record,
+                "ai_hard" => stats.ai_hard = record,
+                "network" => stats.network = record,
+                _ => {}
+            }
+        }
+        Ok(stats)
+    }
+
+    /// 写入战绩文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = format!(
+            "pvp={}\nai_easy={}\nai_medium={}\nai_hard={}\nnetwork={}\n",
+            self.pvp, self.ai_easy, self.ai_medium, self.ai_hard, self.network
+        );
+        fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// 人机对战某个难度的战绩
+    pub fn ai_mut(&mut self, difficulty: Difficulty) -> &mut Record {
+        match difficulty {
+            Difficulty::Easy => &mut self.ai_easy,
+            Difficulty::Medium => &mut self.ai_medium,
+            Difficulty::Hard => &mut self.ai_hard,
+        }
+    }
+
+    /// 人机对战某个难度的战绩
+    pub fn ai(&self, difficulty: Difficulty) -> Record {
+        match difficulty {
+            Difficulty::Easy => self.ai_easy,
+            Difficulty::Medium => self.ai_medium,
+            Difficulty::Hard => self.ai_hard,
+        }
+    }
+}
+
+/// 按玩家一方的结果计入 record，won 为 None 表示和棋
+pub fn add_result(record: &mut Record, won: Option<bool>) {
+    match won {
+        Some(true) => record.wins += 1,
+        Some(false) => record.losses += 1,
+        None => record.draws += 1,
+    }
+}
@@ -0,0 +1,233 @@
+use gomoku_core::protocol::Message;
+use gomoku_core::{Board, Coord, Stone};
+
+/// 默认最多保留的箭头数，超出时先去掉最早画的
+pub const DEFAULT_ARROW_LIMIT: usize = 64;
+
+/// 教学中的一方：主机是老师，加入的一方是学生
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Teacher,
+    Student,
+}
+
+/// 老师点击棋盘时做什么
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    // 黑白交替落子
+    Play,
+    // 摆放黑子或白子
+    Black,
+    White,
+    // 拿掉棋子
+    Erase,
+    // 先点起点再点终点，画一个箭头
+    Arrow,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 5] = [Tool::Play, Tool::Black, Tool::White, Tool::Erase, Tool::Arrow];
+
+    /// 名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tool::Play => "Play",
+            Tool::Black => "Black",
+            Tool::White => "White",
+            Tool::Erase => "Erase",
+            Tool::Arrow => "Arrow",
+        }
+    }
+}
+
+/// 一次教学的棋盘和标注，老师和学生各有一份，通过消息保持一致
+///
+/// 本方的操作先在本地生效，再把 `click` 返回的消息发给对方；对方的消息交给 `receive`。
+/// 老师可以随时摆子和画箭头，把落子交给学生之后，学生只能按顺序落下轮到的一方。
+pub struct Lesson {
+    pub role: Role,
+    pub board: Board,
+    // 下一手交替落子的颜色
+    pub to_move: Stone,
+    pub last_move: Option<Coord>,
+    pub arrows: Vec<(Coord, Coord)>,
+    // 最多保留的箭头数，对方发来再多也不会无限增长
+    arrow_limit: usize,
+    // 学生是否可以落子，这时老师不能用 Play 落子
+    pub student_in_control: bool,
+    // 老师当前使用的工具，学生始终为 Play
+    pub tool: Tool,
+    // 正在画的箭头的起点
+    pub arrow_start: Option<Coord>,
+}
+
+impl Lesson {
+    pub fn new(role: Role, arrow_limit: usize) -> Self {
+        Self {
+            role,
+            board: Board::new(),
+            to_move: Stone::Black,
+            last_move: None,
+            arrows: Vec::new(),
+            arrow_limit,
+            student_in_control: false,
+            tool: Tool::Play,
+            arrow_start: None,
+        }
+    }
+
+    /// 本方现在能否在棋盘上操作
+    pub fn can_click(&self) -> bool {
+        match self.role {
+            Role::Teacher => self.tool != Tool::Play || !self.student_in_control,
+            Role::Student => self.student_in_control,
+        }
+    }
+
+    /// 本方点击 coord，生效时返回要发给对方的消息
+    pub fn click(&mut self, coord: Coord) -> Option<Message> {
+        if !self.can_click() {
+            return None;
+        }
+        let tool = if self.role == Role::Student { Tool::Play } else { self.tool };
+        let message = match tool {
+            Tool::Play if self.board.is_empty_at(coord) => Message::Place { coord, stone: self.to_move },
+            Tool::Black => Message::Place { coord, stone: Stone::Black },
+            Tool::White => Message::Place { coord, stone: Stone::White },
+            Tool::Erase if !self.board.is_empty_at(coord) => Message::Place { coord, stone: Stone::Empty },
+            Tool::Arrow => match self.arrow_start.take() {
+                None => {
+                    self.arrow_start = Some(coord);
+                    return None;
+                }
+                // 再点一次起点取消
+                Some(from) if from == coord => return None,
+                Some(from) => Message::Arrow { from, to: coord },
+            },
+            _ => return None,
+        };
+        self.apply(&message);
+        Some(message)
+    }
+
+    /// 本方的老师操作：清空棋盘、清除箭头或交出、收回落子权，返回要发给对方的消息
+    pub fn command(&mut self, message: Message) -> Message {
+        self.apply(&message);
+        message
+    }
+
+    /// 处理对方发来的消息，不符合对方身份或当前状态时返回原因
+    pub fn receive(&mut self, message: Message) -> Result<(), &'static str> {
+        let on_board = match &message {
+            Message::Place { coord, .. } => coord.is_valid(),
+            Message::Arrow { from, to } => from.is_valid() && to.is_valid(),
+            _ => true,
+        };
+        let allowed = on_board
+            && match (self.role, &message) {
+                // 学生只能在轮到自己时落子
+                (Role::Teacher, &Message::Place { coord, stone }) => {
+                    self.student_in_control && stone == self.to_move && self.board.is_empty_at(coord)
+                }
+                (Role::Teacher, _) => false,
+                (
+                    Role::Student,
+                    Message::Lesson
+                    | Message::Place { .. }
+                    | Message::Arrow { .. }
+                    | Message::ClearBoard
+                    | Message::ClearArrows
+                    | Message::Control { .. },
+                ) => true,
+                (Role::Student, _) => false,
+            };
+        if !allowed {
+            return Err(match self.role {
+                Role::Teacher => "Student sent an invalid move",
+                Role::Student => "Teacher sent an invalid message",
+            });
+        }
+        self.apply(&message);
+        Ok(())
+    }
+
+    /// 双方共用的状态变化
+    fn apply(&mut self, message: &Message) {
+        match *message {
+            Message::Place { coord, stone } => {
+                self.board.set(coord, stone);
+                if stone == Stone::Empty {
+                    if self.last_move == Some(coord) {
+                        self.last_move = None;
+                    }
+                } else {
+                    self.to_move = stone.opponent();
+                    self.last_move = Some(coord);
+                }
+            }
+            Message::Arrow { from, to } => {
+                self.arrows.push((from, to));
+                let excess = self.arrows.len().saturating_sub(self.arrow_limit);
+                self.arrows.drain(..excess);
+            }
+            Message::ClearBoard => {
+                self.board = Board::new();
+                self.to_move = Stone::Black;
+                self.last_move = None;
+            }
+            Message::ClearArrows => {
+                self.arrows.clear();
+                self.arrow_start = None;
+            }
+            Message::Control { student } => self.student_in_control = student,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn student_mirrors_the_teacher_and_plays_only_with_control() {
+        let mut teacher = Lesson::new(Role::Teacher, DEFAULT_ARROW_LIMIT);
+        let mut student = Lesson::new(Role::Student, DEFAULT_ARROW_LIMIT);
+        assert!(student.click(Coord::new(9, 9)).is_none());
+
+        let mut messages = vec![teacher.click(Coord::CENTER)];
+        teacher.tool = Tool::White;
+        messages.push(teacher.click(Coord::new(8, 8)));
+        teacher.tool = Tool::Arrow;
+        assert_eq!(teacher.click(Coord::new(3, 3)), None);
+        messages.push(teacher.click(Coord::new(6, 6)));
+        messages.push(Some(teacher.command(Message::Control { student: true })));
+        for message in messages {
+            student.receive(message.unwrap()).unwrap();
+        }
+
+        assert_eq!(student.board, teacher.board);
+        assert_eq!(student.arrows, [(Coord::new(3, 3), Coord::new(6, 6))]);
+        assert_eq!(student.to_move, Stone::Black);
+        // 学生的落子同步回老师一方
+        let reply = student.click(Coord::new(9, 9)).unwrap();
+        teacher.receive(reply).unwrap();
+        assert_eq!(teacher.board.get(Coord::new(9, 9)), Stone::Black);
+        // 不是轮到的颜色、落在已有棋子上或者老师收回之后都不接受
+        assert!(teacher.receive(Message::Place { coord: Coord::new(1, 1), stone: Stone::Black }).is_err());
+        assert!(teacher.receive(Message::Place { coord: Coord::CENTER, stone: Stone::White }).is_err());
+        teacher.command(Message::Control { student: false });
+        assert!(teacher.receive(Message::Place { coord: Coord::new(1, 1), stone: Stone::White }).is_err());
+        assert!(student.receive(Message::Place { coord: Coord::new(20, 1), stone: Stone::White }).is_err());
+    }
+
+    #[test]
+    fn keeps_only_the_latest_arrows() {
+        let mut student = Lesson::new(Role::Student, 3);
+        for col in 0..5 {
+            student.receive(Message::Arrow { from: Coord::new(col, 0), to: Coord::CENTER }).unwrap();
+        }
+        let starts: Vec<usize> = student.arrows.iter().map(|(from, _)| from.col).collect();
+        assert_eq!(starts, [2, 3, 4]);
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, Context, Result};
+use gomoku_core::Difficulty;
+use std::fs;
+use std::path::Path;
+
+/// 默认统计文件路径
+pub const TELEMETRY_PATH: &str = "gomoku_telemetry.txt";
+
+/// 匿名使用统计，只有玩家在设置中明确开启后才会上报
+///
+/// 只保存累计计数，不包含任何棋局内容、路径或机器信息。统计文件每行一个 `key=value`：
+///
+/// ```text
+/// enabled=false
+/// endpoint=
+/// games_pvp=3
+/// sessions=5
+/// ```
+#[derive(Default)]
+pub struct Telemetry {
+    // 玩家是否同意上报
+    pub enabled: bool,
+    // 上报地址，为空时不上报
+    pub endpoint: String,
+    // 双人对战完成的对局数
+    games_pvp: u32,
+    // 人机对战各难度完成的对局数
+    games_easy: u32,
+    games_medium: u32,
+    games_hard: u32,
+    // 启动次数
+    sessions: u32,
+    // 正常退出的次数，与启动次数的差即为异常退出的次数
+    crash_free_sessions: u32,
+}
+
+impl Telemetry {
+    /// 从文件读取统计，文件不存在时返回默认值（未开启）
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut telemetry = Self::default();
+        if !path.exists() {
+            return Ok(telemetry);
+        }
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let count = || value.parse::<u32>().with_context(|| format!("invalid value for {}: {}", key, value));
+            match key.trim() {
+                "enabled" => telemetry.enabled = parse_bool(value)?,
+                "endpoint" => telemetry.endpoint = value.to_string(),
+                "games_pvp" => telemetry.games_pvp = count()?,
+                "games_easy" => telemetry.games_easy = count()?,
+                "games_medium" => telemetry.games_medium = count()?,
+                "games_hard" => telemetry.games_hard = count()?,
+                "sessions" => telemetry.sessions = count()?,
+                "crash_free_sessions" => telemetry.crash_free_sessions = count()?,
+                _ => {}
+            }
+        }
+        Ok(telemetry)
+    }
+
+    /// 写入统计文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = format!(
+            "enabled={}\nendpoint={}\ngames_pvp={}\ngames_easy={}\ngames_medium={}\ngames_hard={}\nsessions={}\ncrash_free_sessions={}\n",
+            self.enabled,
+            self.endpoint,
+            self.games_pvp,
+            self.games_easy,
+            self.games_medium,
+            self.games_hard,
+            self.sessions,
+            self.crash_free_sessions
+        );
+        fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// 程序启动时调用：记录一次会话，并在玩家同意时上报截至目前的统计
+    pub fn begin_session(&mut self) {
+        if self.enabled && !self.endpoint.is_empty() {
+            upload(self.endpoint.clone(), self.payload());
+        }
+        self.sessions += 1;
+    }
+
+    /// 程序正常退出时调用
+    pub fn end_session(&mut self) {
+        self.crash_free_sessions += 1;
+    }
+
+    /// 记录一局完成的对局，人机对战同时记录 AI 难度
+    pub fn record_game(&mut self, ai: Option<Difficulty>) {
+        match ai {
+            None => self.games_pvp += 1,
+            Some(Difficulty::Easy) => self.games_easy += 1,
+            Some(Difficulty::Medium) => self.games_medium += 1,
+            Some(Difficulty::Hard) => self.games_hard += 1,
+        }
+    }
+
+    /// 上报的内容，设置界面中会原样展示给玩家
+    pub fn payload(&self) -> String {
+        format!(
+            "{{\"version\":\"{}\",\"games\":{{\"pvp\":{},\"ai_easy\":{},\"ai_medium\":{},\"ai_hard\":{}}},\"sessions\":{},\"crash_free_sessions\":{}}}",
+            env!("CARGO_PKG_VERSION"),
+            self.games_pvp,
+            self.games_easy,
+            self.games_medium,
+            self.games_hard,
+            self.sessions,
+            self.crash_free_sessions
+        )
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => bail!("expected true or false, found {}", other),
+    }
+}
+
+/// 在后台把统计发送到 endpoint，失败时静默忽略，不影响游戏
+#[cfg(feature = "native")]
+fn upload(endpoint: String, payload: String) {
+    std::thread::spawn(move || {
+        let _ = ureq::post(&endpoint)
+            .timeout(std::time::Duration::from_secs(5))
+            .set("Content-Type", "application/json")
+            .send_string(&payload);
+    });
+}
+
+/// 网页版不上报统计，设置中也不显示上报选项
+#[cfg(not(feature = "native"))]
+fn upload(_endpoint: String, _payload: String) {}
@@ -0,0 +1,307 @@
+use crate::assets::{AssetKind, Assets};
+use anyhow::{bail, Context, Result};
+use eframe::egui::{vec2, Color32, Painter, Pos2, Stroke};
+use gomoku_core::Stone;
+use std::fs;
+
+/// 棋子的画法
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoneStyle {
+    // 纯色圆片加描边
+    Flat,
+    // 带阴影，从左上角的高光渐变到边缘
+    Shaded,
+}
+
+/// 界面主题：棋盘、棋子和界面文字的颜色以及线条粗细，绘制时都从这里读取
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    // 是否使用 egui 的深色控件
+    pub dark_mode: bool,
+
+    // 窗口和棋盘的底色
+    pub background: Color32,
+
+    // 棋盘线和坐标
+    pub grid: Color32,
+    pub grid_width: f32,
+    pub labels: Color32,
+
+    // 棋子的填充、描边和渐变高光
+    pub stone_style: StoneStyle,
+    pub black_fill: Color32,
+    pub black_edge: Color32,
+    pub black_highlight: Color32,
+    pub white_fill: Color32,
+    pub white_edge: Color32,
+    pub white_highlight: Color32,
+
+    // 最后一步的圆点（相对棋子半径）和获胜的连线
+    pub last_move: Color32,
+    pub last_move_ratio: f32,
+    pub win_line: Color32,
+    pub win_line_width: f32,
+
+    // 标题等强调文字
+    pub accent: Color32,
+    // 说明文字
+    pub muted: Color32,
+    // 错误信息和检查通过的提示
+    pub error: Color32,
+    pub success: Color32,
+    // 禁手等警告
+    pub warning: Color32,
+    // 键盘光标和教学箭头
+    pub annotation: Color32,
+    pub annotation_width: f32,
+    // 解题提示
+    pub puzzle_hint: Color32,
+}
+
+impl Theme {
+    /// 原来的浅黄色棋盘
+    pub const CLASSIC: Theme = Theme {
+        dark_mode: false,
+        background: Color32::LIGHT_YELLOW,
+        grid: Color32::DARK_GRAY,
+        grid_width: 1.0,
+        labels: Color32::DARK_GRAY,
+        stone_style: StoneStyle::Flat,
+        black_fill: Color32::BLACK,
+        black_edge: Color32::BLACK,
+        black_highlight: Color32::BLACK,
+        white_fill: Color32::WHITE,
+        white_edge: Color32::GRAY,
+        white_highlight: Color32::WHITE,
+        last_move: Color32::RED,
+        last_move_ratio: 0.3,
+        win_line: Color32::from_rgb(220, 40, 40),
+        win_line_width: 4.0,
+        accent: Color32::DARK_BLUE,
+        muted: Color32::GRAY,
+        error: Color32::DARK_RED,
+        success: Color32::DARK_GREEN,
+        warning: Color32::RED,
+        annotation: Color32::from_rgb(30, 110, 220),
+        annotation_width: 3.0,
+        puzzle_hint: Color32::from_rgb(220, 120, 0),
+    };
+
+    /// 木纹色棋盘，立体的棋子
+    pub const WOOD: Theme = Theme {
+        background: Color32::from_rgb(220, 179, 92),
+        grid: Color32::from_rgb(64, 48, 24),
+        labels: Color32::from_rgb(64, 48, 24),
+        stone_style: StoneStyle::Shaded,
+        black_fill: Color32::from_rgb(20, 20, 20),
+        black_edge: Color32::from_rgb(10, 10, 10),
+        black_highlight: Color32::from_rgb(110, 110, 110),
+        white_fill: Color32::from_rgb(225, 225, 220),
+        white_edge: Color32::from_rgb(150, 140, 120),
+        white_highlight: Color32::WHITE,
+        accent: Color32::from_rgb(110, 60, 20),
+        muted: Color32::from_rgb(110, 90, 60),
+        ..Theme::CLASSIC
+    };
+
+    /// 深色界面，棋盘和文字都调暗，标记用更亮的颜色
+    pub const DARK: Theme = Theme {
+        dark_mode: true,
+        background: Color32::from_rgb(40, 44, 52),
+        grid: Color32::from_rgb(120, 125, 135),
+        labels: Color32::from_rgb(160, 165, 175),
+        stone_style: StoneStyle::Shaded,
+        black_fill: Color32::from_rgb(12, 12, 14),
+        black_edge: Color32::from_rgb(140, 145, 155),
+        black_highlight: Color32::from_rgb(80, 82, 90),
+        white_fill: Color32::from_rgb(210, 212, 216),
+        white_edge: Color32::from_rgb(110, 112, 118),
+        white_highlight: Color32::WHITE,
+        last_move: Color32::from_rgb(255, 90, 90),
+        win_line: Color32::from_rgb(255, 80, 80),
+        accent: Color32::from_rgb(120, 170, 255),
+        muted: Color32::from_rgb(150, 155, 165),
+        error: Color32::from_rgb(255, 120, 120),
+        success: Color32::from_rgb(110, 220, 110),
+        warning: Color32::from_rgb(255, 80, 80),
+        annotation: Color32::from_rgb(90, 160, 255),
+        puzzle_hint: Color32::from_rgb(255, 170, 50),
+        ..Theme::CLASSIC
+    };
+
+    /// 按主题文件改写颜色和尺寸，没有写到的项保持不变
+    ///
+    /// 每行一个 `key=value`，key 是字段名，颜色写成 `#rrggbb`，`#` 开头的行是注释：
+    ///
+    /// ```text
+    /// # 更粗的棋盘线
+    /// grid=#303030
+    /// grid_width=1.5
+    /// stone_style=shaded
+    /// ```
+    pub fn apply(&mut self, text: &str) -> Result<()> {
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("invalid line: {}", line);
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || format!("invalid value for {}: {}", key, value);
+            let color = || parse_hex(value).with_context(invalid);
+            let number = || value.parse::<f32>().ok().filter(|n| *n >= 0.0).with_context(invalid);
+            match key {
+                "dark_mode" => self.dark_mode = value.parse().with_context(invalid)?,
+                "background" => self.background = color()?,
+                "grid" => self.grid = color()?,
+                "grid_width" => self.grid_width = number()?,
+                "labels" => self.labels = color()?,
+                "stone_style" => {
+                    self.stone_style = match value {
+                        "flat" => StoneStyle::Flat,
+                        "shaded" => StoneStyle::Shaded,
+                        _ => bail!(invalid()),
+                    }
+                }
+                "black_fill" => self.black_fill = color()?,
+                "black_edge" => self.black_edge = color()?,
+                "black_highlight" => self.black_highlight = color()?,
+                "white_fill" => self.white_fill = color()?,
+                "white_edge" => self.white_edge = color()?,
+                "white_highlight" => self.white_highlight = color()?,
+                "last_move" => self.last_move = color()?,
+                "last_move_ratio" => self.last_move_ratio = number()?,
+                "win_line" => self.win_line = color()?,
+                "win_line_width" => self.win_line_width = number()?,
+                "accent" => self.accent = color()?,
+                "muted" => self.muted = color()?,
+                "error" => self.error = color()?,
+                "success" => self.success = color()?,
+                "warning" => self.warning = color()?,
+                "annotation" => self.annotation = color()?,
+                "annotation_width" => self.annotation_width = number()?,
+                "puzzle_hint" => self.puzzle_hint = color()?,
+                // 以后版本新增的项，旧版本读到时忽略
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// 棋子的填充色，空位返回透明
+    pub fn stone_fill(&self, stone: Stone) -> Color32 {
+        match stone {
+            Stone::Black => self.black_fill,
+            Stone::White => self.white_fill,
+            Stone::Empty => Color32::TRANSPARENT,
+        }
+    }
+
+    /// 在 center 处画一枚棋子
+    pub fn paint_stone(&self, painter: &Painter, center: Pos2, radius: f32, stone: Stone) {
+        let (fill, edge, highlight) = match stone {
+            Stone::Black => (self.black_fill, self.black_edge, self.black_highlight),
+            Stone::White => (self.white_fill, self.white_edge, self.white_highlight),
+            Stone::Empty => return,
+        };
+        match self.stone_style {
+            StoneStyle::Flat => painter.circle(center, radius, fill, Stroke::new(1.0, edge)),
+            StoneStyle::Shaded => {
+                painter.circle_filled(center + vec2(radius, radius) * 0.12, radius, Color32::from_black_alpha(70));
+                painter.circle(center, radius, fill, Stroke::new(1.0, edge));
+                // 一层层缩小并移向左上角，叠出渐变的高光
+                for step in 1..=SHADE_STEPS {
+                    let t = step as f32 / SHADE_STEPS as f32;
+                    let offset = vec2(-radius, -radius) * 0.3 * t;
+                    painter.circle_filled(center + offset, radius * (1.0 - 0.75 * t), mix(fill, highlight, t));
+                }
+            }
+        }
+    }
+}
+
+// 立体棋子高光的层数
+const SHADE_STEPS: usize = 6;
+
+/// 从 a 到 b 按 t 取中间色
+fn mix(a: Color32, b: Color32, t: f32) -> Color32 {
+    let channel = |x: u8, y: u8| (x as f32 + (y as f32 - x as f32) * t).round() as u8;
+    Color32::from_rgba_premultiplied(
+        channel(a.r(), b.r()),
+        channel(a.g(), b.g()),
+        channel(a.b(), b.b()),
+        channel(a.a(), b.a()),
+    )
+}
+
+/// 颜色的 `#rrggbb` 写法，用于导出的图片
+pub fn hex(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
+
+/// 解析 `#rrggbb` 写法的颜色
+fn parse_hex(text: &str) -> Option<Color32> {
+    let digits = text.strip_prefix('#').filter(|digits| digits.len() == 6)?;
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    Some(Color32::from_rgb((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+/// 设置中可选的主题
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemeKind {
+    Classic,
+    Wood,
+    Dark,
+}
+
+impl ThemeKind {
+    /// 所有可选的主题
+    pub const ALL: [ThemeKind; 3] = [ThemeKind::Classic, ThemeKind::Wood, ThemeKind::Dark];
+
+    /// 主题名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThemeKind::Classic => "Classic",
+            ThemeKind::Wood => "Wood",
+            ThemeKind::Dark => "Dark",
+        }
+    }
+
+    /// 主题的颜色和尺寸
+    pub fn theme(&self) -> &'static Theme {
+        match self {
+            ThemeKind::Classic => &Theme::CLASSIC,
+            ThemeKind::Wood => &Theme::WOOD,
+            ThemeKind::Dark => &Theme::DARK,
+        }
+    }
+
+    /// 主题的颜色和尺寸，资源目录里有同名的主题文件（如 `themes/dark.txt`）时按文件改写
+    pub fn load(&self, assets: &Assets) -> Result<Theme> {
+        let mut theme = *self.theme();
+        let Some(path) = assets.find(AssetKind::Theme, &self.name().to_lowercase(), &["txt"]) else {
+            return Ok(theme);
+        };
+        let text = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        theme.apply(&text).with_context(|| format!("invalid theme file {}", path.display()))?;
+        Ok(theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_files_override_single_fields() {
+        let mut theme = Theme::CLASSIC;
+        theme.apply("# comment\n\ngrid=#303030\ngrid_width=1.5\nstone_style=shaded\nfuture_key=1\n").unwrap();
+        assert_eq!(theme.grid, Color32::from_rgb(0x30, 0x30, 0x30));
+        assert_eq!(theme.grid_width, 1.5);
+        assert_eq!(theme.stone_style, StoneStyle::Shaded);
+        assert_eq!(theme.background, Theme::CLASSIC.background);
+        assert_eq!(parse_hex(&hex(Theme::WOOD.background)), Some(Theme::WOOD.background));
+
+        assert!(theme.apply("grid=red").is_err());
+        assert!(theme.apply("grid_width=-1").is_err());
+        assert!(theme.apply("no equals sign").is_err());
+    }
+}
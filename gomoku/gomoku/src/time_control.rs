@@ -0,0 +1,100 @@
+/// 时间控制预设
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeControl {
+    Unlimited,
+    Bullet,
+    Blitz,
+    Standard,
+    Rapid,
+    Correspondence,
+}
+
+impl TimeControl {
+    /// 所有可选的预设，按下拉框中的顺序排列
+    pub const ALL: [TimeControl; 6] = [
+        TimeControl::Unlimited,
+        TimeControl::Bullet,
+        TimeControl::Blitz,
+        TimeControl::Standard,
+        TimeControl::Rapid,
+        TimeControl::Correspondence,
+    ];
+
+    /// 预设名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeControl::Unlimited => "Unlimited",
+            TimeControl::Bullet => "Bullet",
+            TimeControl::Blitz => "Blitz",
+            TimeControl::Standard => "Standard",
+            TimeControl::Rapid => "Rapid",
+            TimeControl::Correspondence => "Correspondence",
+        }
+    }
+
+    /// 每方的基础用时（秒），None 表示不限时
+    pub fn base_secs(&self) -> Option<u64> {
+        match self {
+            TimeControl::Unlimited => None,
+            TimeControl::Bullet => Some(60),
+            TimeControl::Blitz => Some(3 * 60),
+            TimeControl::Standard => Some(5 * 60),
+            TimeControl::Rapid => Some(10 * 60),
+            TimeControl::Correspondence => Some(24 * 60 * 60),
+        }
+    }
+
+    /// 每步加秒（秒）
+    pub fn increment_secs(&self) -> u64 {
+        match self {
+            TimeControl::Blitz => 2,
+            TimeControl::Standard => 3,
+            TimeControl::Rapid => 5,
+            _ => 0,
+        }
+    }
+
+    /// 是否按步计时：每走一步重新给满基础用时，而不是整局共用
+    pub fn is_per_move(&self) -> bool {
+        *self == TimeControl::Correspondence
+    }
+
+    /// 简短的时间代码，例如 "3+2"，用于界面显示，联机开局时主机也用它告知对方时间控制
+    pub fn code(&self) -> String {
+        match self {
+            TimeControl::Unlimited => "-".to_string(),
+            TimeControl::Correspondence => "1d/move".to_string(),
+            _ => format!(
+                "{}+{}",
+                self.base_secs().unwrap_or(0) / 60,
+                self.increment_secs()
+            ),
+        }
+    }
+
+    /// 按 `code` 找回预设，不认识的代码返回 None
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|control| control.code() == code)
+    }
+
+    /// 下拉框中显示的文字
+    pub fn label(&self) -> String {
+        match self {
+            TimeControl::Unlimited => self.name().to_string(),
+            _ => format!("{} {}", self.name(), self.code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_identify_presets() {
+        for control in TimeControl::ALL {
+            assert_eq!(TimeControl::from_code(&control.code()), Some(control));
+        }
+        assert_eq!(TimeControl::from_code("7+7"), None);
+    }
+}
@@ -0,0 +1,311 @@
+use anyhow::Result;
+use gomoku_core::protocol::Message;
+use gomoku_core::Stone;
+
+use crate::net::{Listener, NetEvent, NetSession, NetStatus};
+
+/// 选手默认最多保留的公告条数，更早的先丢弃
+pub const DEFAULT_ANNOUNCEMENT_LIMIT: usize = 20;
+
+/// 裁判默认最多接受的选手数，之后连上的直接断开
+pub const DEFAULT_ENTRANT_LIMIT: usize = 64;
+
+/// 裁判每次调整棋钟的毫秒数
+pub const DIRECTOR_CLOCK_STEP: i64 = 30_000;
+
+/// 裁判编排给本方的一盘对局
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pairing {
+    pub round: u32,
+    pub table: u32,
+    pub stone: Stone,
+    // 执黑的在这个地址的端口上创建对局，执白的加入这个地址
+    pub address: String,
+}
+
+impl Pairing {
+    /// 地址里的主机和端口，格式不对时返回 None
+    pub fn host_and_port(&self) -> Option<(&str, u16)> {
+        let (host, port) = self.address.rsplit_once(':')?;
+        Some((host, port.parse().ok()?))
+    }
+}
+
+/// 裁判对本台对局的指令，由界面作用到正在下的对局上
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Directive {
+    // 判定结果，获胜的一方，None 为和棋
+    Result(Option<Stone>),
+    // 给 stone 方的钟加上 delta 毫秒
+    Clock { stone: Stone, delta: i64 },
+}
+
+/// 选手一方与赛事裁判的连接
+///
+/// 裁判的消息只改变这里的状态；判定和调钟作用在对局上，交给界面处理。
+pub struct Entry {
+    pub session: NetSession,
+    // 最近一次编排，还没有编排时为 None
+    pub pairing: Option<Pairing>,
+    // 最近的公告，最新的在最后
+    pub announcements: Vec<String>,
+    // 是否正在下裁判编排的对局，结束后上报结果
+    pub playing: bool,
+    // 是否已经收到 DIRECTOR，确认对方是裁判
+    confirmed: bool,
+    // 最多保留的公告条数
+    announcement_limit: usize,
+}
+
+impl Entry {
+    /// 连接 address（`host:port`）上的裁判，最多保留 announcement_limit 条公告
+    pub fn join(address: &str, announcement_limit: usize) -> Self {
+        Self::new(NetSession::join(address), announcement_limit)
+    }
+
+    fn new(session: NetSession, announcement_limit: usize) -> Self {
+        Self { session, pairing: None, announcements: Vec::new(), playing: false, confirmed: false, announcement_limit }
+    }
+
+    /// 处理裁判发来的消息，需要作用到对局上时返回指令，消息不合法时返回原因
+    pub fn receive(&mut self, message: Message) -> Result<Option<Directive>, &'static str> {
+        if message == Message::Bye {
+            return Err("The tournament director left");
+        }
+        if !self.confirmed {
+            self.confirmed = message == Message::Director;
+            return if self.confirmed { Ok(None) } else { Err("The other side is not a tournament director") };
+        }
+        match message {
+            Message::Pair { round, table, stone, address } if stone != Stone::Empty => {
+                self.pairing = Some(Pairing { round, table, stone, address });
+                self.playing = false;
+                Ok(None)
+            }
+            Message::Result(winner) => {
+                let text = match winner {
+                    Some(stone) => format!("The director declared {} the winner", stone.name()),
+                    None => "The director declared a draw".to_string(),
+                };
+                self.announce(text);
+                self.playing = false;
+                Ok(Some(Directive::Result(winner)))
+            }
+            Message::Clock { stone, delta } if stone != Stone::Empty => {
+                let sign = if delta < 0 { '-' } else { '+' };
+                let seconds = delta.unsigned_abs() / 1000;
+                self.announce(format!("The director adjusted {}'s clock by {}{}s", stone.name(), sign, seconds));
+                Ok(Some(Directive::Clock { stone, delta }))
+            }
+            Message::Announce(text) => {
+                self.announce(text);
+                Ok(None)
+            }
+            _ => Err("The tournament director sent an invalid message"),
+        }
+    }
+
+    fn announce(&mut self, text: String) {
+        self.announcements.push(text);
+        let excess = self.announcements.len().saturating_sub(self.announcement_limit);
+        self.announcements.drain(..excess);
+    }
+}
+
+/// 一位选手：裁判到这位选手的连接和报上的名字
+pub struct Entrant {
+    pub name: String,
+    pub session: NetSession,
+}
+
+impl Entrant {
+    /// 显示的名字，还没报名时用连接的地址
+    pub fn label(&self) -> String {
+        match (self.name.as_str(), self.session.peer_ip()) {
+            ("", Some(ip)) => ip,
+            ("", None) => "(connecting)".to_string(),
+            (name, _) => name.to_string(),
+        }
+    }
+}
+
+/// 本轮的一台，black 和 white 是 `Director::entrants` 的下标
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Table {
+    pub black: usize,
+    pub white: usize,
+    // 选手上报或者裁判判定的结果，Some(None) 为和棋
+    pub result: Option<Option<Stone>>,
+}
+
+/// 赛事裁判一方：在一个端口上接受所有选手，编排对阵、判定结果、调整棋钟和发布公告
+///
+/// 对局本身仍然在两位选手之间直接进行，裁判只和每位选手保持一条连接。
+/// 选手数有上限；只保留本轮的台次，开始下一轮时清空，所以台数不超过选手数的一半。
+pub struct Director {
+    listener: Listener,
+    pub port: u16,
+    pub entrants: Vec<Entrant>,
+    entrant_limit: usize,
+    pub tables: Vec<Table>,
+    pub round: u32,
+    // 界面上正在编辑的对阵、对局端口和公告
+    pub black: usize,
+    pub white: usize,
+    pub game_port: String,
+    pub draft: String,
+}
+
+impl Director {
+    /// 在 port 上接受最多 entrant_limit 位选手，选手之间的对局默认用 game_port
+    pub fn start(port: u16, game_port: u16, entrant_limit: usize) -> Result<Self> {
+        Ok(Self {
+            listener: Listener::bind(port)?,
+            port,
+            entrants: Vec::new(),
+            entrant_limit,
+            tables: Vec::new(),
+            round: 1,
+            black: 0,
+            white: 0,
+            game_port: game_port.to_string(),
+            draft: String::new(),
+        })
+    }
+
+    /// 接受新选手，处理报名和选手上报的结果
+    pub fn poll(&mut self) {
+        while let Some(mut session) = self.listener.accept() {
+            if self.entrants.len() >= self.entrant_limit {
+                session.close("the tournament is full");
+                continue;
+            }
+            self.entrants.push(Entrant { name: String::new(), session });
+        }
+        let mut reports = Vec::new();
+        for (i, entrant) in self.entrants.iter_mut().enumerate() {
+            while let Some(event) = entrant.session.poll() {
+                match event {
+                    NetEvent::Connected => entrant.session.send(Message::Director),
+                    NetEvent::Received(Message::Player(name)) => entrant.name = name,
+                    NetEvent::Received(Message::Result(winner)) => reports.push((i, winner)),
+                    NetEvent::Received(_) | NetEvent::Disconnected(_) => {}
+                }
+            }
+        }
+        // 两位选手都会上报，先到的为准，裁判可以再改判
+        for (entrant, winner) in reports {
+            if let Some(table) = self.tables.iter_mut().find(|t| t.black == entrant || t.white == entrant) {
+                table.result = table.result.or(Some(winner));
+            }
+        }
+    }
+
+    /// entrants[i] 是否在线
+    pub fn is_connected(&self, i: usize) -> bool {
+        self.entrants.get(i).is_some_and(|entrant| entrant.session.status() == &NetStatus::Connected)
+    }
+
+    /// 编排一台对局：black 执黑在 `game_port` 上创建对局，white 执白加入
+    pub fn pair(&mut self, black: usize, white: usize) -> Result<(), &'static str> {
+        if black == white || !self.is_connected(black) || !self.is_connected(white) {
+            return Err("Pick two different connected players");
+        }
+        if self.tables.iter().any(|t| [t.black, t.white].iter().any(|&p| p == black || p == white)) {
+            return Err("A player already has a table this round");
+        }
+        let port: u16 = self.game_port.trim().parse().map_err(|_| "Invalid game port")?;
+        let ip = self.entrants[black].session.peer_ip().ok_or("The black player is not connected")?;
+        let address = match ip.contains(':') {
+            // IPv6 地址要加方括号才能接端口
+            true => format!("[{}]:{}", ip, port),
+            false => format!("{}:{}", ip, port),
+        };
+        self.tables.push(Table { black, white, result: None });
+        let (round, table) = (self.round, self.tables.len() as u32);
+        for (player, stone) in [(black, Stone::Black), (white, Stone::White)] {
+            let address = address.clone();
+            self.entrants[player].session.send(Message::Pair { round, table, stone, address });
+        }
+        Ok(())
+    }
+
+    /// 判定第 table 台（从 0 开始）的结果并通知两位选手
+    pub fn force_result(&mut self, table: usize, winner: Option<Stone>) {
+        let Some(t) = self.tables.get_mut(table) else {
+            return;
+        };
+        t.result = Some(winner);
+        let players = [t.black, t.white];
+        self.send_to(&players, Message::Result(winner));
+    }
+
+    /// 给第 table 台 stone 方的钟加上 delta 毫秒，两位选手的钟一起调整
+    pub fn adjust_clock(&mut self, table: usize, stone: Stone, delta: i64) {
+        if let Some(t) = self.tables.get(table) {
+            let players = [t.black, t.white];
+            self.send_to(&players, Message::Clock { stone, delta });
+        }
+    }
+
+    /// 把正在编辑的公告发给所有选手
+    pub fn announce(&mut self) {
+        let text = self.draft.trim().to_string();
+        if text.is_empty() {
+            return;
+        }
+        let everyone: Vec<usize> = (0..self.entrants.len()).collect();
+        self.send_to(&everyone, Message::Announce(text));
+        self.draft.clear();
+    }
+
+    /// 开始下一轮，本轮的台次清空
+    pub fn next_round(&mut self) {
+        self.round += 1;
+        self.tables.clear();
+    }
+
+    fn send_to(&mut self, players: &[usize], message: Message) {
+        for &player in players {
+            if self.is_connected(player) {
+                self.entrants[player].session.send(message.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gomoku_core::{Checksum, Coord};
+
+    #[test]
+    fn follows_the_director() {
+        let mut entry = Entry::new(NetSession::join("127.0.0.1:1"), 3);
+        // 对方先要表明自己是裁判
+        let mut stranger = Entry::new(NetSession::join("127.0.0.1:1"), 3);
+        assert!(stranger.receive(Message::Announce("hi".to_string())).is_err());
+        assert_eq!(entry.receive(Message::Director), Ok(None));
+
+        let address = "10.0.0.5:5152".to_string();
+        entry.receive(Message::Pair { round: 2, table: 3, stone: Stone::Black, address }).unwrap();
+        let pairing = entry.pairing.clone().unwrap();
+        assert_eq!((pairing.round, pairing.table, pairing.stone), (2, 3, Stone::Black));
+        assert_eq!(pairing.host_and_port(), Some(("10.0.0.5", 5152)));
+
+        let clock = Message::Clock { stone: Stone::White, delta: -30_000 };
+        assert_eq!(entry.receive(clock), Ok(Some(Directive::Clock { stone: Stone::White, delta: -30_000 })));
+        entry.playing = true;
+        assert_eq!(entry.receive(Message::Result(None)), Ok(Some(Directive::Result(None))));
+        assert!(!entry.playing);
+
+        // 公告只保留最近的几条
+        for i in 0..5 {
+            entry.receive(Message::Announce(format!("news {}", i))).unwrap();
+        }
+        assert_eq!(entry.announcements, ["news 2", "news 3", "news 4"]);
+
+        assert!(entry.receive(Message::Move { ply: 0, coord: Coord::CENTER, checksum: Checksum(0) }).is_err());
+        assert!(entry.receive(Message::Bye).is_err());
+    }
+}
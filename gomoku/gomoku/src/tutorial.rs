@@ -0,0 +1,209 @@
+use gomoku_core::{Board, Coord, Rules, Stone};
+
+/// 教程里的一步：提示文字和应手表
+pub struct Step {
+    pub prompt: &'static str,
+    // 学生可以走的点和对手对应的应手，走到表外的点不算数
+    pub responses: &'static [(Coord, Option<Coord>)],
+}
+
+/// 一节教程：固定的局面和一串脚本化的步骤
+///
+/// 对手不用 AI，而是按应手表落子，引擎以后怎么改，课上的局面都按讲解的那样展开。
+pub struct Exercise {
+    pub title: &'static str,
+    pub black: &'static [Coord],
+    pub white: &'static [Coord],
+    // 学生执哪一方
+    pub student: Stone,
+    pub steps: &'static [Step],
+    // 做完之后的总结
+    pub conclusion: &'static str,
+}
+
+/// 内置教程，讲解常见的威胁棋形
+pub const EXERCISES: [Exercise; 3] = [
+    Exercise {
+        title: "A four forces a reply",
+        black: &[Coord::new(6, 7), Coord::new(7, 7), Coord::new(8, 7)],
+        white: &[Coord::new(5, 7), Coord::new(7, 8), Coord::new(8, 8)],
+        student: Stone::Black,
+        steps: &[Step {
+            prompt: "Your three is blocked on the left. Add one stone to make a four.",
+            responses: &[
+                (Coord::new(9, 7), Some(Coord::new(10, 7))),
+                (Coord::new(10, 7), Some(Coord::new(9, 7))),
+            ],
+        }],
+        conclusion: "White had no choice but to block. A four always forces an answer, so it gains you a tempo.",
+    },
+    Exercise {
+        title: "Four-three",
+        black: &[Coord::new(7, 7), Coord::new(8, 7), Coord::new(9, 7), Coord::new(10, 8), Coord::new(10, 9)],
+        white: &[Coord::new(6, 7), Coord::new(9, 8), Coord::new(11, 9)],
+        student: Stone::Black,
+        steps: &[
+            Step {
+                prompt: "Find the point that makes a four and an open three at the same time.",
+                responses: &[(Coord::new(10, 7), Some(Coord::new(11, 7)))],
+            },
+            Step {
+                prompt: "White had to stop the four. Now turn the open three into an open four.",
+                responses: &[
+                    (Coord::new(10, 6), Some(Coord::new(10, 10))),
+                    (Coord::new(10, 10), Some(Coord::new(10, 6))),
+                ],
+            },
+            Step { prompt: "An open four has two ends and White can block only one. Finish the five.", responses: &[] },
+        ],
+        conclusion: "A four-three wins: the four takes White's move, and the three grows into an open four.",
+    },
+    Exercise {
+        title: "Stop the open three",
+        black: &[Coord::new(6, 7), Coord::new(7, 7), Coord::new(8, 7)],
+        white: &[Coord::new(7, 8), Coord::new(8, 6)],
+        student: Stone::White,
+        steps: &[Step {
+            prompt: "Black has an open three. Block it right next to the stones before it becomes an open four.",
+            responses: &[(Coord::new(5, 7), None), (Coord::new(9, 7), None)],
+        }],
+        conclusion: "Blocked. Left alone, the three would have become an open four that cannot be stopped.",
+    },
+];
+
+/// 教程进度
+pub struct Tutorial {
+    // 当前教程编号和进行到的步骤
+    pub index: usize,
+    pub step: usize,
+    pub board: Board,
+    pub last_move: Option<Coord>,
+    // 当前教程是否已经做完
+    pub finished: bool,
+    // 走错时的反馈，为空时显示当前步骤的提示
+    pub message: String,
+}
+
+impl Default for Tutorial {
+    fn default() -> Self {
+        let mut tutorial =
+            Self { index: 0, step: 0, board: Board::new(), last_move: None, finished: false, message: String::new() };
+        tutorial.go_to(0);
+        tutorial
+    }
+}
+
+impl Exercise {
+    /// 教程的初始棋盘
+    pub fn board(&self) -> Board {
+        let mut board = Board::new();
+        for &coord in self.black {
+            board.set(coord, Stone::Black);
+        }
+        for &coord in self.white {
+            board.set(coord, Stone::White);
+        }
+        board
+    }
+}
+
+impl Tutorial {
+    /// 当前教程
+    pub fn exercise(&self) -> &'static Exercise {
+        &EXERCISES[self.index]
+    }
+
+    /// 现在要显示给学生的文字
+    pub fn text(&self) -> &str {
+        if !self.message.is_empty() {
+            &self.message
+        } else if self.finished {
+            self.exercise().conclusion
+        } else {
+            self.exercise().steps[self.step].prompt
+        }
+    }
+
+    /// 学生在 mv 落子，按应手表让对手应一手；返回落子是否被接受
+    ///
+    /// 连成五子总是对的；其余的落子必须在当前步骤的应手表里。
+    pub fn try_move(&mut self, mv: Coord) -> bool {
+        if self.finished || !mv.is_valid() || !self.board.is_empty_at(mv) {
+            return false;
+        }
+        let exercise = self.exercise();
+        let mut board = self.board;
+        board.set(mv, exercise.student);
+        let five = board.winning_line(mv, Rules::DEFAULT_WIN_LENGTH).is_some();
+        let response = exercise.steps[self.step].responses.iter().find(|(accepted, _)| *accepted == mv);
+        if !five && response.is_none() {
+            self.message = "That's not the move this lesson is about. Try again.".to_string();
+            return false;
+        }
+        self.board = board;
+        self.last_move = Some(mv);
+        self.message.clear();
+        if let Some(&(_, Some(reply))) = response.filter(|_| !five) {
+            self.board.set(reply, exercise.student.opponent());
+            self.last_move = Some(reply);
+        }
+        self.step += 1;
+        self.finished = five || self.step >= exercise.steps.len();
+        true
+    }
+
+    /// 进入下一节教程，做完最后一节后从头开始
+    pub fn next_exercise(&mut self) {
+        self.go_to((self.index + 1) % EXERCISES.len());
+    }
+
+    /// 从头开始第 index 节教程
+    pub fn go_to(&mut self, index: usize) {
+        self.index = index;
+        self.step = 0;
+        self.board = self.exercise().board();
+        self.last_move = None;
+        self.finished = false;
+        self.message.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opponent_follows_the_script() {
+        let mut tutorial = Tutorial::default();
+        tutorial.go_to(1);
+        assert!(!tutorial.try_move(Coord::new(0, 0)));
+        assert!(!tutorial.message.is_empty());
+
+        assert!(tutorial.try_move(Coord::new(10, 7)));
+        assert_eq!(tutorial.board.get(Coord::new(11, 7)), Stone::White);
+        assert!(tutorial.try_move(Coord::new(10, 10)));
+        assert_eq!(tutorial.last_move, Some(Coord::new(10, 6)));
+        // 最后一步没有应手表，连成五子就算完成
+        assert!(tutorial.try_move(Coord::new(10, 11)));
+        assert!(tutorial.finished);
+        assert_eq!(tutorial.text(), tutorial.exercise().conclusion);
+    }
+
+    #[test]
+    fn scripts_only_use_empty_points() {
+        for exercise in &EXERCISES {
+            let board = exercise.board();
+            let mut used = Vec::new();
+            for step in exercise.steps {
+                for &(mv, reply) in step.responses {
+                    assert!(board.is_empty_at(mv), "{} {:?}", exercise.title, mv);
+                    assert!(reply.is_none_or(|reply| board.is_empty_at(reply) && reply != mv), "{}", exercise.title);
+                    used.push(mv);
+                }
+            }
+            // 每一步都至少有一种走法能继续下去
+            assert!(exercise.steps.iter().take(exercise.steps.len() - 1).all(|step| !step.responses.is_empty()));
+            assert!(!used.is_empty());
+        }
+    }
+}
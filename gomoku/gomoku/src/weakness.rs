@@ -0,0 +1,87 @@
+use crate::puzzle::PUZZLES;
+use crate::tutorial::EXERCISES;
+use anyhow::{Context, Result};
+use gomoku_core::{Rate, Skill, SkillReport};
+use std::fs;
+use std::path::Path;
+
+pub use gomoku_core::weakness::analyze_game;
+
+/// 默认弱点报告文件路径
+pub const WEAKNESS_PATH: &str = "gomoku_weakness.txt";
+
+/// 一项技能至少有这么多次机会才写进报告，次数太少的比例没有意义
+pub const MIN_CHANCES: u32 = 3;
+
+/// 针对某项弱点的练习：解题模式或教程里的一题
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Drill {
+    Puzzle(usize),
+    Tutorial(usize),
+}
+
+impl Drill {
+    /// 题目的标题
+    pub fn title(&self) -> &'static str {
+        match *self {
+            Drill::Puzzle(index) => PUZZLES[index].title,
+            Drill::Tutorial(index) => EXERCISES[index].title,
+        }
+    }
+}
+
+/// 练习 skill 用的题目，下标对应 `puzzle::PUZZLES` 和 `tutorial::EXERCISES`
+pub fn drill(skill: Skill) -> Drill {
+    match skill {
+        Skill::FinishFive => Drill::Puzzle(0),
+        Skill::BlockFour => Drill::Tutorial(0),
+        Skill::BlockOpenThree => Drill::Tutorial(2),
+        Skill::OpeningAccuracy => Drill::Puzzle(1),
+        Skill::MiddlegameAccuracy | Skill::EndgameAccuracy => Drill::Tutorial(1),
+    }
+}
+
+/// 从文件读取累计的弱点统计，文件不存在时返回空统计
+///
+/// 每行一个 `key=做对次数/机会次数`：
+///
+/// ```text
+/// block_open_three=6/10
+/// opening_accuracy=21/40
+/// ```
+pub fn load(path: &Path) -> Result<SkillReport> {
+    let mut report = SkillReport::default();
+    if !path.exists() {
+        return Ok(report);
+    }
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    for line in text.lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let Some(skill) = Skill::ALL.into_iter().find(|skill| skill.key() == key.trim()) else {
+            continue;
+        };
+        let rate = parse_rate(value).with_context(|| format!("invalid value for {}: {}", key, value))?;
+        report.set_rate(skill, rate);
+    }
+    Ok(report)
+}
+
+/// 写入弱点报告文件
+pub fn save(report: &SkillReport, path: &Path) -> Result<()> {
+    let text: String = Skill::ALL
+        .iter()
+        .map(|&skill| {
+            let rate = report.rate(skill);
+            format!("{}={}/{}\n", skill.key(), rate.hits, rate.chances)
+        })
+        .collect();
+    fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn parse_rate(value: &str) -> Option<Rate> {
+    let (hits, chances) = value.split_once('/')?;
+    let rate = Rate { hits: hits.trim().parse().ok()?, chances: chances.trim().parse().ok()? };
+    (rate.hits <= rate.chances).then_some(rate)
+}
@@ -0,0 +1,85 @@
+//! 比较位掩码棋型扫描和逐格扫描的速度：`cargo bench --bench pattern`
+//!
+//! 在几个中盘局面上，和 AI 的候选点一样只看附近有棋子的空位，对每个方向、双方各评估一次，
+//! 重复多轮取总时间。
+
+use gomoku_core::pattern::{direction_score_scalar, point_scores};
+use gomoku_core::{Board, Coord, Stone};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// 每次计时重复扫描的轮数，以及计时的次数
+const ROUNDS: usize = 500;
+const REPEATS: usize = 15;
+
+// 一个局面和上面要评估的空位
+type Position = (Board, Vec<Coord>);
+
+fn main() {
+    let positions = positions();
+    // 交替运行几次，各取最快的一次，减少机器负载的干扰
+    let mut scalar = Duration::MAX;
+    let mut bits = Duration::MAX;
+    for _ in 0..REPEATS {
+        scalar = scalar.min(time(&positions, scalar_scores));
+        bits = bits.min(time(&positions, |board, coord| {
+            let (own, other) = point_scores(board, coord, Stone::Black, 5);
+            own + other
+        }));
+    }
+    let points: usize = positions.iter().map(|(_, coords)| coords.len()).sum();
+    let calls = (ROUNDS * points) as f64;
+    println!("scalar:  {:>8.2?} ({:.1} ns/point)", scalar, scalar.as_nanos() as f64 / calls);
+    println!("bitmask: {:>8.2?} ({:.1} ns/point)", bits, bits.as_nanos() as f64 / calls);
+    println!("speedup: {:.2}x", scalar.as_secs_f64() / bits.as_secs_f64());
+}
+
+/// 用固定的种子摆出几个疏密不同的局面
+fn positions() -> Vec<Position> {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    (1..=4)
+        .map(|density| {
+            let mut board = Board::new();
+            for ply in 0..density * 15 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                // 集中在棋盘中央的 9 * 9 区域，像真实的对局
+                let coord = Coord::new(3 + state as usize % 9, 3 + (state >> 8) as usize % 9);
+                if board.is_empty_at(coord) {
+                    board.set(coord, Stone::for_ply(ply));
+                }
+            }
+            let near_stones = |coord: Coord| {
+                (-2..=2).any(|dc| (-2..=2).any(|dr| coord.offset(dc, dr).is_some_and(|c| !board.is_empty_at(c))))
+            };
+            let coords = Coord::all().filter(|&c| board.is_empty_at(c) && near_stones(c)).collect();
+            (board, coords)
+        })
+        .collect()
+}
+
+/// 逐格扫描八次，和 `point_scores` 的两项之和相同
+fn scalar_scores(board: &Board, coord: Coord) -> i32 {
+    let mut total = 0;
+    for direction in 0..4 {
+        for stone in [Stone::Black, Stone::White] {
+            total += direction_score_scalar(board, coord, direction, stone, 5);
+        }
+    }
+    total
+}
+
+fn time(positions: &[Position], scan: impl Fn(&Board, Coord) -> i32) -> Duration {
+    let start = Instant::now();
+    let mut total = 0i64;
+    for _ in 0..ROUNDS {
+        for (board, coords) in positions {
+            for &coord in coords {
+                total += scan(black_box(board), black_box(coord)) as i64;
+            }
+        }
+    }
+    black_box(total);
+    start.elapsed()
+}
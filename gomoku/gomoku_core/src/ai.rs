@@ -0,0 +1,822 @@
+use crate::board::{Board, BOARD_SIZE};
+use crate::coord::Coord;
+use crate::opening_book::OpeningBook;
+use crate::pattern;
+use crate::rules::Rules;
+use crate::stone::Stone;
+use crate::transposition::{Bound, Entry, TranspositionTable};
+use crate::zobrist;
+
+/// 一个局面的分析结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    pub best_move: Coord,
+    pub score: i32,
+}
+
+/// 搜索和评估的版本，改动之后同一局面的分析结果可能不同，保存的分析结果随之作废
+pub const ENGINE_VERSION: u32 = 1;
+
+/// 连成五子的得分，远高于任何启发式评估
+const WIN_SCORE: i32 = 1_000_000;
+
+impl Analysis {
+    /// 搜索深度内能够强制获胜
+    pub fn is_win(&self) -> bool {
+        self.score >= WIN_SCORE
+    }
+
+    /// 搜索深度内无论怎么下都会输
+    pub fn is_loss(&self) -> bool {
+        self.score <= -WIN_SCORE
+    }
+}
+
+/// 一次搜索的开销：搜索过的局面数，以及置换表的查询和命中次数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    pub nodes: u64,
+    pub tt_probes: u64,
+    pub tt_hits: u64,
+}
+
+impl SearchStats {
+    /// 置换表命中率，以千分之一为单位，0 到 1000
+    pub fn hit_permille(&self) -> u64 {
+        self.tt_hits * 1000 / self.tt_probes.max(1)
+    }
+}
+
+impl std::ops::AddAssign for SearchStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+    }
+}
+
+// 深度大于 1 时每层最多展开的候选点数
+const MAX_CANDIDATES: usize = 10;
+
+// 候选点与已有棋子的最大距离
+const NEIGHBOR_RANGE: i32 = 2;
+
+// 第一步在天元附近随机时，离天元的最大行列距离
+const NEAR_CENTER_RANGE: i32 = 2;
+
+/// AI 难度，对应不同的搜索深度
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    /// 难度名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    /// 搜索深度（层数），1 层即只看自己这一步
+    pub fn depth(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Medium => 2,
+            Difficulty::Hard => 4,
+        }
+    }
+
+    /// 开局随机化的范围，难度越低可选的点越多、随机的步数越长；困难不随机，总是下最好的一步
+    pub fn opening_variety(&self) -> Option<OpeningVariety> {
+        match self {
+            Difficulty::Easy => Some(OpeningVariety { plies: 10, candidates: 6, margin: 150 }),
+            Difficulty::Medium => Some(OpeningVariety { plies: 6, candidates: 4, margin: 100 }),
+            Difficulty::Hard => None,
+        }
+    }
+}
+
+/// 开局随机化：前 `plies` 步在得分最高的 `candidates` 个点中，
+/// 从得分比最佳点低不超过 `margin` 的点里随机选一步，同一难度的对局不会每次都一模一样
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpeningVariety {
+    pub plies: usize,
+    pub candidates: usize,
+    pub margin: i32,
+}
+
+impl OpeningVariety {
+    /// 用随机数 pick 从按得分从高到低排列的 candidates 中选一步，没有候选点时返回 None
+    ///
+    /// 最佳点能取胜或者其他点会输时只剩最佳点可选，随机化不会放过杀棋或者下出败着。
+    pub fn choose(&self, candidates: &[Analysis], pick: u64) -> Option<Analysis> {
+        let best = *candidates.first()?;
+        let acceptable: Vec<Analysis> = candidates
+            .iter()
+            .take(self.candidates)
+            .filter(|c| c.score >= best.score - self.margin && (!best.is_win() || c.is_win()) && (best.is_loss() || !c.is_loss()))
+            .copied()
+            .collect();
+        acceptable.get((pick % acceptable.len() as u64) as usize).copied()
+    }
+}
+
+/// AI 执黑时第一步的下法，空棋盘上没有可搜索的东西，由这里决定
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FirstMove {
+    // 总是下在天元
+    #[default]
+    Center,
+    // 在天元周围 5x5 的范围内随机选一点
+    NearCenter,
+    // 从开局库里空棋盘的应对中随机选一步，库里没有时下在天元
+    Book,
+}
+
+impl FirstMove {
+    pub const ALL: [FirstMove; 3] = [FirstMove::Center, FirstMove::NearCenter, FirstMove::Book];
+
+    /// 策略名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            FirstMove::Center => "Center",
+            FirstMove::NearCenter => "Near Center",
+            FirstMove::Book => "Opening Book",
+        }
+    }
+
+    /// 用随机数 pick 选出第一步，book 为 None 时按没有开局库处理
+    pub fn choose(&self, book: Option<&OpeningBook>, pick: u64) -> Coord {
+        match self {
+            FirstMove::Center => Coord::CENTER,
+            FirstMove::NearCenter => {
+                let side = (NEAR_CENTER_RANGE * 2 + 1) as u64;
+                let (dcol, drow) = ((pick % side) as i32, (pick / side % side) as i32);
+                Coord::CENTER.offset(dcol - NEAR_CENTER_RANGE, drow - NEAR_CENTER_RANGE).unwrap_or(Coord::CENTER)
+            }
+            FirstMove::Book => {
+                let moves = book.map(|book| book.moves(&Board::new())).unwrap_or_default();
+                moves.get((pick % moves.len().max(1) as u64) as usize).copied().unwrap_or(Coord::CENTER)
+            }
+        }
+    }
+}
+
+/// 启发式评估的权重：落子点对自己的进攻价值和对对手的防守价值各自乘以的系数
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Weights {
+    pub attack: i32,
+    pub defense: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self { attack: 10, defense: 8 }
+    }
+}
+
+/// 候选落点的评估函数
+///
+/// 一次评估同一局面上的一批落点，神经网络等后端可以把整批送到 GPU 上计算，
+/// 传统的棋型评估逐点计算即可。分数越高表示落点越好，只用于排序和搜索的叶子节点。
+///
+/// 得分写入调用方提供的缓冲区，搜索中每个节点都要评估一批，这样不必每次分配内存。
+pub trait Evaluator {
+    /// stone 方在 board 上分别落在 moves 各点的价值，写入与 moves 一一对应的 scores
+    fn evaluate_batch(&self, board: &Board, stone: Stone, moves: &[Coord], rules: Rules, scores: &mut [i32]);
+}
+
+/// 传统的棋型评估：按每个方向上的连子数和两端是否被挡住打分，同时考虑进攻和防守
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Classical {
+    pub weights: Weights,
+}
+
+impl Evaluator for Classical {
+    fn evaluate_batch(&self, board: &Board, stone: Stone, moves: &[Coord], rules: Rules, scores: &mut [i32]) {
+        let length = rules.win_length();
+        for (score, &c) in scores.iter_mut().zip(moves) {
+            *score = evaluate_position(board, c, stone, length, self.weights);
+        }
+    }
+}
+
+/// 搜索 AI：在已有棋子附近生成候选点，用评估函数排序后做带 alpha-beta 剪枝的 negamax 搜索
+#[derive(Clone, Copy, Debug)]
+pub struct Ai<E = Classical> {
+    stone: Stone,
+    difficulty: Difficulty,
+    rules: Rules,
+    first_move: FirstMove,
+    evaluator: E,
+}
+
+impl Ai {
+    /// 创建执 stone 方、使用传统评估的 AI，默认使用标准五子的自由规则
+    pub fn new(stone: Stone, difficulty: Difficulty) -> Self {
+        Self {
+            stone,
+            difficulty,
+            rules: Rules::default(),
+            first_move: FirstMove::default(),
+            evaluator: Classical::default(),
+        }
+    }
+
+    /// 使用指定的评估权重，用于比较不同的评估函数
+    pub fn with_weights(mut self, weights: Weights) -> Self {
+        self.evaluator.weights = weights;
+        self
+    }
+}
+
+impl<E: Evaluator> Ai<E> {
+    /// 按指定规则下棋，不会选择禁手
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// 空棋盘上按 policy 选第一步，默认总是下在天元
+    pub fn with_first_move(mut self, policy: FirstMove) -> Self {
+        self.first_move = policy;
+        self
+    }
+
+    /// 换用另一个评估函数，搜索本身不变
+    pub fn with_evaluator<F: Evaluator>(self, evaluator: F) -> Ai<F> {
+        let Ai { stone, difficulty, rules, first_move, .. } = self;
+        Ai { stone, difficulty, rules, first_move, evaluator }
+    }
+
+    /// AI 执哪一方
+    pub fn stone(&self) -> Stone {
+        self.stone
+    }
+
+    /// AI 难度
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// 寻找最佳落子位置
+    pub fn best_move(&self, board: &Board) -> Analysis {
+        self.best_move_with_stats(board).0
+    }
+
+    /// 寻找最佳落子位置，同时返回搜索的开销，用于比较不同的搜索
+    pub fn best_move_with_stats(&self, board: &Board) -> (Analysis, SearchStats) {
+        let mut board = *board;
+        let depth = self.difficulty.depth();
+        let mut search = Search::new(depth);
+        let window = (-WIN_SCORE * 2, WIN_SCORE * 2);
+        let (score, best_move) = negamax(&mut board, self, self.stone, depth, window, &mut search);
+        let analysis = Analysis {
+            // 棋盘下满时没有候选点，默认中心位置
+            best_move: best_move.unwrap_or(Coord::CENTER),
+            score,
+        };
+        (analysis, search.stats())
+    }
+
+    /// 棋盘为空时按第一步的策略选点，pick 是调用方给出的随机数；棋盘不空时返回 None
+    ///
+    /// 第一步不经过搜索，得分按均势记录。
+    pub fn opening_move(&self, board: &Board, book: Option<&OpeningBook>, pick: u64) -> Option<Analysis> {
+        let empty = Coord::all().all(|c| board.is_empty_at(c));
+        empty.then(|| Analysis { best_move: self.first_move.choose(book, pick), score: 0 })
+    }
+
+    /// 第 ply 步（从 0 开始）按难度的开局随机化在几个相差不多的好点中选一步，pick 是调用方给出的随机数
+    ///
+    /// 空棋盘上按第一步的策略落子（没有开局库可查）；过了开局或者难度不随机时直接返回搜索得到的 best。
+    pub fn vary_opening(&self, board: &Board, ply: usize, best: Analysis, pick: u64) -> Analysis {
+        if let Some(first) = self.opening_move(board, None, pick) {
+            return first;
+        }
+        let Some(variety) = self.difficulty.opening_variety().filter(|v| ply < v.plies) else {
+            return best;
+        };
+        variety.choose(&self.top_moves(board, variety.candidates), pick).unwrap_or(best)
+    }
+
+    /// 分别搜索得分最高的 count 个落点，按得分从高到低排列，用于给玩家提示
+    ///
+    /// 每个候选点都用完整的窗口搜索，比 `best_move` 慢，但每个得分都是准确的。
+    pub fn top_moves(&self, board: &Board, count: usize) -> Vec<Analysis> {
+        let mut board = *board;
+        let depth = self.difficulty.depth();
+        let mut search = Search::new(depth);
+        let limit = if depth > 1 { MAX_CANDIDATES } else { usize::MAX };
+        let window = (-WIN_SCORE * 2, WIN_SCORE * 2);
+        let mut candidates = Vec::new();
+        search.candidates(&board, self, self.stone, limit, &mut candidates);
+        let mut moves: Vec<Analysis> = candidates
+            .into_iter()
+            .map(|candidate| Analysis {
+                best_move: candidate.0,
+                score: score_move(&mut board, self, self.stone, candidate, depth, window, &mut search),
+            })
+            .collect();
+        // 稳定排序，得分相同时保持启发式评估的顺序
+        moves.sort_by_key(|analysis| std::cmp::Reverse(analysis.score));
+        moves.truncate(count);
+        moves
+    }
+
+    /// 评估 AI 在 coord 落子的价值
+    pub fn evaluate_position(&self, board: &Board, coord: Coord) -> i32 {
+        let mut score = [0];
+        self.evaluator.evaluate_batch(board, self.stone, &[coord], self.rules, &mut score);
+        score[0]
+    }
+
+    /// 评估局面对 AI 一方是否有利：双方最好的进攻点得分之差，正数表示 AI 占优
+    ///
+    /// 只看进攻不看防守，用于开局时判断该执哪一方。
+    pub fn advantage(&self, board: &Board) -> i32 {
+        let length = self.rules.win_length();
+        let attack = |stone: Stone| {
+            Coord::all()
+                .filter(|&c| board.is_empty_at(c) && has_neighbor(board, c))
+                .map(|c| pattern::point_scores(board, c, stone, length).0)
+                .max()
+                .unwrap_or(0)
+        };
+        attack(self.stone) - attack(self.stone.opponent())
+    }
+}
+
+/// 一次搜索共用的状态：搜索过的局面数、置换表，以及预先分配好的缓冲区
+///
+/// 搜索在同一个棋盘上落子、撤销，候选点放在按层预留的缓冲区里，展开节点时不分配内存。
+struct Search {
+    nodes: u64,
+    table: TranspositionTable,
+    // 每层的候选点，按剩余深度取用，递归到下一层时不会覆盖上一层的
+    moves: Vec<Vec<(Coord, i32)>>,
+    // 生成候选点时的空位和评估得分，用完即可复用
+    coords: Vec<Coord>,
+    scores: Vec<i32>,
+}
+
+impl Search {
+    /// 为最多 depth 层的搜索分配缓冲区
+    fn new(depth: u32) -> Self {
+        let cells = BOARD_SIZE * BOARD_SIZE;
+        Self {
+            nodes: 0,
+            table: TranspositionTable::default(),
+            moves: (0..=depth).map(|_| Vec::with_capacity(cells)).collect(),
+            coords: Vec::with_capacity(cells),
+            scores: Vec::with_capacity(cells),
+        }
+    }
+
+    fn stats(&self) -> SearchStats {
+        SearchStats { nodes: self.nodes, tt_probes: self.table.probes(), tt_hits: self.table.hits() }
+    }
+
+    /// 生成候选点写入 moves：距离已有棋子不超过 NEIGHBOR_RANGE 的空位，一次评估整批之后
+    /// 从高到低排列，跳过禁手，最多 limit 个
+    fn candidates<E: Evaluator>(
+        &mut self,
+        board: &Board,
+        ai: &Ai<E>,
+        stone: Stone,
+        limit: usize,
+        moves: &mut Vec<(Coord, i32)>,
+    ) {
+        let rules = ai.rules;
+        self.coords.clear();
+        self.coords.extend(Coord::all().filter(|&c| board.is_empty_at(c) && has_neighbor(board, c)));
+        // 空棋盘从中心开始
+        if self.coords.is_empty() && board.is_empty_at(Coord::CENTER) {
+            self.coords.push(Coord::CENTER);
+        }
+        self.scores.clear();
+        self.scores.resize(self.coords.len(), 0);
+        ai.evaluator.evaluate_batch(board, stone, &self.coords, rules, &mut self.scores);
+        moves.clear();
+        moves.extend(self.coords.iter().copied().zip(self.scores.iter().copied()));
+        // 稳定排序需要额外的内存，改用不稳定排序，得分相同时按生成的顺序（先列后行）排列，结果一样
+        moves.sort_unstable_by_key(|&(c, score)| (std::cmp::Reverse(score), c.col, c.row));
+        // 排序后再检查禁手，只需要检查排在前面的候选点
+        let mut kept = 0;
+        for i in 0..moves.len() {
+            if kept == limit {
+                break;
+            }
+            let (c, _) = moves[i];
+            if rules.forbidden(board, c, stone).is_none() {
+                moves[kept] = moves[i];
+                kept += 1;
+            }
+        }
+        moves.truncate(kept);
+    }
+}
+
+/// negamax 搜索，返回 stone 方视角的得分和最佳落点，双方都使用 ai 的规则和评估权重
+///
+/// 搜索过的局面按哈希存入置换表，不同顺序走到同一局面时直接使用保存的结果。
+fn negamax<E: Evaluator>(
+    board: &mut Board,
+    ai: &Ai<E>,
+    stone: Stone,
+    depth: u32,
+    (mut alpha, beta): (i32, i32),
+    search: &mut Search,
+) -> (i32, Option<Coord>) {
+    let key = zobrist::hash_with_side(board, stone);
+    let entry = search.table.probe(key);
+    if let Some(entry) = entry {
+        if let Some(score) = entry.cutoff(depth, (alpha, beta)) {
+            return (score, entry.best_move);
+        }
+    }
+
+    let limit = if depth > 1 { MAX_CANDIDATES } else { usize::MAX };
+    // 借用这一层的缓冲区，搜索完这一层再还回去
+    let mut candidates = std::mem::take(&mut search.moves[depth as usize]);
+    search.candidates(board, ai, stone, limit, &mut candidates);
+    // 先搜上次在这个局面找到的最佳落点，更早剪枝
+    let previous = entry.and_then(|entry| entry.best_move);
+    if let Some(i) = previous.and_then(|mv| candidates.iter().position(|&(c, _)| c == mv)) {
+        candidates[..=i].rotate_right(1);
+    }
+
+    let original_alpha = alpha;
+    // 没有候选点说明棋盘已满，和棋
+    let mut best = (0, None);
+    for (i, &(mv, heuristic)) in candidates.iter().enumerate() {
+        let score = score_move(board, ai, stone, (mv, heuristic), depth, (alpha, beta), search);
+        if i == 0 || score > best.0 {
+            best = (score, Some(mv));
+        }
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    search.moves[depth as usize] = candidates;
+
+    let bound = if best.0 <= original_alpha {
+        Bound::Upper
+    } else if best.0 >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    search.table.store(Entry { key, depth, score: best.0, bound, best_move: best.1 });
+    best
+}
+
+/// stone 方在 mv 落子之后的得分，depth 为包括这一步在内的搜索层数，(alpha, beta) 为搜索窗口
+fn score_move<E: Evaluator>(
+    board: &mut Board,
+    ai: &Ai<E>,
+    stone: Stone,
+    (mv, heuristic): (Coord, i32),
+    depth: u32,
+    (alpha, beta): (i32, i32),
+    search: &mut Search,
+) -> i32 {
+    search.nodes += 1;
+    board.set(mv, stone);
+    let score = if ai.rules.is_win(board, mv) {
+        // 越早获胜得分越高
+        WIN_SCORE + depth as i32
+    } else if depth <= 1 {
+        heuristic
+    } else {
+        -negamax(board, ai, stone.opponent(), depth - 1, (-beta, -alpha), search).0
+    };
+    board.set(mv, Stone::Empty);
+    score
+}
+
+/// coord 附近是否已有棋子
+fn has_neighbor(board: &Board, coord: Coord) -> bool {
+    (-NEIGHBOR_RANGE..=NEIGHBOR_RANGE).any(|dc| {
+        (-NEIGHBOR_RANGE..=NEIGHBOR_RANGE)
+            .any(|dr| coord.offset(dc, dr).is_some_and(|c| !board.is_empty_at(c)))
+    })
+}
+
+/// 评估 stone 方在 coord 落子的价值，同时考虑进攻和防守，length 为获胜需要的连子数
+fn evaluate_position(board: &Board, coord: Coord, stone: Stone, length: usize, weights: Weights) -> i32 {
+    // 自己在各个方向的得分（进攻）和对手的得分（防守）
+    let (attack, defense) = pattern::point_scores(board, coord, stone, length);
+    let mut score = attack * weights.attack + defense * weights.defense;
+
+    // 中心位置加分
+    let center_distance = (coord.col.abs_diff(7) + coord.row.abs_diff(7)) as i32;
+    score += (14 - center_distance) * 2;
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    // 统计每个线程分配内存的次数，测试并行运行时互不影响
+    thread_local! {
+        static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    }
+
+    struct CountingAllocator;
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> u64 {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    #[test]
+    fn opens_in_the_center() {
+        for difficulty in Difficulty::ALL {
+            let ai = Ai::new(Stone::Black, difficulty);
+            assert_eq!(ai.best_move(&Board::new()).best_move, Coord::new(7, 7));
+        }
+    }
+
+    #[test]
+    fn first_move_follows_the_policy() {
+        let empty = Board::new();
+        let ai = Ai::new(Stone::Black, Difficulty::Hard);
+        assert!((0..30).all(|pick| ai.opening_move(&empty, None, pick).unwrap().best_move == Coord::CENTER));
+
+        let near = ai.with_first_move(FirstMove::NearCenter);
+        let best = near.best_move(&empty);
+        let mut moves: Vec<Coord> = (0..25).map(|pick| near.vary_opening(&empty, 0, best, pick).best_move).collect();
+        moves.sort_by_key(|c| (c.col, c.row));
+        moves.dedup();
+        assert_eq!(moves.len(), 25);
+        assert!(moves.iter().all(|c| c.col.abs_diff(7) <= 2 && c.row.abs_diff(7) <= 2));
+
+        let book = OpeningBook::parse("H8 H7\nG7 H8\n").unwrap();
+        let from_book = ai.with_first_move(FirstMove::Book);
+        assert_eq!(from_book.opening_move(&empty, Some(&book), 1).unwrap().best_move, Coord::new(6, 6));
+        assert_eq!(from_book.opening_move(&empty, None, 1).unwrap().best_move, Coord::CENTER);
+
+        // 棋盘不空时交给搜索
+        let mut board = empty;
+        board.set(Coord::CENTER, Stone::Black);
+        assert_eq!(near.opening_move(&board, None, 3), None);
+    }
+
+    #[test]
+    fn varies_openings_only_at_lower_difficulties() {
+        let mut board = Board::new();
+        board.set(Coord::new(7, 7), Stone::Black);
+        let replies = |difficulty: Difficulty, ply: usize| {
+            let ai = Ai::new(Stone::White, difficulty);
+            let best = ai.best_move(&board);
+            let mut moves: Vec<Coord> = (0..12).map(|pick| ai.vary_opening(&board, ply, best, pick).best_move).collect();
+            moves.sort_by_key(|c| (c.col, c.row));
+            moves.dedup();
+            moves
+        };
+        assert!(replies(Difficulty::Easy, 1).len() > replies(Difficulty::Medium, 1).len());
+        assert!(replies(Difficulty::Medium, 1).len() > 1);
+        assert_eq!(replies(Difficulty::Hard, 1).len(), 1);
+        // 过了开局就总是下最好的一步
+        assert_eq!(replies(Difficulty::Easy, 10).len(), 1);
+
+        // 随机化不会放过杀棋
+        let win = Analysis { best_move: Coord::new(0, 0), score: WIN_SCORE };
+        let other = Analysis { best_move: Coord::new(1, 1), score: WIN_SCORE - 1 };
+        let variety = Difficulty::Easy.opening_variety().unwrap();
+        assert!((0..4).all(|pick| variety.choose(&[win, other], pick) == Some(win)));
+        assert_eq!(variety.choose(&[], 0), None);
+    }
+
+    #[test]
+    fn deeper_searches_visit_more_nodes_and_reuse_transpositions() {
+        let mut board = Board::new();
+        board.set(Coord::new(7, 7), Stone::Black);
+        board.set(Coord::new(8, 8), Stone::White);
+        let mut previous = 0;
+        for difficulty in Difficulty::ALL {
+            let ai = Ai::new(Stone::Black, difficulty);
+            let (analysis, stats) = ai.best_move_with_stats(&board);
+            assert_eq!(analysis, ai.best_move(&board));
+            assert!(stats.nodes > previous, "{:?} searched {} nodes", difficulty, stats.nodes);
+            previous = stats.nodes;
+            // 四层搜索中换个顺序就会走到同一局面
+            if difficulty == Difficulty::Hard {
+                assert!(stats.tt_hits > 0 && stats.hit_permille() < 1000, "{:?}", stats);
+            }
+        }
+    }
+
+    #[test]
+    fn self_play_is_reproducible() {
+        // 固定的对局结果，在所有平台和编译选项下都必须相同；有意修改评估或搜索时更新这里的值
+        let mut board = Board::new();
+        let mut total = SearchStats::default();
+        let mut scores = Vec::new();
+        for ply in 0..16 {
+            let ai = Ai::new(Stone::for_ply(ply), Difficulty::Hard);
+            let (analysis, stats) = ai.best_move_with_stats(&board);
+            board.set(analysis.best_move, ai.stone());
+            total += stats;
+            scores.push(analysis.score);
+        }
+        assert_eq!((board.hash(), total.nodes, total.hit_permille()), (2441476006506283326, 63531, 86));
+        let expected = [
+            -286, -306, -920, -924, -1024, -1120, -1114, -1112, -1024, -1130, -1084, -1122, -1034, -1058, -1122, -1104,
+        ];
+        assert_eq!(scores, expected);
+    }
+
+    #[test]
+    fn completes_its_own_five() {
+        let mut board = Board::new();
+        for x in 3..7 {
+            board.set(Coord::new(x, 5), Stone::White);
+        }
+        board.set(Coord::new(2, 5), Stone::Black);
+        for difficulty in Difficulty::ALL {
+            let ai = Ai::new(Stone::White, difficulty);
+            assert_eq!(ai.best_move(&board).best_move, Coord::new(7, 5));
+        }
+    }
+
+    #[test]
+    fn blocks_an_opponent_four() {
+        let mut board = Board::new();
+        for y in 4..8 {
+            board.set(Coord::new(10, y), Stone::Black);
+        }
+        board.set(Coord::new(10, 3), Stone::White);
+        for difficulty in Difficulty::ALL {
+            let ai = Ai::new(Stone::White, difficulty);
+            assert_eq!(ai.best_move(&board).best_move, Coord::new(10, 8));
+        }
+    }
+
+    #[test]
+    fn deeper_search_stops_an_open_three() {
+        // 黑子有活三，白子若不挡，黑子下一步就能形成活四
+        let mut board = Board::new();
+        for x in 6..9 {
+            board.set(Coord::new(x, 7), Stone::Black);
+        }
+        board.set(Coord::new(7, 8), Stone::White);
+        board.set(Coord::new(8, 8), Stone::White);
+        let ai = Ai::new(Stone::White, Difficulty::Hard);
+        let best = ai.best_move(&board).best_move;
+        assert!(best == Coord::new(5, 7) || best == Coord::new(9, 7), "unexpected move {:?}", best);
+    }
+
+    #[test]
+    fn finds_a_win_two_moves_ahead() {
+        // 白子有活三，下一步形成活四后必胜
+        let mut board = Board::new();
+        for y in 5..8 {
+            board.set(Coord::new(3, y), Stone::White);
+        }
+        board.set(Coord::new(10, 10), Stone::Black);
+        board.set(Coord::new(11, 10), Stone::Black);
+        let ai = Ai::new(Stone::White, Difficulty::Hard);
+        let analysis = ai.best_move(&board);
+        assert!(analysis.best_move == Coord::new(3, 4) || analysis.best_move == Coord::new(3, 8));
+        assert!(analysis.score >= WIN_SCORE);
+    }
+
+    #[test]
+    fn top_moves_are_sorted_and_agree_with_the_search() {
+        let mut board = Board::new();
+        for y in 5..8 {
+            board.set(Coord::new(3, y), Stone::White);
+        }
+        board.set(Coord::new(10, 10), Stone::Black);
+        board.set(Coord::new(11, 10), Stone::Black);
+        let ai = Ai::new(Stone::White, Difficulty::Medium);
+        let top = ai.top_moves(&board, 3);
+        assert_eq!(top.len(), 3);
+        assert!(top.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert_eq!(top[0], ai.best_move(&board));
+    }
+
+    #[test]
+    fn plays_for_the_configured_win_length() {
+        // 四子获胜时，白子 (4,5) 已经可以成四
+        let mut board = Board::new();
+        for x in [5, 6, 7] {
+            board.set(Coord::new(x, 5), Stone::White);
+        }
+        board.set(Coord::new(8, 5), Stone::Black);
+        board.set(Coord::new(9, 9), Stone::Black);
+        let rules = Rules::new(RuleSet::Freestyle, 4);
+        for difficulty in Difficulty::ALL {
+            let analysis = Ai::new(Stone::White, difficulty).with_rules(rules).best_move(&board);
+            assert_eq!(analysis.best_move, Coord::new(4, 5));
+            assert!(analysis.score >= WIN_SCORE);
+        }
+    }
+
+    #[test]
+    fn weights_change_the_preferred_move() {
+        // 白子可以延长自己的三，也可以挡住黑子的三
+        let mut board = Board::new();
+        for x in 2..5 {
+            board.set(Coord::new(x, 2), Stone::White);
+        }
+        for x in 8..11 {
+            board.set(Coord::new(x, 10), Stone::Black);
+        }
+        let attacker = Ai::new(Stone::White, Difficulty::Easy).with_weights(Weights { attack: 10, defense: 1 });
+        let defender = Ai::new(Stone::White, Difficulty::Easy).with_weights(Weights { attack: 1, defense: 10 });
+        assert_eq!(attacker.best_move(&board).best_move.row, 2);
+        assert_eq!(defender.best_move(&board).best_move.row, 10);
+    }
+
+    #[test]
+    fn custom_evaluators_drive_the_search() {
+        // 只喜欢左上角的评估函数，记录每批评估了多少个点
+        struct Corner(std::cell::Cell<usize>);
+        impl Evaluator for Corner {
+            fn evaluate_batch(&self, _: &Board, _: Stone, moves: &[Coord], _: Rules, scores: &mut [i32]) {
+                self.0.set(self.0.get().max(moves.len()));
+                for (score, c) in scores.iter_mut().zip(moves) {
+                    *score = -((c.col + c.row) as i32);
+                }
+            }
+        }
+        let mut board = Board::new();
+        board.set(Coord::CENTER, Stone::Black);
+        let ai = Ai::new(Stone::White, Difficulty::Easy).with_evaluator(Corner(Default::default()));
+        assert_eq!(ai.best_move(&board).best_move, Coord::new(5, 5));
+        // 中心周围两格以内的 24 个空位在一批里评估
+        assert_eq!(ai.evaluator.0.get(), 24);
+
+        let classical = Ai::new(Stone::White, Difficulty::Easy);
+        let mut batch = [0];
+        Classical::default().evaluate_batch(&board, Stone::White, &[Coord::new(6, 6)], Rules::default(), &mut batch);
+        assert_eq!(batch, [classical.evaluate_position(&board, Coord::new(6, 6))]);
+    }
+
+    #[test]
+    fn renju_black_avoids_forbidden_moves() {
+        // 黑子在 (7,7) 会连成长连，自由规则下这是唯一的胜着
+        let mut board = Board::new();
+        for c in [(3, 7), (4, 7), (5, 7), (6, 7), (8, 7)] {
+            board.set(c.into(), Stone::Black);
+        }
+        board.set(Coord::new(2, 7), Stone::White);
+        let free = Ai::new(Stone::Black, Difficulty::Easy).best_move(&board);
+        assert_eq!(free.best_move, Coord::CENTER);
+        assert!(free.score >= WIN_SCORE);
+        for difficulty in Difficulty::ALL {
+            let renju = Ai::new(Stone::Black, difficulty).with_rules(Rules::new(RuleSet::Renju, 5));
+            assert_ne!(renju.best_move(&board).best_move, Coord::CENTER);
+        }
+    }
+
+    #[test]
+    fn search_only_allocates_its_buffers() {
+        // 黑方在连珠规则下还要逐个检查禁手
+        let mut board = Board::new();
+        let moves = [(7, 7), (8, 8), (6, 8), (8, 6), (9, 9), (6, 6)];
+        for (i, &c) in moves.iter().enumerate() {
+            board.set(c.into(), Stone::for_ply(i));
+        }
+        for rules in [Rules::default(), Rules::new(RuleSet::Renju, 5)] {
+            let ai = Ai::new(Stone::Black, Difficulty::Hard).with_rules(rules);
+            let before = allocations();
+            let (_, stats) = ai.best_move_with_stats(&board);
+            let count = allocations() - before;
+            // 置换表、候选点的两个缓冲区和 4 层搜索的 5 个缓冲区（外加存放它们的 Vec）各分配一次，
+            // 与搜索的局面数无关
+            assert!(stats.nodes > 1000, "{:?}", stats);
+            assert!(count <= 9, "{} allocations for {} nodes under {}", count, stats.nodes, rules);
+        }
+    }
+}
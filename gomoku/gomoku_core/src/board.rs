@@ -0,0 +1,229 @@
+use crate::coord::Coord;
+use crate::stone::Stone;
+use crate::zobrist;
+
+/// 棋盘边长
+pub const BOARD_SIZE: usize = 15;
+
+// 水平、垂直、两条对角线，以 (dcol, drow) 表示
+pub(crate) const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+// 每个方向的线数，按对角线算，横线和竖线只用前 BOARD_SIZE 条
+const LINE_COUNT: usize = 2 * BOARD_SIZE - 1;
+
+/// 同一直线上一段连续的交叉点，两端都包含在内，如获胜的五子
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub start: Coord,
+    pub end: Coord,
+}
+
+impl Segment {
+    /// 从 start 到 end 依次经过的交叉点
+    pub fn coords(self) -> impl Iterator<Item = Coord> {
+        let step = |from: usize, to: usize| (to as i32 - from as i32).signum();
+        let (dc, dr) = (step(self.start.col, self.end.col), step(self.start.row, self.end.row));
+        let len = self.start.col.abs_diff(self.end.col).max(self.start.row.abs_diff(self.end.row)) as i32 + 1;
+        (0..len).filter_map(move |i| self.start.offset(dc * i, dr * i))
+    }
+}
+
+/// 15 * 15 的棋盘，按 `cells[col][row]` 存储
+///
+/// 另外按四个方向的每条线保存黑白棋子的位掩码，棋型扫描用位运算代替逐格走查，见 `pattern` 模块。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Board {
+    cells: [[Stone; BOARD_SIZE]; BOARD_SIZE],
+    // 所有棋子的 Zobrist 哈希，每次 set 时增量更新
+    hash: u64,
+    // lines[颜色][方向][线]：线上第 i 个交叉点对应第 i + 1 位，第 0 位和线外的位始终为 0
+    lines: [[[u16; LINE_COUNT]; 4]; 2],
+}
+
+impl Board {
+    /// 创建空棋盘
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 测试用：按 (col, row) 摆好黑子和白子的棋盘
+    #[cfg(test)]
+    pub(crate) fn with_stones(black: &[(usize, usize)], white: &[(usize, usize)]) -> Self {
+        let mut board = Self::new();
+        for &c in black {
+            board.set(c.into(), Stone::Black);
+        }
+        for &c in white {
+            board.set(c.into(), Stone::White);
+        }
+        board
+    }
+
+    /// 读取交叉点
+    pub fn get(&self, coord: Coord) -> Stone {
+        self.cells[coord.col][coord.row]
+    }
+
+    /// 设置交叉点
+    pub fn set(&mut self, coord: Coord, stone: Stone) {
+        let cell = &mut self.cells[coord.col][coord.row];
+        self.hash ^= zobrist::key(coord, *cell) ^ zobrist::key(coord, stone);
+        let old = *cell;
+        *cell = stone;
+        for (d, slot) in LINE_SLOTS[coord.col][coord.row].iter().enumerate() {
+            let (line, bit) = (slot.line as usize, 1 << slot.pos);
+            if old != Stone::Empty {
+                self.lines[old as usize - 1][d][line] &= !bit;
+            }
+            if stone != Stone::Empty {
+                self.lines[stone as usize - 1][d][line] |= bit;
+            }
+        }
+    }
+
+    /// slot 所在的第 d 个方向的线上黑子和白子的位掩码，位的含义见 `LineSlot`
+    pub(crate) fn line_masks(&self, slot: LineSlot, d: usize) -> [u16; 2] {
+        [self.lines[0][d][slot.line as usize], self.lines[1][d][slot.line as usize]]
+    }
+
+    /// 局面的 Zobrist 哈希，不区分轮到哪一方，见 `zobrist` 模块
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// 交叉点是否为空
+    pub fn is_empty_at(&self, coord: Coord) -> bool {
+        self.get(coord) == Stone::Empty
+    }
+
+    /// 棋盘是否已经下满
+    pub fn is_full(&self) -> bool {
+        self.cells.iter().flatten().all(|&stone| stone != Stone::Empty)
+    }
+
+    /// coord 上的棋子连成 length 子或以上时，返回整段连子
+    pub fn winning_line(&self, coord: Coord, length: usize) -> Option<Segment> {
+        let current = self.get(coord);
+        if current == Stone::Empty {
+            return None;
+        }
+        // 每个方向先往一边数，再往另一边数，累加，检查是否大于等于 length
+        DIRECTIONS.iter().find_map(|&(dc, dr)| {
+            let forward = self.count_from(coord, dc, dr, current);
+            let back = self.count_from(coord, -dc, -dr, current);
+            if 1 + forward + back < length {
+                return None;
+            }
+            let (forward, back) = (forward as i32, back as i32);
+            Some(Segment {
+                start: coord.offset(-dc * back, -dr * back)?,
+                end: coord.offset(dc * forward, dr * forward)?,
+            })
+        })
+    }
+
+    /// 从 coord 沿 (dc, dr) 方向数连续的 stone 个数，不包含 coord 本身
+    fn count_from(&self, coord: Coord, dc: i32, dr: i32, stone: Stone) -> usize {
+        (1..BOARD_SIZE as i32)
+            .take_while(|&i| coord.offset(dc * i, dr * i).is_some_and(|c| self.get(c) == stone))
+            .count()
+    }
+}
+
+/// 交叉点在某个方向上所在的线
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LineSlot {
+    // 线的编号
+    pub(crate) line: u8,
+    // 交叉点在线上的位置，从 1 开始，沿方向的 dc 递增（竖线沿 dr 递增）
+    pub(crate) pos: u8,
+    // 不在线上的位：第 0 位和线长以外的位
+    pub(crate) off_board: u32,
+}
+
+// 每个交叉点在四个方向上所在的线，按 [col][row][方向] 存放，编译时算好
+static LINE_SLOTS: [[[LineSlot; 4]; BOARD_SIZE]; BOARD_SIZE] = line_slots();
+
+/// coord 所在的第 d 个方向（`DIRECTIONS` 的下标）的线
+pub(crate) fn line_slot(coord: Coord, d: usize) -> LineSlot {
+    LINE_SLOTS[coord.col][coord.row][d]
+}
+
+const fn line_slots() -> [[[LineSlot; 4]; BOARD_SIZE]; BOARD_SIZE] {
+    let last = BOARD_SIZE - 1;
+    let mut slots = [[[LineSlot { line: 0, pos: 0, off_board: 0 }; 4]; BOARD_SIZE]; BOARD_SIZE];
+    let mut col = 0;
+    while col < BOARD_SIZE {
+        let mut row = 0;
+        while row < BOARD_SIZE {
+            let shapes = [
+                (row, col, BOARD_SIZE),
+                (col, row, BOARD_SIZE),
+                (col + last - row, if col < row { col } else { row }, BOARD_SIZE - col.abs_diff(row)),
+                (col + row, col - (col + row).saturating_sub(last), BOARD_SIZE - (col + row).abs_diff(last)),
+            ];
+            let mut d = 0;
+            while d < 4 {
+                let (line, pos, length) = shapes[d];
+                slots[col][row][d] = LineSlot { line: line as u8, pos: pos as u8 + 1, off_board: !(((1 << length) - 1) << 1) };
+                d += 1;
+            }
+            row += 1;
+        }
+        col += 1;
+    }
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_five_in_every_direction() {
+        let lines: [&[(usize, usize)]; 4] = [
+            &[(3, 7), (4, 7), (5, 7), (6, 7), (7, 7)],
+            &[(7, 3), (7, 4), (7, 5), (7, 6), (7, 7)],
+            &[(3, 3), (4, 4), (5, 5), (6, 6), (7, 7)],
+            &[(3, 11), (4, 10), (5, 9), (6, 8), (7, 7)],
+        ];
+        for line in lines {
+            let board = Board::with_stones(line, &[]);
+            for &c in line {
+                assert!(board.winning_line(c.into(), 5).is_some(), "{:?} at {:?}", line, c);
+            }
+        }
+    }
+
+    #[test]
+    fn four_is_not_a_win() {
+        let board = Board::with_stones(&[], &[(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert!(board.winning_line(Coord::new(3, 0), 5).is_none());
+    }
+
+    #[test]
+    fn detects_five_on_the_edge() {
+        let board = Board::with_stones(&[], &[(14, 10), (14, 11), (14, 12), (14, 13), (14, 14)]);
+        assert_eq!(
+            board.winning_line(Coord::new(14, 12), 5),
+            Some(Segment { start: Coord::new(14, 10), end: Coord::new(14, 14) })
+        );
+        assert!(board.winning_line(Coord::new(0, 0), 5).is_none());
+    }
+
+    #[test]
+    fn mixed_colors_break_the_line() {
+        let mut board = Board::with_stones(&[(3, 7), (4, 7), (6, 7), (7, 7)], &[]);
+        board.set(Coord::new(5, 7), Stone::White);
+        assert!(board.winning_line(Coord::CENTER, 5).is_none());
+    }
+
+    #[test]
+    fn winning_line_covers_the_whole_run() {
+        let line = [(3, 11), (4, 10), (5, 9), (6, 8), (7, 7), (8, 6)];
+        let board = Board::with_stones(&line, &[]);
+        let segment = board.winning_line(Coord::new(5, 9), 5).unwrap();
+        assert_eq!(segment, Segment { start: Coord::new(3, 11), end: Coord::new(8, 6) });
+        assert!(segment.coords().eq(line.iter().map(|&c| Coord::from(c))));
+    }
+}
@@ -0,0 +1,85 @@
+//! 局面校验码：联机对局的双方、报告问题的玩家和开发者核对彼此看到的是不是同一个局面。
+//!
+//! 校验码由规则、轮到的一方和每个交叉点上的棋子算出，显示为 8 位十六进制数，如 `3F2A09C1`。
+//! 与 Zobrist 哈希不同，这里的算法（32 位 FNV-1a）固定不变，不同版本、不同平台算出的结果相同。
+//! 落子顺序不同但最终局面相同时校验码也相同。
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::rules::{RuleSet, Rules};
+use crate::stone::Stone;
+
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// 局面校验码
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Checksum(pub u32);
+
+impl Checksum {
+    /// 按 rules 下棋、轮到 to_move 时 board 的校验码
+    pub fn of(board: &Board, to_move: Stone, rules: Rules) -> Self {
+        let set = match rules.set() {
+            RuleSet::Freestyle => 0,
+            RuleSet::Renju => 1,
+        };
+        let header = [set, rules.win_length() as u8, to_move as u8];
+        let cells = Coord::all().map(|c| board.get(c) as u8);
+        let hash = header.into_iter().chain(cells).fold(FNV_OFFSET, |hash, byte| (hash ^ byte as u32).wrapping_mul(FNV_PRIME));
+        Self(hash)
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}", self.0)
+    }
+}
+
+impl FromStr for Checksum {
+    type Err = std::num::ParseIntError;
+
+    /// 解析 8 位以内的十六进制数，不区分大小写
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        u32::from_str_radix(text, 16).map(Checksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn depends_on_the_position_not_the_move_order() {
+        let mut a = GameState::new();
+        let mut b = GameState::new();
+        for mv in [(7, 7), (8, 8), (6, 7), (9, 9)] {
+            a.play(mv.into()).unwrap();
+        }
+        for mv in [(6, 7), (9, 9), (7, 7), (8, 8)] {
+            b.play(mv.into()).unwrap();
+        }
+        assert_eq!(a.checksum(), b.checksum());
+        // 同样的棋子，轮到的一方或者规则不同时校验码不同
+        let board = *a.board();
+        let freestyle = Checksum::of(&board, Stone::Black, Rules::default());
+        assert_eq!(a.checksum(), freestyle);
+        assert_ne!(Checksum::of(&board, Stone::White, Rules::default()), freestyle);
+        assert_ne!(Checksum::of(&board, Stone::Black, Rules::new(RuleSet::Renju, 5)), freestyle);
+        assert_ne!(Checksum::of(&Board::new(), Stone::Black, Rules::default()), freestyle);
+    }
+
+    #[test]
+    fn is_stable_and_round_trips_as_hex() {
+        // 算法固定不变，旧版本的问题报告中的校验码也能核对
+        let empty = Checksum::of(&Board::new(), Stone::Black, Rules::default());
+        assert_eq!(empty.to_string(), "E3801B87");
+        assert_eq!(empty.to_string().parse(), Ok(empty));
+        assert_eq!("3f2a09c1".parse(), Ok(Checksum(0x3f2a_09c1)));
+        assert!("xyz".parse::<Checksum>().is_err());
+    }
+}
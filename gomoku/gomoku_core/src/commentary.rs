@@ -0,0 +1,114 @@
+//! 对局结束后 AI 的简短感想，如“我从第 12 手开始觉得占优”。
+//!
+//! AI 每次轮到自己时记录一次对局面的判断，结束后从这些记录里找出优势确立的时刻
+//! 和判断变化最大的一步。
+
+use crate::ai::{Ai, Analysis};
+use crate::board::Board;
+use crate::game::Outcome;
+use crate::stone::Stone;
+
+// 优势超过这个值时认为一方占优
+const AHEAD_MARGIN: i32 = 100;
+
+// 对手一步之后优势下降超过这个值时认为出乎意料
+const SURPRISE_MARGIN: i32 = 500;
+
+// 比较前后两次判断时的上限，避免强制胜负的分数淹没普通局面的变化
+const SCORE_CAP: i32 = 10_000;
+
+/// AI 轮到自己时对局面的判断
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Evaluation {
+    // AI 这一步是第几手（从 1 开始）
+    pub move_number: usize,
+    // AI 一方的优势，正数表示 AI 占优
+    pub score: i32,
+}
+
+impl Evaluation {
+    /// 根据 AI 在 board 上搜索的结果记录判断，搜索到强制胜负时以搜索为准，
+    /// 否则比较双方最好的进攻点，这样不同难度的记录可以放在一起比较
+    pub fn new(move_number: usize, board: &Board, ai: &Ai, analysis: Analysis) -> Self {
+        let score = if analysis.is_win() || analysis.is_loss() { analysis.score } else { ai.advantage(board) };
+        Self { move_number, score }
+    }
+
+    fn capped(&self) -> i32 {
+        self.score.clamp(-SCORE_CAP, SCORE_CAP)
+    }
+}
+
+/// 执 ai 一方的 AI 对这局棋的感想，每条一句
+pub fn summarize(history: &[Evaluation], ai: Stone, outcome: Outcome) -> Vec<String> {
+    let mut lines = Vec::new();
+    match outcome.winner() {
+        Some(winner) if winner == ai => {
+            if let Some(n) = streak_start(history, |score| score >= AHEAD_MARGIN) {
+                lines.push(if n == history[0].move_number {
+                    "I felt ahead from the very start.".to_string()
+                } else {
+                    format!("I felt ahead from move {}.", n)
+                });
+            }
+        }
+        Some(_) => {
+            if let Some(n) = streak_start(history, |score| score <= -AHEAD_MARGIN) {
+                lines.push(format!("I knew I was in trouble from move {}.", n));
+            }
+        }
+        None => lines.push("Neither of us could find a way through.".to_string()),
+    }
+
+    // 两次判断之间只有玩家的一步，这一步让 AI 的优势下降最多；悔棋之后重做的几步没有记录，跳过
+    let surprise = history
+        .windows(2)
+        .filter(|pair| pair[1].move_number == pair[0].move_number + 2)
+        .map(|pair| (pair[0].move_number + 1, pair[0].capped() - pair[1].capped()))
+        .max_by_key(|&(_, drop)| drop);
+    if let Some((n, _)) = surprise.filter(|&(_, drop)| drop >= SURPRISE_MARGIN) {
+        lines.push(format!("Your move {} surprised me.", n));
+    }
+
+    if lines.is_empty() {
+        lines.push("That was a close game.".to_string());
+    }
+    lines
+}
+
+/// 结尾连续满足 pred 的那段判断从第几手开始
+fn streak_start(history: &[Evaluation], pred: impl Fn(i32) -> bool) -> Option<usize> {
+    let len = history.iter().rev().take_while(|e| pred(e.score)).count();
+    (len > 0).then(|| history[history.len() - len].move_number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(scores: &[i32]) -> Vec<Evaluation> {
+        // AI 执白，轮到它的是第 2、4、6……手
+        scores.iter().enumerate().map(|(i, &score)| Evaluation { move_number: 2 * i + 2, score }).collect()
+    }
+
+    #[test]
+    fn winner_remembers_when_it_took_the_lead() {
+        let lines = summarize(&history(&[0, -20, 150, 400, 1_000_002]), Stone::White, Outcome::Win(Stone::White));
+        assert_eq!(lines, ["I felt ahead from move 6."]);
+
+        let lines = summarize(&history(&[200, 300]), Stone::White, Outcome::Resignation(Stone::White));
+        assert_eq!(lines, ["I felt ahead from the very start."]);
+    }
+
+    #[test]
+    fn loser_points_out_the_surprising_move() {
+        let lines = summarize(&history(&[200, 250, -900, -1200]), Stone::White, Outcome::Win(Stone::Black));
+        assert_eq!(lines, ["I knew I was in trouble from move 6.", "Your move 5 surprised me."]);
+    }
+
+    #[test]
+    fn even_games_get_a_generic_comment() {
+        assert_eq!(summarize(&history(&[10, -10, 20]), Stone::White, Outcome::Draw).len(), 1);
+        assert_eq!(summarize(&[], Stone::Black, Outcome::Timeout(Stone::White)), ["That was a close game."]);
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::board::BOARD_SIZE;
+
+/// 记谱时的行号原点：左上角（默认）或左下角（围棋习惯）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoordOrigin {
+    #[default]
+    TopLeft,
+    BottomLeft,
+}
+
+impl CoordOrigin {
+    /// 所有可选的坐标原点
+    pub const ALL: [CoordOrigin; 2] = [CoordOrigin::TopLeft, CoordOrigin::BottomLeft];
+
+    /// 名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            CoordOrigin::TopLeft => "Top-left",
+            CoordOrigin::BottomLeft => "Bottom-left",
+        }
+    }
+
+    /// 第 row 行（从上往下数，从 0 开始）对应的行号
+    pub fn row_number(&self, row: usize) -> usize {
+        match self {
+            CoordOrigin::TopLeft => row + 1,
+            CoordOrigin::BottomLeft => BOARD_SIZE - row,
+        }
+    }
+}
+
+/// 棋盘上的一个交叉点
+///
+/// `col` 为列，从左往右；`row` 为行，从上往下，都从 0 开始。
+/// 界面绘制和规则判断都使用这一种约定，不再区分 x/y。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Coord {
+    pub col: usize,
+    pub row: usize,
+}
+
+impl Coord {
+    /// 天元
+    pub const CENTER: Coord = Coord::new(7, 7);
+
+    pub const fn new(col: usize, row: usize) -> Self {
+        Self { col, row }
+    }
+
+    /// 是否在棋盘内
+    pub fn is_valid(self) -> bool {
+        self.col < BOARD_SIZE && self.row < BOARD_SIZE
+    }
+
+    /// 沿 (dcol, drow) 移动后的交叉点，出界时返回 None
+    pub fn offset(self, dcol: i32, drow: i32) -> Option<Coord> {
+        let col = usize::try_from(self.col as i32 + dcol).ok()?;
+        let row = usize::try_from(self.row as i32 + drow).ok()?;
+        Some(Coord::new(col, row)).filter(|c| c.is_valid())
+    }
+
+    /// 按列优先遍历棋盘上的所有交叉点
+    pub fn all() -> impl Iterator<Item = Coord> {
+        (0..BOARD_SIZE).flat_map(|col| (0..BOARD_SIZE).map(move |row| Coord::new(col, row)))
+    }
+
+    /// 棋谱记法，例如左上角原点下 (7, 7) -> "H8"
+    ///
+    /// 列用字母 A–O 从左往右表示，行号的起点由 `origin` 决定。
+    pub fn notation(self, origin: CoordOrigin) -> String {
+        format!("{}{}", (b'A' + self.col as u8) as char, origin.row_number(self.row))
+    }
+
+    /// 解析棋谱记法，字母不区分大小写
+    pub fn parse(text: &str, origin: CoordOrigin) -> Option<Coord> {
+        let mut chars = text.trim().chars();
+        let letter = chars.next()?.to_ascii_uppercase();
+        if !letter.is_ascii_uppercase() {
+            return None;
+        }
+        let col = (letter as u8 - b'A') as usize;
+        let number: usize = chars.as_str().parse().ok()?;
+        if number == 0 || number > BOARD_SIZE {
+            return None;
+        }
+        let row = match origin {
+            CoordOrigin::TopLeft => number - 1,
+            CoordOrigin::BottomLeft => BOARD_SIZE - number,
+        };
+        Some(Coord::new(col, row)).filter(|c| c.is_valid())
+    }
+}
+
+/// 以默认的左上角原点显示，例如 "H8"
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.notation(CoordOrigin::default()))
+    }
+}
+
+impl From<(usize, usize)> for Coord {
+    fn from((col, row): (usize, usize)) -> Self {
+        Coord::new(col, row)
+    }
+}
+
+impl From<Coord> for (usize, usize) {
+    fn from(coord: Coord) -> Self {
+        (coord.col, coord.row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn displays_standard_notation() {
+        assert_eq!(Coord::CENTER.to_string(), "H8");
+        assert_eq!(Coord::new(0, 0).notation(CoordOrigin::TopLeft), "A1");
+        assert_eq!(Coord::new(0, 0).notation(CoordOrigin::BottomLeft), "A15");
+        assert_eq!(Coord::new(14, 14).notation(CoordOrigin::BottomLeft), "O1");
+    }
+
+    #[test]
+    fn parses_what_it_displays() {
+        for origin in CoordOrigin::ALL {
+            for coord in Coord::all() {
+                assert_eq!(Coord::parse(&coord.notation(origin), origin), Some(coord));
+            }
+        }
+        assert_eq!(Coord::parse("h8", CoordOrigin::TopLeft), Some(Coord::CENTER));
+        assert_eq!(Coord::parse("P1", CoordOrigin::TopLeft), None);
+        assert_eq!(Coord::parse("A0", CoordOrigin::TopLeft), None);
+        assert_eq!(Coord::parse("A16", CoordOrigin::TopLeft), None);
+    }
+
+    #[test]
+    fn offset_stays_on_the_board() {
+        assert_eq!(Coord::new(0, 0).offset(-1, 0), None);
+        assert_eq!(Coord::new(14, 3).offset(1, 0), None);
+        assert_eq!(Coord::new(3, 3).offset(1, -1), Some(Coord::new(4, 2)));
+    }
+}
@@ -0,0 +1,97 @@
+//! 对局事件的订阅。
+//!
+//! `GameState` 在落子、悔棋、重做和分出胜负时记录事件，界面每帧取出后交给 `EventBus` 分发。
+//! 状态栏、棋谱面板、音效等部件只订阅自己关心的事件，各自从收到的 `Receiver` 中读取，
+//! 不需要互相知道，也不需要每帧检查整个界面状态。
+
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::coord::Coord;
+use crate::stone::Stone;
+
+/// 对局中发生的变化
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEvent {
+    // 落下一子
+    Played { coord: Coord, stone: Stone },
+    // 悔棋撤销一子
+    Undone { coord: Coord, stone: Stone },
+    // 重做一子
+    Redone { coord: Coord, stone: Stone },
+    // 分出胜负，包括一方认输
+    Won(Stone),
+    // 和棋
+    Drawn,
+}
+
+/// 订阅者：只接收 filter 返回 true 的事件
+struct Subscriber {
+    filter: fn(&GameEvent) -> bool,
+    sender: Sender<GameEvent>,
+}
+
+/// 把事件分发给所有订阅者
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 订阅满足 filter 的事件，丢弃返回的 Receiver 即取消订阅
+    pub fn subscribe(&mut self, filter: fn(&GameEvent) -> bool) -> Receiver<GameEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(Subscriber { filter, sender });
+        receiver
+    }
+
+    /// 分发一个事件，顺便移除已经取消的订阅
+    pub fn publish(&mut self, event: GameEvent) {
+        self.subscribers
+            .retain(|s| !(s.filter)(&event) || s.sender.send(event).is_ok());
+    }
+
+    /// 订阅者数量
+    pub fn len(&self) -> usize {
+        self.subscribers.len()
+    }
+
+    /// 是否没有订阅者
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_only_receive_matching_events() {
+        let mut bus = EventBus::new();
+        let moves = bus.subscribe(|e| matches!(e, GameEvent::Played { .. }));
+        let wins = bus.subscribe(|e| matches!(e, GameEvent::Won(_)));
+
+        let played = GameEvent::Played { coord: Coord::CENTER, stone: Stone::Black };
+        bus.publish(played);
+        bus.publish(GameEvent::Won(Stone::Black));
+
+        assert_eq!(moves.try_iter().collect::<Vec<_>>(), vec![played]);
+        assert_eq!(wins.try_iter().collect::<Vec<_>>(), vec![GameEvent::Won(Stone::Black)]);
+    }
+
+    #[test]
+    fn dropped_receivers_unsubscribe() {
+        let mut bus = EventBus::new();
+        let kept = bus.subscribe(|_| true);
+        drop(bus.subscribe(|_| true));
+        assert_eq!(bus.len(), 2);
+
+        bus.publish(GameEvent::Won(Stone::White));
+        assert_eq!(bus.len(), 1);
+        assert_eq!(kept.try_recv(), Ok(GameEvent::Won(Stone::White)));
+    }
+}
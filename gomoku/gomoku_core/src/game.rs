@@ -0,0 +1,488 @@
+use std::fmt;
+
+use crate::board::{Board, Segment, BOARD_SIZE};
+use crate::checksum::Checksum;
+use crate::coord::Coord;
+use crate::events::GameEvent;
+use crate::renju::Forbidden;
+use crate::rules::Rules;
+use crate::stone::Stone;
+
+/// 落子失败的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfBounds,
+    Occupied,
+    GameOver,
+    Forbidden(Forbidden),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::OutOfBounds => write!(f, "move is outside the board"),
+            MoveError::Occupied => write!(f, "point is already occupied"),
+            MoveError::GameOver => write!(f, "game is already over"),
+            MoveError::Forbidden(kind) => write!(f, "{} is forbidden for Black", kind),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// 对局结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // 连成获胜长度
+    Win(Stone),
+    // 对方认输，附带获胜方
+    Resignation(Stone),
+    // 对方超时，附带获胜方
+    Timeout(Stone),
+    // 棋盘下满，或者双方同意和棋
+    Draw,
+}
+
+impl Outcome {
+    /// 获胜方，和棋时返回 None
+    pub fn winner(&self) -> Option<Stone> {
+        match *self {
+            Outcome::Win(stone) | Outcome::Resignation(stone) | Outcome::Timeout(stone) => Some(stone),
+            Outcome::Draw => None,
+        }
+    }
+}
+
+/// 一局棋的状态：棋盘、落子记录、悔棋记录和结果
+///
+/// 黑子先手，双方轮流落子，所以行棋方完全由已落子数决定。
+/// 唯一的例外是尚未取出的事件，调用方应当在每次操作后用 `take_events` 取走。
+#[derive(Clone, Debug, Default)]
+pub struct GameState {
+    board: Board,
+    moves: Vec<Coord>,
+    // 悔棋撤销的落子，最近撤销的在末尾
+    redo_moves: Vec<Coord>,
+    outcome: Option<Outcome>,
+    rules: Rules,
+    // 上次取出之后发生的事件
+    events: Vec<GameEvent>,
+}
+
+impl GameState {
+    /// 创建新对局
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定规则创建新对局
+    pub fn with_rules(rules: Rules) -> Self {
+        Self { rules, ..Self::default() }
+    }
+
+    /// 对局规则
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// 当前棋盘
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// 落子记录，按顺序保存每一步的坐标
+    pub fn moves(&self) -> &[Coord] {
+        &self.moves
+    }
+
+    /// 最后一步的坐标，还没有落子时返回 None
+    pub fn last_move(&self) -> Option<Coord> {
+        self.moves.last().copied()
+    }
+
+    /// 连成获胜的那一段，认输、超时、和棋或尚未结束时返回 None
+    pub fn winning_line(&self) -> Option<Segment> {
+        match self.outcome {
+            Some(Outcome::Win(_)) => self.rules.winning_line(&self.board, self.last_move()?),
+            _ => None,
+        }
+    }
+
+    /// 轮到哪一方落子
+    pub fn to_move(&self) -> Stone {
+        Stone::for_ply(self.moves.len())
+    }
+
+    /// 当前局面的校验码，用于核对双方看到的是同一个局面
+    pub fn checksum(&self) -> Checksum {
+        Checksum::of(&self.board, self.to_move(), self.rules)
+    }
+
+    /// 对局结果，尚未结束时返回 None
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// 赢家，和棋或尚未结束时返回 None
+    pub fn winner(&self) -> Option<Stone> {
+        self.outcome.and_then(|outcome| outcome.winner())
+    }
+
+    /// 对局是否已经结束（分出胜负或和棋）
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// 对局是否以和棋结束
+    pub fn is_draw(&self) -> bool {
+        self.outcome == Some(Outcome::Draw)
+    }
+
+    /// 对局是否以认输、超时或双方同意和棋结束，这些结果不取决于棋盘，悔棋也不能撤销
+    pub fn is_final(&self) -> bool {
+        match self.outcome {
+            Some(Outcome::Resignation(_) | Outcome::Timeout(_)) => true,
+            Some(Outcome::Draw) => self.empty_cells() > 0,
+            Some(Outcome::Win(_)) | None => false,
+        }
+    }
+
+    /// 棋盘上剩下的空位数，为 0 时对局以和棋结束
+    pub fn empty_cells(&self) -> usize {
+        BOARD_SIZE * BOARD_SIZE - self.moves.len()
+    }
+
+    /// stone 方认输，对局已经结束时什么都不做
+    pub fn resign(&mut self, stone: Stone) {
+        self.finish(Outcome::Resignation(stone.opponent()));
+    }
+
+    /// stone 方用完时间判负，对局已经结束时什么都不做
+    pub fn time_out(&mut self, stone: Stone) {
+        self.finish(Outcome::Timeout(stone.opponent()));
+    }
+
+    /// 双方同意和棋，对局已经结束时什么都不做
+    pub fn agree_draw(&mut self) {
+        self.finish(Outcome::Draw);
+    }
+
+    /// 是否有可以重做的落子
+    pub fn can_redo(&self) -> bool {
+        !self.redo_moves.is_empty()
+    }
+
+    /// 取出上次调用之后发生的事件
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// 为行棋方落子，新的落子会清空重做记录
+    pub fn play(&mut self, coord: Coord) -> Result<(), MoveError> {
+        let stone = self.to_move();
+        self.place(coord)?;
+        self.redo_moves.clear();
+        self.events.push(GameEvent::Played { coord, stone });
+        self.push_outcome_event();
+        Ok(())
+    }
+
+    /// 撤销最后一步，返回被撤销的坐标；对局以认输、超时或同意和棋结束时不能悔棋
+    pub fn undo(&mut self) -> Option<Coord> {
+        if self.is_final() {
+            return None;
+        }
+        let coord = self.moves.pop()?;
+        let stone = self.board.get(coord);
+        self.board.set(coord, Stone::Empty);
+        self.outcome = None;
+        self.redo_moves.push(coord);
+        self.events.push(GameEvent::Undone { coord, stone });
+        Some(coord)
+    }
+
+    /// 重做最近一次撤销的落子，返回重做的坐标
+    pub fn redo(&mut self) -> Option<Coord> {
+        let coord = self.redo_moves.pop()?;
+        let stone = self.to_move();
+        match self.place(coord) {
+            Ok(()) => {
+                self.events.push(GameEvent::Redone { coord, stone });
+                self.push_outcome_event();
+                Some(coord)
+            }
+            Err(_) => {
+                self.redo_moves.clear();
+                None
+            }
+        }
+    }
+
+    /// 根据落子记录还原前 ply 步的棋盘
+    pub fn board_at(&self, ply: usize) -> Board {
+        let mut board = Board::new();
+        for (i, &coord) in self.moves.iter().take(ply).enumerate() {
+            board.set(coord, Stone::for_ply(i));
+        }
+        board
+    }
+
+    fn push_outcome_event(&mut self) {
+        match self.outcome {
+            Some(Outcome::Draw) => self.events.push(GameEvent::Drawn),
+            Some(outcome) => self.events.extend(outcome.winner().map(GameEvent::Won)),
+            None => {}
+        }
+    }
+
+    /// 认输、超时或和棋结束对局
+    fn finish(&mut self, outcome: Outcome) {
+        if self.is_over() {
+            return;
+        }
+        self.outcome = Some(outcome);
+        self.push_outcome_event();
+    }
+
+    /// 落子并判断胜负，最后一个空位也没有连成时判为和棋
+    fn place(&mut self, coord: Coord) -> Result<(), MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        if !coord.is_valid() {
+            return Err(MoveError::OutOfBounds);
+        }
+        if !self.board.is_empty_at(coord) {
+            return Err(MoveError::Occupied);
+        }
+        let stone = self.to_move();
+        if let Some(kind) = self.rules.forbidden(&self.board, coord, stone) {
+            return Err(MoveError::Forbidden(kind));
+        }
+        self.board.set(coord, stone);
+        self.moves.push(coord);
+        if self.rules.is_win(&self.board, coord) {
+            self.outcome = Some(Outcome::Win(stone));
+        } else if self.empty_cells() == 0 {
+            self.outcome = Some(Outcome::Draw);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    #[test]
+    fn players_alternate_starting_with_black() {
+        let mut game = GameState::new();
+        assert_eq!(game.to_move(), Stone::Black);
+        game.play(Coord::new(7, 7)).unwrap();
+        assert_eq!(game.board().get(Coord::new(7, 7)), Stone::Black);
+        assert_eq!(game.to_move(), Stone::White);
+        game.play(Coord::new(8, 8)).unwrap();
+        assert_eq!(game.board().get(Coord::new(8, 8)), Stone::White);
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let mut game = GameState::new();
+        game.play(Coord::new(7, 7)).unwrap();
+        assert_eq!(game.play(Coord::new(7, 7)), Err(MoveError::Occupied));
+        assert_eq!(game.play(Coord::new(15, 0)), Err(MoveError::OutOfBounds));
+        assert_eq!(game.moves().len(), 1);
+    }
+
+    #[test]
+    fn five_in_a_row_ends_the_game() {
+        let mut game = GameState::new();
+        for i in 0..4 {
+            game.play(Coord::new(i, 0)).unwrap();
+            game.play(Coord::new(i, 1)).unwrap();
+        }
+        assert_eq!(game.winning_line(), None);
+        game.play(Coord::new(4, 0)).unwrap();
+        assert_eq!(game.winner(), Some(Stone::Black));
+        assert_eq!(game.last_move(), Some(Coord::new(4, 0)));
+        assert_eq!(game.winning_line(), Some(Segment { start: Coord::new(0, 0), end: Coord::new(4, 0) }));
+        assert_eq!(game.play(Coord::new(10, 10)), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_position() {
+        let mut game = GameState::new();
+        for i in 0..4 {
+            game.play(Coord::new(i, 0)).unwrap();
+            game.play(Coord::new(i, 1)).unwrap();
+        }
+        game.play(Coord::new(4, 0)).unwrap();
+
+        assert_eq!(game.undo(), Some(Coord::new(4, 0)));
+        assert_eq!(game.winner(), None);
+        assert!(game.board().is_empty_at(Coord::new(4, 0)));
+        assert_eq!(game.to_move(), Stone::Black);
+
+        assert_eq!(game.redo(), Some(Coord::new(4, 0)));
+        assert_eq!(game.winner(), Some(Stone::Black));
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let mut game = GameState::new();
+        game.play(Coord::new(7, 7)).unwrap();
+        game.undo();
+        assert!(game.can_redo());
+        game.play(Coord::new(8, 8)).unwrap();
+        assert!(!game.can_redo());
+    }
+
+    #[test]
+    fn renju_restricts_only_black() {
+        let mut game = GameState::with_rules(Rules::new(RuleSet::Renju, 5));
+        // 黑 (5,7) (6,7) (7,5) (7,6)，白在远处应对
+        let moves = [(5, 7), (0, 0), (6, 7), (0, 2), (7, 5), (0, 4), (7, 6), (0, 6)];
+        for (col, row) in moves {
+            game.play(Coord::new(col, row)).unwrap();
+        }
+        assert_eq!(game.play(Coord::CENTER), Err(MoveError::Forbidden(Forbidden::DoubleThree)));
+        assert_eq!(game.to_move(), Stone::Black);
+
+        // 自由规则下同一步是合法的
+        let mut free = GameState::new();
+        for (col, row) in moves {
+            free.play(Coord::new(col, row)).unwrap();
+        }
+        assert_eq!(free.play(Coord::CENTER), Ok(()));
+    }
+
+    #[test]
+    fn renju_overline_wins_only_for_white() {
+        let mut game = GameState::with_rules(Rules::new(RuleSet::Renju, 5));
+        // 白方在第 0 列下成长连，黑方散落在第 14 列
+        for row in [0, 1, 2, 4, 5] {
+            game.play(Coord::new(14, row * 2)).unwrap();
+            game.play(Coord::new(0, row)).unwrap();
+        }
+        game.play(Coord::new(14, 13)).unwrap();
+        game.play(Coord::new(0, 3)).unwrap();
+        assert_eq!(game.winner(), Some(Stone::White));
+    }
+
+    #[test]
+    fn four_in_a_row_wins_with_win_length_four() {
+        let mut game = GameState::with_rules(Rules::new(RuleSet::Freestyle, 4));
+        for i in 0..3 {
+            game.play(Coord::new(i, 0)).unwrap();
+            game.play(Coord::new(i, 1)).unwrap();
+        }
+        game.play(Coord::new(3, 0)).unwrap();
+        assert_eq!(game.winner(), Some(Stone::Black));
+    }
+
+    #[test]
+    fn records_events_until_taken() {
+        let mut game = GameState::new();
+        for i in 0..4 {
+            game.play(Coord::new(i, 0)).unwrap();
+            game.play(Coord::new(i, 1)).unwrap();
+        }
+        assert_eq!(game.take_events().len(), 8);
+
+        let last = Coord::new(4, 0);
+        game.play(last).unwrap();
+        game.undo();
+        game.redo();
+        let played = |stone| GameEvent::Played { coord: last, stone };
+        assert_eq!(
+            game.take_events(),
+            vec![
+                played(Stone::Black),
+                GameEvent::Won(Stone::Black),
+                GameEvent::Undone { coord: last, stone: Stone::Black },
+                GameEvent::Redone { coord: last, stone: Stone::Black },
+                GameEvent::Won(Stone::Black),
+            ]
+        );
+        assert!(game.take_events().is_empty());
+    }
+
+    #[test]
+    fn full_board_without_five_is_a_draw() {
+        // 按 (col + 2 * row) % 4 分色：横向和斜向最多连两子，纵向黑白交替，正好 113 黑 112 白
+        let (black, white): (Vec<Coord>, Vec<Coord>) = Coord::all().partition(|c| (c.col + 2 * c.row) % 4 < 2);
+        let mut game = GameState::new();
+        for (&b, &w) in black.iter().zip(&white) {
+            game.play(b).unwrap();
+            game.play(w).unwrap();
+            assert!(!game.is_over());
+        }
+        game.play(black[black.len() - 1]).unwrap();
+        assert_eq!(game.empty_cells(), 0);
+        assert!(game.is_draw());
+        assert_eq!(game.winner(), None);
+        assert_eq!(game.take_events().last(), Some(&GameEvent::Drawn));
+
+        game.undo();
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn running_out_of_time_loses() {
+        let mut game = GameState::new();
+        game.play(Coord::CENTER).unwrap();
+        game.time_out(Stone::White);
+        assert_eq!(game.outcome(), Some(Outcome::Timeout(Stone::Black)));
+        assert_eq!(game.take_events().last(), Some(&GameEvent::Won(Stone::Black)));
+    }
+
+    #[test]
+    fn resigning_and_agreeing_to_a_draw_end_the_game() {
+        let mut game = GameState::new();
+        game.play(Coord::CENTER).unwrap();
+        game.resign(Stone::White);
+        assert_eq!(game.outcome(), Some(Outcome::Resignation(Stone::Black)));
+        assert_eq!(game.winner(), Some(Stone::Black));
+        assert_eq!(game.play(Coord::new(0, 0)), Err(MoveError::GameOver));
+        // 已经结束的对局不能再和棋
+        game.agree_draw();
+        assert_eq!(game.winner(), Some(Stone::Black));
+
+        let mut game = GameState::new();
+        game.agree_draw();
+        assert!(game.is_draw());
+        assert_eq!(game.take_events(), vec![GameEvent::Drawn]);
+    }
+
+    #[test]
+    fn undo_cannot_take_back_a_resignation() {
+        let mut game = GameState::new();
+        game.play(Coord::CENTER).unwrap();
+        game.play(Coord::new(8, 8)).unwrap();
+        game.resign(Stone::Black);
+        assert!(game.is_final());
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.outcome(), Some(Outcome::Resignation(Stone::White)));
+        assert_eq!(game.moves().len(), 2);
+
+        // 同意和棋同样不能悔棋，连成五子的胜负可以
+        let mut game = GameState::new();
+        game.play(Coord::CENTER).unwrap();
+        game.agree_draw();
+        assert_eq!(game.undo(), None);
+        assert!(game.is_draw());
+    }
+
+    #[test]
+    fn board_at_replays_prefix() {
+        let mut game = GameState::new();
+        game.play(Coord::new(7, 7)).unwrap();
+        game.play(Coord::new(8, 8)).unwrap();
+        let board = game.board_at(1);
+        assert_eq!(board.get(Coord::new(7, 7)), Stone::Black);
+        assert!(board.is_empty_at(Coord::new(8, 8)));
+        assert_eq!(game.board_at(2), *game.board());
+    }
+}
@@ -0,0 +1,148 @@
+//! 分享对局用的简短代码，如 `GMK1-F5-HHIHGG`。
+//!
+//! 代码由三段组成：版本前缀、规则（`F` 自由规则或 `R` 连珠规则加获胜长度）和落子记录，
+//! 每步两个字母 `A`–`O`，依次为列和行。只用大写字母、数字和 `-`，正好在二维码的
+//! 字母数字模式里，整局棋编码之后也能放进一个不太密的二维码。
+
+use std::fmt;
+
+use crate::board::BOARD_SIZE;
+use crate::coord::Coord;
+use crate::game::{GameState, MoveError};
+use crate::rules::{RuleSet, Rules};
+
+// 版本前缀，格式变化时递增
+const PREFIX: &str = "GMK1";
+
+/// 解析失败的原因
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameCodeError {
+    // 不是本程序生成的代码，或者版本不对
+    NotGameCode,
+    // 无法识别的规则
+    BadRules(String),
+    // 无法识别的坐标
+    BadCoord(String),
+    // 第 n 步（从 1 开始）不合法
+    IllegalMove(usize, MoveError),
+}
+
+impl fmt::Display for GameCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameCodeError::NotGameCode => write!(f, "not a game code"),
+            GameCodeError::BadRules(rules) => write!(f, "unknown rules '{}'", rules),
+            GameCodeError::BadCoord(coord) => write!(f, "invalid coordinate '{}'", coord),
+            GameCodeError::IllegalMove(n, e) => write!(f, "move {}: {}", n, e),
+        }
+    }
+}
+
+impl std::error::Error for GameCodeError {}
+
+/// 一局棋的规则和落子记录
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GameCode {
+    pub rules: Rules,
+    pub moves: Vec<Coord>,
+}
+
+impl GameCode {
+    /// 生成代码
+    pub fn encode(&self) -> String {
+        let set = match self.rules.set() {
+            RuleSet::Freestyle => 'F',
+            RuleSet::Renju => 'R',
+        };
+        let mut code = format!("{}-{}{}-", PREFIX, set, self.rules.win_length());
+        for mv in &self.moves {
+            code.push(coord_char(mv.col));
+            code.push(coord_char(mv.row));
+        }
+        code
+    }
+
+    /// 解析代码，并按规则重放所有落子确认对局合法
+    ///
+    /// 不区分大小写，首尾的空白会被忽略，方便直接粘贴扫码得到的文本。
+    pub fn parse(text: &str) -> Result<Self, GameCodeError> {
+        let text = text.trim().to_ascii_uppercase();
+        let mut parts = text.split('-');
+        if parts.next() != Some(PREFIX) {
+            return Err(GameCodeError::NotGameCode);
+        }
+        let rules = parts.next().ok_or(GameCodeError::NotGameCode)?;
+        let rules = parse_rules(rules).ok_or_else(|| GameCodeError::BadRules(rules.to_string()))?;
+        let moves = parts.next().ok_or(GameCodeError::NotGameCode)?;
+        if parts.next().is_some() {
+            return Err(GameCodeError::NotGameCode);
+        }
+
+        let mut game = GameState::with_rules(rules);
+        let mut code = GameCode { rules, moves: Vec::new() };
+        for pair in moves.as_bytes().chunks(2) {
+            let mv = parse_coord(pair)?;
+            let n = code.moves.len() + 1;
+            game.play(mv).map_err(|e| GameCodeError::IllegalMove(n, e))?;
+            code.moves.push(mv);
+        }
+        Ok(code)
+    }
+}
+
+fn parse_rules(text: &str) -> Option<Rules> {
+    let mut chars = text.chars();
+    let set = match chars.next()? {
+        'F' => RuleSet::Freestyle,
+        'R' => RuleSet::Renju,
+        _ => return None,
+    };
+    let length = chars.as_str().parse().ok().filter(|length| Rules::WIN_LENGTHS.contains(length))?;
+    let rules = Rules::new(set, length);
+    // 连珠规则固定为五子，写成别的长度说明代码有误
+    (rules.win_length() == length).then_some(rules)
+}
+
+fn coord_char(i: usize) -> char {
+    (b'A' + i as u8) as char
+}
+
+fn parse_coord(pair: &[u8]) -> Result<Coord, GameCodeError> {
+    let bad = || GameCodeError::BadCoord(String::from_utf8_lossy(pair).into_owned());
+    if pair.len() != 2 {
+        return Err(bad());
+    }
+    let index = |b: u8| (b'A'..b'A' + BOARD_SIZE as u8).contains(&b).then(|| (b - b'A') as usize);
+    Ok(Coord::new(index(pair[0]).ok_or_else(bad)?, index(pair[1]).ok_or_else(bad)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(moves: &[(usize, usize)], rules: Rules) -> GameCode {
+        GameCode { rules, moves: moves.iter().map(|&c| c.into()).collect() }
+    }
+
+    #[test]
+    fn round_trips_rules_and_moves() {
+        let game = code(&[(7, 7), (8, 7), (0, 14)], Rules::new(RuleSet::Freestyle, 6));
+        let text = game.encode();
+        assert_eq!(text, "GMK1-F6-HHIHAO");
+        assert_eq!(GameCode::parse(&text).unwrap(), game);
+
+        let empty = code(&[], Rules::new(RuleSet::Renju, 5));
+        assert_eq!(empty.encode(), "GMK1-R5-");
+        assert_eq!(GameCode::parse("  gmk1-r5-\n").unwrap(), empty);
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        assert_eq!(GameCode::parse("(;GM[4])"), Err(GameCodeError::NotGameCode));
+        assert_eq!(GameCode::parse("GMK1-F5"), Err(GameCodeError::NotGameCode));
+        assert_eq!(GameCode::parse("GMK1-R6-HH"), Err(GameCodeError::BadRules("R6".to_string())));
+        assert_eq!(GameCode::parse("GMK1-F5-HHP"), Err(GameCodeError::BadCoord("P".to_string())));
+        assert_eq!(GameCode::parse("GMK1-F5-HHZA"), Err(GameCodeError::BadCoord("ZA".to_string())));
+        assert!(matches!(GameCode::parse("GMK1-F5-HHHH"), Err(GameCodeError::IllegalMove(2, _))));
+    }
+}
@@ -0,0 +1,256 @@
+//! Gomocup（piskvork pbrain）引擎协议，用来让外部引擎代替内置 AI 思考，
+//! 也可以通过 `Brain` 把内置 AI 当作引擎交给其他管理程序。
+//!
+//! 每次轮到引擎时重新开始并发送完整的局面，引擎回复一行落点，所以悔棋和读档都不需要同步状态：
+//!
+//! ```text
+//! START 15         -> OK
+//! INFO rule 0      0 为自由规则，4 为连珠规则
+//! BOARD
+//! 7,7,2            列,行,1 为引擎自己的棋子，2 为对手的棋子，按落子顺序
+//! DONE             -> 8,8
+//! END
+//! ```
+
+use crate::ai::{Ai, Difficulty};
+use crate::board::{Board, BOARD_SIZE};
+use crate::coord::Coord;
+use crate::rules::{RuleSet, Rules};
+use crate::stone::Stone;
+
+// ABOUT 命令的回复
+const ABOUT: &str = r#"name="gomoku", version="0.1", country="CN""#;
+
+/// 引擎的一行回复
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Reply {
+    // 确认 START
+    Ok,
+    // 引擎的落点
+    Move(Coord),
+    // 引擎报告错误，或者不认识命令
+    Error(String),
+    // MESSAGE、DEBUG 等不影响对局的输出
+    Other,
+}
+
+/// 引擎能否按 rules 下棋，协议只支持五子
+pub fn supports(rules: Rules) -> bool {
+    rules.win_length() == Rules::DEFAULT_WIN_LENGTH
+}
+
+/// 开始一局时发送的命令，发送 START 之后要等到 OK
+pub fn start_commands(rules: Rules) -> [String; 2] {
+    let rule = match rules.set() {
+        RuleSet::Freestyle => 0,
+        RuleSet::Renju => 4,
+    };
+    [format!("START {}", BOARD_SIZE), format!("INFO rule {}", rule)]
+}
+
+/// 发送局面并请求执 engine 一方的引擎落子
+pub fn board_commands(moves: &[Coord], engine: Stone) -> Vec<String> {
+    let mut commands = vec!["BOARD".to_string()];
+    for (i, mv) in moves.iter().enumerate() {
+        let field = if Stone::for_ply(i) == engine { 1 } else { 2 };
+        commands.push(format!("{},{},{}", mv.col, mv.row, field));
+    }
+    commands.push("DONE".to_string());
+    commands
+}
+
+/// 解析引擎的一行回复
+pub fn parse_reply(line: &str) -> Reply {
+    let line = line.trim();
+    let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+    match command.to_ascii_uppercase().as_str() {
+        "OK" => Reply::Ok,
+        "ERROR" | "UNKNOWN" => Reply::Error(rest.to_string()),
+        "MESSAGE" | "DEBUG" => Reply::Other,
+        _ if line.contains(',') => match parse_coord(line) {
+            Some(coord) => Reply::Move(coord),
+            None => Reply::Error(format!("invalid move '{}'", line)),
+        },
+        _ => Reply::Other,
+    }
+}
+
+/// 解析 `列,行`
+fn parse_coord(text: &str) -> Option<Coord> {
+    let (col, row) = text.split_once(',')?;
+    Some(Coord::new(col.trim().parse().ok()?, row.trim().parse().ok()?)).filter(|c| c.is_valid())
+}
+
+/// 以 Gomocup 引擎的身份运行内置 AI，逐行处理管理程序发来的命令
+///
+/// 只记录双方的落子，不记录颜色：轮到引擎时双方棋子一样多说明引擎执黑，否则执白。
+pub struct Brain {
+    difficulty: Difficulty,
+    rules: Rules,
+    // 按顺序记录的落子，true 表示引擎自己的棋子
+    moves: Vec<(Coord, bool)>,
+    // 正在接收 BOARD 和 DONE 之间的局面
+    reading_board: bool,
+    // 收到 END 之后不再处理命令
+    finished: bool,
+}
+
+impl Brain {
+    /// 创建按 difficulty 搜索的引擎，规则由管理程序的 INFO rule 决定
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self { difficulty, rules: Rules::default(), moves: Vec::new(), reading_board: false, finished: false }
+    }
+
+    /// 管理程序是否已经结束对局
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// 处理一行命令，返回要回复的一行，不需要回复时返回 None
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if self.reading_board {
+            return self.read_board_line(line);
+        }
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command.to_ascii_uppercase().as_str() {
+            "START" => Some(match rest.parse() {
+                Ok(BOARD_SIZE) => {
+                    self.moves.clear();
+                    "OK".to_string()
+                }
+                _ => format!("ERROR only {}x{} boards are supported", BOARD_SIZE, BOARD_SIZE),
+            }),
+            "RESTART" => {
+                self.moves.clear();
+                Some("OK".to_string())
+            }
+            "INFO" => {
+                self.read_info(rest);
+                None
+            }
+            "BEGIN" => {
+                self.moves.clear();
+                Some(self.think())
+            }
+            "TURN" => Some(match parse_coord(rest) {
+                Some(coord) if self.is_free(coord) => {
+                    self.moves.push((coord, false));
+                    self.think()
+                }
+                _ => format!("ERROR invalid move '{}'", rest),
+            }),
+            "BOARD" => {
+                self.moves.clear();
+                self.reading_board = true;
+                None
+            }
+            "TAKEBACK" => Some(match parse_coord(rest) {
+                Some(coord) => {
+                    self.moves.retain(|&(mv, _)| mv != coord);
+                    "OK".to_string()
+                }
+                None => format!("ERROR invalid move '{}'", rest),
+            }),
+            "ABOUT" => Some(ABOUT.to_string()),
+            "END" => {
+                self.finished = true;
+                None
+            }
+            _ => Some(format!("UNKNOWN {}", command)),
+        }
+    }
+
+    /// BOARD 之后的一行 `列,行,棋子`，1 为引擎自己的棋子，DONE 结束并开始思考
+    fn read_board_line(&mut self, line: &str) -> Option<String> {
+        if line.eq_ignore_ascii_case("DONE") {
+            self.reading_board = false;
+            return Some(self.think());
+        }
+        let (coord, field) = line.rsplit_once(',')?;
+        let coord = parse_coord(coord)?;
+        if self.is_free(coord) {
+            self.moves.push((coord, field.trim() == "1"));
+        }
+        None
+    }
+
+    /// INFO 中只关心规则，其余的时间限制等信息忽略
+    fn read_info(&mut self, rest: &str) {
+        let (key, value) = rest.split_once(' ').unwrap_or((rest, ""));
+        if key.eq_ignore_ascii_case("rule") {
+            // rule 是位掩码，4 表示连珠规则
+            let renju = value.trim().parse::<u32>().is_ok_and(|rule| rule & 4 != 0);
+            let set = if renju { RuleSet::Renju } else { RuleSet::Freestyle };
+            self.rules = Rules::new(set, Rules::DEFAULT_WIN_LENGTH);
+        }
+    }
+
+    fn is_free(&self, coord: Coord) -> bool {
+        self.moves.iter().all(|&(mv, _)| mv != coord)
+    }
+
+    /// 为引擎自己选一步棋，记录并回复落点
+    fn think(&mut self) -> String {
+        let own = self.moves.iter().filter(|&&(_, own)| own).count();
+        let me = if 2 * own == self.moves.len() { Stone::Black } else { Stone::White };
+        let mut board = Board::new();
+        for &(mv, own) in &self.moves {
+            board.set(mv, if own { me } else { me.opponent() });
+        }
+        if board.is_full() {
+            return "ERROR the board is full".to_string();
+        }
+        let coord = Ai::new(me, self.difficulty).with_rules(self.rules).best_move(&board).best_move;
+        self.moves.push((coord, true));
+        format!("{},{}", coord.col, coord.row)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_marks_the_engine_stones_as_its_own() {
+        let moves = [Coord::new(7, 7), Coord::new(8, 7), Coord::new(7, 8)];
+        assert_eq!(board_commands(&moves, Stone::White), ["BOARD", "7,7,2", "8,7,1", "7,8,2", "DONE"]);
+        assert_eq!(board_commands(&[], Stone::Black), ["BOARD", "DONE"]);
+        assert_eq!(start_commands(Rules::new(RuleSet::Renju, 5))[1], "INFO rule 4");
+        assert!(!supports(Rules::new(RuleSet::Freestyle, 6)));
+    }
+
+    #[test]
+    fn parses_replies() {
+        assert_eq!(parse_reply("OK\r\n"), Reply::Ok);
+        assert_eq!(parse_reply("8,9"), Reply::Move(Coord::new(8, 9)));
+        assert_eq!(parse_reply("MESSAGE thinking, depth 12"), Reply::Other);
+        assert_eq!(parse_reply("ERROR unsupported rule"), Reply::Error("unsupported rule".to_string()));
+        assert_eq!(parse_reply("15,3"), Reply::Error("invalid move '15,3'".to_string()));
+    }
+
+    #[test]
+    fn brain_answers_the_manager() {
+        let mut brain = Brain::new(Difficulty::Easy);
+        assert_eq!(brain.handle("START 20").as_deref(), Some("ERROR only 15x15 boards are supported"));
+        assert_eq!(brain.handle("START 15").as_deref(), Some("OK"));
+        assert_eq!(brain.handle("INFO rule 4"), None);
+        assert_eq!(brain.handle("BEGIN").as_deref(), Some("7,7"));
+        assert_eq!(brain.handle("TURN 7,7").as_deref(), Some("ERROR invalid move '7,7'"));
+        assert_eq!(brain.handle("YXSHOWFORBID").as_deref(), Some("UNKNOWN YXSHOWFORBID"));
+
+        // 对手已经四连，引擎必须堵住
+        let mut brain = Brain::new(Difficulty::Easy);
+        for line in ["BOARD", "3,7,2", "3,3,1", "4,7,2", "3,4,1", "5,7,2", "10,10,1", "6,7,2"] {
+            assert_eq!(brain.handle(line), None);
+        }
+        let reply = brain.handle("DONE").unwrap();
+        assert!(reply == "2,7" || reply == "7,7", "{}", reply);
+        brain.handle("END");
+        assert!(brain.is_finished());
+    }
+}
@@ -0,0 +1,267 @@
+//! AI 从同一位玩家的对局里学习：记住开局阶段自己下过的每一步和那局的结果，
+//! 之后遇到同样的局面时重复赢过这位玩家的下法，或者换一条没走过的路。
+//!
+//! 局面按规范形式记录，旋转或翻转之后的同一局面共用一份经验。只记录开局的
+//! `LEARNED_PLIES` 步，之后的局面很少重复出现。记录的条数有上限，超出时先忘掉
+//! 下得最少的那些，长时间对弈之后经验也不会无限增长。
+
+use std::collections::HashMap;
+
+use crate::ai::Analysis;
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::rules::Rules;
+use crate::stone::Stone;
+use crate::symmetry::{self, Symmetry};
+
+/// 记录经验的开局步数
+pub const LEARNED_PLIES: usize = 16;
+
+/// 默认最多记住的 (局面, 落子) 条数
+pub const DEFAULT_CAPACITY: usize = 20_000;
+
+// 候选点的得分比搜索的最佳点低出这个值以内时，才会为了经验改下这一步
+const VARIATION_MARGIN: i32 = 200;
+
+/// AI 怎样利用学到的经验
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Adaptation {
+    // 不使用经验，只按搜索结果下
+    #[default]
+    Off,
+    // 重复赢过玩家的下法
+    Repeat,
+    // 换用走得少的下法，避开输过的
+    Vary,
+}
+
+impl Adaptation {
+    pub const ALL: [Adaptation; 3] = [Adaptation::Off, Adaptation::Repeat, Adaptation::Vary];
+
+    /// 名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Adaptation::Off => "Off",
+            Adaptation::Repeat => "Repeat Wins",
+            Adaptation::Vary => "Vary Lines",
+        }
+    }
+}
+
+/// AI 在某个局面下某一步之后的战绩
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Tally {
+    /// 一共下过几次
+    pub fn played(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+}
+
+/// AI 对一位玩家积累的经验
+#[derive(Clone, Debug)]
+pub struct Experience {
+    // (规范局面的键, 规范局面上的落子) -> 战绩
+    tallies: HashMap<(u64, Coord), Tally>,
+    // 最多记住的条数，为 0 时不再记录新的对局
+    capacity: usize,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self { tallies: HashMap::new(), capacity: DEFAULT_CAPACITY }
+    }
+}
+
+impl Experience {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 修改最多记住的条数，多出的记录会被忘掉
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// 记录过的 (局面, 落子) 个数
+    pub fn len(&self) -> usize {
+        self.tallies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tallies.is_empty()
+    }
+
+    /// 忘掉所有记录，容量不变
+    pub fn clear(&mut self) {
+        self.tallies.clear();
+    }
+
+    /// 记住一局棋，moves 为整局的落子，ai 为 AI 执的一方，won 为 None 表示和棋
+    pub fn record_game(&mut self, moves: &[Coord], ai: Stone, rules: Rules, won: Option<bool>) {
+        let mut board = Board::new();
+        for (ply, &mv) in moves.iter().enumerate().take(LEARNED_PLIES) {
+            let stone = Stone::for_ply(ply);
+            if stone == ai {
+                let (key, symmetry) = position_key(&board, rules);
+                let tally = self.tallies.entry((key, symmetry.apply(mv))).or_default();
+                match won {
+                    Some(true) => tally.wins += 1,
+                    Some(false) => tally.losses += 1,
+                    None => tally.draws += 1,
+                }
+            }
+            board.set(mv, stone);
+        }
+        self.evict();
+    }
+
+    /// 是否在 board 上下过棋，没有下过时不需要为经验做额外的搜索
+    pub fn knows(&self, board: &Board, rules: Rules) -> bool {
+        let (key, _) = position_key(board, rules);
+        self.tallies.keys().any(|&(k, _)| k == key)
+    }
+
+    /// AI 在 board 上下 mv 之后的战绩
+    pub fn tally(&self, board: &Board, rules: Rules, mv: Coord) -> Tally {
+        let (key, symmetry) = position_key(board, rules);
+        self.tallies.get(&(key, symmetry.apply(mv))).copied().unwrap_or_default()
+    }
+
+    /// 按 adaptation 从搜索给出的候选点（按得分从高到低）中选一步
+    ///
+    /// 只在得分与最佳点相差不多的候选点之间选择，不会为了经验下出明显的坏棋。
+    pub fn choose(
+        &self,
+        board: &Board,
+        rules: Rules,
+        adaptation: Adaptation,
+        candidates: &[Analysis],
+    ) -> Option<Analysis> {
+        let best = *candidates.first()?;
+        let acceptable = candidates
+            .iter()
+            .filter(|c| c.score >= best.score - VARIATION_MARGIN && (best.is_loss() || !c.is_loss()));
+        let preference = |tally: Tally| -> i64 {
+            match adaptation {
+                Adaptation::Off => 0,
+                Adaptation::Repeat => tally.wins as i64 - tally.losses as i64,
+                // 走得越少越好，输过的再额外扣分
+                Adaptation::Vary => -(tally.played() as i64) - 2 * tally.losses as i64,
+            }
+        };
+        // 偏好相同时保持搜索的顺序
+        acceptable.min_by_key(|c| -preference(self.tally(board, rules, c.best_move))).copied()
+    }
+
+    /// 所有记录，格式为 (局面键, 落子, 战绩)，用于保存
+    pub fn entries(&self) -> impl Iterator<Item = (u64, Coord, Tally)> + '_ {
+        self.tallies.iter().map(|(&(key, mv), &tally)| (key, mv, tally))
+    }
+
+    /// 恢复 `entries` 保存的一条记录，读完之后调用 `set_capacity` 忘掉多出的记录
+    pub fn insert(&mut self, key: u64, mv: Coord, tally: Tally) {
+        self.tallies.insert((key, mv), tally);
+    }
+
+    /// 超出容量时忘掉下得最少的记录，次数相同时按键的顺序，保证每次忘掉的都一样
+    fn evict(&mut self) {
+        let excess = self.tallies.len().saturating_sub(self.capacity);
+        if excess == 0 {
+            return;
+        }
+        let mut entries: Vec<_> = self.tallies.iter().map(|(&entry, tally)| (tally.played(), entry)).collect();
+        entries.sort_unstable();
+        for (_, entry) in entries.into_iter().take(excess) {
+            self.tallies.remove(&entry);
+        }
+    }
+}
+
+/// 规范局面连同规则的键，以及把原局面变换到规范形式的对称变换
+fn position_key(board: &Board, rules: Rules) -> (u64, Symmetry) {
+    let (canonical, symmetry) = symmetry::canonicalize(board);
+    let mut hash = symmetry::position_hash(&canonical);
+    for byte in [rules.set() as u8, rules.win_length() as u8] {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash, symmetry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    fn analysis(col: usize, row: usize, score: i32) -> Analysis {
+        Analysis { best_move: Coord::new(col, row), score }
+    }
+
+    #[test]
+    fn remembers_symmetric_games_as_one_line() {
+        let mut experience = Experience::new();
+        let rules = Rules::default();
+        // AI 执黑，两局互为左右翻转
+        let game = [Coord::new(7, 7), Coord::new(9, 6), Coord::new(8, 8)];
+        let flipped: Vec<_> = game.iter().map(|&c| Symmetry::FlipHorizontal.apply(c)).collect();
+        experience.record_game(&game, Stone::Black, rules, Some(true));
+        experience.record_game(&flipped, Stone::Black, rules, None);
+
+        let mut board = Board::new();
+        board.set(Coord::new(7, 7), Stone::Black);
+        board.set(Coord::new(9, 6), Stone::White);
+        assert!(experience.knows(&board, rules));
+        assert_eq!(experience.tally(&board, rules, Coord::new(8, 8)), Tally { wins: 1, losses: 0, draws: 1 });
+        assert_eq!(experience.tally(&board, rules, Coord::new(6, 8)), Tally::default());
+        assert_eq!(experience.tally(&Board::new(), rules, Coord::CENTER).played(), 2);
+        assert!(!experience.knows(&board, Rules::new(RuleSet::Renju, 5)));
+        // 对手的落子不算 AI 的经验
+        board.set(Coord::new(8, 8), Stone::Black);
+        assert!(!experience.knows(&board, rules));
+    }
+
+    #[test]
+    fn repeats_wins_and_varies_lines_without_blundering() {
+        let mut experience = Experience::new();
+        let rules = Rules::default();
+        let mut board = Board::new();
+        board.set(Coord::new(7, 7), Stone::Black);
+        experience.record_game(&[Coord::new(7, 7), Coord::new(8, 7)], Stone::White, rules, Some(true));
+        experience.record_game(&[Coord::new(7, 7), Coord::new(8, 8)], Stone::White, rules, Some(false));
+
+        let candidates = [analysis(8, 8, 50), analysis(8, 7, 0), analysis(9, 9, -20), analysis(3, 3, -900)];
+        let choose = |adaptation| experience.choose(&board, rules, adaptation, &candidates).unwrap().best_move;
+        assert_eq!(choose(Adaptation::Off), Coord::new(8, 8));
+        assert_eq!(choose(Adaptation::Repeat), Coord::new(8, 7));
+        assert_eq!(choose(Adaptation::Vary), Coord::new(9, 9));
+
+        // 赢过的一步太差时仍然按搜索下
+        let candidates = [analysis(8, 8, 50), analysis(8, 7, -500)];
+        assert_eq!(experience.choose(&board, rules, Adaptation::Repeat, &candidates), Some(candidates[0]));
+    }
+
+    #[test]
+    fn forgets_the_least_played_moves_beyond_capacity() {
+        let mut experience = Experience::new();
+        let rules = Rules::default();
+        experience.set_capacity(2);
+        experience.record_game(&[Coord::CENTER], Stone::Black, rules, Some(true));
+        experience.record_game(&[Coord::CENTER], Stone::Black, rules, Some(false));
+        experience.record_game(&[Coord::new(0, 0)], Stone::Black, rules, Some(true));
+        experience.record_game(&[Coord::new(1, 1)], Stone::Black, rules, None);
+        assert_eq!(experience.len(), 2);
+        assert_eq!(experience.tally(&Board::new(), rules, Coord::CENTER).played(), 2);
+
+        experience.set_capacity(0);
+        assert!(experience.is_empty());
+        experience.record_game(&[Coord::CENTER], Stone::Black, rules, Some(true));
+        assert!(experience.is_empty());
+    }
+}
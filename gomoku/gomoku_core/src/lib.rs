@@ -0,0 +1,53 @@
+//! 五子棋核心逻辑：棋盘、对局状态、胜负判断和 AI。
+//!
+//! 这个库不依赖任何界面或音频库，可以同时被图形界面、命令行工具和基准测试使用。
+//!
+//! 评分、搜索和所有计数都只用整数运算，同样的输入在任何平台、任何编译选项下都得到逐位相同的结果，
+//! 服务器可以重算并核对客户端报告的分析，比赛也可以完整复现。浮点运算由下面的 lint 禁止。
+
+#![deny(clippy::float_arithmetic)]
+
+pub mod ai;
+pub mod board;
+pub mod checksum;
+pub mod commentary;
+pub mod coord;
+pub mod events;
+pub mod game;
+pub mod game_code;
+pub mod gomocup;
+pub mod learning;
+pub mod line_room;
+pub mod opening_book;
+pub mod pattern;
+pub mod practice;
+pub mod protocol;
+pub mod renju;
+pub mod rules;
+pub mod sgf;
+pub mod stone;
+pub mod swap2;
+pub mod symmetry;
+pub mod threat;
+pub mod transposition;
+pub mod weakness;
+pub mod win_rate;
+pub mod zobrist;
+
+pub use ai::{Ai, Analysis, Classical, Difficulty, Evaluator, FirstMove, SearchStats, Weights, ENGINE_VERSION};
+pub use board::{Board, Segment, BOARD_SIZE};
+pub use checksum::Checksum;
+pub use commentary::Evaluation;
+pub use coord::{Coord, CoordOrigin};
+pub use events::{EventBus, GameEvent};
+pub use game::{GameState, MoveError, Outcome};
+pub use game_code::{GameCode, GameCodeError};
+pub use learning::{Adaptation, Experience};
+pub use opening_book::{BookError, OpeningBook};
+pub use renju::Forbidden;
+pub use rules::{RuleSet, Rules};
+pub use sgf::{SgfError, SgfRecord};
+pub use stone::Stone;
+pub use swap2::{Seat, Swap2, Swap2Choice, Swap2Phase};
+pub use symmetry::Symmetry;
+pub use weakness::{Rate, Skill, SkillReport};
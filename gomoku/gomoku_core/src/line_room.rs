@@ -0,0 +1,104 @@
+//! 连线的发展空间：一步棋和相邻的己方棋子连成一条线之后，这条线还有多少余地。
+//!
+//! 给初学者的提示用：贴着棋盘边的连线只要对方挡住另一端就废了，两端都有余地的连线才有前途。
+//! 只看棋子的位置，不做搜索，也不影响 AI。
+
+use crate::board::{Board, DIRECTIONS};
+use crate::coord::Coord;
+use crate::stone::Stone;
+
+/// 一条连线的发展空间，从好到坏排列
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LineRoom {
+    // 两端都离棋盘边有余地
+    Open,
+    // 一端贴着棋盘边，对方挡住另一端就成了死棋
+    EdgeBound,
+    // 两端之间的空间不够连成获胜长度，再怎么下也连不成
+    Dead,
+}
+
+/// stone 方在 mv 落子后形成的最好的一条连线的发展空间，length 为获胜需要的连子数
+///
+/// 只看 mv 在某个方向上紧挨着己方棋子的线；mv 和任何己方棋子都不相邻时返回 None。
+pub fn line_room(board: &Board, mv: Coord, stone: Stone, length: usize) -> Option<LineRoom> {
+    DIRECTIONS
+        .iter()
+        .filter_map(|&(dc, dr)| {
+            let ahead = side(board, mv, (dc, dr), stone, length);
+            let behind = side(board, mv, (-dc, -dr), stone, length);
+            if ahead.run + behind.run == 0 {
+                return None;
+            }
+            Some(if 1 + ahead.room + behind.room < length {
+                LineRoom::Dead
+            } else if ahead.against_edge() || behind.against_edge() {
+                LineRoom::EdgeBound
+            } else {
+                LineRoom::Open
+            })
+        })
+        .min()
+}
+
+/// 从 mv 沿一个方向看出去的情况
+struct Side {
+    // 紧挨着 mv 的己方棋子数
+    run: usize,
+    // 到棋盘边或对方棋子之前的交叉点数（含己方棋子），最多数到获胜长度
+    room: usize,
+    // 连子之后还有几个空位
+    beyond: usize,
+    // 是不是被棋盘边挡住
+    edge: bool,
+}
+
+impl Side {
+    /// 连子外面最多只剩一个空位就到了棋盘边
+    fn against_edge(&self) -> bool {
+        self.edge && self.beyond <= 1
+    }
+}
+
+fn side(board: &Board, mv: Coord, (dc, dr): (i32, i32), stone: Stone, length: usize) -> Side {
+    let mut side = Side { run: 0, room: 0, beyond: 0, edge: false };
+    for i in 1..=length as i32 {
+        let Some(next) = mv.offset(dc * i, dr * i) else {
+            side.edge = true;
+            break;
+        };
+        let cell = board.get(next);
+        if cell == stone.opponent() {
+            break;
+        }
+        if cell == stone && side.beyond == 0 {
+            side.run += 1;
+        } else {
+            side.beyond += 1;
+        }
+        side.room += 1;
+    }
+    side
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_edge_lines_from_open_ones() {
+        let room = |board: &Board, mv: (usize, usize)| line_room(board, mv.into(), Stone::Black, 5);
+        let near_edge = Board::with_stones(&[(1, 7)], &[]);
+        assert_eq!(room(&near_edge, (0, 7)), Some(LineRoom::EdgeBound));
+        // 连子外只剩一格就到边
+        assert_eq!(room(&near_edge, (2, 7)), Some(LineRoom::EdgeBound));
+        assert_eq!(room(&near_edge, (1, 6)), Some(LineRoom::Open));
+        assert_eq!(room(&near_edge, (4, 4)), None);
+
+        // 两颗白子之间只有四格
+        let squeezed = Board::with_stones(&[(7, 7)], &[(5, 7), (10, 7)]);
+        assert_eq!(room(&squeezed, (8, 7)), Some(LineRoom::Dead));
+        // 有一个方向是活的就按最好的算
+        assert_eq!(room(&Board::with_stones(&[(7, 7), (8, 8)], &[(5, 7), (10, 7)]), (8, 7)), Some(LineRoom::Open));
+    }
+}
@@ -0,0 +1,151 @@
+//! 开局库：常见开局和应对，AI 在开局的前几步直接从这里选一步，不必搜索。
+//!
+//! 开局库是一个文本文件，每行一条开局线，按顺序写出落子（左上角原点的记法，如 `H8`），
+//! `#` 之后是注释。一条线的每个前缀局面都以下一步作为库里的应对：
+//!
+//! ```text
+//! # 直指开局中的花月
+//! H8 H7 I7
+//! ```
+//!
+//! 局面按规范形式记录，旋转或翻转之后的局面同样能查到，查到的落子会换回原来的方向。
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::board::Board;
+use crate::coord::{Coord, CoordOrigin};
+use crate::stone::Stone;
+use crate::symmetry;
+
+/// AI 查询开局库的步数，之后的局面交给搜索
+pub const BOOK_PLIES: usize = 8;
+
+// 内置的开局库
+const BUILTIN: &str = include_str!("openings.txt");
+
+/// 解析开局库失败的原因，行号从 1 开始
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BookError {
+    // 无法识别的坐标
+    BadCoord(usize, String),
+    // 落在同一条线前面已经下过的位置
+    Occupied(usize, Coord),
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::BadCoord(line, coord) => write!(f, "line {}: invalid coordinate '{}'", line, coord),
+            BookError::Occupied(line, coord) => write!(f, "line {}: {} is already taken", line, coord),
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// 开局库
+#[derive(Clone, Debug, Default)]
+pub struct OpeningBook {
+    // 规范局面的键 -> 规范局面上的应对
+    replies: HashMap<u64, Vec<Coord>>,
+}
+
+impl OpeningBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 程序自带的开局库：直指和斜指的 26 种开局，以及白方第 4 步的应对
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN).expect("the built-in opening book is valid")
+    }
+
+    /// 解析开局库文本
+    pub fn parse(text: &str) -> Result<Self, BookError> {
+        let mut book = Self::new();
+        book.extend(text)?;
+        Ok(book)
+    }
+
+    /// 加入另一份开局库文本中的开局线，用于在内置开局库之外加载玩家的文件
+    ///
+    /// 出错时已经读到的开局线仍然保留。
+    pub fn extend(&mut self, text: &str) -> Result<(), BookError> {
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let moves = line.split('#').next().unwrap_or_default();
+            let mut board = Board::new();
+            for (ply, notation) in moves.split_whitespace().enumerate() {
+                let mv = Coord::parse(notation, CoordOrigin::TopLeft)
+                    .ok_or_else(|| BookError::BadCoord(line_number, notation.to_string()))?;
+                if !board.is_empty_at(mv) {
+                    return Err(BookError::Occupied(line_number, mv));
+                }
+                self.insert(&board, mv);
+                board.set(mv, Stone::for_ply(ply));
+            }
+        }
+        Ok(())
+    }
+
+    /// 记录 board 上的一步应对
+    pub fn insert(&mut self, board: &Board, mv: Coord) {
+        let (canonical, symmetry) = symmetry::canonicalize(board);
+        let replies = self.replies.entry(symmetry::position_hash(&canonical)).or_default();
+        let mv = symmetry.apply(mv);
+        if !replies.contains(&mv) {
+            replies.push(mv);
+        }
+    }
+
+    /// 库里有应对的局面个数
+    pub fn len(&self) -> usize {
+        self.replies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.replies.is_empty()
+    }
+
+    /// board 上库里的所有应对，按写入的顺序排列，已经换回 board 的方向
+    pub fn moves(&self, board: &Board) -> Vec<Coord> {
+        let (canonical, symmetry) = symmetry::canonicalize(board);
+        let Some(replies) = self.replies.get(&symmetry::position_hash(&canonical)) else {
+            return Vec::new();
+        };
+        replies.iter().map(|&mv| symmetry.inverse().apply(mv)).filter(|&mv| board.is_empty_at(mv)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symmetry::Symmetry;
+
+    #[test]
+    fn finds_replies_in_any_orientation() {
+        let book = OpeningBook::parse("# comment\nH8 H7 I7 I6  # flower moon\nH8 H7 J7\n\nH8 I7\n").unwrap();
+        assert_eq!(book.moves(&Board::new()), [Coord::CENTER]);
+
+        let mut board = Board::new();
+        board.set(Coord::CENTER, Stone::Black);
+        board.set(Coord::new(7, 6), Stone::White);
+        assert_eq!(book.moves(&board), [Coord::new(8, 6), Coord::new(9, 6)]);
+        // 同一个局面转过来之后，应对也跟着转
+        board.set(Coord::new(8, 6), Stone::Black);
+        for symmetry in Symmetry::ALL {
+            let turned = symmetry.apply_board(&board);
+            assert_eq!(book.moves(&turned), [symmetry.apply(Coord::new(8, 5))], "{:?}", symmetry);
+        }
+        board.set(Coord::new(8, 5), Stone::White);
+        assert!(book.moves(&board).is_empty());
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        assert_eq!(OpeningBook::parse("H8\nH8 Z9").unwrap_err(), BookError::BadCoord(2, "Z9".to_string()));
+        assert_eq!(OpeningBook::parse("H8 I9 H8").unwrap_err(), BookError::Occupied(1, Coord::CENTER));
+        assert!(OpeningBook::builtin().len() > 26);
+    }
+}
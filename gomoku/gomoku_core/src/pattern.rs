@@ -0,0 +1,189 @@
+//! 棋型扫描：沿一条线数连子、判断两端是否被挡住。
+//!
+//! 棋盘为每条线保存了黑白棋子的位掩码（见 `Board`），这里把一条线取成一个整数，
+//! 用 `trailing_ones` / `leading_ones` 一次数出连续的同色棋子，代替逐格调用 `Coord::offset`
+//! 和 `Board::get` 的循环。原来的逐格实现保留为 `direction_score_scalar`，
+//! 供测试核对结果和 `benches/pattern.rs` 对比速度。
+//!
+//! 标准库的 portable SIMD 还只能在 nightly 上使用，所以这里只用稳定版的整数位运算。
+
+use crate::board::{line_slot, Board, LineSlot, DIRECTIONS};
+use crate::coord::Coord;
+use crate::stone::Stone;
+
+// 移位之后中心交叉点所在的位，往前的交叉点在更高的位，往后的在更低的位
+const CENTER: u32 = 16;
+
+/// 从某个交叉点看出去的一条线，整条线移位到中心交叉点落在第 `CENTER` 位
+#[derive(Clone, Copy, Debug)]
+pub struct LineView {
+    // 线上 stone 方的棋子
+    own: u32,
+    // 挡住连子的位置：对方的棋子，以及棋盘外
+    blocked: u32,
+}
+
+impl LineView {
+    /// 从 coord 沿第 direction 个方向（横、竖、主对角线、副对角线）看 stone 方的棋子
+    pub fn new(board: &Board, coord: Coord, direction: usize, stone: Stone) -> Self {
+        let slot = line_slot(coord, direction);
+        let [black, white] = board.line_masks(slot, direction);
+        let (own, other) = if stone == Stone::White { (white, black) } else { (black, white) };
+        Self::from_masks(own, other, slot)
+    }
+
+    fn from_masks(own: u16, other: u16, slot: LineSlot) -> Self {
+        let shift = CENTER - slot.pos as u32;
+        // 线的第 0 位移到 shift 位，更低的位也都在棋盘外
+        let blocked = (other as u32 | slot.off_board) << shift | ((1 << shift) - 1);
+        Self { own: (own as u32) << shift, blocked }
+    }
+
+    /// 中心沿方向往前连续的同色棋子数（不含中心，最多数到 limit），以及连子之后是否被挡住
+    ///
+    /// 数满 limit 时不再看后面的位置，是否被挡住总是 false。
+    pub fn forward(&self, limit: u32) -> (u32, bool) {
+        let run = (self.own >> (CENTER + 1)).trailing_ones();
+        // 用位运算而不是 && 合并条件，避免难以预测的分支
+        (run.min(limit), (self.blocked >> (CENTER + 1 + run)) & (run < limit) as u32 == 1)
+    }
+
+    /// 中心沿方向往后连续的同色棋子数，含义同 `forward`
+    pub fn backward(&self, limit: u32) -> (u32, bool) {
+        // 一条线最长 15 格，往前往后的连子都不会数出 32 位
+        let run = (self.own << (32 - CENTER)).leading_ones();
+        (run.min(limit), (self.blocked >> (CENTER - 1 - run)) & (run < limit) as u32 == 1)
+    }
+}
+
+/// stone 方在 coord 的第 direction 个方向上的棋型得分，length 为获胜需要的连子数
+pub fn direction_score(board: &Board, coord: Coord, direction: usize, stone: Stone, length: usize) -> i32 {
+    view_score(LineView::new(board, coord, direction, stone), length)
+}
+
+/// coord 上四个方向的棋型得分之和，先是 stone 方的，再是对方的
+///
+/// 和分别调用八次 `direction_score` 的结果相同，每条线的位掩码只读一次。
+pub fn point_scores(board: &Board, coord: Coord, stone: Stone, length: usize) -> (i32, i32) {
+    let (mut own, mut other) = (0, 0);
+    for direction in 0..DIRECTIONS.len() {
+        let slot = line_slot(coord, direction);
+        let [black, white] = board.line_masks(slot, direction);
+        let (mine, theirs) = if stone == Stone::White { (white, black) } else { (black, white) };
+        own += view_score(LineView::from_masks(mine, theirs, slot), length);
+        other += view_score(LineView::from_masks(theirs, mine, slot), length);
+    }
+    (own, other)
+}
+
+fn view_score(view: LineView, length: usize) -> i32 {
+    let limit = length as u32 - 1;
+    let (ahead, ahead_blocked) = view.forward(limit);
+    let (behind, behind_blocked) = view.backward(limit);
+    score((ahead + behind) as usize, ahead_blocked as usize + behind_blocked as usize, length)
+}
+
+/// 逐格走查的 `direction_score`，结果完全相同，只用于核对和基准测试
+pub fn direction_score_scalar(board: &Board, coord: Coord, direction: usize, stone: Stone, length: usize) -> i32 {
+    let (dc, dr) = DIRECTIONS[direction];
+    let mut count = 0;
+    let mut blocked = 0;
+
+    // 先向一个方向计数，再向另一个方向计数
+    for sign in [1, -1] {
+        for i in 1..length as i32 {
+            let Some(next) = coord.offset(dc * i * sign, dr * i * sign) else {
+                blocked += 1;
+                break;
+            };
+
+            let cell = board.get(next);
+            if cell == stone {
+                count += 1;
+            } else if cell == Stone::Empty {
+                break;
+            } else {
+                blocked += 1;
+                break;
+            }
+        }
+    }
+    score(count, blocked, length)
+}
+
+/// 两侧共 count 个同色棋子、blocked 端被挡住时的得分
+fn score(count: usize, blocked: usize, length: usize) -> i32 {
+    if count == 0 {
+        return 0;
+    }
+    // 根据还差几子获胜和阻塞情况给分
+    match (length - 1).saturating_sub(count) {
+        0 => 10000, // 连成获胜长度
+        1 => if blocked == 0 { 1000 } else { 100 },
+        2 => if blocked == 0 { 100 } else { 10 },
+        3 => if blocked == 0 { 10 } else { 1 },
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::BOARD_SIZE;
+    use crate::rules::Rules;
+
+    #[test]
+    fn views_stop_at_opponents_and_edges() {
+        let mut board = Board::new();
+        for col in [0, 1, 3, 4] {
+            board.set(Coord::new(col, 14), Stone::Black);
+        }
+        board.set(Coord::new(5, 14), Stone::White);
+        let view = LineView::new(&board, Coord::new(2, 14), 0, Stone::Black);
+        assert_eq!(view.forward(15), (2, true));
+        assert_eq!(view.backward(15), (2, true));
+        assert_eq!(view.forward(1), (1, false));
+        // 副对角线：(2, 14) 往前是 (3, 13)，往后出了棋盘
+        let view = LineView::new(&board, Coord::new(2, 14), 3, Stone::Black);
+        assert_eq!(view.forward(15), (0, false));
+        assert_eq!(view.backward(15), (0, true));
+    }
+
+    #[test]
+    fn matches_the_scalar_scan() {
+        // 伪随机地摆满大约一半的棋盘，逐步比较每个交叉点、方向和获胜长度
+        let mut board = Board::new();
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..6 {
+            for _ in 0..20 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let coord = Coord::new(state as usize % BOARD_SIZE, (state >> 8) as usize % BOARD_SIZE);
+                board.set(coord, [Stone::Black, Stone::White, Stone::Empty][(state >> 16) as usize % 3]);
+            }
+            for coord in Coord::all() {
+                for length in Rules::WIN_LENGTHS {
+                    let mut totals = [0; 2];
+                    for direction in 0..DIRECTIONS.len() {
+                        for (total, stone) in totals.iter_mut().zip([Stone::Black, Stone::White]) {
+                            let scalar = direction_score_scalar(&board, coord, direction, stone, length);
+                            assert_eq!(
+                                direction_score(&board, coord, direction, stone, length),
+                                scalar,
+                                "{:?} {} {:?} {}",
+                                coord,
+                                direction,
+                                stone,
+                                length
+                            );
+                            *total += scalar;
+                        }
+                    }
+                    assert_eq!(point_scores(&board, coord, Stone::Black, length), (totals[0], totals[1]));
+                    assert_eq!(point_scores(&board, coord, Stone::White, length), (totals[1], totals[0]));
+                }
+            }
+        }
+    }
+}
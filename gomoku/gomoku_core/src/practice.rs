@@ -0,0 +1,72 @@
+//! 练习模式：玩家落子之后立即检查这一步是不是失误。
+//!
+//! 只识别在搜索深度内能确定的两类失误：原本有必胜却没有走，以及让对手有了必胜。
+//! 搜索范围有限，这里的“必胜”指在困难难度的搜索深度内能够强制连成。
+
+use crate::ai::{Ai, Difficulty};
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::rules::Rules;
+use crate::stone::Stone;
+
+// 检查失误使用的难度，搜索越深越能发现较长的必胜
+const CHECK_DIFFICULTY: Difficulty = Difficulty::Hard;
+
+/// 一步棋的失误
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mistake {
+    // 落子之前有必胜，落子之后没有了，附带原本的必胜着
+    MissedWin(Coord),
+    // 落子之后对手有必胜，附带对手的必胜着
+    AllowedLoss(Coord),
+}
+
+/// 检查 stone 方在 before 局面下落在 mv 是否为失误，before 是落子之前的棋盘
+pub fn check_move(before: &Board, mv: Coord, stone: Stone, rules: Rules) -> Option<Mistake> {
+    let mut after = *before;
+    after.set(mv, stone);
+    if rules.is_win(&after, mv) {
+        return None;
+    }
+    let best = Ai::new(stone, CHECK_DIFFICULTY).with_rules(rules).best_move(before);
+    let reply = Ai::new(stone.opponent(), CHECK_DIFFICULTY).with_rules(rules).best_move(&after);
+    if best.is_win() && !reply.is_loss() {
+        return Some(Mistake::MissedWin(best.best_move));
+    }
+    // 落子之前已经必败时，无论下在哪里都不算失误
+    if reply.is_win() && !best.is_loss() {
+        return Some(Mistake::AllowedLoss(reply.best_move));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_a_missed_win() {
+        // 黑子有活三，(5,7) 或 (9,7) 形成活四必胜
+        let board = Board::with_stones(&[(6, 7), (7, 7), (8, 7)], &[(0, 0), (0, 2)]);
+        let mistake = check_move(&board, Coord::new(12, 12), Stone::Black, Rules::default());
+        assert!(matches!(mistake, Some(Mistake::MissedWin(_))), "{:?}", mistake);
+        assert_eq!(check_move(&board, Coord::new(5, 7), Stone::Black, Rules::default()), None);
+    }
+
+    #[test]
+    fn flags_an_allowed_loss() {
+        // 白子有活三，黑子必须挡住
+        let board = Board::with_stones(&[(0, 0), (0, 2)], &[(6, 7), (7, 7), (8, 7)]);
+        let mistake = check_move(&board, Coord::new(12, 12), Stone::Black, Rules::default());
+        assert!(matches!(mistake, Some(Mistake::AllowedLoss(_))), "{:?}", mistake);
+        assert_eq!(check_move(&board, Coord::new(5, 7), Stone::Black, Rules::default()), None);
+    }
+
+    #[test]
+    fn winning_moves_and_quiet_positions_are_fine() {
+        let board = Board::with_stones(&[(3, 3), (4, 3), (5, 3), (6, 3)], &[(2, 3)]);
+        assert_eq!(check_move(&board, Coord::new(7, 3), Stone::Black, Rules::default()), None);
+        let board = Board::with_stones(&[(7, 7)], &[(8, 8)]);
+        assert_eq!(check_move(&board, Coord::new(6, 6), Stone::Black, Rules::default()), None);
+    }
+}
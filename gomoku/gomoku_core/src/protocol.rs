@@ -0,0 +1,304 @@
+//! 联机对局的消息协议。
+//!
+//! 每条消息一行文本，连接建立后双方先互发 `HELLO`，版本一致才开始对局：
+//!
+//! ```text
+//! HELLO 7                握手，附带协议版本
+//! MOVE 0 7 7 3F2A09C1    第 0 步落在第 7 列第 7 行，附带落子之后局面的校验码
+//! RESIGN                 认输
+//! TIMEOUT                本方超时判负，双方的钟各走各的，以超时一方的为准
+//! BYE                    离开
+//! ```
+//!
+//! 新开的对局由主机在第一步之前告知时间控制，双方按同一个预设计时：
+//!
+//! ```text
+//! TIME 3+2               基础 3 分钟、每步加 2 秒，- 表示不限时
+//! ```
+//!
+//! 对局中断线之后，任何一方都可以重新创建连接，另一方重新加入，双方沿用原来的颜色。
+//! 连上之后双方都先发 `RESUME`，记录一致才接着下，否则结束连接：
+//!
+//! ```text
+//! RESUME 12 3F2A09C1     已经下了 12 步，当前局面的校验码
+//! ```
+//!
+//! 教学时主机是老师，连上之后先发 `LESSON`，之后双方的棋盘通过下面的消息保持一致：
+//!
+//! ```text
+//! LESSON           开始教学
+//! PLACE 7 7 B      在第 7 列第 7 行放黑子，W 为白子，- 为清除
+//! ARROW 3 4 7 7    老师从 (3, 4) 到 (7, 7) 画一个箭头
+//! CLEAR BOARD      清空棋盘，CLEAR ARROWS 只清除箭头
+//! CONTROL STUDENT  交给学生落子，CONTROL TEACHER 收回
+//! ```
+//!
+//! 解题对战时主机连上之后先发 `DUEL`，双方按同一个种子得到同样的题目顺序，各自做完一题后通报总分：
+//!
+//! ```text
+//! DUEL 12345       开始对战，附带决定题目顺序的种子
+//! SCORE 3 250      做完（解出或跳过）第 3 题，总分 250
+//! ```
+//!
+//! 比赛时赛事裁判在一个端口上接受所有选手的连接，连上之后先发 `DIRECTOR`，选手报上名字。
+//! 裁判编排对阵之后，同一台的两位选手之间照常直接对局，裁判另外给两人发判定、调钟和公告：
+//!
+//! ```text
+//! DIRECTOR                      连上的是赛事裁判
+//! PLAYER Alice Smith            选手报名，名字占这一行剩下的部分
+//! PAIR 2 3 B 10.0.0.5:5152      第 2 轮第 3 台：执黑的在这个端口创建对局，执白的加入这个地址
+//! RESULT W                      裁判判定本台结果：B 黑胜、W 白胜、D 和棋；选手也用它上报对局结果
+//! CLOCK B -30000                给黑方的钟加减毫秒
+//! ANNOUNCE Round 2 starts now   公告，内容占这一行剩下的部分
+//! ```
+
+use crate::checksum::Checksum;
+use crate::coord::Coord;
+use crate::stone::Stone;
+
+/// 协议版本，双方不一致时拒绝连接
+///
+/// 版本 2 加入了教学的消息，版本 3 加入了解题对战的消息，版本 4 在落子消息中加入了局面校验码，
+/// 版本 5 加入了断线后恢复对局的消息，版本 6 加入了时间控制和超时的消息，版本 7 加入了赛事裁判的消息。
+pub const PROTOCOL_VERSION: u32 = 7;
+
+/// 协议消息
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Hello(u32),
+    // 第 ply 步落在 coord，checksum 为落子之后的局面校验码，收到的一方据此确认双方局面一致
+    Move { ply: usize, coord: Coord, checksum: Checksum },
+    Resign,
+    Timeout,
+    Bye,
+    // 主机选择的时间控制代码，如 `3+2`，不含空白
+    Time(String),
+    // 恢复断线的对局：本方记录里已下的步数和当前局面的校验码
+    Resume { ply: usize, checksum: Checksum },
+    Lesson,
+    Place { coord: Coord, stone: Stone },
+    Arrow { from: Coord, to: Coord },
+    ClearBoard,
+    ClearArrows,
+    // 学生是否可以落子
+    Control { student: bool },
+    Duel { seed: u64 },
+    // 做完第 round 题之后的总分
+    Score { round: usize, score: u32 },
+    Director,
+    // 选手的名字
+    Player(String),
+    // 第 round 轮第 table 台，收到的选手执 stone；执黑的在 address 的端口上创建对局，执白的加入 address
+    Pair { round: u32, table: u32, stone: Stone, address: String },
+    // 本台的结果，获胜的一方，None 为和棋
+    Result(Option<Stone>),
+    // 给 stone 方的钟加上 delta 毫秒，负数为扣时
+    Clock { stone: Stone, delta: i64 },
+    Announce(String),
+}
+
+impl Message {
+    /// 编码为一行文本（不含换行）
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Hello(version) => format!("HELLO {}", version),
+            Message::Move { ply, coord, checksum } => format!("MOVE {} {} {} {}", ply, coord.col, coord.row, checksum),
+            Message::Resign => "RESIGN".to_string(),
+            Message::Timeout => "TIMEOUT".to_string(),
+            Message::Bye => "BYE".to_string(),
+            Message::Time(code) => format!("TIME {}", code),
+            Message::Resume { ply, checksum } => format!("RESUME {} {}", ply, checksum),
+            Message::Lesson => "LESSON".to_string(),
+            Message::Place { coord, stone } => {
+                let stone = match stone {
+                    Stone::Black => "B",
+                    Stone::White => "W",
+                    Stone::Empty => "-",
+                };
+                format!("PLACE {} {} {}", coord.col, coord.row, stone)
+            }
+            Message::Arrow { from, to } => format!("ARROW {} {} {} {}", from.col, from.row, to.col, to.row),
+            Message::ClearBoard => "CLEAR BOARD".to_string(),
+            Message::ClearArrows => "CLEAR ARROWS".to_string(),
+            Message::Control { student } => format!("CONTROL {}", if *student { "STUDENT" } else { "TEACHER" }),
+            Message::Duel { seed } => format!("DUEL {}", seed),
+            Message::Score { round, score } => format!("SCORE {} {}", round, score),
+            Message::Director => "DIRECTOR".to_string(),
+            Message::Player(name) => format!("PLAYER {}", single_line(name)),
+            Message::Pair { round, table, stone, address } => {
+                format!("PAIR {} {} {} {}", round, table, stone_code(*stone), address)
+            }
+            Message::Result(winner) => format!("RESULT {}", winner.map_or("D", stone_code)),
+            Message::Clock { stone, delta } => format!("CLOCK {} {}", stone_code(*stone), delta),
+            Message::Announce(text) => format!("ANNOUNCE {}", single_line(text)),
+        }
+    }
+
+    /// 解析一行文本，无法识别时返回 None
+    pub fn decode(line: &str) -> Option<Message> {
+        // 名字和公告里可以有空格，取关键字之后的整行
+        if let Some((keyword, text)) = line.split_once(' ') {
+            let text = text.trim();
+            match keyword {
+                "PLAYER" if !text.is_empty() => return Some(Message::Player(text.to_string())),
+                "ANNOUNCE" if !text.is_empty() => return Some(Message::Announce(text.to_string())),
+                _ => {}
+            }
+        }
+        let mut parts = line.split_whitespace();
+        let message = match parts.next()? {
+            "HELLO" => Message::Hello(parts.next()?.parse().ok()?),
+            "MOVE" => {
+                let ply = parts.next()?.parse().ok()?;
+                let col = parts.next()?.parse().ok()?;
+                let row = parts.next()?.parse().ok()?;
+                let checksum = parts.next()?.parse().ok()?;
+                Message::Move { ply, coord: Coord::new(col, row), checksum }
+            }
+            "RESIGN" => Message::Resign,
+            "TIMEOUT" => Message::Timeout,
+            "BYE" => Message::Bye,
+            "TIME" => Message::Time(parts.next()?.to_string()),
+            "RESUME" => {
+                let ply = parts.next()?.parse().ok()?;
+                Message::Resume { ply, checksum: parts.next()?.parse().ok()? }
+            }
+            "LESSON" => Message::Lesson,
+            "PLACE" => {
+                let col = parts.next()?.parse().ok()?;
+                let row = parts.next()?.parse().ok()?;
+                let stone = match parts.next()? {
+                    "B" => Stone::Black,
+                    "W" => Stone::White,
+                    "-" => Stone::Empty,
+                    _ => return None,
+                };
+                Message::Place { coord: Coord::new(col, row), stone }
+            }
+            "ARROW" => {
+                let mut coord = || Some(Coord::new(parts.next()?.parse().ok()?, parts.next()?.parse().ok()?));
+                let from = coord()?;
+                Message::Arrow { from, to: coord()? }
+            }
+            "CLEAR" => match parts.next()? {
+                "BOARD" => Message::ClearBoard,
+                "ARROWS" => Message::ClearArrows,
+                _ => return None,
+            },
+            "CONTROL" => match parts.next()? {
+                "STUDENT" => Message::Control { student: true },
+                "TEACHER" => Message::Control { student: false },
+                _ => return None,
+            },
+            "DUEL" => Message::Duel { seed: parts.next()?.parse().ok()? },
+            "SCORE" => {
+                let round = parts.next()?.parse().ok()?;
+                Message::Score { round, score: parts.next()?.parse().ok()? }
+            }
+            "DIRECTOR" => Message::Director,
+            "PAIR" => {
+                let round = parts.next()?.parse().ok()?;
+                let table = parts.next()?.parse().ok()?;
+                let stone = parse_stone(parts.next()?)?;
+                Message::Pair { round, table, stone, address: parts.next()?.to_string() }
+            }
+            "RESULT" => match parts.next()? {
+                "D" => Message::Result(None),
+                code => Message::Result(Some(parse_stone(code)?)),
+            },
+            "CLOCK" => {
+                let stone = parse_stone(parts.next()?)?;
+                Message::Clock { stone, delta: parts.next()?.parse().ok()? }
+            }
+            _ => return None,
+        };
+        // 多余的字段说明格式不对
+        parts.next().is_none().then_some(message)
+    }
+}
+
+fn stone_code(stone: Stone) -> &'static str {
+    if stone == Stone::Black { "B" } else { "W" }
+}
+
+fn parse_stone(code: &str) -> Option<Stone> {
+    match code {
+        "B" => Some(Stone::Black),
+        "W" => Some(Stone::White),
+        _ => None,
+    }
+}
+
+// 一条消息只占一行，文字里的换行换成空格
+fn single_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Hello(PROTOCOL_VERSION),
+            Message::Move { ply: 12, coord: Coord::new(3, 14), checksum: Checksum(0x3f2a_09c1) },
+            Message::Resign,
+            Message::Timeout,
+            Message::Bye,
+            Message::Time("3+2".to_string()),
+            Message::Time("-".to_string()),
+            Message::Resume { ply: 12, checksum: Checksum(0x3f2a_09c1) },
+            Message::Lesson,
+            Message::Place { coord: Coord::new(7, 7), stone: Stone::Black },
+            Message::Place { coord: Coord::new(0, 14), stone: Stone::Empty },
+            Message::Arrow { from: Coord::new(3, 4), to: Coord::new(7, 7) },
+            Message::ClearBoard,
+            Message::ClearArrows,
+            Message::Control { student: true },
+            Message::Control { student: false },
+            Message::Duel { seed: u64::MAX },
+            Message::Score { round: 3, score: 250 },
+            Message::Director,
+            Message::Player("Alice Smith".to_string()),
+            Message::Pair { round: 2, table: 3, stone: Stone::Black, address: "10.0.0.5:5152".to_string() },
+            Message::Pair { round: 2, table: 3, stone: Stone::White, address: "[::1]:5152".to_string() },
+            Message::Result(Some(Stone::White)),
+            Message::Result(None),
+            Message::Clock { stone: Stone::Black, delta: -30_000 },
+            Message::Clock { stone: Stone::White, delta: 60_000 },
+            Message::Announce("Round 2 starts now".to_string()),
+        ];
+        for message in messages {
+            assert_eq!(Message::decode(&message.encode()), Some(message));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        assert_eq!(Message::decode("MOVE 1 2 3"), None);
+        assert_eq!(Message::decode("MOVE 1 2 3 3F2A09C1 5"), None);
+        assert_eq!(Message::decode("MOVE 1 2 3 XYZ"), None);
+        assert_eq!(Message::decode("MOVE a b c"), None);
+        assert_eq!(Message::decode("RESUME 12"), None);
+        assert_eq!(Message::decode("PING"), None);
+        assert_eq!(Message::decode("PLACE 7 7 X"), None);
+        assert_eq!(Message::decode("ARROW 1 2 3"), None);
+        assert_eq!(Message::decode("CLEAR"), None);
+        assert_eq!(Message::decode("CONTROL BOTH"), None);
+        assert_eq!(Message::decode("DUEL -1"), None);
+        assert_eq!(Message::decode("SCORE 3"), None);
+        assert_eq!(Message::decode("TIME"), None);
+        assert_eq!(Message::decode("TIME 3 + 2"), None);
+        assert_eq!(Message::decode("PLAYER"), None);
+        assert_eq!(Message::decode("ANNOUNCE  "), None);
+        assert_eq!(Message::decode("PAIR 2 3 E 10.0.0.5:5152"), None);
+        assert_eq!(Message::decode("PAIR 2 3 B"), None);
+        assert_eq!(Message::decode("RESULT X"), None);
+        assert_eq!(Message::decode("CLOCK B 1.5"), None);
+        // 换行不会把一条公告拆成两条消息
+        let announce = Message::Announce("line one\nline two".to_string());
+        assert_eq!(Message::decode(&announce.encode()), Some(Message::Announce("line one line two".to_string())));
+        assert_eq!(Message::decode(""), None);
+    }
+}
@@ -0,0 +1,251 @@
+//! 连珠（Renju）规则的禁手判断。
+//!
+//! 黑方不能下出三三、四四和长连，正好连成五子时不算禁手；白方不受限制。
+//!
+//! - 四：再下一子就能正好连成五子。同一条线上可能有两个四（如 `X_XXX_X` 的中间一子）。
+//! - 活四：有两个点可以连成五子的四，只算一个四。
+//! - 活三：再下一子能形成活四，并且这一子本身不是禁手，所以判断是递归的。
+
+use std::fmt;
+
+use crate::board::{Board, Segment, DIRECTIONS};
+use crate::coord::Coord;
+use crate::stone::Stone;
+
+/// 黑方的禁手类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forbidden {
+    DoubleThree,
+    DoubleFour,
+    Overline,
+}
+
+impl Forbidden {
+    /// 禁手名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Forbidden::DoubleThree => "double three",
+            Forbidden::DoubleFour => "double four",
+            Forbidden::Overline => "overline",
+        }
+    }
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 黑方在空点 coord 落子是否为禁手
+pub fn forbidden(board: &Board, coord: Coord) -> Option<Forbidden> {
+    let mut board = *board;
+    board.set(coord, Stone::Black);
+    forbidden_placed(&mut board, coord)
+}
+
+/// coord 上的黑子是否正好连成五子，长连不算
+pub fn is_exact_five(board: &Board, coord: Coord) -> bool {
+    exact_five(board, coord).is_some()
+}
+
+/// coord 上的黑子正好连成五子时，返回这五子
+pub fn exact_five(board: &Board, coord: Coord) -> Option<Segment> {
+    DIRECTIONS.iter().map(|&d| Run::new(board, coord, d)).find(|run| run.len() == 5).map(|run| Segment {
+        start: run.cell(run.start),
+        end: run.cell(run.end),
+    })
+}
+
+/// 判断已经落在 coord 上的黑子是否为禁手
+fn forbidden_placed(board: &mut Board, coord: Coord) -> Option<Forbidden> {
+    // 正好连成五子时即使同时形成禁手也算胜
+    if is_exact_five(board, coord) {
+        return None;
+    }
+    if DIRECTIONS.iter().any(|&d| Run::new(board, coord, d).len() > 5) {
+        return Some(Forbidden::Overline);
+    }
+    let fours: usize = DIRECTIONS.iter().map(|&d| count_fours(board, coord, d)).sum();
+    if fours >= 2 {
+        return Some(Forbidden::DoubleFour);
+    }
+    let threes = DIRECTIONS.iter().filter(|&&d| is_three(board, coord, d)).count();
+    if threes >= 2 {
+        return Some(Forbidden::DoubleThree);
+    }
+    None
+}
+
+/// coord 在 d 方向上形成几个四
+fn count_fours(board: &mut Board, coord: Coord, d: (i32, i32)) -> usize {
+    let (fives, count) = five_points(board, coord, d);
+    // 活四的两个成五点对应同一组四颗子，只算一个
+    (0..count).filter(|&i| !fives[..i].contains(&fives[i])).count()
+}
+
+/// coord 在 d 方向上是否为活四
+fn is_open_four(board: &mut Board, coord: Coord, d: (i32, i32)) -> bool {
+    let (fives, count) = five_points(board, coord, d);
+    (0..count).any(|i| fives[i + 1..count].contains(&fives[i]))
+}
+
+/// coord 在 d 方向上是否为活三：存在一个不是禁手的点，下在那里能形成活四
+fn is_three(board: &mut Board, coord: Coord, d: (i32, i32)) -> bool {
+    line_points(coord, d).any(|(_, point)| {
+        if !board.is_empty_at(point) {
+            return false;
+        }
+        board.set(point, Stone::Black);
+        let three = is_open_four(board, coord, d) && forbidden_placed(board, point).is_none();
+        board.set(point, Stone::Empty);
+        three
+    })
+}
+
+/// d 方向上能让 coord 正好连成五子的空点，返回五子中除这个空点以外的四颗子，以及这样的点的个数
+///
+/// 四颗子用相对 coord 的位置（-4 到 4）作为位的掩码表示，搜索中频繁调用，不分配内存。
+fn five_points(board: &mut Board, coord: Coord, d: (i32, i32)) -> ([u16; 8], usize) {
+    let mut fives = [0; 8];
+    let mut count = 0;
+    for (i, point) in line_points(coord, d) {
+        if !board.is_empty_at(point) {
+            continue;
+        }
+        board.set(point, Stone::Black);
+        let run = Run::new(board, coord, d);
+        // 已经连成五子时，不相连的空点不会改变连子数，要排除
+        if run.len() == 5 && (run.start..=run.end).contains(&i) {
+            fives[count] = run.mask() & !bit(i);
+            count += 1;
+        }
+        board.set(point, Stone::Empty);
+    }
+    (fives, count)
+}
+
+/// coord 在 d 方向两侧各 4 格以内的点，以及它们相对 coord 的位置
+fn line_points(coord: Coord, (dc, dr): (i32, i32)) -> impl Iterator<Item = (i32, Coord)> {
+    (-4..=4)
+        .filter(|&i| i != 0)
+        .filter_map(move |i| Some((i, coord.offset(dc * i, dr * i)?)))
+}
+
+/// 相对位置 i（-4 到 4）对应的位
+fn bit(i: i32) -> u16 {
+    1 << (i + 4)
+}
+
+/// coord 在 d 方向上连续的黑子，包含 coord 本身，用相对 coord 的起止位置表示
+struct Run {
+    coord: Coord,
+    d: (i32, i32),
+    start: i32,
+    end: i32,
+}
+
+impl Run {
+    fn new(board: &Board, coord: Coord, (dc, dr): (i32, i32)) -> Self {
+        let is_black = |i: i32| coord.offset(dc * i, dr * i).is_some_and(|c| board.get(c) == Stone::Black);
+        let back = (1..).take_while(|&i| is_black(-i)).count() as i32;
+        let forward = (1..).take_while(|&i| is_black(i)).count() as i32;
+        Self { coord, d: (dc, dr), start: -back, end: forward }
+    }
+
+    /// 连子数
+    fn len(&self) -> usize {
+        (self.end - self.start + 1) as usize
+    }
+
+    /// 相对位置 i 上的交叉点
+    fn cell(&self, i: i32) -> Coord {
+        self.coord.offset(self.d.0 * i, self.d.1 * i).expect("run cells are on the board")
+    }
+
+    /// 连子所在位置的掩码，只用于长度不超过 5 的连子
+    fn mask(&self) -> u16 {
+        (self.start..=self.end).map(bit).fold(0, |mask, b| mask | b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossing_open_threes_are_forbidden() {
+        // 横向 (5,7) (6,7) 与纵向 (7,5) (7,6) 在 (7,7) 交叉
+        let board = Board::with_stones(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[]);
+        assert_eq!(forbidden(&board, Coord::CENTER), Some(Forbidden::DoubleThree));
+    }
+
+    #[test]
+    fn split_three_counts_as_a_three() {
+        // 横向 X_X 加上落点，斜向两子
+        let board = Board::with_stones(&[(4, 7), (6, 7), (5, 5), (6, 6)], &[]);
+        assert_eq!(forbidden(&board, Coord::CENTER), Some(Forbidden::DoubleThree));
+    }
+
+    #[test]
+    fn blocked_three_is_not_a_three() {
+        // 横向一端被白子挡住，只剩一个活三
+        let board = Board::with_stones(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[(4, 7)]);
+        assert_eq!(forbidden(&board, Coord::CENTER), None);
+    }
+
+    #[test]
+    fn three_against_the_edge_is_not_a_three() {
+        // 横向贴着棋盘边，无法形成活四
+        let board = Board::with_stones(&[(0, 7), (1, 7), (2, 5), (2, 6)], &[]);
+        assert_eq!(forbidden(&board, Coord::new(2, 7)), None);
+    }
+
+    #[test]
+    fn three_that_only_makes_overlines_is_not_a_three() {
+        // 横向 X__XX?__X：补成四之后另一端只能连成长连，纵向是普通活三
+        let board = Board::with_stones(&[(2, 7), (5, 7), (6, 7), (10, 7), (7, 5), (7, 6)], &[]);
+        assert_eq!(forbidden(&board, Coord::CENTER), None);
+        let board = Board::with_stones(&[(5, 7), (6, 7), (7, 5), (7, 6)], &[]);
+        assert_eq!(forbidden(&board, Coord::CENTER), Some(Forbidden::DoubleThree));
+    }
+
+    #[test]
+    fn crossing_fours_are_forbidden() {
+        let board = Board::with_stones(&[(4, 7), (5, 7), (6, 7), (7, 4), (7, 5), (7, 6)], &[(3, 7), (7, 3)]);
+        assert_eq!(forbidden(&board, Coord::CENTER), Some(Forbidden::DoubleFour));
+    }
+
+    #[test]
+    fn two_fours_on_one_line_are_forbidden() {
+        // X_X?X_X：两侧各有一个成五点，对应不同的四颗子
+        let board = Board::with_stones(&[(4, 7), (6, 7), (8, 7), (10, 7)], &[]);
+        assert_eq!(forbidden(&board, Coord::CENTER), Some(Forbidden::DoubleFour));
+    }
+
+    #[test]
+    fn open_four_counts_once() {
+        let board = Board::with_stones(&[(4, 7), (5, 7), (6, 7)], &[]);
+        assert_eq!(forbidden(&board, Coord::CENTER), None);
+    }
+
+    #[test]
+    fn four_three_is_allowed() {
+        let board = Board::with_stones(&[(4, 7), (5, 7), (6, 7), (7, 5), (7, 6)], &[(3, 7)]);
+        assert_eq!(forbidden(&board, Coord::CENTER), None);
+    }
+
+    #[test]
+    fn overline_is_forbidden_but_exact_five_wins() {
+        let overline = Board::with_stones(&[(2, 7), (3, 7), (4, 7), (5, 7), (6, 7)], &[]);
+        assert_eq!(forbidden(&overline, Coord::CENTER), Some(Forbidden::Overline));
+
+        // 连成五子的同时形成四四，仍然算胜
+        let five = Board::with_stones(&[(3, 7), (4, 7), (5, 7), (6, 7), (7, 4), (7, 5), (7, 6)], &[(7, 3)]);
+        assert_eq!(forbidden(&five, Coord::CENTER), None);
+        let mut placed = five;
+        placed.set(Coord::CENTER, Stone::Black);
+        assert!(is_exact_five(&placed, Coord::CENTER));
+        assert_eq!(exact_five(&placed, Coord::CENTER), Some(Segment { start: Coord::new(3, 7), end: Coord::CENTER }));
+    }
+}
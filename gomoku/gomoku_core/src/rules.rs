@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::board::{Board, Segment};
+use crate::coord::Coord;
+use crate::renju::{self, Forbidden};
+use crate::stone::Stone;
+
+/// 规则变体
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum RuleSet {
+    // 无禁手，连成获胜长度或以上即胜
+    #[default]
+    Freestyle,
+    // 黑方有三三、四四、长连禁手，且必须正好五子才算胜
+    Renju,
+}
+
+impl RuleSet {
+    pub const ALL: [RuleSet; 2] = [RuleSet::Freestyle, RuleSet::Renju];
+
+    /// 规则名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleSet::Freestyle => "Freestyle",
+            RuleSet::Renju => "Renju",
+        }
+    }
+}
+
+/// 一局棋的完整规则：变体和获胜需要的连子数
+///
+/// 连珠规则的禁手都是按五子定义的，所以连珠规则固定为五子。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rules {
+    set: RuleSet,
+    win_length: usize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new(RuleSet::Freestyle, Self::DEFAULT_WIN_LENGTH)
+    }
+}
+
+impl Rules {
+    /// 标准的五子
+    pub const DEFAULT_WIN_LENGTH: usize = 5;
+
+    /// 界面中可以选择的获胜长度
+    pub const WIN_LENGTHS: [usize; 3] = [4, 5, 6];
+
+    /// 创建规则，连珠规则忽略 win_length
+    pub fn new(set: RuleSet, win_length: usize) -> Self {
+        let win_length = match set {
+            RuleSet::Freestyle => win_length,
+            RuleSet::Renju => Self::DEFAULT_WIN_LENGTH,
+        };
+        Self { set, win_length }
+    }
+
+    /// 规则变体
+    pub fn set(&self) -> RuleSet {
+        self.set
+    }
+
+    /// 获胜需要的连子数
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    /// 解析 `Display` 输出的规则名称，如 `Renju` 或 `Freestyle 6`
+    pub fn parse(text: &str) -> Option<Rules> {
+        let mut parts = text.split_whitespace();
+        let name = parts.next()?;
+        let set = RuleSet::ALL.into_iter().find(|set| set.name() == name)?;
+        let win_length = match parts.next() {
+            Some(length) => length.parse().ok().filter(|length| Self::WIN_LENGTHS.contains(length))?,
+            None => Self::DEFAULT_WIN_LENGTH,
+        };
+        parts.next().is_none().then(|| Rules::new(set, win_length))
+    }
+
+    /// stone 在空点 coord 落子是否为禁手
+    pub fn forbidden(&self, board: &Board, coord: Coord, stone: Stone) -> Option<Forbidden> {
+        match (self.set, stone) {
+            (RuleSet::Renju, Stone::Black) => renju::forbidden(board, coord),
+            _ => None,
+        }
+    }
+
+    /// coord 上的棋子是否按本规则获胜
+    pub fn is_win(&self, board: &Board, coord: Coord) -> bool {
+        self.winning_line(board, coord).is_some()
+    }
+
+    /// coord 上的棋子按本规则获胜时，返回连成的那一段
+    pub fn winning_line(&self, board: &Board, coord: Coord) -> Option<Segment> {
+        match (self.set, board.get(coord)) {
+            (RuleSet::Renju, Stone::Black) => renju::exact_five(board, coord),
+            _ => board.winning_line(coord, self.win_length),
+        }
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.set.name())?;
+        if self.win_length != Self::DEFAULT_WIN_LENGTH {
+            write!(f, " {}", self.win_length)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for set in RuleSet::ALL {
+            for length in Rules::WIN_LENGTHS {
+                let rules = Rules::new(set, length);
+                assert_eq!(Rules::parse(&rules.to_string()), Some(rules));
+            }
+        }
+        assert_eq!(Rules::new(RuleSet::Renju, 6).win_length(), 5);
+        assert_eq!(Rules::parse("Freestyle 9"), None);
+        assert_eq!(Rules::parse("Go"), None);
+    }
+
+    #[test]
+    fn win_length_sets_the_line_needed() {
+        let mut board = Board::new();
+        for col in 3..7 {
+            board.set(Coord::new(col, 7), Stone::White);
+        }
+        let last = Coord::new(6, 7);
+        assert!(Rules::new(RuleSet::Freestyle, 4).is_win(&board, last));
+        assert!(!Rules::default().is_win(&board, last));
+
+        board.set(Coord::new(7, 7), Stone::White);
+        assert!(Rules::default().is_win(&board, Coord::CENTER));
+        assert!(!Rules::new(RuleSet::Freestyle, 6).is_win(&board, Coord::CENTER));
+    }
+}
@@ -0,0 +1,305 @@
+//! SGF（Smart Game Format）棋谱的读写。
+//!
+//! 只使用五子棋需要的一小部分属性，写出的文件可以被其他支持 `GM[4]` 的软件打开：
+//!
+//! ```text
+//! (;GM[4]FF[4]SZ[15]PB[Player]PW[AI]RE[B+]RU[Freestyle]
+//! ;B[hh];W[ii];B[gg])
+//! ```
+//!
+//! 坐标用两个小写字母表示，第一个是列，第二个是行（从上往下），`a` 对应 0。
+//! 规则写在 `RU` 属性中（如 `Renju`、`Freestyle 6`），缺少或无法识别时按标准五子读取。
+//! 结果写在 `RE` 属性中：`B+` 表示黑胜，`B+R` 表示白方认输，`B+T` 表示白方超时，
+//! `0` 表示和棋，`?` 表示未结束。每一步的注释（例如引擎的分析）写在该步之后的 `C` 属性中。
+
+use std::fmt;
+
+use crate::board::BOARD_SIZE;
+use crate::coord::Coord;
+use crate::game::{GameState, MoveError, Outcome};
+use crate::rules::Rules;
+use crate::stone::Stone;
+
+/// 读取 SGF 失败的原因
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SgfError {
+    // 文件不是以 `(;` 开头的棋谱
+    NotSgf,
+    // 属性值缺少结尾的 `]`
+    Unterminated,
+    // 不是五子棋棋谱
+    WrongGame(String),
+    // 棋盘大小不是 15
+    WrongSize(String),
+    // 无法识别的坐标
+    BadCoord(String),
+    // 第 n 步（从 1 开始）不合法
+    IllegalMove(usize, MoveError),
+    // 第 n 步不是轮到的一方落子
+    WrongTurn(usize),
+}
+
+impl fmt::Display for SgfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SgfError::NotSgf => write!(f, "not an SGF game record"),
+            SgfError::Unterminated => write!(f, "property value is missing a closing ']'"),
+            SgfError::WrongGame(gm) => write!(f, "not a Gomoku record (GM[{}])", gm),
+            SgfError::WrongSize(sz) => write!(f, "unsupported board size {}", sz),
+            SgfError::BadCoord(coord) => write!(f, "invalid coordinate '{}'", coord),
+            SgfError::IllegalMove(n, e) => write!(f, "move {}: {}", n, e),
+            SgfError::WrongTurn(n) => write!(f, "move {}: played by the wrong color", n),
+        }
+    }
+}
+
+impl std::error::Error for SgfError {}
+
+/// 一份棋谱：双方名字、结果、落子记录和每一步的注释
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SgfRecord {
+    pub black: String,
+    pub white: String,
+    pub rules: Rules,
+    // 对局结果，None 表示尚未结束
+    pub result: Option<Outcome>,
+    pub moves: Vec<Coord>,
+    // 与 moves 一一对应的注释，空字符串表示没有注释；整份棋谱都没有注释时为空
+    pub comments: Vec<String>,
+}
+
+impl SgfRecord {
+    /// 生成 SGF 文本
+    pub fn to_sgf(&self) -> String {
+        let result = match self.result {
+            Some(Outcome::Win(Stone::Black)) => "B+",
+            Some(Outcome::Win(_)) => "W+",
+            Some(Outcome::Resignation(Stone::Black)) => "B+R",
+            Some(Outcome::Resignation(_)) => "W+R",
+            Some(Outcome::Timeout(Stone::Black)) => "B+T",
+            Some(Outcome::Timeout(_)) => "W+T",
+            Some(Outcome::Draw) => "0",
+            None => "?",
+        };
+        let mut text = format!(
+            "(;GM[4]FF[4]SZ[{}]PB[{}]PW[{}]RE[{}]RU[{}]\n",
+            BOARD_SIZE,
+            escape(&self.black),
+            escape(&self.white),
+            result,
+            self.rules
+        );
+        for (i, &mv) in self.moves.iter().enumerate() {
+            let color = if Stone::for_ply(i) == Stone::Black { 'B' } else { 'W' };
+            text.push_str(&format!(";{}[{}{}]", color, coord_char(mv.col), coord_char(mv.row)));
+            if let Some(comment) = self.comments.get(i).filter(|comment| !comment.is_empty()) {
+                text.push_str(&format!("C[{}]\n", escape(comment)));
+            }
+        }
+        text.push_str(")\n");
+        text
+    }
+
+    /// 解析 SGF 文本，并按规则重放所有落子确认棋谱合法
+    ///
+    /// 只读取第一条主线，分支和不认识的属性会被忽略。规则必须出现在第一步之前。
+    pub fn parse(text: &str) -> Result<Self, SgfError> {
+        let body = text.trim_start().strip_prefix('(').ok_or(SgfError::NotSgf)?;
+        if !body.trim_start().starts_with(';') {
+            return Err(SgfError::NotSgf);
+        }
+
+        let mut record = SgfRecord::default();
+        let mut game = GameState::new();
+        for (ident, value) in properties(body)? {
+            match ident.as_str() {
+                "GM" if value != "4" => return Err(SgfError::WrongGame(value)),
+                "SZ" if value != BOARD_SIZE.to_string() => return Err(SgfError::WrongSize(value)),
+                "PB" => record.black = value,
+                "PW" => record.white = value,
+                "RU" if record.moves.is_empty() => {
+                    record.rules = Rules::parse(&value).unwrap_or_default();
+                    game = GameState::with_rules(record.rules);
+                }
+                "RE" => record.result = parse_result(&value),
+                "B" | "W" => {
+                    let n = record.moves.len() + 1;
+                    let stone = if ident == "B" { Stone::Black } else { Stone::White };
+                    if stone != game.to_move() {
+                        return Err(SgfError::WrongTurn(n));
+                    }
+                    let mv = parse_coord(&value)?;
+                    game.play(mv).map_err(|e| SgfError::IllegalMove(n, e))?;
+                    record.moves.push(mv);
+                }
+                // 第一步之前的注释属于整份棋谱，不读取
+                "C" if !record.moves.is_empty() => {
+                    record.comments.resize(record.moves.len(), String::new());
+                    record.comments[record.moves.len() - 1] = value;
+                }
+                _ => {}
+            }
+        }
+        if !record.comments.is_empty() {
+            record.comments.resize(record.moves.len(), String::new());
+        }
+        Ok(record)
+    }
+}
+
+/// 按顺序取出主线上的所有属性，遇到第一个分支结束或整个棋谱结束时停止
+fn properties(body: &str) -> Result<Vec<(String, String)>, SgfError> {
+    let mut props = Vec::new();
+    let mut ident = String::new();
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next().ok_or(SgfError::Unterminated)? {
+                        '\\' => value.push(chars.next().ok_or(SgfError::Unterminated)?),
+                        ']' => break,
+                        c => value.push(c),
+                    }
+                }
+                // 同一属性的多个值（如 `AB[aa][bb]`）沿用上一个属性名
+                props.push((ident.clone(), value));
+            }
+            ')' => break,
+            c if c.is_ascii_uppercase() => {
+                // 属性名紧跟在上一个值之后时重新开始
+                if props.last().is_some_and(|(last, _)| *last == ident) {
+                    ident.clear();
+                }
+                ident.push(c);
+            }
+            _ => {}
+        }
+    }
+    Ok(props)
+}
+
+/// 解析 `RE` 的值，其他软件写的 `B+3.5`、`W+Time`、`Draw` 等也能识别
+fn parse_result(value: &str) -> Option<Outcome> {
+    let winner = match value.chars().next()? {
+        'B' => Stone::Black,
+        'W' => Stone::White,
+        '0' | 'D' => return Some(Outcome::Draw),
+        _ => return None,
+    };
+    let reason = value[1..].trim_start_matches('+');
+    match reason.chars().next() {
+        Some('R') => Some(Outcome::Resignation(winner)),
+        Some('T') => Some(Outcome::Timeout(winner)),
+        _ => Some(Outcome::Win(winner)),
+    }
+}
+
+fn coord_char(i: usize) -> char {
+    (b'a' + i as u8) as char
+}
+
+fn parse_coord(value: &str) -> Result<Coord, SgfError> {
+    let bad = || SgfError::BadCoord(value.to_string());
+    let bytes = value.as_bytes();
+    if bytes.len() != 2 {
+        return Err(bad());
+    }
+    let index = |b: u8| (b'a'..b'a' + BOARD_SIZE as u8).contains(&b).then(|| (b - b'a') as usize);
+    Ok(Coord::new(index(bytes[0]).ok_or_else(bad)?, index(bytes[1]).ok_or_else(bad)?))
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(']', "\\]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::RuleSet;
+
+    fn record(moves: &[(usize, usize)], result: Option<Outcome>) -> SgfRecord {
+        SgfRecord {
+            black: "Player".to_string(),
+            white: "AI".to_string(),
+            rules: Rules::default(),
+            result,
+            moves: moves.iter().map(|&c| c.into()).collect(),
+            comments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn round_trips_a_finished_game() {
+        let moves = [(7, 7), (0, 0), (8, 7), (0, 1), (9, 7), (0, 2), (10, 7), (0, 3), (11, 7)];
+        let original = record(&moves, Some(Outcome::Win(Stone::Black)));
+        let text = original.to_sgf();
+        assert!(text.starts_with("(;GM[4]FF[4]SZ[15]PB[Player]PW[AI]RE[B+]"));
+        assert!(text.contains(";B[hh];W[aa]"));
+        assert_eq!(SgfRecord::parse(&text), Ok(original));
+    }
+
+    #[test]
+    fn round_trips_resignations_timeouts_and_draws() {
+        for result in [Outcome::Resignation(Stone::White), Outcome::Timeout(Stone::Black), Outcome::Draw] {
+            let original = record(&[(7, 7), (8, 8)], Some(result));
+            assert_eq!(SgfRecord::parse(&original.to_sgf()), Ok(original));
+        }
+        let parsed = SgfRecord::parse("(;RE[Draw];B[hh])").unwrap();
+        assert_eq!(parsed.result, Some(Outcome::Draw));
+        let parsed = SgfRecord::parse("(;RE[W+Resign];B[hh])").unwrap();
+        assert_eq!(parsed.result, Some(Outcome::Resignation(Stone::White)));
+    }
+
+    #[test]
+    fn round_trips_escaped_names() {
+        let mut original = record(&[(14, 14)], None);
+        original.black = r"a]b\c".to_string();
+        assert_eq!(SgfRecord::parse(&original.to_sgf()), Ok(original));
+    }
+
+    #[test]
+    fn round_trips_rules() {
+        // 六子规则下黑方的五连不结束对局
+        let moves = [(0, 0), (0, 14), (1, 0), (1, 14), (2, 0), (2, 14), (3, 0), (3, 14), (4, 0), (5, 14)];
+        let mut original = record(&moves, None);
+        original.rules = Rules::new(RuleSet::Freestyle, 6);
+        let text = original.to_sgf();
+        assert!(text.contains("RU[Freestyle 6]"));
+        assert_eq!(SgfRecord::parse(&text), Ok(original));
+    }
+
+    #[test]
+    fn reads_records_from_other_tools() {
+        let text = "(;FF[4]GM[4]SZ[15]AP[Other:1.0]C[comment];B[hh]C[first];W[hi]\n;B[ih](;W[jj])(;W[gg]))";
+        let parsed = SgfRecord::parse(text).unwrap();
+        let expected: Vec<Coord> = [(7, 7), (7, 8), (8, 7), (9, 9)].into_iter().map(Coord::from).collect();
+        assert_eq!(parsed.moves, expected);
+        assert_eq!(parsed.result, None);
+        assert_eq!(parsed.comments, ["first", "", "", ""]);
+    }
+
+    #[test]
+    fn round_trips_move_comments() {
+        let mut original = record(&[(7, 7), (8, 8), (7, 8)], None);
+        original.comments = vec!["Black 50%\nBest: H8".to_string(), String::new(), "a]b".to_string()];
+        let text = original.to_sgf();
+        assert!(text.contains(";B[hh]C[Black 50%\nBest: H8]\n;W[ii];B[hi]C[a\\]b]"));
+        assert_eq!(SgfRecord::parse(&text), Ok(original));
+    }
+
+    #[test]
+    fn rejects_invalid_records() {
+        assert_eq!(SgfRecord::parse("hello"), Err(SgfError::NotSgf));
+        assert_eq!(SgfRecord::parse("(;GM[1];B[aa])"), Err(SgfError::WrongGame("1".to_string())));
+        assert_eq!(SgfRecord::parse("(;SZ[19])"), Err(SgfError::WrongSize("19".to_string())));
+        assert_eq!(SgfRecord::parse("(;B[zz])"), Err(SgfError::BadCoord("zz".to_string())));
+        assert_eq!(SgfRecord::parse("(;W[aa])"), Err(SgfError::WrongTurn(1)));
+        assert_eq!(
+            SgfRecord::parse("(;B[aa];W[aa])"),
+            Err(SgfError::IllegalMove(2, MoveError::Occupied))
+        );
+        assert_eq!(SgfRecord::parse("(;PB[x"), Err(SgfError::Unterminated));
+    }
+}
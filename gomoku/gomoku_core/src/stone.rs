@@ -0,0 +1,37 @@
+/// 棋盘上一个交叉点的状态
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Stone {
+    #[default]
+    Empty = 0,
+    Black = 1,
+    White = 2,
+}
+
+impl Stone {
+    /// 第 ply 步（从 0 开始计数）落下的棋子颜色，黑子先手
+    pub fn for_ply(ply: usize) -> Stone {
+        if ply.is_multiple_of(2) {
+            Stone::Black
+        } else {
+            Stone::White
+        }
+    }
+
+    /// 对手的棋子颜色，空位的对手仍是空位
+    pub fn opponent(self) -> Stone {
+        match self {
+            Stone::Black => Stone::White,
+            Stone::White => Stone::Black,
+            Stone::Empty => Stone::Empty,
+        }
+    }
+
+    /// 名称
+    pub fn name(self) -> &'static str {
+        match self {
+            Stone::Empty => "Empty",
+            Stone::Black => "Black",
+            Stone::White => "White",
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Swap2 开局：用来平衡黑方先手优势的开局流程。
+//!
+//! 1. 先手方摆出黑、白、黑三子；
+//! 2. 后手方选择执黑、执白，或者再摆白、黑两子并把选择权交回；
+//! 3. 摆满五子时由先手方选择执黑或执白。
+//!
+//! 开局的棋子就是普通的落子，颜色照常黑白交替，这里只记录流程进行到哪一步，
+//! 以及双方最后各执哪一方。选定之后轮到白方落子，对局照常进行。
+
+use std::fmt;
+
+use crate::ai::{Ai, Difficulty};
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::rules::Rules;
+use crate::stone::Stone;
+
+// 局面评估差距在这个范围以内时认为双方均衡
+const BALANCED_MARGIN: i32 = 100;
+
+/// AI 作为先手方时摆出的三子：黑子居中，白子贴在斜上方，黑子隔一格斜下
+pub const AI_OPENING: [Coord; 3] = [Coord::new(7, 7), Coord::new(8, 6), Coord::new(8, 8)];
+
+/// 开局中的双方，颜色要到选择之后才确定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Seat {
+    // 先摆三子的一方
+    First,
+    // 先做选择的一方
+    Second,
+}
+
+impl Seat {
+    /// 另一方
+    pub fn other(self) -> Seat {
+        match self {
+            Seat::First => Seat::Second,
+            Seat::Second => Seat::First,
+        }
+    }
+}
+
+/// 开局进行到哪一步
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swap2Phase {
+    // 先手方摆三子
+    PlaceThree,
+    // 后手方在三种选择中选一种
+    FirstChoice,
+    // 后手方再摆两子
+    PlaceTwo,
+    // 先手方选择执黑或执白
+    SecondChoice,
+    // 颜色已经确定
+    Done,
+}
+
+/// 选择方的决定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swap2Choice {
+    TakeBlack,
+    TakeWhite,
+    // 再摆两子，只有后手方第一次选择时可用
+    PlaceTwo,
+}
+
+/// 选择失败的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Swap2Error {
+    // 现在不是选择的时候
+    NotChoosing,
+    // 五子之后只能选颜色
+    CannotPlaceTwo,
+}
+
+impl fmt::Display for Swap2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Swap2Error::NotChoosing => write!(f, "no choice is pending"),
+            Swap2Error::CannotPlaceTwo => write!(f, "two more stones were already placed"),
+        }
+    }
+}
+
+impl std::error::Error for Swap2Error {}
+
+/// 一次 Swap2 开局的进度
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Swap2 {
+    phase: Swap2Phase,
+    // 是否选择了再摆两子
+    placed_two: bool,
+    // 选定之后先手方执哪一方
+    first_stone: Option<Stone>,
+}
+
+impl Default for Swap2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Swap2 {
+    /// 开始新的开局，先手方先摆三子
+    pub fn new() -> Self {
+        Self { phase: Swap2Phase::PlaceThree, placed_two: false, first_stone: None }
+    }
+
+    /// 当前步骤
+    pub fn phase(&self) -> Swap2Phase {
+        self.phase
+    }
+
+    /// 颜色是否已经确定
+    pub fn is_done(&self) -> bool {
+        self.phase == Swap2Phase::Done
+    }
+
+    /// 开局一共摆了几子，颜色确定之后不能悔棋悔到这之前
+    pub fn stones(&self) -> usize {
+        if self.placed_two { 5 } else { 3 }
+    }
+
+    /// 现在该哪一方摆子，不在摆子阶段时返回 None
+    pub fn placer(&self) -> Option<Seat> {
+        match self.phase {
+            Swap2Phase::PlaceThree => Some(Seat::First),
+            Swap2Phase::PlaceTwo => Some(Seat::Second),
+            _ => None,
+        }
+    }
+
+    /// 现在该哪一方做选择，不在选择阶段时返回 None
+    pub fn chooser(&self) -> Option<Seat> {
+        match self.phase {
+            Swap2Phase::FirstChoice => Some(Seat::Second),
+            Swap2Phase::SecondChoice => Some(Seat::First),
+            _ => None,
+        }
+    }
+
+    /// 每次开局落子之后调用，stones 为棋盘上的棋子数，摆够之后进入选择阶段
+    pub fn advance(&mut self, stones: usize) {
+        if stones >= self.stones() {
+            self.phase = match self.phase {
+                Swap2Phase::PlaceThree => Swap2Phase::FirstChoice,
+                Swap2Phase::PlaceTwo => Swap2Phase::SecondChoice,
+                phase => phase,
+            };
+        }
+    }
+
+    /// 选择方做出决定
+    pub fn choose(&mut self, choice: Swap2Choice) -> Result<(), Swap2Error> {
+        let chooser = self.chooser().ok_or(Swap2Error::NotChoosing)?;
+        let stone = match choice {
+            Swap2Choice::TakeBlack => Stone::Black,
+            Swap2Choice::TakeWhite => Stone::White,
+            Swap2Choice::PlaceTwo if self.placed_two => return Err(Swap2Error::CannotPlaceTwo),
+            Swap2Choice::PlaceTwo => {
+                self.placed_two = true;
+                self.phase = Swap2Phase::PlaceTwo;
+                return Ok(());
+            }
+        };
+        self.first_stone = Some(match chooser {
+            Seat::First => stone,
+            Seat::Second => stone.opponent(),
+        });
+        self.phase = Swap2Phase::Done;
+        Ok(())
+    }
+
+    /// seat 一方执哪一方，颜色确定之前返回 None
+    pub fn stone_of(&self, seat: Seat) -> Option<Stone> {
+        self.first_stone.map(|stone| match seat {
+            Seat::First => stone,
+            Seat::Second => stone.opponent(),
+        })
+    }
+
+    /// AI 作为选择方时的决定：明显占优的一方直接拿走，局面均衡时再摆两子把选择交回去
+    pub fn ai_choice(&self, board: &Board, rules: Rules) -> Swap2Choice {
+        // 开局摆完之后总是轮到白方，所以从白方的角度评估
+        let white = Ai::new(Stone::White, Difficulty::Easy).with_rules(rules).advantage(board);
+        match self.phase {
+            Swap2Phase::FirstChoice if white.abs() <= BALANCED_MARGIN => Swap2Choice::PlaceTwo,
+            _ if white >= 0 => Swap2Choice::TakeWhite,
+            _ => Swap2Choice::TakeBlack,
+        }
+    }
+
+    /// AI 作为摆子方时的落子：先手方摆固定的均衡开局，后手方按正常搜索给当前颜色落子
+    ///
+    /// 固定开局整体平移到以 first 为第一子的位置，first 一般由 AI 的第一步策略选出；
+    /// 第一子摆下之后按它在棋盘上的位置平移，first 不再起作用。
+    pub fn ai_move(&self, board: &Board, rules: Rules, difficulty: Difficulty, first: Coord) -> Coord {
+        let stones = Coord::all().filter(|&c| !board.is_empty_at(c)).count();
+        let to_move = Stone::for_ply(stones);
+        // 摆三子的阶段棋盘上最多只有一颗黑子，就是第一子
+        let anchor = Coord::all().find(|&c| board.get(c) == Stone::Black).unwrap_or(first);
+        let (dcol, drow) = (anchor.col as i32 - AI_OPENING[0].col as i32, anchor.row as i32 - AI_OPENING[0].row as i32);
+        match AI_OPENING.get(stones).and_then(|coord| coord.offset(dcol, drow)) {
+            Some(coord) if self.phase == Swap2Phase::PlaceThree && board.is_empty_at(coord) => coord,
+            _ => Ai::new(to_move, difficulty).with_rules(rules).best_move(board).best_move,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_player_can_take_a_color_after_three_stones() {
+        let mut swap = Swap2::new();
+        assert_eq!(swap.placer(), Some(Seat::First));
+        swap.advance(2);
+        assert_eq!(swap.phase(), Swap2Phase::PlaceThree);
+        swap.advance(3);
+        assert_eq!(swap.chooser(), Some(Seat::Second));
+
+        swap.choose(Swap2Choice::TakeBlack).unwrap();
+        assert!(swap.is_done());
+        assert_eq!(swap.stone_of(Seat::Second), Some(Stone::Black));
+        assert_eq!(swap.stone_of(Seat::First), Some(Stone::White));
+        assert_eq!(swap.stones(), 3);
+    }
+
+    #[test]
+    fn placing_two_passes_the_choice_back() {
+        let mut swap = Swap2::new();
+        swap.advance(3);
+        swap.choose(Swap2Choice::PlaceTwo).unwrap();
+        assert_eq!(swap.placer(), Some(Seat::Second));
+        assert_eq!(swap.choose(Swap2Choice::TakeWhite), Err(Swap2Error::NotChoosing));
+
+        swap.advance(4);
+        assert_eq!(swap.phase(), Swap2Phase::PlaceTwo);
+        swap.advance(5);
+        assert_eq!(swap.chooser(), Some(Seat::First));
+        assert_eq!(swap.choose(Swap2Choice::PlaceTwo), Err(Swap2Error::CannotPlaceTwo));
+
+        swap.choose(Swap2Choice::TakeWhite).unwrap();
+        assert_eq!(swap.stone_of(Seat::First), Some(Stone::White));
+        assert_eq!(swap.stones(), 5);
+    }
+
+    #[test]
+    fn ai_takes_the_side_with_a_clear_advantage() {
+        // 黑方已经有活三
+        let board = Board::with_stones(&[(6, 7), (7, 7), (8, 7)], &[(3, 3)]);
+        let mut swap = Swap2::new();
+        swap.advance(3);
+        assert_eq!(swap.ai_choice(&board, Rules::default()), Swap2Choice::TakeBlack);
+    }
+
+    #[test]
+    fn ai_opening_is_balanced_and_legal() {
+        let mut swap = Swap2::new();
+        let mut board = Board::new();
+        for ply in 0..3 {
+            let mv = swap.ai_move(&board, Rules::default(), Difficulty::Easy, Coord::CENTER);
+            board.set(mv, Stone::for_ply(ply));
+            swap.advance(ply + 1);
+        }
+        assert_eq!(swap.phase(), Swap2Phase::FirstChoice);
+        assert_eq!(swap.ai_choice(&board, Rules::default()), Swap2Choice::PlaceTwo);
+
+        // 第一子不在天元时整个开局跟着平移
+        let (mut swap, mut board) = (Swap2::new(), Board::new());
+        for (ply, coord) in AI_OPENING.iter().enumerate() {
+            let mv = swap.ai_move(&board, Rules::default(), Difficulty::Easy, Coord::new(5, 9));
+            assert_eq!(mv, coord.offset(-2, 2).unwrap());
+            board.set(mv, Stone::for_ply(ply));
+            swap.advance(ply + 1);
+        }
+    }
+}
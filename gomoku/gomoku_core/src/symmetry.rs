@@ -0,0 +1,177 @@
+//! 棋盘的 8 种对称变换和局面的规范化。
+//!
+//! 旋转和翻转得到的局面在棋理上完全相同。开局库、开局数据库和分享局面代码时，
+//! 先把局面变换到规范形式，对称的局面就能得到同一个键。
+//!
+//! 规范形式是 8 种变换结果中按 `Coord::all()` 顺序逐点比较最小的那个棋盘。
+
+use crate::board::{Board, BOARD_SIZE};
+use crate::coord::Coord;
+
+// 最后一行或一列的下标
+const LAST: usize = BOARD_SIZE - 1;
+
+/// 正方形棋盘的一种对称变换
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    // 顺时针旋转
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // 左右翻转
+    FlipHorizontal,
+    // 上下翻转
+    FlipVertical,
+    // 沿左上到右下的对角线翻转
+    FlipDiagonal,
+    // 沿右上到左下的对角线翻转
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// 变换一个交叉点
+    pub fn apply(self, coord: Coord) -> Coord {
+        let Coord { col, row } = coord;
+        match self {
+            Symmetry::Identity => Coord::new(col, row),
+            Symmetry::Rotate90 => Coord::new(LAST - row, col),
+            Symmetry::Rotate180 => Coord::new(LAST - col, LAST - row),
+            Symmetry::Rotate270 => Coord::new(row, LAST - col),
+            Symmetry::FlipHorizontal => Coord::new(LAST - col, row),
+            Symmetry::FlipVertical => Coord::new(col, LAST - row),
+            Symmetry::FlipDiagonal => Coord::new(row, col),
+            Symmetry::FlipAntiDiagonal => Coord::new(LAST - row, LAST - col),
+        }
+    }
+
+    /// 逆变换，用来把规范局面上的落子换回原来的局面
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+
+    /// 变换整个棋盘
+    pub fn apply_board(self, board: &Board) -> Board {
+        let mut result = Board::new();
+        for coord in Coord::all() {
+            result.set(self.apply(coord), board.get(coord));
+        }
+        result
+    }
+}
+
+/// 把局面变换到规范形式，返回规范棋盘和使用的变换
+///
+/// 多个变换得到同一个棋盘时（局面本身对称），返回 `Symmetry::ALL` 中靠前的那个。
+pub fn canonicalize(board: &Board) -> (Board, Symmetry) {
+    Symmetry::ALL
+        .iter()
+        .map(|&symmetry| (symmetry.apply_board(board), symmetry))
+        .min_by(|(a, _), (b, _)| cells(a).cmp(cells(b)))
+        .expect("there is always at least one symmetry")
+}
+
+/// 局面的哈希值，使用 FNV-1a，不同版本之间保持一致
+pub fn position_hash(board: &Board) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for cell in cells(board) {
+        hash ^= cell as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// 规范形式的哈希值，对称的局面得到同一个值
+pub fn canonical_hash(board: &Board) -> u64 {
+    position_hash(&canonicalize(board).0)
+}
+
+/// 按 `Coord::all()` 顺序排列的所有交叉点
+fn cells(board: &Board) -> impl Iterator<Item = u8> + '_ {
+    Coord::all().map(|c| board.get(c) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stone::Stone;
+
+    // 没有任何对称性的局面
+    fn asymmetric() -> Board {
+        let mut board = Board::new();
+        board.set(Coord::new(7, 7), Stone::Black);
+        board.set(Coord::new(8, 6), Stone::White);
+        board.set(Coord::new(9, 6), Stone::Black);
+        board.set(Coord::new(2, 12), Stone::White);
+        board
+    }
+
+    #[test]
+    fn transforms_corners_and_center() {
+        let corner = Coord::new(0, 0);
+        assert_eq!(Symmetry::Rotate90.apply(corner), Coord::new(14, 0));
+        assert_eq!(Symmetry::Rotate180.apply(corner), Coord::new(14, 14));
+        assert_eq!(Symmetry::Rotate270.apply(corner), Coord::new(0, 14));
+        assert_eq!(Symmetry::FlipDiagonal.apply(Coord::new(3, 1)), Coord::new(1, 3));
+        for symmetry in Symmetry::ALL {
+            assert_eq!(symmetry.apply(Coord::CENTER), Coord::CENTER);
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_each_symmetry() {
+        for symmetry in Symmetry::ALL {
+            for coord in Coord::all() {
+                assert_eq!(symmetry.inverse().apply(symmetry.apply(coord)), coord);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_positions_share_a_hash() {
+        let board = asymmetric();
+        let expected = canonical_hash(&board);
+        let mut plain = Vec::new();
+        for symmetry in Symmetry::ALL {
+            let transformed = symmetry.apply_board(&board);
+            assert_eq!(canonical_hash(&transformed), expected, "{:?}", symmetry);
+            assert_eq!(canonicalize(&transformed).0, canonicalize(&board).0);
+            plain.push(position_hash(&transformed));
+        }
+        // 变换前的哈希值各不相同，说明相等不是因为哈希太粗
+        plain.sort_unstable();
+        plain.dedup();
+        assert_eq!(plain.len(), 8);
+    }
+
+    #[test]
+    fn canonicalize_reports_the_symmetry_used() {
+        let board = Symmetry::Rotate90.apply_board(&asymmetric());
+        let (canonical, symmetry) = canonicalize(&board);
+        assert_eq!(symmetry.apply_board(&board), canonical);
+        assert_eq!(symmetry.inverse().apply_board(&canonical), board);
+    }
+
+    #[test]
+    fn different_positions_have_different_hashes() {
+        let mut other = asymmetric();
+        other.set(Coord::new(0, 0), Stone::Black);
+        assert_ne!(canonical_hash(&other), canonical_hash(&asymmetric()));
+        assert_ne!(canonical_hash(&Board::new()), canonical_hash(&asymmetric()));
+    }
+}
@@ -0,0 +1,221 @@
+//! 威胁识别：落子之后能形成几子连、两端是否开放。
+//!
+//! 和 AI 的棋型评估一样用 `pattern::LineView` 沿四个方向数连子，供棋谱注释、解题提示
+//! 和棋盘上的威胁标注共用。
+
+use crate::board::{Board, BOARD_SIZE, DIRECTIONS};
+use crate::coord::Coord;
+use crate::pattern::LineView;
+use crate::rules::Rules;
+use crate::stone::Stone;
+
+/// 威胁等级，从弱到强排列
+///
+/// 等级相对于获胜需要的连子数 k 命名：`Five` 为连成 k 子，`Four` 差一子，`Three` 差两子。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThreatLevel {
+    None,
+    Three,
+    OpenThree,
+    Four,
+    OpenFour,
+    Five,
+}
+
+impl ThreatLevel {
+    /// 威胁名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ThreatLevel::None => "none",
+            ThreatLevel::Three => "three",
+            ThreatLevel::OpenThree => "open three",
+            ThreatLevel::Four => "four",
+            ThreatLevel::OpenFour => "open four",
+            ThreatLevel::Five => "five in a row",
+        }
+    }
+}
+
+/// 在某个空位落子后形成的威胁
+#[derive(Clone, Copy, Debug)]
+pub struct Threat {
+    // 落子位置
+    pub mv: Coord,
+    // 威胁等级
+    pub level: ThreatLevel,
+    // 形成威胁的连子两端（包含落子位置）
+    pub line: (Coord, Coord),
+}
+
+/// 评估 stone 方在空位 mv 落子后形成的最强威胁，length 为获胜需要的连子数
+pub fn threat_at(board: &Board, mv: Coord, stone: Stone, length: usize) -> Threat {
+    let mut best = Threat {
+        mv,
+        level: ThreatLevel::None,
+        line: (mv, mv),
+    };
+    for d in 0..DIRECTIONS.len() {
+        let (level, line) = direction_threat(board, mv, d, stone, length);
+        if level > best.level {
+            best.level = level;
+            best.line = line;
+        }
+    }
+    best
+}
+
+/// stone 方在空位 mv 落子后第 d 个方向上形成的威胁等级和连子两端
+fn direction_threat(board: &Board, mv: Coord, d: usize, stone: Stone, length: usize) -> (ThreatLevel, (Coord, Coord)) {
+    let (dc, dr) = DIRECTIONS[d];
+    let view = LineView::new(board, mv, d, stone);
+    let (behind, start_blocked) = view.backward(BOARD_SIZE as u32);
+    let (ahead, end_blocked) = view.forward(BOARD_SIZE as u32);
+    let (behind, ahead) = (behind as i32, ahead as i32);
+    let start = mv.offset(-dc * behind, -dr * behind).expect("runs stay on the board");
+    let end = mv.offset(dc * ahead, dr * ahead).expect("runs stay on the board");
+    let count = (behind + ahead + 1) as usize;
+    // 连子外侧是空位才算开放的一端
+    let open = !start_blocked as u8 + !end_blocked as u8;
+    let level = match (length.saturating_sub(count), open) {
+        (0, _) => ThreatLevel::Five,
+        (1, 2) => ThreatLevel::OpenFour,
+        (1, 1) => ThreatLevel::Four,
+        (2, 2) => ThreatLevel::OpenThree,
+        (2, 1) => ThreatLevel::Three,
+        _ => ThreatLevel::None,
+    };
+    (level, (start, end))
+}
+
+/// 一方在某个空位落子后的威胁，用于在棋盘上标注
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointThreat {
+    pub mv: Coord,
+    pub stone: Stone,
+    // 四个方向中最强的威胁
+    pub level: ThreatLevel,
+    // 是否同时在两个以上方向形成活三或更强的威胁（双三、四三、双四），对方通常已经挡不住
+    pub double: bool,
+}
+
+/// 双方所有落子后能形成活三或更强威胁的空位，先黑后白，各自按坐标顺序排列
+///
+/// 连珠规则下黑方的禁手点不能落子，不算威胁。
+pub fn threat_map(board: &Board, rules: Rules) -> Vec<PointThreat> {
+    let length = rules.win_length();
+    let mut threats = Vec::new();
+    for stone in [Stone::Black, Stone::White] {
+        for mv in Coord::all().filter(|&c| board.is_empty_at(c)) {
+            let mut levels = [ThreatLevel::None; DIRECTIONS.len()];
+            for (d, level) in levels.iter_mut().enumerate() {
+                *level = direction_threat(board, mv, d, stone, length).0;
+            }
+            let level = levels.into_iter().max().unwrap_or(ThreatLevel::None);
+            if level < ThreatLevel::OpenThree || rules.forbidden(board, mv, stone).is_some() {
+                continue;
+            }
+            let double = levels.iter().filter(|&&l| l >= ThreatLevel::OpenThree).count() >= 2;
+            threats.push(PointThreat { mv, stone, level, double });
+        }
+    }
+    threats
+}
+
+/// 找出 stone 方所有最强的威胁点
+pub fn best_threats(board: &Board, stone: Stone, length: usize) -> Vec<Threat> {
+    let mut best: Vec<Threat> = Vec::new();
+    for mv in Coord::all().filter(|&c| board.is_empty_at(c)) {
+        let threat = threat_at(board, mv, stone, length);
+        match best.first().map(|t| t.level) {
+            Some(level) if threat.level < level => {}
+            Some(level) if threat.level == level => best.push(threat),
+            _ => best = vec![threat],
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_line_shapes() {
+        let mut board = Board::new();
+        board.set(Coord::new(6, 7), Stone::Black);
+        board.set(Coord::new(7, 7), Stone::Black);
+        assert_eq!(threat_at(&board, Coord::new(8, 7), Stone::Black, 5).level, ThreatLevel::OpenThree);
+
+        board.set(Coord::new(8, 7), Stone::Black);
+        let threat = threat_at(&board, Coord::new(9, 7), Stone::Black, 5);
+        assert_eq!(threat.level, ThreatLevel::OpenFour);
+        assert_eq!(threat.line, (Coord::new(6, 7), Coord::new(9, 7)));
+
+        board.set(Coord::new(5, 7), Stone::White);
+        assert_eq!(threat_at(&board, Coord::new(9, 7), Stone::Black, 5).level, ThreatLevel::Four);
+        assert_eq!(threat_at(&board, Coord::new(9, 7), Stone::White, 5).level, ThreatLevel::None);
+    }
+
+    #[test]
+    fn edge_counts_as_blocked() {
+        let mut board = Board::new();
+        for y in 0..3 {
+            board.set(Coord::new(0, y), Stone::White);
+        }
+        assert_eq!(threat_at(&board, Coord::new(0, 3), Stone::White, 5).level, ThreatLevel::Four);
+    }
+
+    #[test]
+    fn best_threats_prefers_five() {
+        let mut board = Board::new();
+        for x in 2..6 {
+            board.set(Coord::new(x, 2), Stone::Black);
+        }
+        board.set(Coord::new(1, 2), Stone::White);
+        let threats = best_threats(&board, Stone::Black, 5);
+        assert_eq!(threats.len(), 1);
+        assert_eq!(threats[0].mv, Coord::new(6, 2));
+        assert_eq!(threats[0].level, ThreatLevel::Five);
+    }
+
+    #[test]
+    fn threat_map_marks_both_sides_and_double_threats() {
+        let mut board = Board::new();
+        // 黑方横竖各两子，交点 (7, 7) 落子形成双活三
+        for c in [(5, 7), (6, 7), (7, 5), (7, 6)] {
+            board.set(c.into(), Stone::Black);
+        }
+        // 白方斜线三子，两端落子都成活四
+        for c in [(10, 10), (11, 11), (12, 12)] {
+            board.set(c.into(), Stone::White);
+        }
+        let threats = threat_map(&board, Rules::default());
+        let at = |c: (usize, usize), stone: Stone| threats.iter().find(|t| t.mv == Coord::from(c) && t.stone == stone).copied();
+
+        let cross = at((7, 7), Stone::Black).unwrap();
+        assert!(cross.double && cross.level == ThreatLevel::OpenThree, "{:?}", cross);
+        let single = at((4, 7), Stone::Black).unwrap();
+        assert!(!single.double && single.level == ThreatLevel::OpenThree, "{:?}", single);
+        assert_eq!(at((9, 9), Stone::White).map(|t| t.level), Some(ThreatLevel::OpenFour));
+        assert_eq!(at((13, 13), Stone::White).map(|t| t.level), Some(ThreatLevel::OpenFour));
+        // 只有一子的方向和远离棋子的空位不标注
+        assert!(at((0, 0), Stone::Black).is_none() && at((7, 7), Stone::White).is_none());
+        assert!(threats.iter().all(|t| t.level >= ThreatLevel::OpenThree));
+
+        // 连珠规则下黑方的双三是禁手，不算威胁
+        let renju = threat_map(&board, Rules::new(crate::rules::RuleSet::Renju, 5));
+        assert!(!renju.iter().any(|t| t.mv == Coord::new(7, 7) && t.stone == Stone::Black));
+    }
+
+    #[test]
+    fn levels_follow_the_win_length() {
+        let mut board = Board::new();
+        board.set(Coord::new(6, 7), Stone::Black);
+        board.set(Coord::new(7, 7), Stone::Black);
+        assert_eq!(threat_at(&board, Coord::new(8, 7), Stone::Black, 4).level, ThreatLevel::OpenFour);
+        assert_eq!(threat_at(&board, Coord::new(8, 7), Stone::Black, 6).level, ThreatLevel::None);
+
+        board.set(Coord::new(8, 7), Stone::Black);
+        assert_eq!(threat_at(&board, Coord::new(9, 7), Stone::Black, 4).level, ThreatLevel::Five);
+    }
+}
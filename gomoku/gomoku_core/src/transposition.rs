@@ -0,0 +1,130 @@
+//! 置换表：按 Zobrist 哈希记住搜索过的局面的得分，不同顺序走到同一局面时不必再搜一遍。
+
+use crate::coord::Coord;
+
+/// 默认的表项个数，必须是 2 的幂
+pub const DEFAULT_SIZE: usize = 1 << 16;
+
+/// 保存的得分与真实得分的关系，由搜索窗口决定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    // 得分就是真实得分
+    Exact,
+    // 发生了 beta 剪枝，真实得分不低于保存的得分
+    Lower,
+    // 没有一步超过 alpha，真实得分不高于保存的得分
+    Upper,
+}
+
+/// 一个局面的搜索结果
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    // 从这个局面往下搜索的层数
+    pub depth: u32,
+    pub score: i32,
+    pub bound: Bound,
+    pub best_move: Option<Coord>,
+}
+
+impl Entry {
+    /// 在 (alpha, beta) 窗口内搜索 depth 层时，能否直接使用保存的得分
+    pub fn cutoff(&self, depth: u32, (alpha, beta): (i32, i32)) -> Option<i32> {
+        if self.depth < depth {
+            return None;
+        }
+        match self.bound {
+            Bound::Exact => Some(self.score),
+            Bound::Lower if self.score >= beta => Some(self.score),
+            Bound::Upper if self.score <= alpha => Some(self.score),
+            _ => None,
+        }
+    }
+}
+
+/// 固定大小的置换表，哈希冲突时保留搜索层数更深的结果
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    // 查询次数和命中次数，用于衡量置换表的效果
+    probes: u64,
+    hits: u64,
+}
+
+impl TranspositionTable {
+    /// 创建有 size 个表项的置换表，size 向上取整到 2 的幂
+    pub fn new(size: usize) -> Self {
+        Self { entries: vec![None; size.max(1).next_power_of_two()], probes: 0, hits: 0 }
+    }
+
+    /// 查询局面 key 的搜索结果
+    pub fn probe(&mut self, key: u64) -> Option<Entry> {
+        self.probes += 1;
+        let entry = self.entries[self.index(key)].filter(|entry| entry.key == key);
+        if entry.is_some() {
+            self.hits += 1;
+        }
+        entry
+    }
+
+    /// 保存搜索结果，同一个位置上已有更深的其他局面时不覆盖
+    pub fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        if slot.is_none_or(|old| old.key == entry.key || old.depth <= entry.depth) {
+            *slot = Some(entry);
+        }
+    }
+
+    /// 查询次数
+    pub fn probes(&self) -> u64 {
+        self.probes
+    }
+
+    /// 命中次数
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, depth: u32, score: i32, bound: Bound) -> Entry {
+        Entry { key, depth, score, bound, best_move: None }
+    }
+
+    #[test]
+    fn bounds_decide_when_a_stored_score_is_usable() {
+        let window = (-10, 10);
+        assert_eq!(entry(1, 3, 5, Bound::Exact).cutoff(3, window), Some(5));
+        assert_eq!(entry(1, 2, 5, Bound::Exact).cutoff(3, window), None);
+        assert_eq!(entry(1, 3, 12, Bound::Lower).cutoff(2, window), Some(12));
+        assert_eq!(entry(1, 3, 5, Bound::Lower).cutoff(3, window), None);
+        assert_eq!(entry(1, 3, -12, Bound::Upper).cutoff(3, window), Some(-12));
+        assert_eq!(entry(1, 3, 5, Bound::Upper).cutoff(3, window), None);
+    }
+
+    #[test]
+    fn keeps_deeper_results_on_collisions() {
+        let mut table = TranspositionTable::new(4);
+        table.store(entry(1, 3, 0, Bound::Exact));
+        // 5 和 1 落在同一个位置
+        table.store(entry(5, 1, 0, Bound::Exact));
+        assert_eq!(table.probe(1).map(|e| e.depth), Some(3));
+        assert_eq!(table.probe(5), None);
+        table.store(entry(5, 4, 0, Bound::Exact));
+        assert_eq!(table.probe(5).map(|e| e.depth), Some(4));
+        assert_eq!((table.probes(), table.hits()), (3, 2));
+    }
+}
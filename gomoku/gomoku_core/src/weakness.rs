@@ -0,0 +1,228 @@
+//! 弱点报告：逐步分析玩家下过的对局，统计几类常见失误出现的比例。
+//!
+//! 每一类技能记下“有多少次机会”和“其中做对了几次”，多局累加之后就能看出玩家
+//! 经常在哪里出错，例如十次该挡活三的时候有四次没挡。威胁类的技能只看棋形，
+//! 各阶段的准确率和中等难度 AI 认为最好的几步比较。
+
+use crate::ai::{Ai, Difficulty};
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::rules::Rules;
+use crate::stone::Stone;
+use crate::threat::{self, ThreatLevel};
+
+// 比较准确率时使用的难度和候选数，落在 AI 的前几个候选里就算准确
+const ACCURACY_DIFFICULTY: Difficulty = Difficulty::Medium;
+const ACCURACY_CANDIDATES: usize = 3;
+
+// 前几手多半是开局定式，不计入准确率
+const FIRST_RATED_MOVE: usize = 5;
+const LAST_OPENING_MOVE: usize = 10;
+const LAST_MIDDLEGAME_MOVE: usize = 30;
+
+/// 弱点报告统计的技能
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Skill {
+    // 自己能连成五子时连上
+    FinishFive,
+    // 挡住对手的四
+    BlockFour,
+    // 挡住对手的活三，或者用自己的四抢先
+    BlockOpenThree,
+    // 各阶段的落子和 AI 的候选一致
+    OpeningAccuracy,
+    MiddlegameAccuracy,
+    EndgameAccuracy,
+}
+
+impl Skill {
+    pub const ALL: [Skill; 6] = [
+        Skill::FinishFive,
+        Skill::BlockFour,
+        Skill::BlockOpenThree,
+        Skill::OpeningAccuracy,
+        Skill::MiddlegameAccuracy,
+        Skill::EndgameAccuracy,
+    ];
+
+    /// 保存时使用的名字
+    pub fn key(&self) -> &'static str {
+        match self {
+            Skill::FinishFive => "finish_five",
+            Skill::BlockFour => "block_four",
+            Skill::BlockOpenThree => "block_open_three",
+            Skill::OpeningAccuracy => "opening_accuracy",
+            Skill::MiddlegameAccuracy => "middlegame_accuracy",
+            Skill::EndgameAccuracy => "endgame_accuracy",
+        }
+    }
+
+    /// 报告里的一句话，rate 为这项技能的统计
+    pub fn describe(&self, rate: Rate) -> String {
+        let missed = 100 - rate.percent();
+        match self {
+            Skill::FinishFive => format!("You miss a winning five {}% of the time", missed),
+            Skill::BlockFour => format!("You miss blocking a four {}% of the time", missed),
+            Skill::BlockOpenThree => format!("You miss open-three blocks {}% of the time", missed),
+            Skill::OpeningAccuracy => {
+                format!("Your move {}-{} accuracy is {}%", FIRST_RATED_MOVE, LAST_OPENING_MOVE, rate.percent())
+            }
+            Skill::MiddlegameAccuracy => format!(
+                "Your move {}-{} accuracy is {}%",
+                LAST_OPENING_MOVE + 1,
+                LAST_MIDDLEGAME_MOVE,
+                rate.percent()
+            ),
+            Skill::EndgameAccuracy => {
+                format!("Your accuracy after move {} is {}%", LAST_MIDDLEGAME_MOVE, rate.percent())
+            }
+        }
+    }
+}
+
+/// 一项技能的机会次数和做对的次数
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rate {
+    pub hits: u32,
+    pub chances: u32,
+}
+
+impl Rate {
+    /// 做对的百分比，没有机会时按 100% 计
+    pub fn percent(&self) -> u32 {
+        if self.chances == 0 {
+            return 100;
+        }
+        (self.hits as u64 * 100 / self.chances as u64) as u32
+    }
+
+    fn add(&mut self, hit: bool) {
+        self.chances += 1;
+        self.hits += hit as u32;
+    }
+}
+
+/// 各项技能的统计，可以逐局累加
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SkillReport {
+    rates: [Rate; Skill::ALL.len()],
+}
+
+impl SkillReport {
+    /// 一项技能的统计
+    pub fn rate(&self, skill: Skill) -> Rate {
+        self.rates[skill as usize]
+    }
+
+    /// 改写一项技能的统计，读取保存的报告时使用
+    pub fn set_rate(&mut self, skill: Skill, rate: Rate) {
+        self.rates[skill as usize] = rate;
+    }
+
+    /// 把另一份统计累加进来
+    pub fn merge(&mut self, other: &SkillReport) {
+        for (rate, other) in self.rates.iter_mut().zip(other.rates) {
+            rate.hits += other.hits;
+            rate.chances += other.chances;
+        }
+    }
+
+    /// 至少有 min_chances 次机会的技能，做得最差的排在前面
+    pub fn weakest(&self, min_chances: u32) -> Vec<(Skill, Rate)> {
+        let mut skills: Vec<_> = Skill::ALL
+            .iter()
+            .map(|&skill| (skill, self.rate(skill)))
+            .filter(|(_, rate)| rate.chances >= min_chances)
+            .collect();
+        skills.sort_by_key(|(_, rate)| rate.percent());
+        skills
+    }
+
+    fn add(&mut self, skill: Skill, hit: bool) {
+        self.rates[skill as usize].add(hit);
+    }
+}
+
+/// 分析一局棋里 player 一方的每一步
+pub fn analyze_game(moves: &[Coord], player: Stone, rules: Rules) -> SkillReport {
+    let mut report = SkillReport::default();
+    let ai = Ai::new(player, ACCURACY_DIFFICULTY).with_rules(rules);
+    let mut board = Board::new();
+    for (i, &mv) in moves.iter().enumerate() {
+        let stone = Stone::for_ply(i);
+        if stone == player {
+            analyze_move(&mut report, &board, mv, i + 1, &ai, rules);
+        }
+        board.set(mv, stone);
+        if rules.is_win(&board, mv) {
+            break;
+        }
+    }
+    report
+}
+
+/// 分析第 number 手（从 1 开始）：board 为落子之前的局面
+fn analyze_move(report: &mut SkillReport, board: &Board, mv: Coord, number: usize, ai: &Ai, rules: Rules) {
+    let length = rules.win_length();
+    let player = ai.stone();
+    let best_level = |board: &Board, stone: Stone| {
+        threat::best_threats(board, stone, length).first().map_or(ThreatLevel::None, |t| t.level)
+    };
+    let own = best_level(board, player);
+    let opponent = best_level(board, player.opponent());
+    let mut after = *board;
+    after.set(mv, player);
+    let made = threat::threat_at(board, mv, player, length).level;
+
+    // 按紧急程度只统计一项：能赢时看有没有赢，对手要连五时看有没有挡，依此类推
+    if own == ThreatLevel::Five {
+        report.add(Skill::FinishFive, rules.is_win(&after, mv));
+    } else if opponent == ThreatLevel::Five {
+        report.add(Skill::BlockFour, best_level(&after, player.opponent()) < ThreatLevel::Five);
+    } else if opponent == ThreatLevel::OpenFour {
+        let blocked = best_level(&after, player.opponent()) < ThreatLevel::OpenFour;
+        report.add(Skill::BlockOpenThree, blocked || made >= ThreatLevel::Four);
+    }
+
+    let skill = match number {
+        n if n < FIRST_RATED_MOVE => return,
+        n if n <= LAST_OPENING_MOVE => Skill::OpeningAccuracy,
+        n if n <= LAST_MIDDLEGAME_MOVE => Skill::MiddlegameAccuracy,
+        _ => Skill::EndgameAccuracy,
+    };
+    let candidates = ai.top_moves(board, ACCURACY_CANDIDATES);
+    report.add(skill, candidates.iter().any(|analysis| analysis.best_move == mv));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(moves: &[(usize, usize)]) -> Vec<Coord> {
+        moves.iter().map(|&c| c.into()).collect()
+    }
+
+    #[test]
+    fn counts_missed_blocks_and_wins() {
+        // 白方活三之后黑方没挡，白方冲成活四之后黑方挡了一头，白方连五
+        let moves = coords(&[(0, 0), (7, 7), (0, 2), (8, 7), (0, 4), (9, 7), (0, 6), (10, 7), (6, 7), (11, 7)]);
+        let report = analyze_game(&moves, Stone::Black, Rules::default());
+        assert_eq!(report.rate(Skill::BlockOpenThree), Rate { hits: 0, chances: 1 });
+        assert_eq!(report.rate(Skill::BlockFour), Rate { hits: 0, chances: 1 });
+        assert_eq!(report.rate(Skill::FinishFive).chances, 0);
+        assert_eq!(report.rate(Skill::OpeningAccuracy).chances, 3);
+
+        // 白方一方只有一次连五的机会并且连上了
+        let white = analyze_game(&moves, Stone::White, Rules::default());
+        assert_eq!(white.rate(Skill::FinishFive), Rate { hits: 1, chances: 1 });
+
+        let mut total = report;
+        total.merge(&report);
+        assert_eq!(total.rate(Skill::BlockFour), Rate { hits: 0, chances: 2 });
+        let weakest = total.weakest(2);
+        assert_eq!(weakest[0].1.percent(), 0);
+        assert!(weakest.iter().all(|(_, rate)| rate.chances >= 2));
+        let text = Skill::BlockOpenThree.describe(Rate { hits: 6, chances: 10 });
+        assert_eq!(text, "You miss open-three blocks 40% of the time");
+    }
+}
@@ -0,0 +1,65 @@
+//! 胜率模型：把 AI 对局面的判断（`Evaluation` 的得分）换算成这一方获胜的概率。
+//!
+//! 得分是评估函数的内部单位，大小并不直观。这里按逻辑斯蒂曲线 1 / (1 + e^(-score / SCALE))
+//! 换算成胜率，`SCALE` 用 `gomoku --calibrate` 在自对弈数据上拟合。曲线查表并线性插值，
+//! 和库里其他计算一样只用整数运算。
+
+/// 逻辑斯蒂曲线的尺度：得分为 SCALE 时胜率约为 73%
+///
+/// 由 `gomoku --calibrate 400 --difficulty medium --seed 1` 拟合，修改评估函数之后应重新拟合。
+/// 同等水平的 AI 自对弈绝大多数下满棋盘成和，没有搜索到强制胜负时得分对结果的影响很小，
+/// 拟合出的曲线很平缓（取到了拟合范围的上限）；搜索到强制胜负时胜率仍然是 0 或 100%。
+pub const SCALE: i32 = 20_000;
+
+// 曲线在 t = 0, 1/4, 2/4, … 8 处的取值，单位为万分之一；t 更大时按 100% 计
+const CURVE_STEPS: i64 = 4;
+const CURVE: [i64; 33] = [
+    5000, 5622, 6225, 6792, 7311, 7773, 8176, 8520, 8808, 9047, 9241, 9399, 9526, 9627, 9707, 9770, 9820, 9859, 9890,
+    9914, 9933, 9948, 9959, 9968, 9975, 9981, 9985, 9988, 9991, 9993, 9994, 9996, 9997,
+];
+
+/// 得分 score 对应的胜率，以千分之一为单位，0 到 1000
+pub fn win_permille(score: i32) -> u32 {
+    win_permille_with(score, SCALE)
+}
+
+/// 按尺度 scale 换算的胜率，用于拟合时比较不同的尺度
+pub fn win_permille_with(score: i32, scale: i32) -> u32 {
+    let scale = scale.max(1) as i64;
+    // 按 |score| 查表，负分由对称性得到
+    let steps = score.unsigned_abs() as i64 * CURVE_STEPS;
+    let index = (steps / scale) as usize;
+    let ten_thousandths = match (CURVE.get(index), CURVE.get(index + 1)) {
+        (Some(&low), Some(&high)) => low + (high - low) * (steps % scale) / scale,
+        _ => 10_000,
+    };
+    let permille = ((ten_thousandths + 5) / 10) as u32;
+    if score < 0 { 1000 - permille } else { permille }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_a_symmetric_logistic_curve() {
+        assert_eq!(win_permille(0), 500);
+        assert_eq!(win_permille(SCALE), 731);
+        assert_eq!(win_permille(-SCALE), 269);
+        // 插值在表格的两点之间
+        assert_eq!(win_permille_with(50, 200), 562);
+        assert_eq!(win_permille_with(25, 200), 531);
+        // 强制胜负时是确定的
+        assert_eq!(win_permille(1_000_000), 1000);
+        assert_eq!(win_permille(-1_000_000), 0);
+        assert_eq!(win_permille(i32::MIN), 0);
+
+        let mut previous = 0;
+        for score in (-10 * SCALE..=10 * SCALE).step_by(7) {
+            let permille = win_permille(score);
+            assert!(permille >= previous, "{} at {}", permille, score);
+            assert_eq!(permille + win_permille(-score), 1000);
+            previous = permille;
+        }
+    }
+}
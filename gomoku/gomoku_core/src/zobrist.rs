@@ -0,0 +1,119 @@
+//! Zobrist 哈希：每个交叉点上的黑子、白子各对应一个随机数，局面的哈希是所有棋子对应的
+//! 随机数的异或。落子和提子都只需要异或一次，搜索中可以随着落子增量更新。
+//!
+//! 随机数由固定的种子生成，同一个局面在每次运行中得到同样的哈希，但不保证不同版本之间一致，
+//! 需要保存到文件的哈希请使用 `symmetry::position_hash`。
+
+use crate::board::{Board, BOARD_SIZE};
+use crate::coord::Coord;
+use crate::stone::Stone;
+
+/// 轮到白方时额外异或的值，区分棋子相同但轮到不同一方的局面
+pub const WHITE_TO_MOVE: u64 = mix(2 * (BOARD_SIZE * BOARD_SIZE) as u64);
+
+// 每个交叉点上黑子和白子的随机数，按 `col * BOARD_SIZE + row` 排列，序号接着用于 `WHITE_TO_MOVE`
+const KEYS: [[u64; 2]; BOARD_SIZE * BOARD_SIZE] = {
+    let mut keys = [[0; 2]; BOARD_SIZE * BOARD_SIZE];
+    let mut i = 0;
+    while i < keys.len() {
+        keys[i] = [mix(2 * i as u64), mix(2 * i as u64 + 1)];
+        i += 1;
+    }
+    keys
+};
+
+/// stone 放在 coord 上对应的随机数，空位为 0
+pub fn key(coord: Coord, stone: Stone) -> u64 {
+    match stone {
+        Stone::Empty => 0,
+        Stone::Black => KEYS[coord.col * BOARD_SIZE + coord.row][0],
+        Stone::White => KEYS[coord.col * BOARD_SIZE + coord.row][1],
+    }
+}
+
+/// 从头计算局面的哈希，与 `Board::hash` 增量维护的值相同，用于校验
+pub fn hash(board: &Board) -> u64 {
+    Coord::all().fold(0, |hash, c| hash ^ key(c, board.get(c)))
+}
+
+/// 轮到 stone 方时局面的哈希
+pub fn hash_with_side(board: &Board, stone: Stone) -> u64 {
+    match stone {
+        Stone::White => board.hash() ^ WHITE_TO_MOVE,
+        _ => board.hash(),
+    }
+}
+
+/// SplitMix64 的输出函数，把序号打散成随机数
+const fn mix(index: u64) -> u64 {
+    let mut z = index.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// 类似国际象棋的 perft：从 board 出发枚举 depth 步以内的所有落子顺序，
+    /// 每一步都检查增量维护的哈希与从头计算的一致，撤销后回到原值，返回走到的局面数
+    fn perft(board: &mut Board, stone: Stone, depth: u32, moves: &[Coord]) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let before = board.hash();
+        let mut count = 0;
+        for &mv in moves {
+            if !board.is_empty_at(mv) {
+                continue;
+            }
+            board.set(mv, stone);
+            assert_eq!(board.hash(), hash(board), "after {:?} at {:?}", stone, mv);
+            assert_ne!(board.hash(), before);
+            count += perft(board, stone.opponent(), depth - 1, moves);
+            board.set(mv, Stone::Empty);
+            assert_eq!(board.hash(), before);
+        }
+        count
+    }
+
+    #[test]
+    fn incremental_hash_matches_a_full_recount() {
+        let mut board = Board::new();
+        assert_eq!(board.hash(), 0);
+        board.set(Coord::CENTER, Stone::Black);
+        board.set(Coord::new(8, 8), Stone::White);
+        let area: Vec<Coord> = Coord::all().filter(|c| c.col.abs_diff(7) <= 2 && c.row.abs_diff(7) <= 2).collect();
+        // 5 * 5 的区域里已有 2 子，剩下 23 个空位
+        assert_eq!(perft(&mut board, Stone::Black, 3, &area), 23 * 22 * 21);
+        // 直接换子也要先异或掉原来的棋子
+        board.set(Coord::CENTER, Stone::White);
+        assert_eq!(board.hash(), hash(&board));
+    }
+
+    #[test]
+    fn transpositions_share_a_hash() {
+        let moves = [Coord::new(7, 7), Coord::new(8, 8), Coord::new(6, 6), Coord::new(9, 9)];
+        let mut forward = Board::new();
+        for (i, &mv) in moves.iter().enumerate() {
+            forward.set(mv, Stone::for_ply(i));
+        }
+        // 黑白各自的落子换一个顺序，得到同一个局面
+        let mut reordered = Board::new();
+        for i in [2, 3, 0, 1] {
+            reordered.set(moves[i], Stone::for_ply(i));
+        }
+        assert_eq!(forward, reordered);
+        assert_eq!(forward.hash(), reordered.hash());
+        assert_ne!(hash_with_side(&forward, Stone::Black), hash_with_side(&forward, Stone::White));
+    }
+
+    #[test]
+    fn keys_are_distinct() {
+        let keys: HashSet<u64> = KEYS.iter().flatten().copied().chain([WHITE_TO_MOVE]).collect();
+        assert_eq!(keys.len(), 2 * BOARD_SIZE * BOARD_SIZE + 1);
+        assert!(!keys.contains(&0));
+    }
+}